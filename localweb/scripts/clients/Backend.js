@@ -6,7 +6,21 @@ class Backend {
     }
 
     async getFolders() {
-        return this._postAuthenticated('/select-folders');
+        const data = await this._postAuthenticated('/select-folders');
+        if (!data || !data.manual) {
+            return data;
+        }
+        // No folder dialog on a headless server, so ask for the paths instead
+        const input = window.prompt('Enter the full path of a folder on the server (separate multiple paths with new lines or ";")');
+        const paths = (input || '').split(/[;\n]/).map(path => path.trim()).filter(path => path.length > 0);
+        if (paths.length === 0) {
+            return { folders: [] };
+        }
+        try {
+            return await this._postAuthenticated('/select-folders', { paths });
+        } catch (error) {
+            throw new Error(error?.body?.error || 'Failed to select folders');
+        }
     }
 
     async getConfiguration() {
@@ -61,6 +75,45 @@ class Backend {
         return this._deleteAuthenticated('/profile/' + profileId);
     }
 
+    async getSessions() {
+        return this._getAuthenticated('/sessions');
+    }
+
+    async revokeSession(sessionId) {
+        return this._deleteAuthenticated('/sessions/' + sessionId);
+    }
+
+    async revokeAllSessions() {
+        return this._deleteAuthenticated('/sessions');
+    }
+
+    async getScanJob(indexId) {
+        return this._getAuthenticated('/index/' + indexId + '/scan-job');
+    }
+
+    async cancelScanJob(indexId) {
+        return this._deleteAuthenticated('/index/' + indexId + '/scan-job');
+    }
+
+    async pauseScanJob(indexId) {
+        return this._postAuthenticated('/index/' + indexId + '/scan-job/pause');
+    }
+
+    async resumeScanJob(indexId) {
+        return this._postAuthenticated('/index/' + indexId + '/scan-job/resume');
+    }
+
+    async getScanRuns(indexId) {
+        return this._getAuthenticated('/index/' + indexId + '/scan-runs');
+    }
+
+    // EventSource can't send headers, so the token goes in the query string
+    subscribeScanEvents(onScanJob) {
+        const source = new EventSource(`${API_URL}/scan-events?${new URLSearchParams({ token: this._token })}`);
+        source.addEventListener('scan-job', (event) => onScanJob(JSON.parse(event.data)));
+        return source;
+    }
+
     async _handleError(response) {
         let errorData = {
             status: response.status,
@@ -1,13 +1,14 @@
 -- ============================================================================
 -- Index Stream — Core Video Schema (SQLite)
 -- Tailored to video content with items/versions/parts and JSON metadata.
+--
+-- Migration 0001: the baseline schema. Installs created before migrations
+-- existed already have it, so every statement must stay idempotent. Later
+-- changes go in new numbered files, applied files are never edited. Each
+-- migration runs in a transaction; foreign keys and WAL are set up by the
+-- connection pool.
 -- ============================================================================
 
-PRAGMA foreign_keys = ON;
-PRAGMA journal_mode = WAL;
-
-BEGIN;
-
 -- ----------------------------------------------------------------------------
 -- TOKENS - authentication tokens
 -- Columns:
@@ -245,5 +246,3 @@ BEGIN
            (SELECT added_at       FROM video_items WHERE id = NEW.parent_id)
          );
 END;
-
-COMMIT;
@@ -0,0 +1,9 @@
+-- ============================================================================
+-- Migration 0002: token activity
+-- Sliding token expiry and session listing.
+--   last_used_at : epoch seconds of the last authenticated request
+--   ip_address   : client IP of the last authenticated request
+-- ============================================================================
+
+ALTER TABLE tokens ADD COLUMN last_used_at INTEGER;
+ALTER TABLE tokens ADD COLUMN ip_address TEXT;
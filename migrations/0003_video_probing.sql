@@ -0,0 +1,40 @@
+-- ============================================================================
+-- Migration 0003: video probing
+-- Technical details of video parts, filled by the prober.
+--   video_parts.probed_at : when the file was last analyzed (epoch seconds)
+-- ============================================================================
+
+ALTER TABLE video_parts ADD COLUMN probed_at INTEGER;
+
+-- ----------------------------------------------------------------------------
+-- VIDEO STREAMS — elementary streams inside a part (filled by the prober)
+-- kind: 'video' | 'audio' | 'subtitle'
+-- Columns:
+--   id           : autoincrement surrogate key
+--   part_id      : FK to video_parts.id
+--   stream_index : index of the stream inside the container
+--   codec        : codec name ("h264","hevc","aac","subrip",...)
+--   language     : ISO 639 language tag if present ("eng")
+--   title        : track title if present ("Commentary")
+--   channels     : audio channel count
+--   width/height : video dimensions
+--   is_default   : 0/1 default disposition
+--   is_forced    : 0/1 forced disposition (subtitles)
+-- ----------------------------------------------------------------------------
+CREATE TABLE IF NOT EXISTS video_streams (
+  id               INTEGER PRIMARY KEY AUTOINCREMENT,
+  part_id          INTEGER NOT NULL REFERENCES video_parts(id) ON DELETE CASCADE,
+
+  stream_index     INTEGER NOT NULL,
+  kind             TEXT NOT NULL CHECK (kind IN ('video','audio','subtitle')),
+  codec            TEXT,
+  language         TEXT,
+  title            TEXT,
+  channels         INTEGER,
+  width            INTEGER,
+  height           INTEGER,
+  is_default       INTEGER NOT NULL DEFAULT 0,
+  is_forced        INTEGER NOT NULL DEFAULT 0,
+
+  UNIQUE(part_id, stream_index)
+);
@@ -0,0 +1,37 @@
+-- ============================================================================
+-- Migration 0004: scan jobs
+-- Progress of the latest scan of each index.
+-- ============================================================================
+
+-- ----------------------------------------------------------------------------
+-- SCAN JOBS — progress of the latest scan of each index
+-- kind: 'full' | 'incremental'
+-- status: 'scanning' | 'done' | 'failed'
+-- Columns:
+--   index_id         : FK to indexes.id (one row per index, replaced by each scan)
+--   files_discovered : media files found by the folder walk so far
+--   files_processed  : media files handled so far (unchanged, added, updated or failed)
+--   current_folder   : folder being scanned
+--   items_added      : new media files added
+--   items_updated    : changed or moved media files updated
+--   items_removed    : deleted media files removed
+--   errors           : files or folders that failed to scan
+--   started_at/updated_at/finished_at : epoch seconds
+-- ----------------------------------------------------------------------------
+CREATE TABLE IF NOT EXISTS scan_jobs (
+  index_id         INTEGER PRIMARY KEY REFERENCES indexes(id) ON DELETE CASCADE,
+  kind             TEXT NOT NULL DEFAULT 'full' CHECK (kind IN ('full','incremental')),
+  status           TEXT NOT NULL DEFAULT 'scanning' CHECK (status IN ('scanning','done','failed')),
+
+  files_discovered INTEGER NOT NULL DEFAULT 0,
+  files_processed  INTEGER NOT NULL DEFAULT 0,
+  current_folder   TEXT,
+  items_added      INTEGER NOT NULL DEFAULT 0,
+  items_updated    INTEGER NOT NULL DEFAULT 0,
+  items_removed    INTEGER NOT NULL DEFAULT 0,
+  errors           INTEGER NOT NULL DEFAULT 0,
+
+  started_at       INTEGER NOT NULL DEFAULT (strftime('%s','now')),
+  updated_at       INTEGER NOT NULL DEFAULT (strftime('%s','now')),
+  finished_at      INTEGER
+);
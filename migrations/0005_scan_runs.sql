@@ -0,0 +1,65 @@
+-- ============================================================================
+-- Migration 0005: scan runs
+-- Paused and cancelled scans, and the history of the scans of each index.
+-- SQLite can't change a CHECK constraint, so scan_jobs is rebuilt with the
+-- new statuses.
+-- ============================================================================
+
+CREATE TABLE scan_jobs_new (
+  index_id         INTEGER PRIMARY KEY REFERENCES indexes(id) ON DELETE CASCADE,
+  kind             TEXT NOT NULL DEFAULT 'full' CHECK (kind IN ('full','incremental')),
+  status           TEXT NOT NULL DEFAULT 'scanning' CHECK (status IN ('scanning','paused','done','failed','cancelled')),
+
+  files_discovered INTEGER NOT NULL DEFAULT 0,
+  files_processed  INTEGER NOT NULL DEFAULT 0,
+  current_folder   TEXT,
+  items_added      INTEGER NOT NULL DEFAULT 0,
+  items_updated    INTEGER NOT NULL DEFAULT 0,
+  items_removed    INTEGER NOT NULL DEFAULT 0,
+  errors           INTEGER NOT NULL DEFAULT 0,
+
+  started_at       INTEGER NOT NULL DEFAULT (strftime('%s','now')),
+  updated_at       INTEGER NOT NULL DEFAULT (strftime('%s','now')),
+  finished_at      INTEGER
+);
+
+INSERT INTO scan_jobs_new
+SELECT index_id, kind, status, files_discovered, files_processed, current_folder,
+       items_added, items_updated, items_removed, errors, started_at, updated_at, finished_at
+FROM scan_jobs;
+
+DROP TABLE scan_jobs;
+ALTER TABLE scan_jobs_new RENAME TO scan_jobs;
+
+-- ----------------------------------------------------------------------------
+-- SCAN RUNS — history of the scans of each index
+-- kind: 'full' | 'incremental'
+-- outcome: 'running' | 'done' | 'failed' | 'cancelled' | 'interrupted'
+--   ('interrupted' runs were still running when the server stopped)
+-- Columns:
+--   index_id      : FK to indexes.id
+--   counters      : same meaning as in scan_jobs, final values of the run
+--   error_message : why the run failed
+--   started_at/finished_at : epoch seconds
+-- Consecutive failed or interrupted full runs back off automatic retries.
+-- ----------------------------------------------------------------------------
+CREATE TABLE IF NOT EXISTS scan_runs (
+  id               INTEGER PRIMARY KEY,
+  index_id         INTEGER NOT NULL REFERENCES indexes(id) ON DELETE CASCADE,
+  kind             TEXT NOT NULL DEFAULT 'full' CHECK (kind IN ('full','incremental')),
+  outcome          TEXT NOT NULL DEFAULT 'running' CHECK (outcome IN ('running','done','failed','cancelled','interrupted')),
+
+  files_discovered INTEGER NOT NULL DEFAULT 0,
+  files_processed  INTEGER NOT NULL DEFAULT 0,
+  items_added      INTEGER NOT NULL DEFAULT 0,
+  items_updated    INTEGER NOT NULL DEFAULT 0,
+  items_removed    INTEGER NOT NULL DEFAULT 0,
+  errors           INTEGER NOT NULL DEFAULT 0,
+  error_message    TEXT,
+
+  started_at       INTEGER NOT NULL DEFAULT (strftime('%s','now')),
+  finished_at      INTEGER
+);
+
+CREATE INDEX IF NOT EXISTS idx_scan_runs_index_started
+  ON scan_runs(index_id, started_at);
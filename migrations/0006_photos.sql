@@ -0,0 +1,72 @@
+-- ============================================================================
+-- Migration 0006: photos
+-- Photo index type.
+-- ============================================================================
+
+-- ----------------------------------------------------------------------------
+-- PHOTO ALBUMS — folders of a photo index that contain photos
+-- Columns:
+--   index_id   : FK to indexes.id
+--   parent_id  : album of the parent folder (NULL for top-level albums)
+--   path       : absolute folder path
+--   title      : folder name
+-- ----------------------------------------------------------------------------
+CREATE TABLE IF NOT EXISTS photo_albums (
+  id               INTEGER PRIMARY KEY AUTOINCREMENT,
+  index_id         INTEGER NOT NULL REFERENCES indexes(id) ON DELETE CASCADE,
+  parent_id        INTEGER REFERENCES photo_albums(id) ON DELETE SET NULL,
+  path             TEXT NOT NULL,
+  title            TEXT NOT NULL,
+
+  created_at       INTEGER NOT NULL DEFAULT (strftime('%s','now')),
+  updated_at       INTEGER NOT NULL DEFAULT (strftime('%s','now')),
+
+  UNIQUE(index_id, path)
+);
+
+CREATE INDEX IF NOT EXISTS idx_photo_albums_parent
+  ON photo_albums(parent_id);
+
+-- ----------------------------------------------------------------------------
+-- PHOTO ITEMS — photo files with their EXIF information
+-- Columns:
+--   index_id/album_id : owning index and folder album
+--   path              : absolute file path (unique)
+--   size/mtime/fast_hash : file identity, as for video_parts
+--   width/height      : pixel dimensions of the original (NULL if it can't be decoded)
+--   taken_at          : EXIF capture date, falling back to mtime (epoch seconds)
+--   camera_make/camera_model : EXIF camera
+--   latitude/longitude : EXIF GPS position in decimal degrees
+--   orientation       : EXIF orientation (1-8)
+--   has_thumbnail     : 1 when a thumbnail was generated (stored by fast_hash)
+--   added_at/updated_at : bookkeeping (updated_at marks photos seen by a scan)
+-- ----------------------------------------------------------------------------
+CREATE TABLE IF NOT EXISTS photo_items (
+  id               INTEGER PRIMARY KEY AUTOINCREMENT,
+  index_id         INTEGER NOT NULL REFERENCES indexes(id) ON DELETE CASCADE,
+  album_id         INTEGER REFERENCES photo_albums(id) ON DELETE SET NULL,
+
+  path             TEXT NOT NULL UNIQUE,
+  size             INTEGER,
+  mtime            INTEGER,
+  fast_hash        TEXT,
+
+  width            INTEGER,
+  height           INTEGER,
+  taken_at         INTEGER NOT NULL,
+  camera_make      TEXT,
+  camera_model     TEXT,
+  latitude         REAL,
+  longitude        REAL,
+  orientation      INTEGER,
+  has_thumbnail    INTEGER NOT NULL DEFAULT 0,           -- boolean: 0 = false, 1 = true
+
+  added_at         INTEGER NOT NULL DEFAULT (strftime('%s','now')),
+  updated_at       INTEGER NOT NULL DEFAULT (strftime('%s','now'))
+);
+
+CREATE INDEX IF NOT EXISTS idx_photo_items_index_taken
+  ON photo_items(index_id, taken_at DESC);
+
+CREATE INDEX IF NOT EXISTS idx_photo_items_album_taken
+  ON photo_items(album_id, taken_at DESC);
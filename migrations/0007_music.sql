@@ -0,0 +1,79 @@
+-- ============================================================================
+-- Migration 0007: music
+-- Music index type.
+-- ============================================================================
+
+-- ----------------------------------------------------------------------------
+-- MUSIC ITEMS — artist/album/track hierarchy built from the tags of music files
+-- type: 'artist' | 'album' | 'track'
+-- Columns:
+--   index_id     : FK to indexes.id
+--   parent_id    : hierarchy link (album->artist, track->album)
+--   title        : artist name, album title or track title
+--   sort_title   : normalized sort key (e.g., "Beatles, The")
+--   year         : release year (albums and tracks)
+--   number       : track number (tracks)
+--   disc_number  : disc number (tracks)
+--   genre        : genre tag (tracks, copied to albums)
+--   has_cover    : 1 when cover art was extracted for the album (stored by album ID)
+--   added_at/updated_at : bookkeeping
+-- ----------------------------------------------------------------------------
+CREATE TABLE IF NOT EXISTS music_items (
+  id               INTEGER PRIMARY KEY AUTOINCREMENT,
+  index_id         INTEGER NOT NULL REFERENCES indexes(id) ON DELETE CASCADE,
+  type             TEXT NOT NULL CHECK (type IN ('artist','album','track')),
+  parent_id        INTEGER REFERENCES music_items(id) ON DELETE CASCADE,
+
+  title            TEXT NOT NULL,
+  sort_title       TEXT,
+  year             INTEGER,
+  number           INTEGER,
+  disc_number      INTEGER,
+  genre            TEXT,
+  has_cover        INTEGER NOT NULL DEFAULT 0,           -- boolean: 0 = false, 1 = true
+
+  added_at         INTEGER NOT NULL DEFAULT (strftime('%s','now')),
+  updated_at       INTEGER NOT NULL DEFAULT (strftime('%s','now'))
+);
+
+CREATE INDEX IF NOT EXISTS idx_music_items_index_type_title
+  ON music_items(index_id, type, title COLLATE NOCASE);
+
+CREATE INDEX IF NOT EXISTS idx_music_items_parent
+  ON music_items(parent_id);
+
+-- ----------------------------------------------------------------------------
+-- MUSIC FILES — the audio file of each track
+-- Columns:
+--   item_id           : FK to music_items.id (the track)
+--   index_id          : FK to indexes.id
+--   path              : absolute file path (unique)
+--   size/mtime/fast_hash : file identity, as for video_parts
+--   duration_ms       : playback duration
+--   codec/sample_rate/channels : audio stream information
+--   created_at/updated_at : bookkeeping (updated_at marks files seen by a scan)
+-- ----------------------------------------------------------------------------
+CREATE TABLE IF NOT EXISTS music_files (
+  id               INTEGER PRIMARY KEY AUTOINCREMENT,
+  item_id          INTEGER NOT NULL REFERENCES music_items(id) ON DELETE CASCADE,
+  index_id         INTEGER NOT NULL REFERENCES indexes(id) ON DELETE CASCADE,
+
+  path             TEXT NOT NULL UNIQUE,
+  size             INTEGER,
+  mtime            INTEGER,
+  fast_hash        TEXT,
+
+  duration_ms      INTEGER,
+  codec            TEXT,
+  sample_rate      INTEGER,
+  channels         INTEGER,
+
+  created_at       INTEGER NOT NULL DEFAULT (strftime('%s','now')),
+  updated_at       INTEGER NOT NULL DEFAULT (strftime('%s','now'))
+);
+
+CREATE INDEX IF NOT EXISTS idx_music_files_item
+  ON music_files(item_id);
+
+CREATE INDEX IF NOT EXISTS idx_music_files_index
+  ON music_files(index_id);
@@ -0,0 +1,36 @@
+-- ============================================================================
+-- Migration 0008: video artwork
+-- Posters, backdrops and thumbnails of video items.
+--   video_items.artwork_checked_at : when embedded cover art and frames were
+--                                    last extracted (epoch seconds)
+-- ============================================================================
+
+ALTER TABLE video_items ADD COLUMN artwork_checked_at INTEGER;
+
+-- ----------------------------------------------------------------------------
+-- VIDEO ARTWORK — posters, backdrops and thumbnails cached for video items
+-- kind: 'poster' | 'fanart' | 'thumb'
+-- source: 'sidecar' (image next to the media) | 'embedded' (cover art stream) | 'frame' (grabbed frame)
+-- Columns:
+--   item_id      : FK to video_items.id
+--   source_path  : sidecar image path (NULL for embedded/frame artwork)
+--   source_mtime : sidecar image mtime, to notice replaced images
+--   width/height : dimensions of the cached image
+-- The image itself is stored in the app data cache, keyed by item ID and kind.
+-- ----------------------------------------------------------------------------
+CREATE TABLE IF NOT EXISTS video_artwork (
+  id               INTEGER PRIMARY KEY AUTOINCREMENT,
+  item_id          INTEGER NOT NULL REFERENCES video_items(id) ON DELETE CASCADE,
+
+  kind             TEXT NOT NULL CHECK (kind IN ('poster','fanart','thumb')),
+  source           TEXT NOT NULL CHECK (source IN ('sidecar','embedded','frame')),
+  source_path      TEXT,
+  source_mtime     INTEGER,
+  width            INTEGER,
+  height           INTEGER,
+
+  created_at       INTEGER NOT NULL DEFAULT (strftime('%s','now')),
+  updated_at       INTEGER NOT NULL DEFAULT (strftime('%s','now')),
+
+  UNIQUE(item_id, kind)
+);
@@ -0,0 +1,8 @@
+-- ============================================================================
+-- Migration 0009: metadata refresh
+-- Metadata agents.
+--   video_items.metadata_refreshed_at : when metadata agents last matched the
+--                                       item (epoch seconds)
+-- ============================================================================
+
+ALTER TABLE video_items ADD COLUMN metadata_refreshed_at INTEGER;
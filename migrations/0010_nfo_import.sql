@@ -0,0 +1,8 @@
+-- ============================================================================
+-- Migration 0010: NFO import
+-- Kodi-style NFO sidecar metadata.
+--   video_items.nfo_mtime : modification time of the imported NFO file
+--                           (epoch seconds), NULL without one
+-- ============================================================================
+
+ALTER TABLE video_items ADD COLUMN nfo_mtime INTEGER;
@@ -0,0 +1,40 @@
+-- ============================================================================
+-- Migration 0011: subtitles
+-- Sidecar and embedded text subtitles of video parts.
+-- ============================================================================
+
+-- ----------------------------------------------------------------------------
+-- SUBTITLES — text subtitles of a part, from sidecar files or extracted from the container
+-- source: 'sidecar' (file next to the media or in a Subs folder) | 'embedded' (text stream of the part)
+-- format: 'srt' | 'ass' | 'ssa' | 'vtt'
+-- Columns:
+--   part_id      : FK to video_parts.id
+--   path         : sidecar file, or the copy of an embedded stream extracted to the app data cache
+--   stream_index : index of the embedded stream inside the container (NULL for sidecars)
+--   language     : ISO 639-2 language code if known ("eng")
+--   title        : remaining name tags or the track title ("Commentary")
+--   is_default   : 0/1 default track
+--   is_forced    : 0/1 forced (foreign parts only)
+--   is_sdh       : 0/1 for the deaf and hard of hearing
+--   mtime        : sidecar file mtime, to notice replaced files
+-- ----------------------------------------------------------------------------
+CREATE TABLE IF NOT EXISTS subtitles (
+  id               INTEGER PRIMARY KEY AUTOINCREMENT,
+  part_id          INTEGER NOT NULL REFERENCES video_parts(id) ON DELETE CASCADE,
+
+  source           TEXT NOT NULL CHECK (source IN ('sidecar','embedded')),
+  path             TEXT NOT NULL,
+  stream_index     INTEGER,
+  format           TEXT NOT NULL CHECK (format IN ('srt','ass','ssa','vtt')),
+  language         TEXT,
+  title            TEXT,
+  is_default       INTEGER NOT NULL DEFAULT 0,
+  is_forced        INTEGER NOT NULL DEFAULT 0,
+  is_sdh           INTEGER NOT NULL DEFAULT 0,
+  mtime            INTEGER,
+
+  created_at       INTEGER NOT NULL DEFAULT (strftime('%s','now')),
+  updated_at       INTEGER NOT NULL DEFAULT (strftime('%s','now')),
+
+  UNIQUE(part_id, path)
+);
@@ -0,0 +1,37 @@
+-- ============================================================================
+-- Migration 0012: watch state
+-- Per-profile playback progress and watched flags.
+-- ============================================================================
+
+-- ----------------------------------------------------------------------------
+-- WATCH STATE — playback progress and watched flags of a video item per profile
+-- Playable items (movies, episodes, videos, extras) are reported by clients;
+-- seasons and shows are rolled up from their children (watched once all are).
+-- Columns:
+--   profile_id      : FK to profiles.id
+--   item_id         : FK to video_items.id
+--   position_ms     : resume point, 0 when not started or finished
+--   watched         : 0/1 watched flag
+--   play_count      : number of times the item was watched to the end
+--   last_watched_at : epoch seconds of the last progress report or watched mark
+--   updated_at      : bookkeeping
+-- ----------------------------------------------------------------------------
+CREATE TABLE IF NOT EXISTS watch_state (
+  profile_id       INTEGER NOT NULL REFERENCES profiles(id) ON DELETE CASCADE,
+  item_id          INTEGER NOT NULL REFERENCES video_items(id) ON DELETE CASCADE,
+
+  position_ms      INTEGER NOT NULL DEFAULT 0,
+  watched          INTEGER NOT NULL DEFAULT 0,           -- boolean: 0 = false, 1 = true
+  play_count       INTEGER NOT NULL DEFAULT 0,
+  last_watched_at  INTEGER,
+
+  updated_at       INTEGER NOT NULL DEFAULT (strftime('%s','now')),
+
+  PRIMARY KEY (profile_id, item_id)
+);
+
+CREATE INDEX IF NOT EXISTS idx_watch_state_profile_last_watched
+  ON watch_state(profile_id, last_watched_at DESC);
+
+CREATE INDEX IF NOT EXISTS idx_watch_state_item
+  ON watch_state(item_id);
@@ -0,0 +1,82 @@
+-- ============================================================================
+-- Migration 0013: video search
+-- Full-text search over video titles and metadata.
+-- ============================================================================
+
+-- ----------------------------------------------------------------------------
+-- VIDEO SEARCH — FTS5 index over video item titles and metadata
+-- rowid is video_items.id. Tokens are case and diacritic folded ("Amelie"
+-- finds "Amélie"); the prefix indexes speed up search-as-you-type.
+-- Columns:
+--   title      : video_items.title
+--   sort_title : video_items.sort_title
+--   aka        : original, matched and alternative titles from metadata
+--   overview   : metadata overview (plot)
+--   cast_names : names of the cast members from metadata
+-- Kept in sync with video_items by the triggers below.
+-- ----------------------------------------------------------------------------
+CREATE VIRTUAL TABLE IF NOT EXISTS video_items_fts USING fts5(
+  title,
+  sort_title,
+  aka,
+  overview,
+  cast_names,
+  tokenize = 'unicode61 remove_diacritics 2',
+  prefix = '2 3'
+);
+
+CREATE TRIGGER IF NOT EXISTS trg_video_items_fts_insert
+AFTER INSERT ON video_items
+FOR EACH ROW
+BEGIN
+  INSERT INTO video_items_fts (rowid, title, sort_title, aka, overview, cast_names)
+  VALUES (
+    NEW.id,
+    NEW.title,
+    NEW.sort_title,
+    trim(COALESCE(json_extract(NEW.metadata, '$.original_title'), '') || ' ' ||
+         COALESCE(json_extract(NEW.metadata, '$.matched_title'), '') || ' ' ||
+         COALESCE((SELECT group_concat(value, ' ') FROM json_each(NEW.metadata, '$.aka_titles')), '')),
+    json_extract(NEW.metadata, '$.overview'),
+    (SELECT group_concat(json_extract(value, '$.name'), ' ') FROM json_each(NEW.metadata, '$.cast') WHERE type = 'object')
+  );
+END;
+
+CREATE TRIGGER IF NOT EXISTS trg_video_items_fts_update
+AFTER UPDATE OF title, sort_title, metadata ON video_items
+FOR EACH ROW
+BEGIN
+  DELETE FROM video_items_fts WHERE rowid = OLD.id;
+  INSERT INTO video_items_fts (rowid, title, sort_title, aka, overview, cast_names)
+  VALUES (
+    NEW.id,
+    NEW.title,
+    NEW.sort_title,
+    trim(COALESCE(json_extract(NEW.metadata, '$.original_title'), '') || ' ' ||
+         COALESCE(json_extract(NEW.metadata, '$.matched_title'), '') || ' ' ||
+         COALESCE((SELECT group_concat(value, ' ') FROM json_each(NEW.metadata, '$.aka_titles')), '')),
+    json_extract(NEW.metadata, '$.overview'),
+    (SELECT group_concat(json_extract(value, '$.name'), ' ') FROM json_each(NEW.metadata, '$.cast') WHERE type = 'object')
+  );
+END;
+
+CREATE TRIGGER IF NOT EXISTS trg_video_items_fts_delete
+AFTER DELETE ON video_items
+FOR EACH ROW
+BEGIN
+  DELETE FROM video_items_fts WHERE rowid = OLD.id;
+END;
+
+-- Index items that existed before the search index was added
+INSERT INTO video_items_fts (rowid, title, sort_title, aka, overview, cast_names)
+SELECT
+  vi.id,
+  vi.title,
+  vi.sort_title,
+  trim(COALESCE(json_extract(vi.metadata, '$.original_title'), '') || ' ' ||
+       COALESCE(json_extract(vi.metadata, '$.matched_title'), '') || ' ' ||
+       COALESCE((SELECT group_concat(value, ' ') FROM json_each(vi.metadata, '$.aka_titles')), '')),
+  json_extract(vi.metadata, '$.overview'),
+  (SELECT group_concat(json_extract(value, '$.name'), ' ') FROM json_each(vi.metadata, '$.cast') WHERE type = 'object')
+FROM video_items vi
+WHERE NOT EXISTS (SELECT 1 FROM video_items_fts f WHERE f.rowid = vi.id);
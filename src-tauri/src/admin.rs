@@ -0,0 +1,349 @@
+//! Administration of a server from the command line
+//! Works on the same database and config.json as the server, which may be running at the same time
+
+use crate::api::config::hash_password;
+use crate::api::folders::{check_typed_folders, filter_child_folders};
+use crate::config::{self, PathProvider};
+use crate::constants::INDEX_TYPES;
+use crate::db::models::{Index, Profile};
+use crate::db::repos::{IndexesRepo, ProfilesRepo};
+use crate::models::config::{BackupSettings, Configuration};
+use crate::scanning::video_scanning::is_video_file;
+use crate::utils::video_classifier::{classify_path, ClassificationResult, MediaType};
+use crate::db;
+use anyhow::{anyhow, bail, Result};
+use sqlx::SqlitePool;
+use std::path::{Path, PathBuf};
+use tokio::fs;
+use uuid::Uuid;
+
+/// Open the database of the server, migrating it to the current schema
+pub async fn open_database(paths: &dyn PathProvider) -> Result<SqlitePool> {
+    let db_path = config::sqlite_path(paths)?;
+    let db_pool = db::pool::connect_pool(&db_path).await?;
+    db::migrations::run_migrations(&db_pool, &db_path).await?;
+    Ok(db_pool)
+}
+
+/// Read config.json, failing if the server has not been set up yet
+async fn read_configuration(paths: &dyn PathProvider) -> Result<Configuration> {
+    let config_path = config::config_path(paths)?;
+    let config_json = fs::read_to_string(&config_path).await
+        .map_err(|_| anyhow!("Server is not set up yet, run `init` first"))?;
+    Ok(serde_json::from_str(&config_json)?)
+}
+
+/// Write config.json
+async fn write_configuration(paths: &dyn PathProvider, configuration: &Configuration) -> Result<()> {
+    let config_path = config::config_path(paths)?;
+    fs::write(&config_path, serde_json::to_string_pretty(configuration)?).await?;
+    Ok(())
+}
+
+/// Set up a new server with a name and password, like the setup page does
+pub async fn init_server_config(paths: &dyn PathProvider, name: &str, password: &str) -> Result<Configuration> {
+    if config::config_path(paths)?.exists() {
+        bail!("Server is already set up, use `password` to reset its password");
+    }
+    if name.trim().is_empty() {
+        bail!("Server name is required and cannot be empty");
+    }
+    if password.trim().is_empty() {
+        bail!("Password is required and cannot be empty");
+    }
+
+    let configuration = Configuration {
+        id: Uuid::new_v4().to_string(),
+        name: name.trim().to_string(),
+        password: hash_password(password).map_err(|e| anyhow!("Failed to hash password: {}", e))?,
+        backup: BackupSettings::default(),
+    };
+    write_configuration(paths, &configuration).await?;
+    Ok(configuration)
+}
+
+/// Replace the password clients use to log in
+pub async fn reset_password(paths: &dyn PathProvider, password: &str) -> Result<()> {
+    if password.trim().is_empty() {
+        bail!("Password is required and cannot be empty");
+    }
+
+    let mut configuration = read_configuration(paths).await?;
+    configuration.password = hash_password(password).map_err(|e| anyhow!("Failed to hash password: {}", e))?;
+    write_configuration(paths, &configuration).await
+}
+
+/// Get an index by ID, failing if it doesn't exist
+async fn get_index(db_pool: &SqlitePool, index_id: i64) -> Result<Index> {
+    IndexesRepo::new(db_pool.clone()).get_index_by_id(index_id).await?
+        .ok_or_else(|| anyhow!("Index {} not found", index_id))
+}
+
+/// Add a local index of folders on this machine, queued for its first scan
+pub async fn add_index(db_pool: &SqlitePool, name: &str, r#type: &str, icon: &str, folders: Vec<String>) -> Result<Index> {
+    if name.trim().is_empty() {
+        bail!("Index name is required and cannot be empty");
+    }
+    if !INDEX_TYPES.contains(&r#type) {
+        bail!("Unknown index type '{}', expected one of: {}", r#type, INDEX_TYPES.join(", "));
+    }
+
+    let folders = check_typed_folders(folders)
+        .map_err(|invalid| anyhow!("Not a folder: {}", invalid.join(", ")))?;
+    if folders.is_empty() {
+        bail!("At least one folder is required");
+    }
+
+    let indexes_repo = IndexesRepo::new(db_pool.clone());
+    if indexes_repo.name_exists(name.trim(), None).await? {
+        bail!("An index named '{}' already exists", name.trim());
+    }
+
+    let metadata = serde_json::json!({
+        "folders": filter_child_folders(folders),
+    });
+    let index_id = indexes_repo.add_index(name.trim().to_string(), r#type.to_string(), Some(icon.to_string()), metadata).await?;
+    get_index(db_pool, index_id).await
+}
+
+/// Remove an index with its custom icon
+pub async fn remove_index(db_pool: &SqlitePool, paths: &dyn PathProvider, index_id: i64) -> Result<Index> {
+    let index = get_index(db_pool, index_id).await?;
+
+    let icons_dir = config::icons_dir(paths)?;
+    let icon_extensions = ["png", "jpg", "jpeg", "gif", "bmp", "webp"];
+    for ext in &icon_extensions {
+        let icon_path = icons_dir.join(format!("index_{}.{}", index_id, ext));
+        if icon_path.exists() {
+            fs::remove_file(&icon_path).await?;
+        }
+    }
+
+    IndexesRepo::new(db_pool.clone()).delete_index(index_id).await?;
+    Ok(index)
+}
+
+/// Queue a scan of an index, picked up by the scanning process of the server
+pub async fn queue_index_scan(db_pool: &SqlitePool, index_id: i64) -> Result<Index> {
+    let index = get_index(db_pool, index_id).await?;
+
+    match index.scan_status.as_str() {
+        "done" | "failed" => {
+            IndexesRepo::new(db_pool.clone()).update_scan_status(index_id, "queued".to_string()).await?;
+            get_index(db_pool, index_id).await
+        }
+        "queued" => bail!("Index '{}' is already queued for scanning", index.name),
+        _ => bail!("Index '{}' is currently being scanned", index.name),
+    }
+}
+
+/// Get a profile by ID, failing if it doesn't exist
+async fn get_profile(db_pool: &SqlitePool, profile_id: i64) -> Result<Profile> {
+    ProfilesRepo::new(db_pool.clone()).get_profile_by_id(profile_id).await?
+        .ok_or_else(|| anyhow!("Profile {} not found", profile_id))
+}
+
+/// Add a profile
+pub async fn add_profile(db_pool: &SqlitePool, name: &str, color: &str) -> Result<Profile> {
+    if name.trim().is_empty() {
+        bail!("Profile name is required and cannot be empty");
+    }
+    if color.trim().is_empty() {
+        bail!("Profile color is required and cannot be empty");
+    }
+
+    let profile_id = ProfilesRepo::new(db_pool.clone()).add_profile(name.trim().to_string(), color.trim().to_string()).await?;
+    get_profile(db_pool, profile_id).await
+}
+
+/// Rename a profile or change its color, keeping what isn't given
+pub async fn update_profile(db_pool: &SqlitePool, profile_id: i64, name: Option<&str>, color: Option<&str>) -> Result<Profile> {
+    let profile = get_profile(db_pool, profile_id).await?;
+
+    let name = name.map(str::trim).unwrap_or(&profile.name);
+    let color = color.map(str::trim).unwrap_or(&profile.color);
+    if name.is_empty() {
+        bail!("Profile name cannot be empty");
+    }
+    if color.is_empty() {
+        bail!("Profile color cannot be empty");
+    }
+
+    ProfilesRepo::new(db_pool.clone()).update_profile(profile_id, name.to_string(), color.to_string()).await?;
+    get_profile(db_pool, profile_id).await
+}
+
+/// Remove a profile
+pub async fn remove_profile(db_pool: &SqlitePool, profile_id: i64) -> Result<Profile> {
+    let profile = get_profile(db_pool, profile_id).await?;
+    ProfilesRepo::new(db_pool.clone()).delete_profile(profile_id).await?;
+    Ok(profile)
+}
+
+/// Collect the video files of a path the way a scan would see them, a single file is classified as is
+pub fn collect_video_files(path: &Path) -> Result<Vec<PathBuf>> {
+    if path.is_file() {
+        return Ok(vec![path.to_path_buf()]);
+    }
+    if !path.is_dir() {
+        bail!("No such file or folder: {}", path.display());
+    }
+
+    let mut files = Vec::new();
+    let mut dirs = vec![path.to_path_buf()];
+    while let Some(dir) = dirs.pop() {
+        for entry in std::fs::read_dir(&dir)? {
+            let entry_path = entry?.path();
+            if entry_path.is_dir() {
+                dirs.push(entry_path);
+            } else if is_video_file(&entry_path) {
+                files.push(entry_path);
+            }
+        }
+    }
+    files.sort();
+    Ok(files)
+}
+
+/// Describe how a video file is classified, without touching the database
+pub fn describe_classification(file_path: &Path) -> String {
+    let classified = classify_path(file_path.to_string_lossy().as_ref());
+    format!("{} -> {}", file_path.display(), describe_classification_result(&classified))
+}
+
+fn describe_classification_result(classified: &ClassificationResult) -> String {
+    match classified.media_type {
+        MediaType::Extra => match &classified.extra {
+            Some(extra) => format!("Extra ({}) of {}", extra.extra_type, extra.path),
+            None => "Extra".to_string(),
+        },
+        MediaType::TvEpisode => match &classified.tv_episode {
+            Some(tv) => {
+                let episodes = match tv.ep_end {
+                    Some(ep_end) => format!("S{:02}E{:02}-E{:02}", tv.season, tv.episode, ep_end),
+                    None => format!("S{:02}E{:02}", tv.season, tv.episode),
+                };
+                let mut description = format!("TV episode: {} {}", tv.show_name, episodes);
+                if let Some(air_date) = &tv.air_date {
+                    description.push_str(&format!(" (aired {})", air_date));
+                }
+                if let Some(title) = &tv.title {
+                    description.push_str(&format!(" - {}", title));
+                }
+                description
+            }
+            None => "TV episode".to_string(),
+        },
+        MediaType::Movie => match &classified.movie {
+            Some(movie) => {
+                let mut description = format!("Movie: {}", movie.title);
+                if let Some(year) = movie.year {
+                    description.push_str(&format!(" ({})", year));
+                }
+                if let Some(part) = movie.part {
+                    description.push_str(&format!(", part {}", part));
+                }
+                if let Some(version) = &movie.version {
+                    description.push_str(&format!(", version {}", version));
+                }
+                description
+            }
+            None => "Movie".to_string(),
+        },
+        MediaType::Generic => match &classified.generic {
+            Some(generic) => format!("Other video: {}", generic.title),
+            None => "Other video".to_string(),
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::DataDir;
+
+    async fn test_server() -> (DataDir, SqlitePool) {
+        let paths = DataDir(std::env::temp_dir().join(format!("admin_test_{}", Uuid::new_v4())));
+        let db_pool = open_database(&paths).await.unwrap();
+        (paths, db_pool)
+    }
+
+    #[tokio::test]
+    async fn test_init_and_reset_password() {
+        let (paths, db_pool) = test_server().await;
+
+        assert!(reset_password(&paths, "secret").await.is_err());
+        let configuration = init_server_config(&paths, " Living Room ", "secret").await.unwrap();
+        assert_eq!(configuration.name, "Living Room");
+        assert!(init_server_config(&paths, "Again", "secret").await.is_err());
+
+        reset_password(&paths, "changed").await.unwrap();
+        let reset = read_configuration(&paths).await.unwrap();
+        assert_eq!(reset.id, configuration.id);
+        assert_ne!(reset.password, configuration.password);
+        assert!(reset_password(&paths, " ").await.is_err());
+
+        db_pool.close().await;
+        let _ = std::fs::remove_dir_all(&paths.0);
+    }
+
+    #[tokio::test]
+    async fn test_index_commands() {
+        let (paths, db_pool) = test_server().await;
+        let movies = paths.0.join("movies");
+        std::fs::create_dir_all(movies.join("kids")).unwrap();
+        let folders = vec![
+            movies.to_string_lossy().to_string(),
+            movies.join("kids").to_string_lossy().to_string(),
+        ];
+
+        assert!(add_index(&db_pool, "Movies", "films", "home", folders.clone()).await.is_err());
+        assert!(add_index(&db_pool, "Movies", "videos", "home", vec!["relative/path".to_string()]).await.is_err());
+
+        let index = add_index(&db_pool, "Movies", "videos", "home", folders.clone()).await.unwrap();
+        assert_eq!(index.folders(), vec![movies.to_string_lossy().to_string()]);
+        assert_eq!(index.scan_status, "queued");
+        assert!(add_index(&db_pool, "Movies", "videos", "home", folders).await.is_err());
+
+        // A queued index can't be queued again, a scanned one can
+        assert!(queue_index_scan(&db_pool, index.id).await.is_err());
+        IndexesRepo::new(db_pool.clone()).update_scan_status(index.id, "done".to_string()).await.unwrap();
+        assert_eq!(queue_index_scan(&db_pool, index.id).await.unwrap().scan_status, "queued");
+
+        remove_index(&db_pool, &paths, index.id).await.unwrap();
+        assert!(remove_index(&db_pool, &paths, index.id).await.is_err());
+
+        db_pool.close().await;
+        let _ = std::fs::remove_dir_all(&paths.0);
+    }
+
+    #[tokio::test]
+    async fn test_profile_commands() {
+        let (paths, db_pool) = test_server().await;
+
+        assert!(add_profile(&db_pool, "", "#3B82F6").await.is_err());
+        let profile = add_profile(&db_pool, "Kids", "#3B82F6").await.unwrap();
+
+        let renamed = update_profile(&db_pool, profile.id, Some("Children"), None).await.unwrap();
+        assert_eq!(renamed.name, "Children");
+        assert_eq!(renamed.color, "#3B82F6");
+
+        remove_profile(&db_pool, profile.id).await.unwrap();
+        assert!(update_profile(&db_pool, profile.id, None, Some("#FF0000")).await.is_err());
+
+        db_pool.close().await;
+        let _ = std::fs::remove_dir_all(&paths.0);
+    }
+
+    #[test]
+    fn test_describe_classification() {
+        assert_eq!(
+            describe_classification(Path::new("/media/Shows/Severance/Season 1/Severance S01E02.mkv")),
+            "/media/Shows/Severance/Season 1/Severance S01E02.mkv -> TV episode: Severance S01E02"
+        );
+        assert_eq!(
+            describe_classification(Path::new("/media/Movies/Heat (1995)/Heat (1995).mkv")),
+            "/media/Movies/Heat (1995)/Heat (1995).mkv -> Movie: Heat (1995)"
+        );
+    }
+}
@@ -0,0 +1,253 @@
+use crate::api::state::AppState;
+use crate::backup::{backup_file_path, backups_dir, create_backup, list_backups, next_backup_file_name, read_backup_settings, save_backup_settings, stage_restore, BackupTrigger};
+use crate::config::get_app_data_dir;
+use crate::models::config::BackupSettings;
+use std::path::PathBuf;
+use tokio::io::AsyncWriteExt;
+use tokio_stream::{Stream, StreamExt};
+use warp::hyper::body::Buf;
+use warp::reject::custom;
+
+// Custom error types for backup operations
+#[derive(Debug)]
+pub struct BackupError;
+
+impl warp::reject::Reject for BackupError {}
+
+// Helper function to get the app data directory holding the server state
+fn data_dir(app_state: &AppState) -> Result<PathBuf, warp::Rejection> {
+    get_app_data_dir(app_state.paths.as_ref())
+        .map_err(|e| {
+            eprintln!("Failed to get app data directory: {}", e);
+            custom(BackupError)
+        })
+}
+
+// Helper function to build a JSON error reply
+fn error_reply(message: &str, status: warp::http::StatusCode) -> warp::reply::WithStatus<warp::reply::Json> {
+    warp::reply::with_status(
+        warp::reply::json(&serde_json::json!({
+            "success": false,
+            "error": message
+        })),
+        status,
+    )
+}
+
+// Handler for listing the backups and the automatic backup settings
+pub async fn handle_get_backups(app_state: AppState) -> Result<impl warp::reply::Reply, warp::Rejection> {
+    let data_dir = data_dir(&app_state)?;
+
+    let backups = list_backups(&data_dir).await
+        .map_err(|e| {
+            eprintln!("Failed to list backups: {}", e);
+            custom(BackupError)
+        })?;
+    let settings = read_backup_settings(&data_dir).await
+        .map_err(|e| {
+            eprintln!("Failed to read backup settings: {}", e);
+            custom(BackupError)
+        })?;
+
+    Ok(warp::reply::with_status(
+        warp::reply::json(&serde_json::json!({
+            "backups": backups,
+            "settings": settings
+        })),
+        warp::http::StatusCode::OK,
+    ))
+}
+
+// Handler for backing up the server state now
+pub async fn handle_create_backup(app_state: AppState) -> Result<impl warp::reply::Reply, warp::Rejection> {
+    let data_dir = data_dir(&app_state)?;
+
+    let backup = create_backup(&app_state.db_pool, &data_dir, BackupTrigger::Manual).await
+        .map_err(|e| {
+            eprintln!("Failed to create backup: {}", e);
+            custom(BackupError)
+        })?;
+
+    println!("Backup {} created", backup.file_name);
+
+    Ok(warp::reply::with_status(
+        warp::reply::json(&serde_json::json!({
+            "success": true,
+            "backup": backup
+        })),
+        warp::http::StatusCode::CREATED,
+    ))
+}
+
+// Handler for updating the automatic backup settings
+pub async fn handle_update_backup_settings(
+    app_state: AppState,
+    settings: BackupSettings,
+) -> Result<impl warp::reply::Reply, warp::Rejection> {
+    if let Err(message) = settings.validate() {
+        return Ok(error_reply(&message, warp::http::StatusCode::BAD_REQUEST));
+    }
+
+    let data_dir = data_dir(&app_state)?;
+    let saved = save_backup_settings(&data_dir, settings.clone()).await
+        .map_err(|e| {
+            eprintln!("Failed to save backup settings: {}", e);
+            custom(BackupError)
+        })?;
+
+    if !saved {
+        return Ok(error_reply("Server is not configured", warp::http::StatusCode::NOT_FOUND));
+    }
+
+    Ok(warp::reply::with_status(
+        warp::reply::json(&serde_json::json!({
+            "success": true,
+            "settings": settings
+        })),
+        warp::http::StatusCode::OK,
+    ))
+}
+
+// Handler for downloading a backup archive, e.g. to move the server to another machine
+pub async fn handle_download_backup(
+    app_state: AppState,
+    file_name: String,
+) -> Result<Box<dyn warp::Reply>, warp::Rejection> {
+    let data_dir = data_dir(&app_state)?;
+    let backups_dir = backups_dir(&data_dir).map_err(|_| custom(BackupError))?;
+
+    let archive_path = match backup_file_path(&backups_dir, &file_name) {
+        Some(path) if path.is_file() => path,
+        _ => return Ok(Box::new(error_reply("Backup not found", warp::http::StatusCode::NOT_FOUND))),
+    };
+
+    let archive = tokio::fs::read(&archive_path).await
+        .map_err(|e| {
+            eprintln!("Failed to read backup {:?}: {}", archive_path, e);
+            custom(BackupError)
+        })?;
+
+    Ok(Box::new(warp::reply::with_header(
+        warp::reply::with_header(archive, "Content-Type", "application/gzip"),
+        "Content-Disposition",
+        format!("attachment; filename=\"{}\"", file_name),
+    )))
+}
+
+// Handler for deleting a backup archive
+pub async fn handle_delete_backup(
+    app_state: AppState,
+    file_name: String,
+) -> Result<impl warp::reply::Reply, warp::Rejection> {
+    let data_dir = data_dir(&app_state)?;
+    let backups_dir = backups_dir(&data_dir).map_err(|_| custom(BackupError))?;
+
+    let archive_path = match backup_file_path(&backups_dir, &file_name) {
+        Some(path) if path.is_file() => path,
+        _ => return Ok(error_reply("Backup not found", warp::http::StatusCode::NOT_FOUND)),
+    };
+
+    tokio::fs::remove_file(&archive_path).await
+        .map_err(|e| {
+            eprintln!("Failed to delete backup {:?}: {}", archive_path, e);
+            custom(BackupError)
+        })?;
+
+    println!("Backup {} deleted", file_name);
+
+    Ok(warp::reply::with_status(
+        warp::reply::json(&serde_json::json!({
+            "success": true,
+            "message": "Backup deleted successfully"
+        })),
+        warp::http::StatusCode::OK,
+    ))
+}
+
+// Handler for restoring one of the backups in the backups directory
+// The restore is checked now and applied on the next start of the server
+pub async fn handle_restore_backup(
+    app_state: AppState,
+    file_name: String,
+) -> Result<impl warp::reply::Reply, warp::Rejection> {
+    let data_dir = data_dir(&app_state)?;
+    let backups_dir = backups_dir(&data_dir).map_err(|_| custom(BackupError))?;
+
+    let archive_path = match backup_file_path(&backups_dir, &file_name) {
+        Some(path) if path.is_file() => path,
+        _ => return Ok(error_reply("Backup not found", warp::http::StatusCode::NOT_FOUND)),
+    };
+
+    match stage_restore(&archive_path, &data_dir).await {
+        Ok(manifest) => {
+            println!("Restore of backup {} queued for the next start", file_name);
+            Ok(warp::reply::with_status(
+                warp::reply::json(&serde_json::json!({
+                    "success": true,
+                    "message": "Restart the server to finish the restore",
+                    "backup": manifest
+                })),
+                warp::http::StatusCode::OK,
+            ))
+        }
+        Err(e) => Ok(error_reply(&e.to_string(), warp::http::StatusCode::BAD_REQUEST)),
+    }
+}
+
+// Handler for restoring an uploaded backup archive (request body), e.g. from another machine
+// The archive is kept in the backups directory and applied on the next start of the server
+pub async fn handle_upload_restore<S, B>(
+    app_state: AppState,
+    body: S,
+) -> Result<impl warp::reply::Reply, warp::Rejection>
+where
+    S: Stream<Item = Result<B, warp::Error>> + Send + 'static,
+    B: Buf + Send,
+{
+    let data_dir = data_dir(&app_state)?;
+    let backups_dir = backups_dir(&data_dir).map_err(|_| custom(BackupError))?;
+    let upload_path = backups_dir.join(format!(".upload-{}.partial", uuid::Uuid::new_v4()));
+
+    // Stream the archive to disk, it may not fit in memory
+    let write_upload = async {
+        let mut file = tokio::fs::File::create(&upload_path).await?;
+        let mut body = Box::pin(body);
+        while let Some(chunk) = body.next().await {
+            let mut chunk = chunk.map_err(std::io::Error::other)?;
+            let bytes = chunk.copy_to_bytes(chunk.remaining());
+            file.write_all(&bytes).await?;
+        }
+        file.sync_all().await
+    };
+    if let Err(e) = write_upload.await {
+        eprintln!("Failed to receive backup upload: {}", e);
+        let _ = tokio::fs::remove_file(&upload_path).await;
+        return Err(custom(BackupError));
+    }
+
+    match stage_restore(&upload_path, &data_dir).await {
+        Ok(manifest) => {
+            let file_name = next_backup_file_name(&backups_dir, BackupTrigger::Uploaded);
+            tokio::fs::rename(&upload_path, backups_dir.join(&file_name)).await
+                .map_err(|e| {
+                    eprintln!("Failed to keep uploaded backup: {}", e);
+                    custom(BackupError)
+                })?;
+
+            println!("Restore of uploaded backup {} queued for the next start", file_name);
+            Ok(warp::reply::with_status(
+                warp::reply::json(&serde_json::json!({
+                    "success": true,
+                    "message": "Restart the server to finish the restore",
+                    "file_name": file_name,
+                    "backup": manifest
+                })),
+                warp::http::StatusCode::OK,
+            ))
+        }
+        Err(e) => {
+            let _ = tokio::fs::remove_file(&upload_path).await;
+            Ok(error_reply(&e.to_string(), warp::http::StatusCode::BAD_REQUEST))
+        }
+    }
+}
@@ -1,4 +1,4 @@
-use crate::models::config::{Configuration, IncomingConfiguration, ServerPasswordUpdate, ServerNameUpdate, IncomingProfile, IncomingMediaIndex};
+use crate::models::config::{BackupSettings, Configuration, IncomingConfiguration, ServerPasswordUpdate, ServerNameUpdate, IncomingProfile, IncomingMediaIndex};
 use crate::api::responses::{DatabaseConfigurationResponse, ProfileResponse, IndexResponse};
 use crate::db::repos::{ProfilesRepo, IndexesRepo};
 use crate::api::state::AppState;
@@ -29,12 +29,11 @@ impl warp::reject::Reject for ConfigGetError {}
 pub async fn handle_get_configuration(
     app_state: AppState,
 ) -> Result<impl warp::reply::Reply, warp::Rejection> {
-    // Get the app handle
-    let app_handle_guard = app_state.app_handle.lock().await;
-    let app_handle = app_handle_guard.as_ref().ok_or_else(|| warp::reject::custom(ConfigGetError))?;
+    // Get the app data paths
+    let paths = app_state.paths.clone();
     
     // Get the config file path using OS app data directory
-    let config_path = config_path(app_handle)
+    let config_path = config_path(paths.as_ref())
         .map_err(|e| {
             eprintln!("Failed to get config path: {}", e);
             warp::reject::custom(ConfigGetError)
@@ -79,12 +78,11 @@ pub async fn handle_save_configuration(
     app_state: AppState,
     incoming_config: IncomingConfiguration,
 ) -> Result<impl warp::reply::Reply, warp::Rejection> {
-    // Get the app handle
-    let app_handle_guard = app_state.app_handle.lock().await;
-    let app_handle = app_handle_guard.as_ref().ok_or_else(|| warp::reject::custom(ConfigSaveError))?;
+    // Get the app data paths
+    let paths = app_state.paths.clone();
     
     // Get the config file path using OS app data directory
-    let config_path = config_path(app_handle)
+    let config_path = config_path(paths.as_ref())
         .map_err(|e| {
             eprintln!("Failed to get config path: {}", e);
             warp::reject::custom(ConfigSaveError)
@@ -99,6 +97,7 @@ pub async fn handle_save_configuration(
                 eprintln!("Failed to hash password: {}", e);
                 warp::reject::custom(ConfigSaveError)
             })?,
+        backup: BackupSettings::default(),
     };
     
     // Save the configuration as JSON
@@ -210,12 +209,11 @@ pub async fn handle_update_server_password(
         ));
     }
 
-    // Get the app handle
-    let app_handle_guard = app_state.app_handle.lock().await;
-    let app_handle = app_handle_guard.as_ref().ok_or_else(|| warp::reject::custom(ConfigSaveError))?;
+    // Get the app data paths
+    let paths = app_state.paths.clone();
     
     // Get the config file path using OS app data directory
-    let config_path = config_path(app_handle)
+    let config_path = config_path(paths.as_ref())
         .map_err(|e| {
             eprintln!("Failed to get config path: {}", e);
             warp::reject::custom(ConfigSaveError)
@@ -281,12 +279,11 @@ pub async fn handle_update_server_name(
         ));
     }
 
-    // Get the app handle
-    let app_handle_guard = app_state.app_handle.lock().await;
-    let app_handle = app_handle_guard.as_ref().ok_or_else(|| warp::reject::custom(ConfigSaveError))?;
+    // Get the app data paths
+    let paths = app_state.paths.clone();
     
     // Get the config file path using OS app data directory
-    let config_path = config_path(app_handle)
+    let config_path = config_path(paths.as_ref())
         .map_err(|e| {
             eprintln!("Failed to get config path: {}", e);
             warp::reject::custom(ConfigSaveError)
@@ -347,12 +344,11 @@ pub async fn handle_get_index_icon(
         )));
     }
 
-    // Get the app handle
-    let app_handle_guard = app_state.app_handle.lock().await;
-    let app_handle = app_handle_guard.as_ref().ok_or_else(|| warp::reject::custom(ConfigGetError))?;
+    // Get the app data paths
+    let paths = app_state.paths.clone();
     
     // Get the icons directory using OS app data directory
-    let icons_dir = crate::config::icons_dir(app_handle)
+    let icons_dir = crate::config::icons_dir(paths.as_ref())
         .map_err(|e| {
             eprintln!("Failed to get icons directory: {}", e);
             warp::reject::custom(ConfigGetError)
@@ -408,7 +404,7 @@ pub async fn handle_get_index_icon(
 }
 
 // Helper function to hash passwords
-fn hash_password(password: &str) -> Result<String, argon2::password_hash::Error> {
+pub(crate) fn hash_password(password: &str) -> Result<String, argon2::password_hash::Error> {
     let salt = SaltString::generate(&mut OsRng);
     let argon2 = Argon2::default();
     let password_hash = argon2.hash_password(password.as_bytes(), &salt)?;
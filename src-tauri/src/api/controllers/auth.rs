@@ -1,25 +1,27 @@
-use crate::api::router::{HttpRequest, HttpResponse, extract_user_agent};
+use crate::api::router::{HttpRequest, HttpResponse, extract_user_agent, extract_request_token};
 use crate::models::config::Configuration;
 use crate::api::responses::FilteredIndexResponse;
 use crate::db::repos::{ProfilesRepo, IndexesRepo};
 use crate::utils::token::{generate_secure_token, add_token_to_storage, token_exists};
-use crate::config::config_path;
+use crate::config::{config_path, PathProvider};
 use argon2::{Argon2, PasswordVerifier};
 use argon2::password_hash::PasswordHashString;
 use serde_json;
 use std::future::Future;
 use std::pin::Pin;
-use std::sync::OnceLock;
+use std::sync::{Arc, OnceLock};
 use sqlx::SqlitePool;
 
-/// Global app handle for auth controller
-static APP_HANDLE: OnceLock<tauri::AppHandle> = OnceLock::new();
+/// Global app data paths for auth controller
+static PATHS: OnceLock<Arc<dyn PathProvider>> = OnceLock::new();
 
 /// Global database pool for auth controller
 static DB_POOL: OnceLock<SqlitePool> = OnceLock::new();
 
-pub fn init_auth_app_handle(app_handle: tauri::AppHandle) {
-    APP_HANDLE.set(app_handle).expect("Failed to initialize auth app handle");
+pub fn init_auth_paths(paths: Arc<dyn PathProvider>) {
+    if PATHS.set(paths).is_err() {
+        panic!("Failed to initialize auth paths");
+    }
 }
 
 pub fn init_auth_db_pool(db_pool: SqlitePool) {
@@ -28,8 +30,8 @@ pub fn init_auth_db_pool(db_pool: SqlitePool) {
 
 /// Load configuration from file
 async fn load_configuration() -> Result<Option<Configuration>, Box<dyn std::error::Error + Send + Sync>> {
-    let app_handle = APP_HANDLE.get().ok_or("App handle not initialized")?;
-    let config_path = config_path(app_handle)
+    let paths = PATHS.get().ok_or("Paths not initialized")?;
+    let config_path = config_path(paths.as_ref())
         .map_err(|e| {
             eprintln!("Failed to get config path: {}", e);
             std::io::Error::new(std::io::ErrorKind::Other, format!("{}", e))
@@ -79,9 +81,10 @@ pub fn handle_login(request: &HttpRequest) -> Pin<Box<dyn Future<Output = Result
         let user_agent = extract_user_agent(&request.headers);
         
         // Parse JSON body
-        let body = match request.body.as_ref() {
-            Some(body) => body,
-            None => {
+        let body = match request.body.read_limited().await {
+            Ok(Some(body)) => body,
+            Err(response) => return Ok(response),
+            Ok(None) => {
                 let response_body = serde_json::json!({
                     "success": false,
                     "message": "No request body provided"
@@ -92,7 +95,7 @@ pub fn handle_login(request: &HttpRequest) -> Pin<Box<dyn Future<Output = Result
                     .with_json_body(&response_body.to_string()));
             }
         };
-        let login_data: serde_json::Value = match serde_json::from_str(body) {
+        let login_data: serde_json::Value = match serde_json::from_slice(&body) {
             Ok(data) => data,
             Err(_) => {
                 let response_body = serde_json::json!({
@@ -118,7 +121,7 @@ pub fn handle_login(request: &HttpRequest) -> Pin<Box<dyn Future<Output = Result
             let auth_token = generate_secure_token();
             
             // Store token with user agent
-            if let Err(e) = add_token_to_storage(&auth_token, &user_agent).await {
+            if let Err(e) = add_token_to_storage(&auth_token, &user_agent, request.remote_addr.as_deref()).await {
                 eprintln!("Warning: Failed to store token: {}", e);
             }
             
@@ -172,24 +175,8 @@ pub fn handle_login(request: &HttpRequest) -> Pin<Box<dyn Future<Output = Result
 pub fn handle_token_check(request: &HttpRequest) -> Pin<Box<dyn Future<Output = Result<HttpResponse, Box<dyn std::error::Error + Send + Sync>>> + Send + 'static>> {
     let request = request.clone();
     Box::pin(async move {
-        // Extract token from query parameters
-        let query_start = request.path.find('?');
-        let token = if let Some(start) = query_start {
-            let query_string = &request.path[start + 1..];
-            if let Some(token_start) = query_string.find("token=") {
-                let token_value = &query_string[token_start + 6..];
-                // Remove any additional parameters after the token
-                if let Some(ampersand) = token_value.find('&') {
-                    &token_value[..ampersand]
-                } else {
-                    token_value
-                }
-            } else {
-                ""
-            }
-        } else {
-            ""
-        };
+        // Extract token from the Authorization header or query parameters
+        let token = extract_request_token(&request).unwrap_or("");
         
         if token.is_empty() {
             let response_body = serde_json::json!({
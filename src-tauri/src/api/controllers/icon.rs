@@ -1,29 +1,32 @@
-use crate::api::router::{HttpRequest, HttpResponse};
-use crate::config::icons_dir;
+use crate::api::router::{AuthenticatedRequest, HttpResponse};
+use crate::config::{icons_dir, PathProvider};
 use std::fs;
 use std::path::PathBuf;
-use std::sync::OnceLock;
+use std::sync::{Arc, OnceLock};
 
-/// Global app handle for icon operations (used by HTTPS server)
-static APP_HANDLE: OnceLock<tauri::AppHandle> = OnceLock::new();
+/// Global app data paths for icon operations (used by HTTPS server)
+static PATHS: OnceLock<Arc<dyn PathProvider>> = OnceLock::new();
 
-/// Initialize the global app handle for icon operations
-pub fn init_icon_app_handle(app_handle: tauri::AppHandle) {
-    APP_HANDLE.set(app_handle).expect("Failed to initialize icon app handle");
+/// Initialize the global app data paths for icon operations
+pub fn init_icon_paths(paths: Arc<dyn PathProvider>) {
+    if PATHS.set(paths).is_err() {
+        panic!("Failed to initialize icon paths");
+    }
 }
 
-/// Get the global app handle for icon operations
-pub fn get_app_handle() -> Option<&'static tauri::AppHandle> {
-    APP_HANDLE.get()
+/// Get the global app data paths for icon operations
+pub fn get_paths() -> Option<&'static dyn PathProvider> {
+    PATHS.get().map(|paths| paths.as_ref())
 }
 
 /// Handle icon endpoint for serving custom icons by index ID
-pub fn handle_index_icon(request: &HttpRequest) -> std::pin::Pin<Box<dyn std::future::Future<Output = Result<HttpResponse, Box<dyn std::error::Error + Send + Sync>>> + Send + 'static>> {
+pub fn handle_index_icon(request: &AuthenticatedRequest) -> std::pin::Pin<Box<dyn std::future::Future<Output = Result<HttpResponse, Box<dyn std::error::Error + Send + Sync>>> + Send + 'static>> {
     let request = request.clone();
     Box::pin(async move {
         // Extract index_id from the path
         // Expected path format: /api/index/{index_id}/icon
-        let path_parts: Vec<&str> = request.path.split('/').collect();
+        let clean_path = request.path.split('?').next().unwrap_or("");
+        let path_parts: Vec<&str> = clean_path.split('/').collect();
         
         if path_parts.len() < 5 || path_parts[1] != "api" || path_parts[2] != "index" || path_parts[4] != "icon" {
             return Ok(HttpResponse::new(404)
@@ -39,8 +42,8 @@ pub fn handle_index_icon(request: &HttpRequest) -> std::pin::Pin<Box<dyn std::fu
         }
         
         // Get the icons directory path using OS app data directory
-        let app_handle = APP_HANDLE.get().ok_or("App handle not initialized")?;
-        let icons_dir = icons_dir(app_handle)
+        let paths = get_paths().ok_or("Paths not initialized")?;
+        let icons_dir = icons_dir(paths)
             .map_err(|e| {
                 eprintln!("Failed to get icons directory: {}", e);
                 std::io::Error::new(std::io::ErrorKind::Other, format!("{}", e))
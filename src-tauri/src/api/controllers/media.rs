@@ -0,0 +1,368 @@
+use crate::api::controllers::stream::serve_file_with_ranges;
+use crate::api::router::{AuthenticatedRequest, HttpResponse, get_path_segment, get_query_param, decode_query_value, error_response, parse_path_id};
+use crate::api::responses::{VideoItemResponse, VideoVersionResponse, VideoPartResponse};
+use crate::db::models::VideoItem;
+use crate::db::repos::{IndexesRepo, VideoRepo, WatchStateRepo};
+use crate::metadata::{configured_agents, refresh_item_metadata};
+use crate::scanning::{artwork_variant_width, ARTWORK_KINDS};
+use crate::utils::image::{create_resized_to_width, video_artwork_path};
+use crate::utils::search::fts_match_query;
+use serde_json;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::OnceLock;
+use sqlx::SqlitePool;
+
+/// Global database pool for media controller
+static DB_POOL: OnceLock<SqlitePool> = OnceLock::new();
+
+/// Default and maximum page sizes for item listings
+const DEFAULT_PAGE_LIMIT: i64 = 50;
+const MAX_PAGE_LIMIT: i64 = 500;
+
+/// Item types that can be used to filter listings
+const VIDEO_ITEM_TYPES: [&str; 6] = ["video", "movie", "show", "season", "episode", "extra"];
+
+/// Item types search results are grouped by, with the key of their group
+const SEARCH_ITEM_TYPES: [(&str, &str); 3] = [("movie", "movies"), ("show", "shows"), ("episode", "episodes")];
+
+/// Default and maximum number of search results per group
+const DEFAULT_SEARCH_LIMIT: i64 = 20;
+const MAX_SEARCH_LIMIT: i64 = 100;
+
+/// Supported sort orders for listings
+const SORT_ORDERS: [&str; 3] = ["title", "year", "latest_added"];
+
+pub fn init_media_db_pool(db_pool: SqlitePool) {
+    DB_POOL.set(db_pool).expect("Failed to initialize media database pool");
+}
+
+/// Parse the optional `profile_id` query parameter that adds the watch state of a profile to items
+/// Returns Err when the parameter is not a valid ID
+fn parse_profile_param(path: &str) -> Result<Option<i64>, ()> {
+    match get_query_param(path, "profile_id").filter(|id| !id.is_empty()) {
+        None => Ok(None),
+        Some(id) => id.parse::<i64>().map(Some).map_err(|_| ()),
+    }
+}
+
+/// Add the watch state of the requested profile to an item response
+async fn with_profile_watch_state(response: VideoItemResponse, watch_state_repo: &WatchStateRepo, profile_id: Option<i64>, item_id: i64) -> Result<VideoItemResponse, anyhow::Error> {
+    match profile_id {
+        Some(profile_id) => Ok(response.with_watch_state(watch_state_repo.get_watch_state(profile_id, item_id).await?)),
+        None => Ok(response),
+    }
+}
+
+/// Build the responses of a list of items, loading the artwork and watch state of all of them at once
+pub async fn video_item_responses(video_repo: &VideoRepo, watch_state_repo: &WatchStateRepo, profile_id: Option<i64>, items: Vec<VideoItem>) -> Result<Vec<VideoItemResponse>, anyhow::Error> {
+    let item_ids: Vec<i64> = items.iter().map(|item| item.id).collect();
+    let mut artwork_by_item = video_repo.get_video_artwork_by_items(&item_ids).await?;
+    let mut watch_states = match profile_id {
+        Some(profile_id) => Some(watch_state_repo.get_watch_states(profile_id, &item_ids).await?),
+        None => None,
+    };
+
+    Ok(items.into_iter().map(|item| {
+        let item_id = item.id;
+        let response = VideoItemResponse::from(item).with_artwork(artwork_by_item.remove(&item_id).unwrap_or_default());
+        match watch_states.as_mut() {
+            Some(watch_states) => response.with_watch_state(watch_states.remove(&item_id)),
+            None => response,
+        }
+    }).collect())
+}
+
+/// Handle listing items of an index
+/// Expected path format: /api/index/{index_id}/items?type=movie&sort=title&offset=0&limit=50&profile_id=1
+pub fn handle_index_items(request: &AuthenticatedRequest) -> Pin<Box<dyn Future<Output = Result<HttpResponse, Box<dyn std::error::Error + Send + Sync>>> + Send + 'static>> {
+    let request = request.clone();
+    Box::pin(async move {
+        let index_id = match parse_path_id(&request.path, 3) {
+            Some(id) => id,
+            None => return Ok(error_response(400, "Bad Request", "Invalid index ID")),
+        };
+
+        // Validate query parameters
+        let item_type = get_query_param(&request.path, "type").filter(|t| !t.is_empty());
+        if let Some(t) = item_type {
+            if !VIDEO_ITEM_TYPES.contains(&t) {
+                return Ok(error_response(400, "Bad Request", "Invalid item type"));
+            }
+        }
+
+        let sort = get_query_param(&request.path, "sort").filter(|s| !s.is_empty()).unwrap_or("title");
+        if !SORT_ORDERS.contains(&sort) {
+            return Ok(error_response(400, "Bad Request", "Invalid sort order, expected title, year or latest_added"));
+        }
+
+        let offset = match get_query_param(&request.path, "offset").map(|o| o.parse::<i64>()) {
+            None => 0,
+            Some(Ok(offset)) if offset >= 0 => offset,
+            Some(_) => return Ok(error_response(400, "Bad Request", "Invalid offset")),
+        };
+
+        let limit = match get_query_param(&request.path, "limit").map(|l| l.parse::<i64>()) {
+            None => DEFAULT_PAGE_LIMIT,
+            Some(Ok(limit)) if limit > 0 => limit.min(MAX_PAGE_LIMIT),
+            Some(_) => return Ok(error_response(400, "Bad Request", "Invalid limit")),
+        };
+
+        let profile_id = match parse_profile_param(&request.path) {
+            Ok(profile_id) => profile_id,
+            Err(_) => return Ok(error_response(400, "Bad Request", "Invalid profile ID")),
+        };
+
+        let db_pool = DB_POOL.get().ok_or("Database pool not initialized")?.clone();
+        let indexes_repo = IndexesRepo::new(db_pool.clone());
+        let watch_state_repo = WatchStateRepo::new(db_pool.clone());
+        let video_repo = VideoRepo::new(db_pool);
+
+        if indexes_repo.get_index_by_id(index_id).await?.is_none() {
+            return Ok(error_response(404, "Not Found", "Index not found"));
+        }
+
+        let total = video_repo.count_video_items(index_id, item_type).await?;
+        let page = video_repo.get_video_items_page(index_id, item_type, sort, offset, limit).await?;
+        let items = video_item_responses(&video_repo, &watch_state_repo, profile_id, page).await?;
+
+        let response_body = serde_json::json!({
+            "success": true,
+            "items": items,
+            "total": total,
+            "offset": offset,
+            "limit": limit
+        });
+
+        Ok(HttpResponse::new(200)
+            .with_cors()
+            .with_json_body(&response_body.to_string()))
+    })
+}
+
+/// Handle searching video titles and metadata, with results grouped by movie, show and episode
+/// Words match the start of words in titles, alternative titles, cast names and overviews, ignoring case and accents
+/// Expected path format: /api/search?q=matrix&index=1&type=movie&limit=20&profile_id=1
+pub fn handle_search(request: &AuthenticatedRequest) -> Pin<Box<dyn Future<Output = Result<HttpResponse, Box<dyn std::error::Error + Send + Sync>>> + Send + 'static>> {
+    let request = request.clone();
+    Box::pin(async move {
+        let query = decode_query_value(get_query_param(&request.path, "q").unwrap_or(""));
+        let match_query = match fts_match_query(&query) {
+            Some(match_query) => match_query,
+            None => return Ok(error_response(400, "Bad Request", "Missing search query")),
+        };
+
+        let index_id = match get_query_param(&request.path, "index").filter(|i| !i.is_empty()).map(|i| i.parse::<i64>()) {
+            None => None,
+            Some(Ok(index_id)) => Some(index_id),
+            Some(Err(_)) => return Ok(error_response(400, "Bad Request", "Invalid index ID")),
+        };
+
+        let item_type = get_query_param(&request.path, "type").filter(|t| !t.is_empty());
+        if let Some(t) = item_type {
+            if !SEARCH_ITEM_TYPES.iter().any(|(search_type, _)| *search_type == t) {
+                return Ok(error_response(400, "Bad Request", "Invalid item type, expected movie, show or episode"));
+            }
+        }
+
+        let limit = match get_query_param(&request.path, "limit").map(|l| l.parse::<i64>()) {
+            None => DEFAULT_SEARCH_LIMIT,
+            Some(Ok(limit)) if limit > 0 => limit.min(MAX_SEARCH_LIMIT),
+            Some(_) => return Ok(error_response(400, "Bad Request", "Invalid limit")),
+        };
+
+        let profile_id = match parse_profile_param(&request.path) {
+            Ok(profile_id) => profile_id,
+            Err(_) => return Ok(error_response(400, "Bad Request", "Invalid profile ID")),
+        };
+
+        let db_pool = DB_POOL.get().ok_or("Database pool not initialized")?.clone();
+        let watch_state_repo = WatchStateRepo::new(db_pool.clone());
+        let video_repo = VideoRepo::new(db_pool);
+
+        let mut results = serde_json::Map::new();
+        for (search_type, group) in SEARCH_ITEM_TYPES {
+            let mut items = Vec::new();
+            if item_type.is_none_or(|t| t == search_type) {
+                let matches = video_repo.search_video_items(&match_query, index_id, search_type, limit).await?;
+                items = video_item_responses(&video_repo, &watch_state_repo, profile_id, matches).await?;
+            }
+            results.insert(group.to_string(), serde_json::to_value(items)?);
+        }
+
+        let response_body = serde_json::json!({
+            "success": true,
+            "query": query,
+            "results": results
+        });
+
+        Ok(HttpResponse::new(200)
+            .with_cors()
+            .with_json_body(&response_body.to_string()))
+    })
+}
+
+/// Handle fetching a single item with its children, versions, parts, streams and subtitles
+/// Expected path format: /api/item/{item_id}?profile_id=1
+pub fn handle_item_details(request: &AuthenticatedRequest) -> Pin<Box<dyn Future<Output = Result<HttpResponse, Box<dyn std::error::Error + Send + Sync>>> + Send + 'static>> {
+    let request = request.clone();
+    Box::pin(async move {
+        let item_id = match parse_path_id(&request.path, 3) {
+            Some(id) => id,
+            None => return Ok(error_response(400, "Bad Request", "Invalid item ID")),
+        };
+
+        let profile_id = match parse_profile_param(&request.path) {
+            Ok(profile_id) => profile_id,
+            Err(_) => return Ok(error_response(400, "Bad Request", "Invalid profile ID")),
+        };
+
+        let db_pool = DB_POOL.get().ok_or("Database pool not initialized")?.clone();
+        let watch_state_repo = WatchStateRepo::new(db_pool.clone());
+        let video_repo = VideoRepo::new(db_pool);
+
+        let item = match video_repo.get_video_item_by_id(item_id).await? {
+            Some(item) => item,
+            None => return Ok(error_response(404, "Not Found", "Item not found")),
+        };
+
+        // Children are ordered by season/episode number, then title
+        let children = video_repo.get_video_item_children(item.id).await?;
+        let children = video_item_responses(&video_repo, &watch_state_repo, profile_id, children).await?;
+
+        let mut versions = Vec::new();
+        for version in video_repo.get_video_versions_by_item(item.id).await? {
+            let mut parts = Vec::new();
+            for part in video_repo.get_video_parts_by_version(version.id).await? {
+                let streams = video_repo.get_video_streams_by_part(part.id).await?;
+                let subtitles = video_repo.get_subtitles_by_part(part.id).await?;
+                parts.push(VideoPartResponse::new(part, streams, subtitles));
+            }
+            versions.push(VideoVersionResponse::new(version, parts));
+        }
+
+        let item_id = item.id;
+        let artwork = video_repo.get_video_artwork_by_item(item_id).await?;
+        let item_response = with_profile_watch_state(VideoItemResponse::from(item).with_artwork(artwork), &watch_state_repo, profile_id, item_id).await?;
+        let response_body = serde_json::json!({
+            "success": true,
+            "item": item_response,
+            "children": children,
+            "versions": versions
+        });
+
+        Ok(HttpResponse::new(200)
+            .with_cors()
+            .with_json_body(&response_body.to_string()))
+    })
+}
+
+/// Handle serving the artwork of an item, optionally resized to a width (`?w=`)
+/// Posters and backdrops fall back to those of the parent item (episodes use the season, then the show)
+/// Expected path format: /api/item/{item_id}/artwork/{kind}?w=320
+pub fn handle_item_artwork(request: &AuthenticatedRequest) -> Pin<Box<dyn Future<Output = Result<HttpResponse, Box<dyn std::error::Error + Send + Sync>>> + Send + 'static>> {
+    let request = request.clone();
+    Box::pin(async move {
+        let item_id = match parse_path_id(&request.path, 3) {
+            Some(id) => id,
+            None => return Ok(error_response(400, "Bad Request", "Invalid item ID")),
+        };
+
+        let kind = match get_path_segment(&request.path, 5).filter(|kind| ARTWORK_KINDS.contains(kind)) {
+            Some(kind) => kind.to_string(),
+            None => return Ok(error_response(400, "Bad Request", "Invalid artwork kind, expected poster, fanart or thumb")),
+        };
+
+        let requested_width = match get_query_param(&request.path, "w").filter(|w| !w.is_empty()).map(|w| w.parse::<u32>()) {
+            None => None,
+            Some(Ok(width)) if width > 0 => Some(width),
+            Some(_) => return Ok(error_response(400, "Bad Request", "Invalid width")),
+        };
+
+        let video_repo = VideoRepo::new(DB_POOL.get().ok_or("Database pool not initialized")?.clone());
+
+        let mut item = match video_repo.get_video_item_by_id(item_id).await? {
+            Some(item) => item,
+            None => return Ok(error_response(404, "Not Found", "Item not found")),
+        };
+
+        let artwork = loop {
+            if let Some(artwork) = video_repo.get_video_artwork(item.id, &kind).await? {
+                break artwork;
+            }
+            let parent = match item.parent_id.filter(|_| kind != "thumb") {
+                Some(parent_id) => video_repo.get_video_item_by_id(parent_id).await?,
+                None => None,
+            };
+            item = match parent {
+                Some(parent) => parent,
+                None => return Ok(error_response(404, "Not Found", "Item has no artwork of this kind")),
+            };
+        };
+
+        let original_path = match video_artwork_path(artwork.item_id, &kind, None) {
+            Some(path) => path,
+            None => return Ok(error_response(404, "Not Found", "Item has no artwork of this kind")),
+        };
+
+        // Variants are created on first request and removed when the artwork changes
+        let variant_width = requested_width.and_then(|width| artwork_variant_width(width, artwork.width));
+        let path = match variant_width.and_then(|width| video_artwork_path(artwork.item_id, &kind, Some(width)).map(|path| (width, path))) {
+            Some((width, variant_path)) => {
+                if !variant_path.is_file() {
+                    let (source, destination) = (original_path.clone(), variant_path.clone());
+                    tokio::task::spawn_blocking(move || create_resized_to_width(&source, &destination, width)).await??;
+                }
+                variant_path
+            }
+            None => original_path,
+        };
+
+        serve_file_with_ranges(&request, &path, "image/jpeg").await
+    })
+}
+
+/// Handle matching a movie or show with the metadata agents again (e.g. after fixing its folder name)
+/// Expected path format: /api/item/{item_id}/metadata/refresh
+pub fn handle_refresh_item_metadata(request: &AuthenticatedRequest) -> Pin<Box<dyn Future<Output = Result<HttpResponse, Box<dyn std::error::Error + Send + Sync>>> + Send + 'static>> {
+    let request = request.clone();
+    Box::pin(async move {
+        let item_id = match parse_path_id(&request.path, 3) {
+            Some(id) => id,
+            None => return Ok(error_response(400, "Bad Request", "Invalid item ID")),
+        };
+
+        let video_repo = VideoRepo::new(DB_POOL.get().ok_or("Database pool not initialized")?.clone());
+
+        let item = match video_repo.get_video_item_by_id(item_id).await? {
+            Some(item) => item,
+            None => return Ok(error_response(404, "Not Found", "Item not found")),
+        };
+        if item.r#type != "movie" && item.r#type != "show" {
+            return Ok(error_response(400, "Bad Request", "Only movies and shows can be matched"));
+        }
+
+        let agents = configured_agents();
+        if agents.is_empty() {
+            return Ok(error_response(503, "Service Unavailable", "No metadata agents are configured"));
+        }
+
+        let matched = match refresh_item_metadata(&video_repo, &agents, &item).await {
+            Ok(matched) => matched,
+            Err(e) => return Ok(error_response(502, "Bad Gateway", &format!("Metadata provider failed: {}", e))),
+        };
+        video_repo.update_video_item_metadata_refreshed(item.id, Some(chrono::Utc::now().timestamp())).await?;
+
+        let item = video_repo.get_video_item_by_id(item.id).await?.ok_or("Item disappeared")?;
+        let artwork = video_repo.get_video_artwork_by_item(item.id).await?;
+        let response_body = serde_json::json!({
+            "success": true,
+            "matched": matched,
+            "item": VideoItemResponse::from(item).with_artwork(artwork)
+        });
+
+        Ok(HttpResponse::new(200)
+            .with_cors()
+            .with_json_body(&response_body.to_string()))
+    })
+}
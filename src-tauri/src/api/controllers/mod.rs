@@ -2,8 +2,20 @@ pub mod auth;
 pub mod static_files;
 pub mod api;
 pub mod icon;
+pub mod stream;
+pub mod media;
+pub mod transcode;
+pub mod photos;
+pub mod music;
+pub mod watch;
 
 pub use auth::{handle_login, handle_token_check};
 pub use static_files::handle_static_files;
 pub use api::handle_ping;
 pub use icon::handle_index_icon;
+pub use stream::{handle_part_stream, handle_subtitle};
+pub use media::{handle_index_items, handle_search, handle_item_details, handle_item_artwork, handle_refresh_item_metadata};
+pub use transcode::{handle_start_transcode, handle_transcode_file, handle_stop_transcode};
+pub use photos::{handle_index_photos, handle_index_albums, handle_photo_original, handle_photo_thumbnail};
+pub use music::{handle_index_music, handle_music_item_details, handle_music_cover, handle_track_stream};
+pub use watch::{handle_report_progress, handle_mark_watched, handle_watch_hub};
@@ -0,0 +1,176 @@
+use crate::api::controllers::stream::{get_media_content_type, serve_file_with_ranges};
+use crate::api::router::{AuthenticatedRequest, HttpResponse, get_query_param, error_response, parse_path_id};
+use crate::api::responses::MusicItemResponse;
+use crate::db::repos::{IndexesRepo, MusicRepo};
+use crate::utils::activity::record_playback_activity;
+use crate::utils::image::music_cover_path;
+use serde_json;
+use std::future::Future;
+use std::path::Path;
+use std::pin::Pin;
+use std::sync::OnceLock;
+use sqlx::SqlitePool;
+
+/// Global database pool for music controller
+static DB_POOL: OnceLock<SqlitePool> = OnceLock::new();
+
+/// Default and maximum page sizes for music listings
+const DEFAULT_PAGE_LIMIT: i64 = 50;
+const MAX_PAGE_LIMIT: i64 = 500;
+
+/// Item types that can be used to filter listings
+const MUSIC_ITEM_TYPES: [&str; 3] = ["artist", "album", "track"];
+
+pub fn init_music_db_pool(db_pool: SqlitePool) {
+    DB_POOL.set(db_pool).expect("Failed to initialize music database pool");
+}
+
+/// Handle listing artists, albums or tracks of a music index
+/// Expected path format: /api/index/{index_id}/music?type=artist&offset=0&limit=50
+pub fn handle_index_music(request: &AuthenticatedRequest) -> Pin<Box<dyn Future<Output = Result<HttpResponse, Box<dyn std::error::Error + Send + Sync>>> + Send + 'static>> {
+    let request = request.clone();
+    Box::pin(async move {
+        let index_id = match parse_path_id(&request.path, 3) {
+            Some(id) => id,
+            None => return Ok(error_response(400, "Bad Request", "Invalid index ID")),
+        };
+
+        // Validate query parameters
+        let item_type = get_query_param(&request.path, "type").filter(|t| !t.is_empty());
+        if let Some(t) = item_type {
+            if !MUSIC_ITEM_TYPES.contains(&t) {
+                return Ok(error_response(400, "Bad Request", "Invalid item type, expected artist, album or track"));
+            }
+        }
+
+        let offset = match get_query_param(&request.path, "offset").map(|o| o.parse::<i64>()) {
+            None => 0,
+            Some(Ok(offset)) if offset >= 0 => offset,
+            Some(_) => return Ok(error_response(400, "Bad Request", "Invalid offset")),
+        };
+
+        let limit = match get_query_param(&request.path, "limit").map(|l| l.parse::<i64>()) {
+            None => DEFAULT_PAGE_LIMIT,
+            Some(Ok(limit)) if limit > 0 => limit.min(MAX_PAGE_LIMIT),
+            Some(_) => return Ok(error_response(400, "Bad Request", "Invalid limit")),
+        };
+
+        let db_pool = DB_POOL.get().ok_or("Database pool not initialized")?.clone();
+        let indexes_repo = IndexesRepo::new(db_pool.clone());
+        let music_repo = MusicRepo::new(db_pool);
+
+        if indexes_repo.get_index_by_id(index_id).await?.is_none() {
+            return Ok(error_response(404, "Not Found", "Index not found"));
+        }
+
+        let total = music_repo.count_music_items(index_id, item_type).await?;
+        let mut items = Vec::new();
+        for item in music_repo.get_music_items_page(index_id, item_type, offset, limit).await? {
+            let file = if item.r#type == "track" { music_repo.get_music_file_by_item(item.id).await? } else { None };
+            items.push(MusicItemResponse::new(item, file));
+        }
+
+        let response_body = serde_json::json!({
+            "success": true,
+            "items": items,
+            "total": total,
+            "offset": offset,
+            "limit": limit
+        });
+
+        Ok(HttpResponse::new(200)
+            .with_cors()
+            .with_json_body(&response_body.to_string()))
+    })
+}
+
+/// Handle fetching an artist with its albums, an album with its tracks, or a single track
+/// Expected path format: /api/music/{item_id}
+pub fn handle_music_item_details(request: &AuthenticatedRequest) -> Pin<Box<dyn Future<Output = Result<HttpResponse, Box<dyn std::error::Error + Send + Sync>>> + Send + 'static>> {
+    let request = request.clone();
+    Box::pin(async move {
+        let item_id = match parse_path_id(&request.path, 3) {
+            Some(id) => id,
+            None => return Ok(error_response(400, "Bad Request", "Invalid item ID")),
+        };
+
+        let music_repo = MusicRepo::new(DB_POOL.get().ok_or("Database pool not initialized")?.clone());
+
+        let item = match music_repo.get_music_item_by_id(item_id).await? {
+            Some(item) => item,
+            None => return Ok(error_response(404, "Not Found", "Item not found")),
+        };
+
+        // Albums are ordered by year, tracks by disc and track number
+        let mut children = Vec::new();
+        for child in music_repo.get_music_item_children(item.id).await? {
+            let file = if child.r#type == "track" { music_repo.get_music_file_by_item(child.id).await? } else { None };
+            children.push(MusicItemResponse::new(child, file));
+        }
+
+        let file = if item.r#type == "track" { music_repo.get_music_file_by_item(item.id).await? } else { None };
+        let response_body = serde_json::json!({
+            "success": true,
+            "item": MusicItemResponse::new(item, file),
+            "children": children
+        });
+
+        Ok(HttpResponse::new(200)
+            .with_cors()
+            .with_json_body(&response_body.to_string()))
+    })
+}
+
+/// Handle serving the cover art of an album (tracks use the cover of their album)
+/// Expected path format: /api/music/{item_id}/cover
+pub fn handle_music_cover(request: &AuthenticatedRequest) -> Pin<Box<dyn Future<Output = Result<HttpResponse, Box<dyn std::error::Error + Send + Sync>>> + Send + 'static>> {
+    let request = request.clone();
+    Box::pin(async move {
+        let item_id = match parse_path_id(&request.path, 3) {
+            Some(id) => id,
+            None => return Ok(error_response(400, "Bad Request", "Invalid item ID")),
+        };
+
+        let music_repo = MusicRepo::new(DB_POOL.get().ok_or("Database pool not initialized")?.clone());
+
+        let mut item = match music_repo.get_music_item_by_id(item_id).await? {
+            Some(item) => item,
+            None => return Ok(error_response(404, "Not Found", "Item not found")),
+        };
+
+        if let (Some(parent_id), "track") = (item.parent_id, item.r#type.as_str()) {
+            if let Some(album) = music_repo.get_music_item_by_id(parent_id).await? {
+                item = album;
+            }
+        }
+
+        let cover_path = match music_cover_path(item.id).filter(|_| item.has_cover != 0) {
+            Some(cover_path) => cover_path,
+            None => return Ok(error_response(404, "Not Found", "Item has no cover")),
+        };
+
+        serve_file_with_ranges(&request, &cover_path, "image/jpeg").await
+    })
+}
+
+/// Handle streaming the audio file of a track
+/// Expected path format: /api/track/{track_id}/stream
+pub fn handle_track_stream(request: &AuthenticatedRequest) -> Pin<Box<dyn Future<Output = Result<HttpResponse, Box<dyn std::error::Error + Send + Sync>>> + Send + 'static>> {
+    let request = request.clone();
+    Box::pin(async move {
+        let track_id = match parse_path_id(&request.path, 3) {
+            Some(id) => id,
+            None => return Ok(error_response(400, "Bad Request", "Invalid track ID")),
+        };
+
+        let music_repo = MusicRepo::new(DB_POOL.get().ok_or("Database pool not initialized")?.clone());
+        let music_file = match music_repo.get_music_file_by_item(track_id).await? {
+            Some(music_file) => music_file,
+            None => return Ok(error_response(404, "Not Found", "Track not found")),
+        };
+
+        record_playback_activity();
+        let path = Path::new(&music_file.path);
+        serve_file_with_ranges(&request, path, get_media_content_type(path)).await
+    })
+}
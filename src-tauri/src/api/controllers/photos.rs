@@ -0,0 +1,179 @@
+use crate::api::controllers::stream::{get_media_content_type, serve_file_with_ranges};
+use crate::api::router::{AuthenticatedRequest, HttpResponse, get_query_param, error_response, parse_path_id};
+use crate::api::responses::{PhotoAlbumResponse, PhotoItemResponse};
+use crate::db::repos::{IndexesRepo, PhotoRepo};
+use crate::utils::image::photo_thumbnail_path;
+use serde_json;
+use std::collections::HashMap;
+use std::future::Future;
+use std::path::Path;
+use std::pin::Pin;
+use std::sync::OnceLock;
+use sqlx::SqlitePool;
+
+/// Global database pool for photos controller
+static DB_POOL: OnceLock<SqlitePool> = OnceLock::new();
+
+/// Default and maximum page sizes for photo listings
+const DEFAULT_PAGE_LIMIT: i64 = 100;
+const MAX_PAGE_LIMIT: i64 = 1000;
+
+pub fn init_photos_db_pool(db_pool: SqlitePool) {
+    DB_POOL.set(db_pool).expect("Failed to initialize photos database pool");
+}
+
+/// Handle listing photos of an index by date taken, optionally limited to one album
+/// Expected path format: /api/index/{index_id}/photos?album=12&offset=0&limit=100
+pub fn handle_index_photos(request: &AuthenticatedRequest) -> Pin<Box<dyn Future<Output = Result<HttpResponse, Box<dyn std::error::Error + Send + Sync>>> + Send + 'static>> {
+    let request = request.clone();
+    Box::pin(async move {
+        let index_id = match parse_path_id(&request.path, 3) {
+            Some(id) => id,
+            None => return Ok(error_response(400, "Bad Request", "Invalid index ID")),
+        };
+
+        // Validate query parameters
+        let album_id = match get_query_param(&request.path, "album").filter(|a| !a.is_empty()).map(|a| a.parse::<i64>()) {
+            None => None,
+            Some(Ok(album_id)) => Some(album_id),
+            Some(Err(_)) => return Ok(error_response(400, "Bad Request", "Invalid album ID")),
+        };
+
+        let offset = match get_query_param(&request.path, "offset").map(|o| o.parse::<i64>()) {
+            None => 0,
+            Some(Ok(offset)) if offset >= 0 => offset,
+            Some(_) => return Ok(error_response(400, "Bad Request", "Invalid offset")),
+        };
+
+        let limit = match get_query_param(&request.path, "limit").map(|l| l.parse::<i64>()) {
+            None => DEFAULT_PAGE_LIMIT,
+            Some(Ok(limit)) if limit > 0 => limit.min(MAX_PAGE_LIMIT),
+            Some(_) => return Ok(error_response(400, "Bad Request", "Invalid limit")),
+        };
+
+        let db_pool = DB_POOL.get().ok_or("Database pool not initialized")?.clone();
+        let indexes_repo = IndexesRepo::new(db_pool.clone());
+        let photo_repo = PhotoRepo::new(db_pool);
+
+        if indexes_repo.get_index_by_id(index_id).await?.is_none() {
+            return Ok(error_response(404, "Not Found", "Index not found"));
+        }
+
+        if let Some(album_id) = album_id {
+            match photo_repo.get_photo_album_by_id(album_id).await? {
+                Some(album) if album.index_id == index_id => {}
+                _ => return Ok(error_response(404, "Not Found", "Album not found")),
+            }
+        }
+
+        let total = photo_repo.count_photo_items(index_id, album_id).await?;
+        let photos: Vec<PhotoItemResponse> = photo_repo.get_photo_items_page(index_id, album_id, offset, limit).await?
+            .into_iter()
+            .map(PhotoItemResponse::from)
+            .collect();
+
+        let response_body = serde_json::json!({
+            "success": true,
+            "photos": photos,
+            "total": total,
+            "offset": offset,
+            "limit": limit
+        });
+
+        Ok(HttpResponse::new(200)
+            .with_cors()
+            .with_json_body(&response_body.to_string()))
+    })
+}
+
+/// Handle listing the albums of an index with their photo counts and covers
+/// Expected path format: /api/index/{index_id}/albums
+pub fn handle_index_albums(request: &AuthenticatedRequest) -> Pin<Box<dyn Future<Output = Result<HttpResponse, Box<dyn std::error::Error + Send + Sync>>> + Send + 'static>> {
+    let request = request.clone();
+    Box::pin(async move {
+        let index_id = match parse_path_id(&request.path, 3) {
+            Some(id) => id,
+            None => return Ok(error_response(400, "Bad Request", "Invalid index ID")),
+        };
+
+        let db_pool = DB_POOL.get().ok_or("Database pool not initialized")?.clone();
+        let indexes_repo = IndexesRepo::new(db_pool.clone());
+        let photo_repo = PhotoRepo::new(db_pool);
+
+        if indexes_repo.get_index_by_id(index_id).await?.is_none() {
+            return Ok(error_response(404, "Not Found", "Index not found"));
+        }
+
+        let stats: HashMap<i64, (i64, i64)> = photo_repo.get_photo_album_stats(index_id).await?
+            .into_iter()
+            .map(|(album_id, photo_count, cover_photo_id)| (album_id, (photo_count, cover_photo_id)))
+            .collect();
+
+        // Albums that only contain other albums have no photos of their own
+        let albums: Vec<PhotoAlbumResponse> = photo_repo.get_photo_albums(index_id).await?
+            .into_iter()
+            .map(|album| {
+                let (photo_count, cover_photo_id) = match stats.get(&album.id) {
+                    Some((photo_count, cover_photo_id)) => (*photo_count, Some(*cover_photo_id)),
+                    None => (0, None),
+                };
+                PhotoAlbumResponse::new(album, photo_count, cover_photo_id)
+            })
+            .collect();
+
+        let response_body = serde_json::json!({
+            "success": true,
+            "albums": albums
+        });
+
+        Ok(HttpResponse::new(200)
+            .with_cors()
+            .with_json_body(&response_body.to_string()))
+    })
+}
+
+/// Handle serving the original file of a photo
+/// Expected path format: /api/photo/{photo_id}/original
+pub fn handle_photo_original(request: &AuthenticatedRequest) -> Pin<Box<dyn Future<Output = Result<HttpResponse, Box<dyn std::error::Error + Send + Sync>>> + Send + 'static>> {
+    let request = request.clone();
+    Box::pin(async move {
+        let photo_id = match parse_path_id(&request.path, 3) {
+            Some(id) => id,
+            None => return Ok(error_response(400, "Bad Request", "Invalid photo ID")),
+        };
+
+        let photo_repo = PhotoRepo::new(DB_POOL.get().ok_or("Database pool not initialized")?.clone());
+        let photo = match photo_repo.get_photo_item_by_id(photo_id).await? {
+            Some(photo) => photo,
+            None => return Ok(error_response(404, "Not Found", "Photo not found")),
+        };
+
+        let path = Path::new(&photo.path);
+        serve_file_with_ranges(&request, path, get_media_content_type(path)).await
+    })
+}
+
+/// Handle serving the thumbnail of a photo
+/// Expected path format: /api/photo/{photo_id}/thumbnail
+pub fn handle_photo_thumbnail(request: &AuthenticatedRequest) -> Pin<Box<dyn Future<Output = Result<HttpResponse, Box<dyn std::error::Error + Send + Sync>>> + Send + 'static>> {
+    let request = request.clone();
+    Box::pin(async move {
+        let photo_id = match parse_path_id(&request.path, 3) {
+            Some(id) => id,
+            None => return Ok(error_response(400, "Bad Request", "Invalid photo ID")),
+        };
+
+        let photo_repo = PhotoRepo::new(DB_POOL.get().ok_or("Database pool not initialized")?.clone());
+        let photo = match photo_repo.get_photo_item_by_id(photo_id).await? {
+            Some(photo) => photo,
+            None => return Ok(error_response(404, "Not Found", "Photo not found")),
+        };
+
+        let thumbnail_path = match photo.fast_hash.as_deref().filter(|_| photo.has_thumbnail != 0).and_then(photo_thumbnail_path) {
+            Some(thumbnail_path) => thumbnail_path,
+            None => return Ok(error_response(404, "Not Found", "Photo has no thumbnail")),
+        };
+
+        serve_file_with_ranges(&request, &thumbnail_path, "image/jpeg").await
+    })
+}
@@ -61,13 +61,10 @@ pub fn handle_static_files(request: &HttpRequest) -> Pin<Box<dyn Future<Output =
         if request.method == "GET" && !request.path.starts_with("/api/") {
             match serve_static_file(&request.path).await {
                 Ok((content, content_type)) => {
-                    // Convert content to string for response
-                    let content_str = String::from_utf8_lossy(&content);
-                    
                     Ok(HttpResponse::new(200)
                         .with_cors()
                         .with_header("Content-Type", content_type)
-                        .with_body(&content_str))
+                        .with_binary_body(content))
                 }
                 Err(_) => {
                     // File not found, return 404
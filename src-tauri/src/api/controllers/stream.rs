@@ -0,0 +1,284 @@
+use crate::api::router::{AuthenticatedRequest, HttpRequest, HttpResponse, error_response, get_header, get_query_param, parse_path_id};
+use crate::db::repos::VideoRepo;
+use crate::utils::activity::record_playback_activity;
+use crate::utils::subtitles::srt_to_vtt;
+use chrono::{DateTime, Utc};
+use std::future::Future;
+use std::path::Path;
+use std::pin::Pin;
+use std::sync::OnceLock;
+use sqlx::SqlitePool;
+
+/// Global database pool for stream controller
+static DB_POOL: OnceLock<SqlitePool> = OnceLock::new();
+
+pub fn init_stream_db_pool(db_pool: SqlitePool) {
+    DB_POOL.set(db_pool).expect("Failed to initialize stream database pool");
+}
+
+/// Requested byte range resolved against the size of the file
+#[derive(Debug, Clone, PartialEq)]
+pub enum ByteRange {
+    /// No (usable) range requested, serve the whole file
+    Full,
+    /// Inclusive byte range to serve
+    Partial { start: u64, end: u64 },
+    /// Range lies outside of the file
+    Unsatisfiable,
+}
+
+/// Parse a `Range` header value (e.g. "bytes=0-1023", "bytes=500-", "bytes=-500")
+/// Only single ranges are supported; multi-range requests fall back to the full file
+pub fn parse_range_header(value: &str, file_size: u64) -> ByteRange {
+    let spec = match value.trim().strip_prefix("bytes=") {
+        Some(spec) => spec.trim(),
+        None => return ByteRange::Full,
+    };
+
+    if spec.contains(',') {
+        return ByteRange::Full;
+    }
+
+    let (start_str, end_str) = match spec.split_once('-') {
+        Some(parts) => (parts.0.trim(), parts.1.trim()),
+        None => return ByteRange::Full,
+    };
+
+    if start_str.is_empty() {
+        // Suffix range: last N bytes of the file
+        let suffix_len = match end_str.parse::<u64>() {
+            Ok(len) => len,
+            Err(_) => return ByteRange::Full,
+        };
+        if suffix_len == 0 || file_size == 0 {
+            return ByteRange::Unsatisfiable;
+        }
+        let start = file_size.saturating_sub(suffix_len);
+        return ByteRange::Partial { start, end: file_size - 1 };
+    }
+
+    let start = match start_str.parse::<u64>() {
+        Ok(start) => start,
+        Err(_) => return ByteRange::Full,
+    };
+
+    if start >= file_size {
+        return ByteRange::Unsatisfiable;
+    }
+
+    let end = if end_str.is_empty() {
+        file_size - 1
+    } else {
+        match end_str.parse::<u64>() {
+            Ok(end) if end >= start => end.min(file_size - 1),
+            _ => return ByteRange::Full,
+        }
+    };
+
+    ByteRange::Partial { start, end }
+}
+
+/// Get content type for a media file based on its extension
+pub fn get_media_content_type(path: &Path) -> &'static str {
+    match path.extension().and_then(|ext| ext.to_str()).map(|ext| ext.to_lowercase()).as_deref() {
+        Some("mp4") | Some("m4v") => "video/mp4",
+        Some("mkv") => "video/x-matroska",
+        Some("webm") => "video/webm",
+        Some("avi") => "video/x-msvideo",
+        Some("mov") => "video/quicktime",
+        Some("wmv") => "video/x-ms-wmv",
+        Some("flv") => "video/x-flv",
+        Some("ts") | Some("m2ts") => "video/mp2t",
+        Some("mpeg") | Some("mpg") => "video/mpeg",
+        Some("jpg") | Some("jpeg") => "image/jpeg",
+        Some("png") => "image/png",
+        Some("gif") => "image/gif",
+        Some("webp") => "image/webp",
+        Some("tif") | Some("tiff") => "image/tiff",
+        Some("bmp") => "image/bmp",
+        Some("heic") => "image/heic",
+        Some("heif") => "image/heif",
+        Some("mp3") => "audio/mpeg",
+        Some("flac") => "audio/flac",
+        Some("ogg") | Some("oga") | Some("opus") => "audio/ogg",
+        Some("m4a") | Some("m4b") => "audio/mp4",
+        Some("aac") => "audio/aac",
+        Some("wav") => "audio/wav",
+        Some("srt") => "application/x-subrip",
+        Some("ass") | Some("ssa") => "text/x-ssa",
+        Some("vtt") => "text/vtt",
+        _ => "application/octet-stream",
+    }
+}
+
+/// Format a timestamp as an HTTP date (RFC 7231 IMF-fixdate)
+fn format_http_date(timestamp: i64) -> String {
+    DateTime::<Utc>::from_timestamp(timestamp, 0)
+        .unwrap_or_else(Utc::now)
+        .format("%a, %d %b %Y %H:%M:%S GMT")
+        .to_string()
+}
+
+/// Serve a file from disk honoring `Range` and `If-Range` request headers
+/// The file is streamed from disk, so only the requested bytes are ever read
+pub async fn serve_file_with_ranges(
+    request: &HttpRequest,
+    path: &Path,
+    content_type: &str,
+) -> Result<HttpResponse, Box<dyn std::error::Error + Send + Sync>> {
+    let metadata = match tokio::fs::metadata(path).await {
+        Ok(metadata) if metadata.is_file() => metadata,
+        _ => {
+            return Ok(HttpResponse::new(404)
+                .with_cors()
+                .with_body("File not found"));
+        }
+    };
+
+    let file_size = metadata.len();
+    let mtime = metadata.modified()?
+        .duration_since(std::time::UNIX_EPOCH)?
+        .as_secs() as i64;
+    let etag = format!("\"{:x}-{:x}\"", file_size, mtime);
+    let last_modified = format_http_date(mtime);
+
+    // If-Range: only honor the range when the validator still matches the file
+    let range_allowed = match get_header(&request.headers, "if-range") {
+        Some(validator) => validator == etag || validator == last_modified,
+        None => true,
+    };
+
+    let range = match get_header(&request.headers, "range") {
+        Some(value) if range_allowed => parse_range_header(value, file_size),
+        _ => ByteRange::Full,
+    };
+
+    let response = match range {
+        ByteRange::Full => {
+            HttpResponse::new(200)
+                .with_file_body(path.to_path_buf(), 0, file_size)
+        }
+        ByteRange::Partial { start, end } => {
+            HttpResponse::new(206)
+                .with_header("Content-Range", &format!("bytes {}-{}/{}", start, end, file_size))
+                .with_file_body(path.to_path_buf(), start, end - start + 1)
+        }
+        ByteRange::Unsatisfiable => {
+            return Ok(HttpResponse::new(416)
+                .with_cors()
+                .with_header("Content-Range", &format!("bytes */{}", file_size))
+                .with_header("Accept-Ranges", "bytes"));
+        }
+    };
+
+    Ok(response
+        .with_cors()
+        .with_header("Access-Control-Expose-Headers", "Content-Range, Content-Length, Accept-Ranges")
+        .with_header("Content-Type", content_type)
+        .with_header("Accept-Ranges", "bytes")
+        .with_header("ETag", &etag)
+        .with_header("Last-Modified", &last_modified))
+}
+
+/// Handle streaming a video part by ID
+/// Expected path format: /api/part/{part_id}/stream
+pub fn handle_part_stream(request: &AuthenticatedRequest) -> Pin<Box<dyn Future<Output = Result<HttpResponse, Box<dyn std::error::Error + Send + Sync>>> + Send + 'static>> {
+    let request = request.clone();
+    Box::pin(async move {
+        let part_id = match parse_path_id(&request.path, 3) {
+            Some(id) => id,
+            None => return Ok(error_response(400, "Bad Request", "Invalid part ID")),
+        };
+
+        let video_repo = VideoRepo::new(DB_POOL.get().ok_or("Database pool not initialized")?.clone());
+        let video_part = match video_repo.get_video_part_by_id(part_id).await? {
+            Some(part) => part,
+            None => {
+                return Ok(HttpResponse::new(404)
+                    .with_cors()
+                    .with_body("Part not found"));
+            }
+        };
+
+        record_playback_activity();
+        let path = Path::new(&video_part.path);
+        serve_file_with_ranges(&request, path, get_media_content_type(path)).await
+    })
+}
+
+/// Handle serving a subtitle by ID, as stored or converted to WebVTT for browser players (`?format=vtt`)
+/// Expected path format: /api/subtitle/{subtitle_id}?format=vtt
+pub fn handle_subtitle(request: &AuthenticatedRequest) -> Pin<Box<dyn Future<Output = Result<HttpResponse, Box<dyn std::error::Error + Send + Sync>>> + Send + 'static>> {
+    let request = request.clone();
+    Box::pin(async move {
+        let subtitle_id = match parse_path_id(&request.path, 3) {
+            Some(id) => id,
+            None => return Ok(error_response(400, "Bad Request", "Invalid subtitle ID")),
+        };
+
+        let video_repo = VideoRepo::new(DB_POOL.get().ok_or("Database pool not initialized")?.clone());
+        let subtitle = match video_repo.get_subtitle_by_id(subtitle_id).await? {
+            Some(subtitle) => subtitle,
+            None => {
+                return Ok(HttpResponse::new(404)
+                    .with_cors()
+                    .with_body("Subtitle not found"));
+            }
+        };
+
+        let path = Path::new(&subtitle.path);
+        let wants_vtt = get_query_param(&request.path, "format") == Some("vtt");
+        if !wants_vtt || subtitle.format == "vtt" {
+            return serve_file_with_ranges(&request, path, get_media_content_type(path)).await;
+        }
+        if subtitle.format != "srt" {
+            return Ok(HttpResponse::new(415)
+                .with_cors()
+                .with_body("Unsupported Media Type: Only SRT subtitles can be converted to WebVTT"));
+        }
+
+        // Converted on the fly, SRT files are small; non-UTF-8 files are read lossily
+        let content = match tokio::fs::read(path).await {
+            Ok(content) => content,
+            Err(_) => {
+                return Ok(HttpResponse::new(404)
+                    .with_cors()
+                    .with_body("File not found"));
+            }
+        };
+
+        Ok(HttpResponse::new(200)
+            .with_cors()
+            .with_header("Content-Type", "text/vtt; charset=utf-8")
+            .with_body(&srt_to_vtt(&String::from_utf8_lossy(&content))))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_range_header() {
+        assert_eq!(parse_range_header("bytes=0-99", 1000), ByteRange::Partial { start: 0, end: 99 });
+        assert_eq!(parse_range_header("bytes=500-", 1000), ByteRange::Partial { start: 500, end: 999 });
+        assert_eq!(parse_range_header("bytes=-100", 1000), ByteRange::Partial { start: 900, end: 999 });
+        assert_eq!(parse_range_header("bytes=-5000", 1000), ByteRange::Partial { start: 0, end: 999 });
+        assert_eq!(parse_range_header("bytes=900-5000", 1000), ByteRange::Partial { start: 900, end: 999 });
+    }
+
+    #[test]
+    fn test_parse_range_header_unsatisfiable() {
+        assert_eq!(parse_range_header("bytes=1000-", 1000), ByteRange::Unsatisfiable);
+        assert_eq!(parse_range_header("bytes=-0", 1000), ByteRange::Unsatisfiable);
+        assert_eq!(parse_range_header("bytes=0-", 0), ByteRange::Unsatisfiable);
+    }
+
+    #[test]
+    fn test_parse_range_header_ignored() {
+        assert_eq!(parse_range_header("items=0-99", 1000), ByteRange::Full);
+        assert_eq!(parse_range_header("bytes=0-99,200-299", 1000), ByteRange::Full);
+        assert_eq!(parse_range_header("bytes=99-0", 1000), ByteRange::Full);
+        assert_eq!(parse_range_header("bytes=abc-", 1000), ByteRange::Full);
+    }
+}
@@ -0,0 +1,206 @@
+use crate::api::router::{AuthenticatedRequest, HttpRequest, HttpResponse, error_response, get_path_segment, get_query_param, parse_path_id};
+use crate::db::repos::VideoRepo;
+use crate::transcoding::{
+    get_transcode_manager, build_master_playlist, build_variant_playlist, parse_segment_name,
+    TooManySessions, TranscodeOptions, MASTER_PLAYLIST_NAME, VARIANT_PLAYLIST_NAME,
+};
+use crate::utils::activity::record_playback_activity;
+use crate::utils::probe::probe_media_file;
+use serde_json;
+use std::future::Future;
+use std::path::PathBuf;
+use std::pin::Pin;
+use std::sync::OnceLock;
+use sqlx::SqlitePool;
+
+/// Global database pool for transcode controller
+static DB_POOL: OnceLock<SqlitePool> = OnceLock::new();
+
+pub fn init_transcode_db_pool(db_pool: SqlitePool) {
+    DB_POOL.set(db_pool).expect("Failed to initialize transcode database pool");
+}
+
+/// Parse transcode options from an optional JSON body
+/// Expected format: {"video_bitrate": 4000, "max_height": 720, "audio_channels": 2}
+fn parse_transcode_options(body: Option<&[u8]>) -> Result<TranscodeOptions, &'static str> {
+    let mut options = TranscodeOptions::default();
+
+    let body = match body.filter(|b| !b.trim_ascii().is_empty()) {
+        Some(body) => body,
+        None => return Ok(options),
+    };
+    let data: serde_json::Value = serde_json::from_slice(body).map_err(|_| "Invalid JSON in request body")?;
+
+    if let Some(value) = data.get("video_bitrate").filter(|v| !v.is_null()) {
+        options.video_bitrate_kbps = value.as_u64()
+            .filter(|kbps| (200..=80_000).contains(kbps))
+            .ok_or("video_bitrate must be between 200 and 80000 kbps")? as u32;
+    }
+    if let Some(value) = data.get("max_height").filter(|v| !v.is_null()) {
+        options.max_height = Some(value.as_u64()
+            .filter(|height| (144..=4320).contains(height))
+            .ok_or("max_height must be between 144 and 4320")? as u32);
+    }
+    if let Some(value) = data.get("audio_channels") {
+        // null keeps the source channel layout
+        options.audio_channels = match value {
+            serde_json::Value::Null => None,
+            _ => Some(value.as_u64()
+                .filter(|channels| (1..=8).contains(channels))
+                .ok_or("audio_channels must be between 1 and 8")? as u32),
+        };
+    }
+
+    Ok(options)
+}
+
+/// Handle starting a transcode session for a video part
+/// Expected path format: /api/part/{part_id}/transcode
+pub fn handle_start_transcode(request: &AuthenticatedRequest) -> Pin<Box<dyn Future<Output = Result<HttpResponse, Box<dyn std::error::Error + Send + Sync>>> + Send + 'static>> {
+    let request = request.clone();
+    Box::pin(async move {
+        let part_id = match parse_path_id(&request.path, 3) {
+            Some(id) => id,
+            None => return Ok(error_response(400, "Bad Request", "Invalid part ID")),
+        };
+
+        let body = match request.body.read_limited().await {
+            Ok(body) => body,
+            Err(response) => return Ok(response),
+        };
+        let options = match parse_transcode_options(body.as_deref()) {
+            Ok(options) => options,
+            Err(message) => return Ok(error_response(400, "Bad Request", message)),
+        };
+
+        let manager = match get_transcode_manager() {
+            Some(manager) => manager,
+            None => return Ok(error_response(503, "Service Unavailable", "Transcoding is not available")),
+        };
+
+        let video_repo = VideoRepo::new(DB_POOL.get().ok_or("Database pool not initialized")?.clone());
+        let video_part = match video_repo.get_video_part_by_id(part_id).await? {
+            Some(part) => part,
+            None => return Ok(error_response(404, "Not Found", "Part not found")),
+        };
+
+        // The playlist lists every segment up front, so the duration must be known
+        let duration_ms = match video_part.duration_ms {
+            Some(duration_ms) => Some(duration_ms),
+            None => probe_media_file(std::path::Path::new(&video_part.path)).await.ok().and_then(|probe| probe.duration_ms),
+        };
+        let duration_ms = match duration_ms.filter(|d| *d > 0) {
+            Some(duration_ms) => duration_ms,
+            None => return Ok(error_response(500, "Internal server error", "Unable to determine media duration")),
+        };
+
+        let session = match manager.start_session(&request.token, part_id, PathBuf::from(&video_part.path), duration_ms, options).await {
+            Ok(session) => session,
+            Err(e) if e.is::<TooManySessions>() => {
+                return Ok(error_response(503, "Service Unavailable", "Too many transcode sessions are running"));
+            }
+            Err(e) => return Err(e.into()),
+        };
+
+        let response_body = serde_json::json!({
+            "success": true,
+            "session_id": session.id,
+            "playlist_url": format!("/api/transcode/{}/{}?key={}", session.id, MASTER_PLAYLIST_NAME, session.key)
+        });
+
+        Ok(HttpResponse::new(200)
+            .with_cors()
+            .with_json_body(&response_body.to_string()))
+    })
+}
+
+/// Handle serving playlists and segments of a transcode session
+/// Authorized by the session key rather than a token, players fetch the URIs inside playlists without custom headers
+/// Expected path format: /api/transcode/{session_id}/{file}?key={key}
+pub fn handle_transcode_file(request: &HttpRequest) -> Pin<Box<dyn Future<Output = Result<HttpResponse, Box<dyn std::error::Error + Send + Sync>>> + Send + 'static>> {
+    let request = request.clone();
+    Box::pin(async move {
+        let (session_id, file_name) = match (get_path_segment(&request.path, 3), get_path_segment(&request.path, 4)) {
+            (Some(session_id), Some(file_name)) => (session_id, file_name),
+            _ => return Ok(error_response(400, "Bad Request", "Invalid transcode path")),
+        };
+
+        let manager = match get_transcode_manager() {
+            Some(manager) => manager,
+            None => return Ok(error_response(503, "Service Unavailable", "Transcoding is not available")),
+        };
+
+        let key = get_query_param(&request.path, "key").unwrap_or("");
+
+        // Carry the key along in the URIs inside playlists
+        let query = format!("?key={}", key);
+
+        if file_name == MASTER_PLAYLIST_NAME || file_name == VARIANT_PLAYLIST_NAME {
+            let (duration_ms, options) = match manager.session_info(session_id, key).await {
+                Some(info) => info,
+                None => return Ok(error_response(404, "Not Found", "Transcode session not found")),
+            };
+            record_playback_activity();
+
+            let playlist = if file_name == MASTER_PLAYLIST_NAME {
+                build_master_playlist(options.bandwidth(), &query)
+            } else {
+                build_variant_playlist(duration_ms, &query)
+            };
+
+            return Ok(HttpResponse::new(200)
+                .with_cors()
+                .with_header("Content-Type", "application/vnd.apple.mpegurl")
+                .with_header("Cache-Control", "no-cache")
+                .with_body(&playlist));
+        }
+
+        let segment_index = match parse_segment_name(file_name) {
+            Some(index) => index,
+            None => return Ok(error_response(404, "Not Found", "File not found")),
+        };
+
+        let segment_path = match manager.get_segment(session_id, key, segment_index).await? {
+            Some(path) => path,
+            None => return Ok(error_response(404, "Not Found", "Segment not found")),
+        };
+        record_playback_activity();
+
+        let length = tokio::fs::metadata(&segment_path).await?.len();
+
+        Ok(HttpResponse::new(200)
+            .with_cors()
+            .with_header("Content-Type", "video/mp2t")
+            .with_file_body(segment_path, 0, length))
+    })
+}
+
+/// Handle stopping a transcode session
+/// Expected path format: /api/transcode/{session_id}
+pub fn handle_stop_transcode(request: &AuthenticatedRequest) -> Pin<Box<dyn Future<Output = Result<HttpResponse, Box<dyn std::error::Error + Send + Sync>>> + Send + 'static>> {
+    let request = request.clone();
+    Box::pin(async move {
+        let session_id = match get_path_segment(&request.path, 3) {
+            Some(session_id) => session_id,
+            None => return Ok(error_response(400, "Bad Request", "Invalid session ID")),
+        };
+
+        let manager = match get_transcode_manager() {
+            Some(manager) => manager,
+            None => return Ok(error_response(503, "Service Unavailable", "Transcoding is not available")),
+        };
+
+        if !manager.stop_session(session_id).await {
+            return Ok(error_response(404, "Not Found", "Transcode session not found"));
+        }
+
+        let response_body = serde_json::json!({
+            "success": true,
+            "message": "Transcode session stopped"
+        });
+
+        Ok(HttpResponse::new(200)
+            .with_cors()
+            .with_json_body(&response_body.to_string()))
+    })
+}
@@ -0,0 +1,250 @@
+use crate::api::controllers::media::video_item_responses;
+use crate::api::router::{AuthenticatedRequest, HttpResponse, get_query_param, error_response, parse_path_id};
+use crate::api::responses::VideoItemResponse;
+use crate::constants::{RESUME_MIN_POSITION_MS, WATCHED_THRESHOLD};
+use crate::db::models::VideoItem;
+use crate::db::repos::{ProfilesRepo, VideoRepo, WatchStateRepo};
+use crate::utils::activity::record_playback_activity;
+use serde_json;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::OnceLock;
+use sqlx::SqlitePool;
+
+/// Global database pool for watch state controller
+static DB_POOL: OnceLock<SqlitePool> = OnceLock::new();
+
+/// Default and maximum number of items in Continue Watching and Next Up
+const DEFAULT_HUB_LIMIT: i64 = 20;
+const MAX_HUB_LIMIT: i64 = 100;
+
+/// Item types whose watch state is rolled up from their children
+const PARENT_ITEM_TYPES: [&str; 2] = ["season", "show"];
+
+pub fn init_watch_db_pool(db_pool: SqlitePool) {
+    DB_POOL.set(db_pool).expect("Failed to initialize watch database pool");
+}
+
+/// Check whether a playback position is close enough to the end to count as watched
+pub fn is_watched_position(position_ms: i64, runtime_ms: Option<i64>) -> bool {
+    runtime_ms
+        .filter(|runtime_ms| *runtime_ms > 0)
+        .is_some_and(|runtime_ms| position_ms as f64 >= runtime_ms as f64 * WATCHED_THRESHOLD)
+}
+
+/// Get the resume point kept for a playback position (positions at the very start aren't kept)
+pub fn resume_position(position_ms: i64) -> i64 {
+    if position_ms < RESUME_MIN_POSITION_MS { 0 } else { position_ms }
+}
+
+/// Resolve the profile and item of a watch state request, or the error response to send
+/// Tokens aren't tied to a profile, so the profile only has to exist; watch state is not private between profiles
+async fn resolve_profile_item(db_pool: &SqlitePool, path: &str) -> Result<Result<(i64, VideoItem), HttpResponse>, Box<dyn std::error::Error + Send + Sync>> {
+    let profile_id = match parse_path_id(path, 3) {
+        Some(id) => id,
+        None => return Ok(Err(error_response(400, "Bad Request", "Invalid profile ID"))),
+    };
+    let item_id = match parse_path_id(path, 5) {
+        Some(id) => id,
+        None => return Ok(Err(error_response(400, "Bad Request", "Invalid item ID"))),
+    };
+
+    if ProfilesRepo::new(db_pool.clone()).get_profile_by_id(profile_id).await?.is_none() {
+        return Ok(Err(error_response(404, "Not Found", "Profile not found")));
+    }
+    match VideoRepo::new(db_pool.clone()).get_video_item_by_id(item_id).await? {
+        Some(item) => Ok(Ok((profile_id, item))),
+        None => Ok(Err(error_response(404, "Not Found", "Item not found"))),
+    }
+}
+
+/// Recompute the watch state of the seasons and shows above an item
+async fn rollup_watch_state(video_repo: &VideoRepo, watch_state_repo: &WatchStateRepo, profile_id: i64, item: &VideoItem) -> Result<(), anyhow::Error> {
+    let mut parent_id = item.parent_id;
+    while let Some(id) = parent_id {
+        let parent = match video_repo.get_video_item_by_id(id).await? {
+            Some(parent) if PARENT_ITEM_TYPES.contains(&parent.r#type.as_str()) => parent,
+            _ => break,
+        };
+        watch_state_repo.refresh_parent_watch_state(profile_id, parent.id).await?;
+        parent_id = parent.parent_id;
+    }
+
+    Ok(())
+}
+
+/// Build the JSON response of an item with its artwork and watch state
+async fn item_response(video_repo: &VideoRepo, watch_state_repo: &WatchStateRepo, profile_id: i64, item: VideoItem) -> Result<VideoItemResponse, anyhow::Error> {
+    let artwork = video_repo.get_video_artwork_by_item(item.id).await?;
+    let watch_state = watch_state_repo.get_watch_state(profile_id, item.id).await?;
+    Ok(VideoItemResponse::from(item).with_artwork(artwork).with_watch_state(watch_state))
+}
+
+/// Handle a playback progress report of a client
+/// Positions near the end mark the item as watched, earlier ones are kept as resume point
+/// Expected path format: /api/profile/{profile_id}/item/{item_id}/progress
+/// Expected body: {"position_ms": 1234000, "duration_ms": 5400000} (duration_ms only used when the runtime is unknown)
+pub fn handle_report_progress(request: &AuthenticatedRequest) -> Pin<Box<dyn Future<Output = Result<HttpResponse, Box<dyn std::error::Error + Send + Sync>>> + Send + 'static>> {
+    let request = request.clone();
+    Box::pin(async move {
+        let body = match request.body.read_limited().await {
+            Ok(body) => body,
+            Err(response) => return Ok(response),
+        };
+        let data: serde_json::Value = match body.as_deref().map(serde_json::from_slice) {
+            Some(Ok(data)) => data,
+            _ => return Ok(error_response(400, "Bad Request", "Invalid JSON in request body")),
+        };
+        let position_ms = match data.get("position_ms").and_then(|value| value.as_i64()).filter(|position| *position >= 0) {
+            Some(position_ms) => position_ms,
+            None => return Ok(error_response(400, "Bad Request", "position_ms must be a non-negative number")),
+        };
+        let reported_duration_ms = data.get("duration_ms").and_then(|value| value.as_i64());
+
+        let db_pool = DB_POOL.get().ok_or("Database pool not initialized")?.clone();
+        let (profile_id, item) = match resolve_profile_item(&db_pool, &request.path).await? {
+            Ok(resolved) => resolved,
+            Err(response) => return Ok(response),
+        };
+        if PARENT_ITEM_TYPES.contains(&item.r#type.as_str()) {
+            return Ok(error_response(400, "Bad Request", "Progress can only be reported for playable items"));
+        }
+
+        record_playback_activity();
+
+        let video_repo = VideoRepo::new(db_pool.clone());
+        let watch_state_repo = WatchStateRepo::new(db_pool);
+
+        // The longest version decides, clients may play any of them
+        let runtime_ms = video_repo.get_video_versions_by_item(item.id).await?
+            .into_iter()
+            .filter_map(|version| version.runtime_ms)
+            .max()
+            .or(reported_duration_ms);
+
+        if is_watched_position(position_ms, runtime_ms) {
+            // Only the first report past the threshold counts a play
+            let already_finished = watch_state_repo.get_watch_state(profile_id, item.id).await?
+                .is_some_and(|state| state.watched != 0 && state.position_ms == 0);
+            if !already_finished {
+                watch_state_repo.set_watched(profile_id, item.id, true).await?;
+                rollup_watch_state(&video_repo, &watch_state_repo, profile_id, &item).await?;
+            }
+        } else {
+            watch_state_repo.save_progress(profile_id, item.id, resume_position(position_ms)).await?;
+        }
+
+        let response_body = serde_json::json!({
+            "success": true,
+            "item": item_response(&video_repo, &watch_state_repo, profile_id, item).await?
+        });
+
+        Ok(HttpResponse::new(200)
+            .with_cors()
+            .with_json_body(&response_body.to_string()))
+    })
+}
+
+/// Handle marking an item as watched (POST) or unwatched (DELETE)
+/// Seasons and shows mark all their episodes, then the watch state is rolled up to the parents
+/// Expected path format: /api/profile/{profile_id}/item/{item_id}/watched
+pub fn handle_mark_watched(request: &AuthenticatedRequest) -> Pin<Box<dyn Future<Output = Result<HttpResponse, Box<dyn std::error::Error + Send + Sync>>> + Send + 'static>> {
+    let request = request.clone();
+    Box::pin(async move {
+        let watched = request.method != "DELETE";
+
+        let db_pool = DB_POOL.get().ok_or("Database pool not initialized")?.clone();
+        let (profile_id, item) = match resolve_profile_item(&db_pool, &request.path).await? {
+            Ok(resolved) => resolved,
+            Err(response) => return Ok(response),
+        };
+
+        let video_repo = VideoRepo::new(db_pool.clone());
+        let watch_state_repo = WatchStateRepo::new(db_pool);
+
+        // Deepest items first, so seasons are rolled up before their show
+        for descendant in video_repo.get_video_item_subtree(item.id).await? {
+            if PARENT_ITEM_TYPES.contains(&descendant.r#type.as_str()) {
+                watch_state_repo.refresh_parent_watch_state(profile_id, descendant.id).await?;
+            } else {
+                watch_state_repo.set_watched(profile_id, descendant.id, watched).await?;
+            }
+        }
+        rollup_watch_state(&video_repo, &watch_state_repo, profile_id, &item).await?;
+
+        let response_body = serde_json::json!({
+            "success": true,
+            "item": item_response(&video_repo, &watch_state_repo, profile_id, item).await?
+        });
+
+        Ok(HttpResponse::new(200)
+            .with_cors()
+            .with_json_body(&response_body.to_string()))
+    })
+}
+
+/// Handle listing the Continue Watching or Next Up items of a profile (readable with any valid token)
+/// Expected path format: /api/profile/{profile_id}/continue-watching?limit=20
+///                       /api/profile/{profile_id}/next-up?limit=20
+pub fn handle_watch_hub(request: &AuthenticatedRequest) -> Pin<Box<dyn Future<Output = Result<HttpResponse, Box<dyn std::error::Error + Send + Sync>>> + Send + 'static>> {
+    let request = request.clone();
+    Box::pin(async move {
+        let profile_id = match parse_path_id(&request.path, 3) {
+            Some(id) => id,
+            None => return Ok(error_response(400, "Bad Request", "Invalid profile ID")),
+        };
+
+        let limit = match get_query_param(&request.path, "limit").map(|l| l.parse::<i64>()) {
+            None => DEFAULT_HUB_LIMIT,
+            Some(Ok(limit)) if limit > 0 => limit.min(MAX_HUB_LIMIT),
+            Some(_) => return Ok(error_response(400, "Bad Request", "Invalid limit")),
+        };
+
+        let db_pool = DB_POOL.get().ok_or("Database pool not initialized")?.clone();
+        if ProfilesRepo::new(db_pool.clone()).get_profile_by_id(profile_id).await?.is_none() {
+            return Ok(error_response(404, "Not Found", "Profile not found"));
+        }
+
+        let video_repo = VideoRepo::new(db_pool.clone());
+        let watch_state_repo = WatchStateRepo::new(db_pool);
+
+        let clean_path = request.path.split('?').next().unwrap_or("");
+        let hub_items = if clean_path.ends_with("/next-up") {
+            watch_state_repo.get_next_up(profile_id, limit).await?
+        } else {
+            watch_state_repo.get_continue_watching(profile_id, limit).await?
+        };
+
+        let items = video_item_responses(&video_repo, &watch_state_repo, Some(profile_id), hub_items).await?;
+
+        let response_body = serde_json::json!({
+            "success": true,
+            "items": items
+        });
+
+        Ok(HttpResponse::new(200)
+            .with_cors()
+            .with_json_body(&response_body.to_string()))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_is_watched_position() {
+        assert!(is_watched_position(5_400_000, Some(6_000_000)));
+        assert!(is_watched_position(7_000_000, Some(6_000_000)));
+        assert!(!is_watched_position(5_399_999, Some(6_000_000)));
+        assert!(!is_watched_position(5_400_000, None));
+        assert!(!is_watched_position(0, Some(0)));
+    }
+
+    #[test]
+    fn test_resume_position() {
+        assert_eq!(resume_position(10_000), 0);
+        assert_eq!(resume_position(30_000), 30_000);
+        assert_eq!(resume_position(1_234_000), 1_234_000);
+    }
+}
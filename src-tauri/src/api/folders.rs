@@ -1,9 +1,19 @@
 use crate::api::state::AppState;
+use serde::Deserialize;
+use std::path::Path;
+#[cfg(feature = "desktop")]
 use tauri::WindowBuilder;
+#[cfg(feature = "desktop")]
 use tauri_plugin_dialog::DialogExt;
 
+// Request structure for folders typed in by the user instead of picked in a dialog
+#[derive(Debug, Deserialize)]
+pub struct TypedFoldersRequest {
+    pub paths: Vec<String>,
+}
+
 // Filter out child folders to avoid redundancy
-fn filter_child_folders(folders: Vec<String>) -> Vec<String> {
+pub(crate) fn filter_child_folders(folders: Vec<String>) -> Vec<String> {
     if folders.is_empty() {
         return folders;
     }
@@ -31,24 +41,101 @@ fn filter_child_folders(folders: Vec<String>) -> Vec<String> {
     filtered
 }
 
-// Handler for folder selection endpoint
-pub async fn handle_select_folders(app_state: AppState) -> Result<impl warp::Reply, warp::Rejection> {
-    let state = app_state.app_handle.lock().await;
-    let app_handle = state.as_ref().ok_or_else(|| warp::reject::custom(FolderSelectionError))?;
+// Check folders typed in by the user: each must be an absolute path to an existing directory
+// Returns the folders without trailing separators, or the paths that are not valid folders
+pub(crate) fn check_typed_folders(paths: Vec<String>) -> Result<Vec<String>, Vec<String>> {
+    let mut folders = Vec::new();
+    let mut invalid = Vec::new();
     
-    // Use Tauri's folder selection dialog
-    let folders = select_folders(app_handle.clone()).await
-        .map_err(|_| warp::reject::custom(FolderSelectionError))?;
+    for path in paths {
+        let path = path.trim();
+        if path.is_empty() {
+            continue;
+        }
+        // Keep the root of the filesystem as is
+        let folder = match path.trim_end_matches(['/', '\\']) {
+            "" => path,
+            trimmed => trimmed,
+        };
+        
+        if Path::new(folder).is_absolute() && Path::new(folder).is_dir() {
+            folders.push(folder.to_string());
+        } else {
+            invalid.push(path.to_string());
+        }
+    }
+    
+    if invalid.is_empty() { Ok(folders) } else { Err(invalid) }
+}
+
+// Handler for folder selection endpoint
+// With a body ({"paths": [...]}) the typed paths are checked, otherwise the native dialog is shown
+// Servers without a desktop reply with "manual": true, so the user types the paths instead
+pub async fn handle_select_folders(app_state: AppState, body: warp::hyper::body::Bytes) -> Result<impl warp::Reply, warp::Rejection> {
+    if !body.is_empty() {
+        let request: TypedFoldersRequest = match serde_json::from_slice(&body) {
+            Ok(request) => request,
+            Err(_) => {
+                return Ok(warp::reply::with_status(
+                    warp::reply::json(&serde_json::json!({
+                        "error": "Invalid request, expected {\"paths\": [...]}"
+                    })),
+                    warp::http::StatusCode::BAD_REQUEST,
+                ));
+            }
+        };
+        
+        return Ok(match check_typed_folders(request.paths) {
+            Ok(folders) => warp::reply::with_status(
+                warp::reply::json(&serde_json::json!({
+                    "folders": filter_child_folders(folders)
+                })),
+                warp::http::StatusCode::OK,
+            ),
+            Err(invalid) => warp::reply::with_status(
+                warp::reply::json(&serde_json::json!({
+                    "error": format!("Not a folder on the server: {}", invalid.join(", ")),
+                    "invalid": invalid
+                })),
+                warp::http::StatusCode::BAD_REQUEST,
+            ),
+        });
+    }
     
-    // Filter out child folders
-    let filtered_folders = filter_child_folders(folders);
+    #[cfg(feature = "desktop")]
+    {
+        let app_handle = app_state.app_handle.lock().await.clone();
+        if let Some(app_handle) = app_handle {
+            // Use Tauri's folder selection dialog
+            let folders = select_folders(app_handle).await
+                .map_err(|_| warp::reject::custom(FolderSelectionError))?;
+            
+            // Filter out child folders
+            let filtered_folders = filter_child_folders(folders);
+            
+            return Ok(warp::reply::with_status(
+                warp::reply::json(&serde_json::json!({
+                    "folders": filtered_folders
+                })),
+                warp::http::StatusCode::OK,
+            ));
+        }
+    }
+    #[cfg(not(feature = "desktop"))]
+    let _ = app_state;
     
-    Ok(warp::reply::json(&serde_json::json!({
-        "folders": filtered_folders
-    })))
+    // No dialog available, the user has to type the paths
+    Ok(warp::reply::with_status(
+        warp::reply::json(&serde_json::json!({
+            "folders": [],
+            "manual": true
+        })),
+        warp::http::StatusCode::OK,
+    ))
 }
 
 // Tauri command for folder selection
+#[cfg(feature = "desktop")]
 #[tauri::command]
 pub async fn select_folders(app: tauri::AppHandle) -> Result<Vec<String>, String> {
     // Generate a unique window label to avoid conflicts
@@ -98,7 +185,26 @@ pub async fn select_folders(app: tauri::AppHandle) -> Result<Vec<String>, String
 }
 
 // Custom error type for folder selection
+#[cfg(feature = "desktop")]
 #[derive(Debug)]
 struct FolderSelectionError;
 
+#[cfg(feature = "desktop")]
 impl warp::reject::Reject for FolderSelectionError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_check_typed_folders() {
+        let dir = std::env::temp_dir();
+        let dir_str = dir.to_string_lossy().trim_end_matches(['/', '\\']).to_string();
+        
+        assert_eq!(check_typed_folders(vec![format!(" {}/ ", dir_str), "".to_string()]), Ok(vec![dir_str.clone()]));
+        assert_eq!(check_typed_folders(vec!["relative/path".to_string()]), Err(vec!["relative/path".to_string()]));
+        
+        let missing = dir.join("index-media-server-missing-folder").to_string_lossy().to_string();
+        assert_eq!(check_typed_folders(vec![dir_str, missing.clone()]), Err(vec![missing]));
+    }
+}
@@ -1,14 +1,33 @@
 use warp::path::FullPath;
 use crate::constants::DEFAULT_HTTPS_PORT;
+use std::path::PathBuf;
+use std::sync::OnceLock;
+
+/// Directory of the local web interface, when not the default one
+static WEB_DIR: OnceLock<PathBuf> = OnceLock::new();
+
+/// Serve the local web interface from another directory (e.g. the headless server outside the source tree)
+pub fn init_web_dir(web_dir: PathBuf) {
+    WEB_DIR.set(web_dir).expect("Failed to initialize web directory");
+}
+
+/// Get the directory of the local web interface
+fn get_web_dir() -> PathBuf {
+    if let Some(web_dir) = WEB_DIR.get() {
+        return web_dir.clone();
+    }
+    
+    // Get the current working directory and construct absolute paths
+    // Tauri runs from src-tauri directory, so we need to go up one level to find localweb/
+    let current_dir = std::env::current_dir().unwrap_or_else(|_| PathBuf::from("."));
+    current_dir.parent().unwrap_or(&current_dir).join("localweb")
+}
 
 // Handler for serving static files with SPA fallback
 pub async fn handle_static_file(path: FullPath) -> Result<Box<dyn warp::Reply>, warp::Rejection> {
     let path_str = path.as_str();
     
-    // Get the current working directory and construct absolute paths
-    // Tauri runs from src-tauri directory, so we need to go up one level to find localweb/
-    let current_dir = std::env::current_dir().unwrap_or_else(|_| std::path::PathBuf::from("."));
-    let web_dir = current_dir.parent().unwrap_or(&current_dir).join("localweb");
+    let web_dir = get_web_dir();
     
     let file_path = if path_str == "/" {
         web_dir.join("index.html")
@@ -4,12 +4,20 @@ use crate::models::config::IncomingConfiguration;
 use crate::api::folders::handle_select_folders;
 use crate::api::config::{handle_get_configuration, handle_save_configuration, handle_update_server_password, handle_update_server_name, handle_get_index_icon};
 use crate::api::profiles::{handle_get_profiles, handle_create_profile, handle_update_profile, handle_delete_profile};
-use crate::api::indexes::{handle_get_indexes, handle_create_local_index, handle_update_index, handle_delete_index, handle_queue_index_scan};
+use crate::api::indexes::{handle_get_indexes, handle_create_local_index, handle_update_index, handle_delete_index, handle_queue_index_scan, handle_get_scan_job, handle_scan_events,
+    handle_cancel_index_scan, handle_set_index_scan_paused, handle_get_scan_runs};
+use crate::api::sessions::{handle_get_sessions, handle_revoke_session, handle_revoke_all_sessions};
+use crate::api::backups::{handle_get_backups, handle_create_backup, handle_update_backup_settings, handle_download_backup, handle_delete_backup,
+    handle_restore_backup, handle_upload_restore};
 use crate::api::handlers::{handle_ping, handle_connect_code, handle_static_file};
-use crate::models::config::{ServerPasswordUpdate, ServerNameUpdate, IncomingProfile, IncomingMediaIndex, IndexUpdateRequest};
+use std::collections::HashMap;
+use std::net::IpAddr;
+use crate::models::config::{ServerPasswordUpdate, ServerNameUpdate, IncomingProfile, IncomingMediaIndex, IndexUpdateRequest, BackupSettings};
 
 /// Start the HTTP server for browser communication and static file serving
+/// The desktop app only listens on localhost, the headless server may listen on other addresses
 pub async fn start_http_server(
+    http_host: IpAddr,
     http_port: u16,
     app_state: AppState,
     startup_token: String,
@@ -31,6 +39,29 @@ pub async fn start_http_server(
     let app_state_get_indexes = app_state.clone();
     let app_state_get_index_icon = app_state.clone();
     let app_state_queue_scan = app_state.clone();
+    let app_state_get_scan_job = app_state.clone();
+    let app_state_get_scan_runs = app_state.clone();
+    let app_state_get_backups = app_state.clone();
+    let app_state_create_backup = app_state.clone();
+    let app_state_update_backup_settings = app_state.clone();
+    let app_state_download_backup = app_state.clone();
+    let app_state_delete_backup = app_state.clone();
+    let app_state_restore_backup = app_state.clone();
+    let app_state_upload_restore = app_state.clone();
+
+    // Token validation filter for EventSource requests, which cannot send headers
+    let query_token = startup_token.clone();
+    let query_token_validation = warp::query::<HashMap<String, String>>()
+        .and_then(move |query: HashMap<String, String>| {
+            let expected_token = query_token.clone();
+            async move {
+                if query.get("token") == Some(&expected_token) {
+                    Ok(())
+                } else {
+                    Err(warp::reject::custom(TokenValidationError))
+                }
+            }
+        });
 
     // Token validation filter for API endpoints
     let token_validation = warp::header::<String>("authorization")
@@ -55,8 +86,9 @@ pub async fn start_http_server(
         .and(warp::path("select-folders"))
         .and(warp::post())
         .and(token_validation.clone())
+        .and(warp::body::bytes())
         .and(warp::any().map(move || app_state_select.clone()))
-        .and_then(|_, app_state: AppState| handle_select_folders(app_state));
+        .and_then(|_, body, app_state: AppState| handle_select_folders(app_state, body));
 
     let get_configuration = warp::path("api")
         .and(warp::path("config"))
@@ -150,6 +182,7 @@ pub async fn start_http_server(
     let delete_index = warp::path("api")
         .and(warp::path("index"))
         .and(warp::path::param::<String>())
+        .and(warp::path::end())
         .and(warp::delete())
         .and(token_validation.clone())
         .and(warp::any().map(move || app_state_delete_index.clone()))
@@ -169,6 +202,93 @@ pub async fn start_http_server(
         .and(warp::any().map(move || app_state_get_indexes.clone()))
         .and_then(|_, app_state: AppState| handle_get_indexes(app_state));
 
+    // Session routes (tokens issued to clients of the HTTPS server)
+    let get_sessions = warp::path("api")
+        .and(warp::path("sessions"))
+        .and(warp::path::end())
+        .and(warp::get())
+        .and(token_validation.clone())
+        .and_then(|_| handle_get_sessions());
+
+    let revoke_session = warp::path("api")
+        .and(warp::path("sessions"))
+        .and(warp::path::param::<String>())
+        .and(warp::path::end())
+        .and(warp::delete())
+        .and(token_validation.clone())
+        .and_then(|session_id: String, _| handle_revoke_session(session_id));
+
+    let revoke_all_sessions = warp::path("api")
+        .and(warp::path("sessions"))
+        .and(warp::path::end())
+        .and(warp::delete())
+        .and(token_validation.clone())
+        .and_then(|_| handle_revoke_all_sessions());
+
+    // Backup routes (archives of the database, config.json and icons)
+    let get_backups = warp::path("api")
+        .and(warp::path("backups"))
+        .and(warp::path::end())
+        .and(warp::get())
+        .and(token_validation.clone())
+        .and(warp::any().map(move || app_state_get_backups.clone()))
+        .and_then(|_, app_state: AppState| handle_get_backups(app_state));
+
+    let create_backup = warp::path("api")
+        .and(warp::path("backup"))
+        .and(warp::path::end())
+        .and(warp::post())
+        .and(token_validation.clone())
+        .and(warp::any().map(move || app_state_create_backup.clone()))
+        .and_then(|_, app_state: AppState| handle_create_backup(app_state));
+
+    let update_backup_settings = warp::path("api")
+        .and(warp::path("backup"))
+        .and(warp::path("settings"))
+        .and(warp::path::end())
+        .and(warp::put())
+        .and(token_validation.clone())
+        .and(warp::body::json())
+        .and(warp::any().map(move || app_state_update_backup_settings.clone()))
+        .and_then(|_, settings: BackupSettings, app_state: AppState| handle_update_backup_settings(app_state, settings));
+
+    let download_backup = warp::path("api")
+        .and(warp::path("backup"))
+        .and(warp::path::param::<String>())
+        .and(warp::path::end())
+        .and(warp::get())
+        .and(token_validation.clone())
+        .and(warp::any().map(move || app_state_download_backup.clone()))
+        .and_then(|file_name: String, _, app_state: AppState| handle_download_backup(app_state, file_name));
+
+    let delete_backup = warp::path("api")
+        .and(warp::path("backup"))
+        .and(warp::path::param::<String>())
+        .and(warp::path::end())
+        .and(warp::delete())
+        .and(token_validation.clone())
+        .and(warp::any().map(move || app_state_delete_backup.clone()))
+        .and_then(|file_name: String, _, app_state: AppState| handle_delete_backup(app_state, file_name));
+
+    let restore_backup = warp::path("api")
+        .and(warp::path("backup"))
+        .and(warp::path::param::<String>())
+        .and(warp::path("restore"))
+        .and(warp::path::end())
+        .and(warp::post())
+        .and(token_validation.clone())
+        .and(warp::any().map(move || app_state_restore_backup.clone()))
+        .and_then(|file_name: String, _, app_state: AppState| handle_restore_backup(app_state, file_name));
+
+    let upload_restore = warp::path("api")
+        .and(warp::path("restore"))
+        .and(warp::path::end())
+        .and(warp::post())
+        .and(token_validation.clone())
+        .and(warp::body::stream())
+        .and(warp::any().map(move || app_state_upload_restore.clone()))
+        .and_then(|_, body, app_state: AppState| handle_upload_restore(app_state, body));
+
     // Icon serving route (no authorization required for img tags)
     let get_index_icon = warp::path("api")
         .and(warp::path("index"))
@@ -182,11 +302,68 @@ pub async fn start_http_server(
         .and(warp::path("index"))
         .and(warp::path::param::<String>())
         .and(warp::path("scan-job"))
+        .and(warp::path::end())
         .and(warp::post())
         .and(token_validation.clone())
         .and(warp::any().map(move || app_state_queue_scan.clone()))
         .and_then(|index_id: String, _, app_state: AppState| handle_queue_index_scan(app_state, index_id));
 
+    let get_scan_job = warp::path("api")
+        .and(warp::path("index"))
+        .and(warp::path::param::<String>())
+        .and(warp::path("scan-job"))
+        .and(warp::path::end())
+        .and(warp::get())
+        .and(token_validation.clone())
+        .and(warp::any().map(move || app_state_get_scan_job.clone()))
+        .and_then(|index_id: String, _, app_state: AppState| handle_get_scan_job(app_state, index_id));
+
+    let cancel_index_scan = warp::path("api")
+        .and(warp::path("index"))
+        .and(warp::path::param::<String>())
+        .and(warp::path("scan-job"))
+        .and(warp::path::end())
+        .and(warp::delete())
+        .and(token_validation.clone())
+        .and_then(|index_id: String, _| handle_cancel_index_scan(index_id));
+
+    let pause_index_scan = warp::path("api")
+        .and(warp::path("index"))
+        .and(warp::path::param::<String>())
+        .and(warp::path("scan-job"))
+        .and(warp::path("pause"))
+        .and(warp::path::end())
+        .and(warp::post())
+        .and(token_validation.clone())
+        .and_then(|index_id: String, _| handle_set_index_scan_paused(index_id, true));
+
+    let resume_index_scan = warp::path("api")
+        .and(warp::path("index"))
+        .and(warp::path::param::<String>())
+        .and(warp::path("scan-job"))
+        .and(warp::path("resume"))
+        .and(warp::path::end())
+        .and(warp::post())
+        .and(token_validation.clone())
+        .and_then(|index_id: String, _| handle_set_index_scan_paused(index_id, false));
+
+    let get_scan_runs = warp::path("api")
+        .and(warp::path("index"))
+        .and(warp::path::param::<String>())
+        .and(warp::path("scan-runs"))
+        .and(warp::path::end())
+        .and(warp::get())
+        .and(token_validation.clone())
+        .and(warp::any().map(move || app_state_get_scan_runs.clone()))
+        .and_then(|index_id: String, _, app_state: AppState| handle_get_scan_runs(app_state, index_id));
+
+    // Live scan progress of all indexes as Server-Sent Events
+    let scan_events = warp::path("api")
+        .and(warp::path("scan-events"))
+        .and(warp::get())
+        .and(query_token_validation)
+        .and_then(|_| handle_scan_events());
+
     // Static file serving with SPA fallback (only for non-API paths)
     let static_files = warp::path::full()
         .and_then(|path: warp::path::FullPath| async move {
@@ -216,6 +393,22 @@ pub async fn start_http_server(
         .or(delete_index)
         .or(get_index_icon)
         .or(queue_index_scan)
+        .or(get_scan_job)
+        .or(cancel_index_scan)
+        .or(pause_index_scan)
+        .or(resume_index_scan)
+        .or(get_scan_runs)
+        .or(scan_events)
+        .or(get_sessions)
+        .or(revoke_session)
+        .or(revoke_all_sessions)
+        .or(get_backups)
+        .or(create_backup)
+        .or(update_backup_settings)
+        .or(download_backup)
+        .or(delete_backup)
+        .or(restore_backup)
+        .or(upload_restore)
         .or(static_files)
         .recover(move |rejection: warp::Rejection| async move {
             if rejection.find::<TokenValidationError>().is_some() {
@@ -234,11 +427,11 @@ pub async fn start_http_server(
         .with(warp::cors()
             .allow_any_origin()
             .allow_headers(vec!["content-type", "authorization"])
-            .allow_methods(vec!["GET", "POST", "PUT", "OPTIONS"]));
+            .allow_methods(vec!["GET", "POST", "PUT", "DELETE", "OPTIONS"]));
 
-    println!("🚀 Index Media Server running on http://localhost:{}", http_port);
+    println!("🚀 Index Media Server running on http://{}:{}", if http_host.is_loopback() { "localhost".to_string() } else { http_host.to_string() }, http_port);
     warp::serve(routes)
-        .run(([127, 0, 0, 1], http_port))
+        .run((http_host, http_port))
         .await;
 
     Ok(())
@@ -1,4 +1,4 @@
-use crate::config::certs_dir;
+use crate::config::{certs_dir, PathProvider};
 use crate::constants::DEFAULT_HTTPS_PORT;
 use rcgen::generate_simple_self_signed;
 use std::fs::{self, File};
@@ -13,7 +13,7 @@ use rustls::{pki_types::{CertificateDer, PrivateKeyDer}, ServerConfig};
 use chrono::{DateTime, Utc};
 use crate::utils::network::find_available_port;
 use super::router::{Router, handle_connection_with_router};
-use super::controllers::{handle_login, handle_token_check, handle_ping, handle_static_files, handle_index_icon};
+use super::controllers::{handle_login, handle_token_check, handle_ping, handle_static_files, handle_index_icon, handle_part_stream, handle_subtitle, handle_index_items, handle_search, handle_item_details, handle_item_artwork, handle_refresh_item_metadata, handle_start_transcode, handle_transcode_file, handle_stop_transcode, handle_index_photos, handle_index_albums, handle_photo_original, handle_photo_thumbnail, handle_index_music, handle_music_item_details, handle_music_cover, handle_track_stream, handle_report_progress, handle_mark_watched, handle_watch_hub};
 
 /// Certificate storage paths
 const CERT_FILE: &str = "https_cert.pem";
@@ -61,13 +61,13 @@ fn get_network_interfaces() -> Vec<String> {
 }
 
 /// Get the data directory path for certificate storage
-fn get_cert_data_dir(app_handle: &tauri::AppHandle) -> Result<PathBuf, Box<dyn std::error::Error>> {
-    Ok(certs_dir(app_handle)?)
+fn get_cert_data_dir(paths: &dyn PathProvider) -> Result<PathBuf, Box<dyn std::error::Error>> {
+    Ok(certs_dir(paths)?)
 }
 
 /// Get the full path for a certificate file
-fn get_cert_file_path(app_handle: &tauri::AppHandle, filename: &str) -> Result<PathBuf, Box<dyn std::error::Error>> {
-    let mut path = get_cert_data_dir(app_handle)?;
+fn get_cert_file_path(paths: &dyn PathProvider, filename: &str) -> Result<PathBuf, Box<dyn std::error::Error>> {
+    let mut path = get_cert_data_dir(paths)?;
     path.push(filename);
     Ok(path)
 }
@@ -106,10 +106,10 @@ pub fn generate_self_signed_cert() -> Result<(Vec<u8>, Vec<u8>, DateTime<Utc>),
 }
 
 /// Save certificate files and expiration date
-fn save_certificate_files(app_handle: &tauri::AppHandle, cert_pem: Vec<u8>, key_pem: Vec<u8>, expiry: DateTime<Utc>) -> Result<(), Box<dyn std::error::Error>> {
-    let cert_path = get_cert_file_path(app_handle, CERT_FILE)?;
-    let key_path = get_cert_file_path(app_handle, KEY_FILE)?;
-    let expiry_path = get_cert_file_path(app_handle, CERT_EXPIRY_FILE)?;
+fn save_certificate_files(paths: &dyn PathProvider, cert_pem: Vec<u8>, key_pem: Vec<u8>, expiry: DateTime<Utc>) -> Result<(), Box<dyn std::error::Error>> {
+    let cert_path = get_cert_file_path(paths, CERT_FILE)?;
+    let key_path = get_cert_file_path(paths, KEY_FILE)?;
+    let expiry_path = get_cert_file_path(paths, CERT_EXPIRY_FILE)?;
     
     fs::write(&cert_path, cert_pem)?;
     fs::write(&key_path, key_pem)?;
@@ -123,8 +123,8 @@ fn save_certificate_files(app_handle: &tauri::AppHandle, cert_pem: Vec<u8>, key_
 }
 
 /// Load certificate expiration date
-fn load_certificate_expiry(app_handle: &tauri::AppHandle) -> Result<Option<DateTime<Utc>>, Box<dyn std::error::Error>> {
-    let expiry_path = get_cert_file_path(app_handle, CERT_EXPIRY_FILE)?;
+fn load_certificate_expiry(paths: &dyn PathProvider) -> Result<Option<DateTime<Utc>>, Box<dyn std::error::Error>> {
+    let expiry_path = get_cert_file_path(paths, CERT_EXPIRY_FILE)?;
     
     if !expiry_path.exists() {
         return Ok(None);
@@ -136,15 +136,15 @@ fn load_certificate_expiry(app_handle: &tauri::AppHandle) -> Result<Option<DateT
 }
 
 /// Check if certificate files exist
-fn certificate_files_exist(app_handle: &tauri::AppHandle) -> Result<bool, Box<dyn std::error::Error>> {
-    let cert_path = get_cert_file_path(app_handle, CERT_FILE)?;
-    let key_path = get_cert_file_path(app_handle, KEY_FILE)?;
+fn certificate_files_exist(paths: &dyn PathProvider) -> Result<bool, Box<dyn std::error::Error>> {
+    let cert_path = get_cert_file_path(paths, CERT_FILE)?;
+    let key_path = get_cert_file_path(paths, KEY_FILE)?;
     Ok(cert_path.exists() && key_path.exists())
 }
 
 /// Check if certificate needs renewal (expires within 72 hours)
-fn certificate_needs_renewal(app_handle: &tauri::AppHandle) -> Result<bool, Box<dyn std::error::Error>> {
-    match load_certificate_expiry(app_handle)? {
+fn certificate_needs_renewal(paths: &dyn PathProvider) -> Result<bool, Box<dyn std::error::Error>> {
+    match load_certificate_expiry(paths)? {
         Some(expiry) => {
             let now = Utc::now();
             let time_until_expiry = expiry - now;
@@ -161,8 +161,8 @@ fn certificate_needs_renewal(app_handle: &tauri::AppHandle) -> Result<bool, Box<
 }
 
 /// Load certificates from PEM files
-fn load_certs(app_handle: &tauri::AppHandle, filename: &str) -> Result<Vec<CertificateDer<'static>>, Box<dyn std::error::Error>> {
-    let cert_path = get_cert_file_path(app_handle, filename)?;
+fn load_certs(paths: &dyn PathProvider, filename: &str) -> Result<Vec<CertificateDer<'static>>, Box<dyn std::error::Error>> {
+    let cert_path = get_cert_file_path(paths, filename)?;
     let certfile = File::open(cert_path)?;
     let mut reader = BufReader::new(certfile);
     let certs = rustls_pemfile::certs(&mut reader)?;
@@ -170,8 +170,8 @@ fn load_certs(app_handle: &tauri::AppHandle, filename: &str) -> Result<Vec<Certi
 }
 
 /// Load private key from PEM file
-fn load_private_key(app_handle: &tauri::AppHandle, filename: &str) -> Result<PrivateKeyDer<'static>, Box<dyn std::error::Error>> {
-    let key_path = get_cert_file_path(app_handle, filename)?;
+fn load_private_key(paths: &dyn PathProvider, filename: &str) -> Result<PrivateKeyDer<'static>, Box<dyn std::error::Error>> {
+    let key_path = get_cert_file_path(paths, filename)?;
     let keyfile = File::open(key_path)?;
     let mut reader = BufReader::new(keyfile);
     let mut keys = rustls_pemfile::pkcs8_private_keys(&mut reader)?;
@@ -179,20 +179,20 @@ fn load_private_key(app_handle: &tauri::AppHandle, filename: &str) -> Result<Pri
 }
 
 /// Ensure certificate exists and is valid
-async fn ensure_valid_certificate(app_handle: &tauri::AppHandle) -> Result<(), Box<dyn std::error::Error>> {
+async fn ensure_valid_certificate(paths: &dyn PathProvider) -> Result<(), Box<dyn std::error::Error>> {
     // Check if certificate files exist
-    if !certificate_files_exist(app_handle)? {
+    if !certificate_files_exist(paths)? {
         println!("📜 No existing certificate found, generating new one...");
         let (cert_pem, key_pem, expiry) = generate_self_signed_cert()?;
-        save_certificate_files(app_handle, cert_pem, key_pem, expiry)?;
+        save_certificate_files(paths, cert_pem, key_pem, expiry)?;
         return Ok(());
     }
     
     // Check if certificate needs renewal
-    if certificate_needs_renewal(app_handle)? {
+    if certificate_needs_renewal(paths)? {
         println!("🔄 Certificate needs renewal, generating new one...");
         let (cert_pem, key_pem, expiry) = generate_self_signed_cert()?;
-        save_certificate_files(app_handle, cert_pem, key_pem, expiry)?;
+        save_certificate_files(paths, cert_pem, key_pem, expiry)?;
     } else {
         println!("✅ Existing certificate is valid");
     }
@@ -201,7 +201,7 @@ async fn ensure_valid_certificate(app_handle: &tauri::AppHandle) -> Result<(), B
 }
 
 /// Periodic certificate renewal check
-async fn periodic_certificate_check(app_handle: tauri::AppHandle) {
+async fn periodic_certificate_check(paths: Arc<dyn PathProvider>) {
     let mut interval = interval(Duration::from_secs(PERIODIC_CHECK_INTERVAL_HOURS * 3600));
     
     loop {
@@ -209,7 +209,7 @@ async fn periodic_certificate_check(app_handle: tauri::AppHandle) {
         
         println!("🔍 Performing periodic certificate check...");
         
-        if let Err(e) = ensure_valid_certificate(&app_handle).await {
+        if let Err(e) = ensure_valid_certificate(paths.as_ref()).await {
             eprintln!("❌ Error during periodic certificate check: {}", e);
         } else {
             println!("✅ Periodic certificate check completed successfully");
@@ -219,25 +219,23 @@ async fn periodic_certificate_check(app_handle: tauri::AppHandle) {
 
 /// Start the HTTPS server for network access
 pub async fn start_https_server(app_state: crate::api::state::AppState) -> Result<u16, Box<dyn std::error::Error>> {
-    // Get the app handle
-    let app_handle_guard = app_state.app_handle.lock().await;
-    let app_handle = app_handle_guard.as_ref().ok_or("App handle not available")?.clone();
-    drop(app_handle_guard); // Release the lock
+    let paths = app_state.paths.clone();
     
     // Ensure we have a valid certificate
-    ensure_valid_certificate(&app_handle).await?;
+    ensure_valid_certificate(paths.as_ref()).await?;
     
     // Start periodic certificate check
-    tokio::spawn(periodic_certificate_check(app_handle.clone()));
+    tokio::spawn(periodic_certificate_check(paths.clone()));
     
     // Load certificates and private key
-    let certs = load_certs(&app_handle, CERT_FILE)?;
-    let key = load_private_key(&app_handle, KEY_FILE)?;
+    let certs = load_certs(paths.as_ref(), CERT_FILE)?;
+    let key = load_private_key(paths.as_ref(), KEY_FILE)?;
     
-    // Create TLS configuration
-    let tls_config = ServerConfig::builder()
+    // Create TLS configuration, offering HTTP/2 to clients that support it
+    let mut tls_config = ServerConfig::builder()
         .with_no_client_auth()
         .with_single_cert(certs, key)?;
+    tls_config.alpn_protocols = vec![b"h2".to_vec(), b"http/1.1".to_vec()];
     
     let tls_config = Arc::new(tls_config);
     let tls_acceptor = TlsAcceptor::from(tls_config);
@@ -267,16 +265,41 @@ pub async fn start_https_server(app_state: crate::api::state::AppState) -> Resul
     for interface in &interfaces {
         println!("     https://{}:{}/", interface, port);
     }
-    println!("   Certificate stored in: {}", get_cert_data_dir(&app_handle)?.display());
+    println!("   Certificate stored in: {}", get_cert_data_dir(paths.as_ref())?.display());
     println!("   Periodic renewal check every {} hours", PERIODIC_CHECK_INTERVAL_HOURS);
     
-    // Create router and add routes
+    // Create router and add routes (routes require a valid token unless added as public)
     let mut router = Router::new();
-    router.add_route("POST", "/api/login", handle_login);
-    router.add_route("GET", "/api/token*", handle_token_check);
-    router.add_route("GET", "/api/ping", handle_ping);
+    router.add_public_route("POST", "/api/login", handle_login);
+    router.add_public_route("GET", "/api/token*", handle_token_check);
+    router.add_public_route("GET", "/api/ping", handle_ping);
     router.add_route("GET", "/api/index/{index_id}/icon", handle_index_icon);
-    router.add_route("GET", "*", handle_static_files);
+    router.add_route("GET", "/api/index/{index_id}/items", handle_index_items);
+    router.add_route("GET", "/api/search", handle_search);
+    router.add_route("GET", "/api/item/{item_id}", handle_item_details);
+    router.add_route("GET", "/api/item/{item_id}/artwork/{kind}", handle_item_artwork);
+    router.add_route("POST", "/api/item/{item_id}/metadata/refresh", handle_refresh_item_metadata);
+    router.add_route("GET", "/api/part/{part_id}/stream", handle_part_stream);
+    router.add_route("GET", "/api/subtitle/{subtitle_id}", handle_subtitle);
+    router.add_route("POST", "/api/part/{part_id}/transcode", handle_start_transcode);
+    router.add_public_route("GET", "/api/transcode/{session_id}/{file}", handle_transcode_file);
+    router.add_route("DELETE", "/api/transcode/{session_id}", handle_stop_transcode);
+    router.add_route("GET", "/api/index/{index_id}/photos", handle_index_photos);
+    router.add_route("GET", "/api/index/{index_id}/albums", handle_index_albums);
+    router.add_route("GET", "/api/photo/{photo_id}/original", handle_photo_original);
+    router.add_route("GET", "/api/photo/{photo_id}/thumbnail", handle_photo_thumbnail);
+    router.add_route("GET", "/api/index/{index_id}/music", handle_index_music);
+    router.add_route("GET", "/api/music/{item_id}", handle_music_item_details);
+    router.add_route("GET", "/api/music/{item_id}/cover", handle_music_cover);
+    router.add_route("GET", "/api/track/{track_id}/stream", handle_track_stream);
+    // Profiles are shared household labels without credentials: any valid token may use any profile
+    router.add_route("POST", "/api/profile/{profile_id}/item/{item_id}/progress", handle_report_progress);
+    router.add_route("POST", "/api/profile/{profile_id}/item/{item_id}/watched", handle_mark_watched);
+    router.add_route("DELETE", "/api/profile/{profile_id}/item/{item_id}/watched", handle_mark_watched);
+    router.add_route("GET", "/api/profile/{profile_id}/continue-watching", handle_watch_hub);
+    router.add_route("GET", "/api/profile/{profile_id}/next-up", handle_watch_hub);
+    router.add_public_route("GET", "*", handle_static_files);
+    let router = Arc::new(router);
     
     // Accept connections and handle them
     loop {
@@ -288,7 +311,7 @@ pub async fn start_https_server(app_state: crate::api::state::AppState) -> Resul
                 tokio::spawn(async move {
                     match tls_acceptor.accept(stream).await {
                         Ok(tls_stream) => {
-                            if let Err(e) = handle_connection_with_router(tls_stream, &router).await {
+                            if let Err(e) = handle_connection_with_router(tls_stream, Some(addr.ip().to_string()), router).await {
                                 eprintln!("Error handling connection from {}: {}", addr, e);
                             }
                         }
@@ -1,11 +1,15 @@
 use crate::models::config::{IncomingMediaIndex, IndexUpdateRequest};
-use crate::api::responses::IndexResponse;
-use crate::db::repos::IndexesRepo;
+use crate::api::responses::{IndexResponse, ScanJobResponse, ScanRunResponse};
+use crate::db::repos::{IndexesRepo, ScanJobsRepo, ScanRunsRepo};
+use crate::scanning::{subscribe_scan_events, cancel_scan, set_scan_paused};
 use crate::api::state::AppState;
 use crate::config::{config_path, icons_dir};
+use crate::constants::SCAN_RUNS_KEPT;
 use crate::utils::image::detect_image_extension;
 use base64::{Engine as _, engine::general_purpose};
 use tokio::fs;
+use tokio_stream::wrappers::BroadcastStream;
+use tokio_stream::StreamExt;
 use warp::reject::custom;
 
 // Custom error types for index operations
@@ -66,17 +70,16 @@ pub async fn handle_create_local_index(
         ));
     }
 
-    // Get the app handle
-    let app_handle_guard = app_state.app_handle.lock().await;
-    let app_handle = app_handle_guard.as_ref().ok_or_else(|| custom(IndexError))?;
+    // Get the app data paths
+    let paths = app_state.paths.clone();
     
-    let icons_dir = icons_dir(app_handle)
+    let icons_dir = icons_dir(paths.as_ref())
         .map_err(|e| {
             eprintln!("Failed to get icons directory: {}", e);
             custom(IndexError)
         })?;
     
-    let config_path = config_path(app_handle)
+    let config_path = config_path(paths.as_ref())
         .map_err(|e| {
             eprintln!("Failed to get config path: {}", e);
             custom(IndexError)
@@ -199,17 +202,28 @@ pub async fn handle_update_index(
         ));
     }
 
-    // Get the app handle
-    let app_handle_guard = app_state.app_handle.lock().await;
-    let app_handle = app_handle_guard.as_ref().ok_or_else(|| custom(IndexError))?;
+    if let Some(scan_schedule) = &index_request.scan_schedule {
+        if let Err(message) = scan_schedule.validate() {
+            return Ok(warp::reply::with_status(
+                warp::reply::json(&serde_json::json!({
+                    "success": false,
+                    "error": message
+                })),
+                warp::http::StatusCode::BAD_REQUEST,
+            ));
+        }
+    }
+
+    // Get the app data paths
+    let paths = app_state.paths.clone();
     
-    let icons_dir = icons_dir(app_handle)
+    let icons_dir = icons_dir(paths.as_ref())
         .map_err(|e| {
             eprintln!("Failed to get icons directory: {}", e);
             custom(IndexError)
         })?;
     
-    let config_path = config_path(app_handle)
+    let config_path = config_path(paths.as_ref())
         .map_err(|e| {
             eprintln!("Failed to get config path: {}", e);
             custom(IndexError)
@@ -277,9 +291,11 @@ pub async fn handle_update_index(
                  existing_index.name, index_id, extension, icon_path);
     }
     
-    // Prepare updated metadata
+    // Prepare updated metadata, keeping the current schedule unless a new one was sent
+    let scan_schedule = index_request.scan_schedule.unwrap_or_else(|| existing_index.scan_schedule());
     let metadata = serde_json::json!({
         "folders": index_request.folders,
+        "scan_schedule": scan_schedule,
     });
     
     // Update the index
@@ -322,12 +338,10 @@ pub async fn handle_delete_index(
     app_state: AppState,
     index_id: String,
 ) -> Result<impl warp::reply::Reply, warp::Rejection> {
-    // Get the app handle from app state
-    let app_handle_guard = app_state.app_handle.lock().await;
-    let app_handle = app_handle_guard.as_ref().ok_or_else(|| custom(IndexError))?.clone();
-    drop(app_handle_guard); // Release the lock
+    // Get the app data paths from app state
+    let paths = app_state.paths.clone();
     
-    let config_path = config_path(&app_handle)
+    let config_path = config_path(paths.as_ref())
         .map_err(|e| {
             eprintln!("Failed to get config path: {}", e);
             custom(IndexError)
@@ -368,7 +382,7 @@ pub async fn handle_delete_index(
         })?;
     
     // Try to remove associated icon file if it exists
-    let icons_dir = icons_dir(&app_handle)
+    let icons_dir = icons_dir(paths.as_ref())
         .map_err(|e| {
             eprintln!("Failed to get icons directory: {}", e);
             custom(IndexError)
@@ -491,3 +505,160 @@ pub async fn handle_queue_index_scan(
         }
     }
 }
+
+// Handler for getting the progress of the latest scan of an index
+pub async fn handle_get_scan_job(
+    app_state: AppState,
+    index_id: String,
+) -> Result<impl warp::reply::Reply, warp::Rejection> {
+    // Parse index_id as i64
+    let index_id = index_id.parse::<i64>()
+        .map_err(|_| {
+            eprintln!("Invalid index ID format");
+            custom(IndexError)
+        })?;
+    
+    let indexes_repo = IndexesRepo::new(app_state.db_pool.clone());
+    let index = indexes_repo.get_index_by_id(index_id).await
+        .map_err(|e| {
+            eprintln!("Failed to fetch index: {}", e);
+            custom(IndexError)
+        })?;
+    
+    if index.is_none() {
+        return Ok(warp::reply::with_status(
+            warp::reply::json(&serde_json::json!({
+                "success": false,
+                "error": "Index not found",
+                "message": "The specified index does not exist"
+            })),
+            warp::http::StatusCode::NOT_FOUND,
+        ));
+    }
+    
+    let scan_jobs_repo = ScanJobsRepo::new(app_state.db_pool.clone());
+    let scan_job = scan_jobs_repo.get_scan_job(index_id).await
+        .map_err(|e| {
+            eprintln!("Failed to fetch scan job: {}", e);
+            custom(IndexError)
+        })?;
+    
+    // scan_job is null until the index is scanned for the first time
+    Ok(warp::reply::with_status(
+        warp::reply::json(&serde_json::json!({
+            "success": true,
+            "scan_job": scan_job.map(ScanJobResponse::from)
+        })),
+        warp::http::StatusCode::OK,
+    ))
+}
+
+// Handler for the Server-Sent Events stream of scan progress of all indexes
+pub async fn handle_scan_events() -> Result<impl warp::reply::Reply, warp::Rejection> {
+    let events = BroadcastStream::new(subscribe_scan_events())
+        .filter_map(|update| {
+            // Lagging clients skip missed updates; every update carries the full progress anyway
+            update.ok().map(|scan_job| {
+                warp::sse::Event::default()
+                    .event("scan-job")
+                    .json_data(ScanJobResponse::from(scan_job))
+            })
+        });
+    
+    Ok(warp::sse::reply(warp::sse::keep_alive().stream(events)))
+}
+
+// Handler for cancelling the running scan of an index
+pub async fn handle_cancel_index_scan(index_id: String) -> Result<impl warp::reply::Reply, warp::Rejection> {
+    // Parse index_id as i64
+    let index_id = index_id.parse::<i64>()
+        .map_err(|_| {
+            eprintln!("Invalid index ID format");
+            custom(IndexError)
+        })?;
+    
+    // The scan stops at its next checkpoint, so the cancellation is only requested here
+    if !cancel_scan(index_id) {
+        return Ok(no_running_scan_reply());
+    }
+    
+    println!("Cancellation requested for scan of index {}", index_id);
+    
+    Ok(warp::reply::with_status(
+        warp::reply::json(&serde_json::json!({
+            "success": true,
+            "message": "Scan cancellation requested"
+        })),
+        warp::http::StatusCode::ACCEPTED,
+    ))
+}
+
+// Handler for pausing or resuming the running scan of an index
+pub async fn handle_set_index_scan_paused(
+    index_id: String,
+    paused: bool,
+) -> Result<impl warp::reply::Reply, warp::Rejection> {
+    // Parse index_id as i64
+    let index_id = index_id.parse::<i64>()
+        .map_err(|_| {
+            eprintln!("Invalid index ID format");
+            custom(IndexError)
+        })?;
+    
+    if !set_scan_paused(index_id, paused) {
+        return Ok(no_running_scan_reply());
+    }
+    
+    println!("Scan of index {} {}", index_id, if paused { "paused" } else { "resumed" });
+    
+    Ok(warp::reply::with_status(
+        warp::reply::json(&serde_json::json!({
+            "success": true,
+            "message": if paused { "Scan paused" } else { "Scan resumed" }
+        })),
+        warp::http::StatusCode::OK,
+    ))
+}
+
+// Reply for scan controls of an index that isn't being scanned
+fn no_running_scan_reply() -> warp::reply::WithStatus<warp::reply::Json> {
+    warp::reply::with_status(
+        warp::reply::json(&serde_json::json!({
+            "success": false,
+            "error": "No running scan",
+            "message": "This index is not currently being scanned"
+        })),
+        warp::http::StatusCode::CONFLICT,
+    )
+}
+
+// Handler for getting the scan history of an index
+pub async fn handle_get_scan_runs(
+    app_state: AppState,
+    index_id: String,
+) -> Result<impl warp::reply::Reply, warp::Rejection> {
+    // Parse index_id as i64
+    let index_id = index_id.parse::<i64>()
+        .map_err(|_| {
+            eprintln!("Invalid index ID format");
+            custom(IndexError)
+        })?;
+    
+    let scan_runs_repo = ScanRunsRepo::new(app_state.db_pool.clone());
+    // Full and incremental runs are kept separately
+    let scan_runs = scan_runs_repo.get_scan_runs(index_id, SCAN_RUNS_KEPT * 2).await
+        .map_err(|e| {
+            eprintln!("Failed to fetch scan runs: {}", e);
+            custom(IndexError)
+        })?;
+    
+    let scan_runs: Vec<ScanRunResponse> = scan_runs.into_iter().map(ScanRunResponse::from).collect();
+    
+    Ok(warp::reply::with_status(
+        warp::reply::json(&serde_json::json!({
+            "success": true,
+            "scan_runs": scan_runs
+        })),
+        warp::http::StatusCode::OK,
+    ))
+}
@@ -9,6 +9,8 @@ pub mod controllers;
 pub mod responses;
 pub mod profiles;
 pub mod indexes;
+pub mod sessions;
+pub mod backups;
 
 pub use config::*;
 pub use folders::*;
@@ -20,4 +22,6 @@ pub use router::*;
 pub use responses::*;
 pub use profiles::*;
 pub use indexes::*;
+pub use sessions::*;
+pub use backups::*;
 pub use controllers::{handle_login, handle_token_check, handle_ping, handle_static_files};
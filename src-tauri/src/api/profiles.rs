@@ -65,12 +65,11 @@ pub async fn handle_create_profile(
         ));
     }
 
-    // Get the app handle
-    let app_handle_guard = app_state.app_handle.lock().await;
-    let app_handle = app_handle_guard.as_ref().ok_or_else(|| custom(ProfileError))?;
+    // Get the app data paths
+    let paths = app_state.paths.clone();
     
     // Get the config file path using OS app data directory
-    let config_path = config_path(app_handle)
+    let config_path = config_path(paths.as_ref())
         .map_err(|e| {
             eprintln!("Failed to get config path: {}", e);
             custom(ProfileError)
@@ -151,12 +150,11 @@ pub async fn handle_update_profile(
         ));
     }
 
-    // Get the app handle
-    let app_handle_guard = app_state.app_handle.lock().await;
-    let app_handle = app_handle_guard.as_ref().ok_or_else(|| custom(ProfileError))?;
+    // Get the app data paths
+    let paths = app_state.paths.clone();
     
     // Get the config file path using OS app data directory
-    let config_path = config_path(app_handle)
+    let config_path = config_path(paths.as_ref())
         .map_err(|e| {
             eprintln!("Failed to get config path: {}", e);
             custom(ProfileError)
@@ -235,12 +233,11 @@ pub async fn handle_delete_profile(
     app_state: AppState,
     profile_id: String,
 ) -> Result<impl warp::reply::Reply, warp::Rejection> {
-    // Get the app handle
-    let app_handle_guard = app_state.app_handle.lock().await;
-    let app_handle = app_handle_guard.as_ref().ok_or_else(|| custom(ProfileError))?;
+    // Get the app data paths
+    let paths = app_state.paths.clone();
     
     // Get the config file path using OS app data directory
-    let config_path = config_path(app_handle)
+    let config_path = config_path(paths.as_ref())
         .map_err(|e| {
             eprintln!("Failed to get config path: {}", e);
             custom(ProfileError)
@@ -1,5 +1,7 @@
 use serde::Serialize;
-use crate::db::models::{Profile as DbProfile, Index as DbIndex};
+use std::collections::BTreeMap;
+use crate::models::config::ScanSchedule;
+use crate::db::models::{TokenSession as DbTokenSession, Profile as DbProfile, Index as DbIndex, VideoItem as DbVideoItem, VideoVersion as DbVideoVersion, VideoPart as DbVideoPart, VideoStream as DbVideoStream, VideoArtwork as DbVideoArtwork, Subtitle as DbSubtitle, ScanJob as DbScanJob, ScanRun as DbScanRun, WatchState as DbWatchState, PhotoItem as DbPhotoItem, PhotoAlbum as DbPhotoAlbum, MusicItem as DbMusicItem, MusicFile as DbMusicFile};
 
 /// Database-based configuration response that fetches profiles and indexes from database
 #[derive(Debug, Serialize)]
@@ -28,6 +30,28 @@ impl From<DbProfile> for ProfileResponse {
     }
 }
 
+/// Client session response structure
+#[derive(Debug, Serialize)]
+pub struct SessionResponse {
+    pub id: String,
+    pub user_agent: String,
+    pub ip_address: Option<String>,
+    pub created_at: i64,
+    pub last_used_at: i64,
+}
+
+impl From<DbTokenSession> for SessionResponse {
+    fn from(session: DbTokenSession) -> Self {
+        Self {
+            id: session.id.to_string(),
+            user_agent: session.user_agent.unwrap_or_else(|| "Unknown".to_string()),
+            ip_address: session.ip_address,
+            created_at: session.created_at,
+            last_used_at: session.last_used_at,
+        }
+    }
+}
+
 /// Index response structure matching the old API format
 #[derive(Debug, Serialize)]
 pub struct IndexResponse {
@@ -36,6 +60,7 @@ pub struct IndexResponse {
     pub r#type: String,
     pub icon: String,
     pub folders: Vec<String>,
+    pub scan_schedule: ScanSchedule,
     pub scan_status: String,
     pub last_scanned_at: i64,
 }
@@ -56,6 +81,7 @@ impl From<DbIndex> for IndexResponse {
         } else {
             Vec::new()
         };
+        let scan_schedule = index.scan_schedule();
         
         Self {
             id: index.id.to_string(),
@@ -63,12 +89,89 @@ impl From<DbIndex> for IndexResponse {
             r#type: index.r#type,
             icon: index.icon.unwrap_or_else(|| "custom".to_string()),
             folders,
+            scan_schedule,
             scan_status: index.scan_status,
             last_scanned_at: index.last_scanned_at,
         }
     }
 }
 
+/// Scan progress response for the latest scan of an index
+#[derive(Debug, Serialize)]
+pub struct ScanJobResponse {
+    pub index_id: String,
+    pub kind: String,
+    pub status: String,
+    pub files_discovered: i64,
+    pub files_processed: i64,
+    pub current_folder: Option<String>,
+    pub items_added: i64,
+    pub items_updated: i64,
+    pub items_removed: i64,
+    pub errors: i64,
+    pub eta_secs: Option<i64>,
+    pub started_at: i64,
+    pub updated_at: i64,
+    pub finished_at: Option<i64>,
+}
+
+impl From<DbScanJob> for ScanJobResponse {
+    fn from(scan_job: DbScanJob) -> Self {
+        Self {
+            index_id: scan_job.index_id.to_string(),
+            eta_secs: scan_job.eta_secs(),
+            kind: scan_job.kind,
+            status: scan_job.status,
+            files_discovered: scan_job.files_discovered,
+            files_processed: scan_job.files_processed,
+            current_folder: scan_job.current_folder,
+            items_added: scan_job.items_added,
+            items_updated: scan_job.items_updated,
+            items_removed: scan_job.items_removed,
+            errors: scan_job.errors,
+            started_at: scan_job.started_at,
+            updated_at: scan_job.updated_at,
+            finished_at: scan_job.finished_at,
+        }
+    }
+}
+
+/// Scan run response for the scan history of an index
+#[derive(Debug, Serialize)]
+pub struct ScanRunResponse {
+    pub id: String,
+    pub kind: String,
+    pub outcome: String,
+    pub files_discovered: i64,
+    pub files_processed: i64,
+    pub items_added: i64,
+    pub items_updated: i64,
+    pub items_removed: i64,
+    pub errors: i64,
+    pub error_message: Option<String>,
+    pub started_at: i64,
+    pub finished_at: Option<i64>,
+}
+
+impl From<DbScanRun> for ScanRunResponse {
+    fn from(scan_run: DbScanRun) -> Self {
+        Self {
+            id: scan_run.id.to_string(),
+            kind: scan_run.kind,
+            outcome: scan_run.outcome,
+            files_discovered: scan_run.files_discovered,
+            files_processed: scan_run.files_processed,
+            items_added: scan_run.items_added,
+            items_updated: scan_run.items_updated,
+            items_removed: scan_run.items_removed,
+            errors: scan_run.errors,
+            error_message: scan_run.error_message,
+            started_at: scan_run.started_at,
+            finished_at: scan_run.finished_at,
+        }
+    }
+}
+
 /// Filtered index response for auth endpoints (only id, name, type, icon)
 #[derive(Debug, Serialize)]
 pub struct FilteredIndexResponse {
@@ -88,3 +191,336 @@ impl From<DbIndex> for FilteredIndexResponse {
         }
     }
 }
+
+
+/// Video item response structure for media browsing endpoints
+#[derive(Debug, Serialize)]
+pub struct VideoItemResponse {
+    pub id: String,
+    pub index_id: String,
+    pub r#type: String,
+    pub parent_id: Option<String>,
+    pub title: String,
+    pub sort_title: Option<String>,
+    pub year: Option<i64>,
+    pub number: Option<i64>,
+    pub metadata: serde_json::Value,
+    pub added_at: i64,
+    pub latest_added_at: i64,
+    /// Artwork URLs keyed by kind ("poster", "fanart", "thumb")
+    pub artwork: BTreeMap<String, String>,
+    /// Watch state of the requested profile, None when no profile was given
+    pub watch_state: Option<WatchStateResponse>,
+}
+
+impl VideoItemResponse {
+    /// Add the URLs of the artwork stored for the item
+    pub fn with_artwork(mut self, artwork: Vec<DbVideoArtwork>) -> Self {
+        for artwork in artwork {
+            let url = format!("/api/item/{}/artwork/{}", self.id, artwork.kind);
+            self.artwork.insert(artwork.kind, url);
+        }
+        self
+    }
+    
+    /// Add the watch state of the requested profile (unwatched when nothing was stored yet)
+    pub fn with_watch_state(mut self, watch_state: Option<DbWatchState>) -> Self {
+        self.watch_state = Some(watch_state.map(WatchStateResponse::from).unwrap_or_default());
+        self
+    }
+}
+
+impl From<DbVideoItem> for VideoItemResponse {
+    fn from(item: DbVideoItem) -> Self {
+        let metadata = item.metadata_json().unwrap_or_else(|_| serde_json::json!({}));
+        
+        Self {
+            id: item.id.to_string(),
+            index_id: item.index_id.to_string(),
+            r#type: item.r#type,
+            parent_id: item.parent_id.map(|id| id.to_string()),
+            title: item.title,
+            sort_title: item.sort_title,
+            year: item.year,
+            number: item.number,
+            metadata,
+            added_at: item.added_at,
+            latest_added_at: item.latest_added_at,
+            artwork: BTreeMap::new(),
+            watch_state: None,
+        }
+    }
+}
+
+/// Watch state response structure of a video item for a profile
+#[derive(Debug, Default, Serialize)]
+pub struct WatchStateResponse {
+    pub position_ms: i64,
+    pub watched: bool,
+    pub play_count: i64,
+    pub last_watched_at: Option<i64>,
+}
+
+impl From<DbWatchState> for WatchStateResponse {
+    fn from(watch_state: DbWatchState) -> Self {
+        Self {
+            position_ms: watch_state.position_ms,
+            watched: watch_state.watched != 0,
+            play_count: watch_state.play_count,
+            last_watched_at: watch_state.last_watched_at,
+        }
+    }
+}
+
+/// Video version response structure including its parts
+#[derive(Debug, Serialize)]
+pub struct VideoVersionResponse {
+    pub id: String,
+    pub edition: Option<String>,
+    pub source: Option<String>,
+    pub container: Option<String>,
+    pub resolution: Option<String>,
+    pub hdr: bool,
+    pub audio_channels: Option<i64>,
+    pub bitrate: Option<i64>,
+    pub runtime_ms: Option<i64>,
+    pub parts: Vec<VideoPartResponse>,
+}
+
+impl VideoVersionResponse {
+    pub fn new(version: DbVideoVersion, parts: Vec<VideoPartResponse>) -> Self {
+        Self {
+            id: version.id.to_string(),
+            edition: version.edition,
+            source: version.source,
+            container: version.container,
+            resolution: version.resolution,
+            hdr: version.hdr != 0,
+            audio_channels: version.audio_channels,
+            bitrate: version.bitrate,
+            runtime_ms: version.runtime_ms,
+            parts,
+        }
+    }
+}
+
+/// Video part response structure (file paths are not exposed, only a stream URL)
+#[derive(Debug, Serialize)]
+pub struct VideoPartResponse {
+    pub id: String,
+    pub part_index: i64,
+    pub file_name: String,
+    pub size: Option<i64>,
+    pub duration_ms: Option<i64>,
+    pub stream_url: String,
+    pub streams: Vec<VideoStreamResponse>,
+    pub subtitles: Vec<SubtitleResponse>,
+}
+
+impl VideoPartResponse {
+    pub fn new(part: DbVideoPart, streams: Vec<DbVideoStream>, subtitles: Vec<DbSubtitle>) -> Self {
+        let file_name = std::path::Path::new(&part.path)
+            .file_name()
+            .and_then(|name| name.to_str())
+            .unwrap_or("")
+            .to_string();
+        
+        Self {
+            id: part.id.to_string(),
+            part_index: part.part_index,
+            file_name,
+            size: part.size,
+            duration_ms: part.duration_ms,
+            stream_url: format!("/api/part/{}/stream", part.id),
+            streams: streams.into_iter().map(VideoStreamResponse::from).collect(),
+            subtitles: subtitles.into_iter().map(SubtitleResponse::from).collect(),
+        }
+    }
+}
+
+/// Video/audio/subtitle stream response structure
+#[derive(Debug, Serialize)]
+pub struct VideoStreamResponse {
+    pub index: i64,
+    pub kind: String,
+    pub codec: Option<String>,
+    pub language: Option<String>,
+    pub title: Option<String>,
+    pub channels: Option<i64>,
+    pub width: Option<i64>,
+    pub height: Option<i64>,
+    pub is_default: bool,
+    pub is_forced: bool,
+}
+
+impl From<DbVideoStream> for VideoStreamResponse {
+    fn from(stream: DbVideoStream) -> Self {
+        Self {
+            index: stream.stream_index,
+            kind: stream.kind,
+            codec: stream.codec,
+            language: stream.language,
+            title: stream.title,
+            channels: stream.channels,
+            width: stream.width,
+            height: stream.height,
+            is_default: stream.is_default != 0,
+            is_forced: stream.is_forced != 0,
+        }
+    }
+}
+
+/// Subtitle response structure (file paths are not exposed, only the original and WebVTT URLs)
+#[derive(Debug, Serialize)]
+pub struct SubtitleResponse {
+    pub id: String,
+    pub source: String,
+    pub format: String,
+    pub language: Option<String>,
+    pub title: Option<String>,
+    pub is_default: bool,
+    pub is_forced: bool,
+    pub is_sdh: bool,
+    pub url: String,
+    /// Only set for formats that can be converted to WebVTT for browser players
+    pub vtt_url: Option<String>,
+}
+
+impl From<DbSubtitle> for SubtitleResponse {
+    fn from(subtitle: DbSubtitle) -> Self {
+        let vtt_url = matches!(subtitle.format.as_str(), "srt" | "vtt")
+            .then(|| format!("/api/subtitle/{}?format=vtt", subtitle.id));
+        
+        Self {
+            id: subtitle.id.to_string(),
+            source: subtitle.source,
+            format: subtitle.format,
+            language: subtitle.language,
+            title: subtitle.title,
+            is_default: subtitle.is_default != 0,
+            is_forced: subtitle.is_forced != 0,
+            is_sdh: subtitle.is_sdh != 0,
+            url: format!("/api/subtitle/{}", subtitle.id),
+            vtt_url,
+        }
+    }
+}
+
+/// Photo response structure (file paths are not exposed, only original and thumbnail URLs)
+#[derive(Debug, Serialize)]
+pub struct PhotoItemResponse {
+    pub id: String,
+    pub index_id: String,
+    pub album_id: Option<String>,
+    pub file_name: String,
+    pub size: Option<i64>,
+    pub width: Option<i64>,
+    pub height: Option<i64>,
+    pub taken_at: i64,
+    pub camera_make: Option<String>,
+    pub camera_model: Option<String>,
+    pub latitude: Option<f64>,
+    pub longitude: Option<f64>,
+    pub orientation: Option<i64>,
+    pub original_url: String,
+    pub thumbnail_url: Option<String>,
+}
+
+impl From<DbPhotoItem> for PhotoItemResponse {
+    fn from(photo: DbPhotoItem) -> Self {
+        let file_name = std::path::Path::new(&photo.path)
+            .file_name()
+            .and_then(|name| name.to_str())
+            .unwrap_or("")
+            .to_string();
+        
+        Self {
+            id: photo.id.to_string(),
+            index_id: photo.index_id.to_string(),
+            album_id: photo.album_id.map(|id| id.to_string()),
+            file_name,
+            size: photo.size,
+            width: photo.width,
+            height: photo.height,
+            taken_at: photo.taken_at,
+            camera_make: photo.camera_make,
+            camera_model: photo.camera_model,
+            latitude: photo.latitude,
+            longitude: photo.longitude,
+            orientation: photo.orientation,
+            original_url: format!("/api/photo/{}/original", photo.id),
+            thumbnail_url: (photo.has_thumbnail != 0).then(|| format!("/api/photo/{}/thumbnail", photo.id)),
+        }
+    }
+}
+
+/// Photo album response structure with its photo count and cover
+#[derive(Debug, Serialize)]
+pub struct PhotoAlbumResponse {
+    pub id: String,
+    pub parent_id: Option<String>,
+    pub title: String,
+    pub photo_count: i64,
+    pub cover_thumbnail_url: Option<String>,
+}
+
+impl PhotoAlbumResponse {
+    pub fn new(album: DbPhotoAlbum, photo_count: i64, cover_photo_id: Option<i64>) -> Self {
+        Self {
+            id: album.id.to_string(),
+            parent_id: album.parent_id.map(|id| id.to_string()),
+            title: album.title,
+            photo_count,
+            cover_thumbnail_url: cover_photo_id.map(|id| format!("/api/photo/{}/thumbnail", id)),
+        }
+    }
+}
+
+/// Music item response structure (artist, album or track)
+/// Tracks include their file information and a stream URL
+#[derive(Debug, Serialize)]
+pub struct MusicItemResponse {
+    pub id: String,
+    pub index_id: String,
+    pub r#type: String,
+    pub parent_id: Option<String>,
+    pub title: String,
+    pub sort_title: Option<String>,
+    pub year: Option<i64>,
+    pub number: Option<i64>,
+    pub disc_number: Option<i64>,
+    pub genre: Option<String>,
+    pub cover_url: Option<String>,
+    pub duration_ms: Option<i64>,
+    pub codec: Option<String>,
+    pub sample_rate: Option<i64>,
+    pub channels: Option<i64>,
+    pub stream_url: Option<String>,
+    pub added_at: i64,
+}
+
+impl MusicItemResponse {
+    pub fn new(item: DbMusicItem, file: Option<DbMusicFile>) -> Self {
+        let stream_url = file.as_ref().map(|_| format!("/api/track/{}/stream", item.id));
+        
+        Self {
+            id: item.id.to_string(),
+            index_id: item.index_id.to_string(),
+            r#type: item.r#type,
+            parent_id: item.parent_id.map(|id| id.to_string()),
+            title: item.title,
+            sort_title: item.sort_title,
+            year: item.year,
+            number: item.number,
+            disc_number: item.disc_number,
+            genre: item.genre,
+            cover_url: (item.has_cover != 0).then(|| format!("/api/music/{}/cover", item.id)),
+            duration_ms: file.as_ref().and_then(|file| file.duration_ms),
+            codec: file.as_ref().and_then(|file| file.codec.clone()),
+            sample_rate: file.as_ref().and_then(|file| file.sample_rate),
+            channels: file.as_ref().and_then(|file| file.channels),
+            stream_url,
+            added_at: item.added_at,
+        }
+    }
+}
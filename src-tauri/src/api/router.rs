@@ -1,21 +1,116 @@
-use tokio::io::{AsyncReadExt, AsyncWriteExt};
-use tokio_rustls::server::TlsStream;
-use tokio::net::TcpStream;
+use bytes::Bytes;
+use http_body_util::{BodyExt, Full, Limited, StreamBody};
+use http_body_util::combinators::{BoxBody, UnsyncBoxBody};
+use hyper::body::{Frame, Incoming};
+use hyper::header::{HeaderMap, HeaderName, HeaderValue, CONTENT_LENGTH};
+use hyper::service::service_fn;
+use hyper::{Request, Response, StatusCode};
+use hyper_util::rt::{TokioExecutor, TokioIo};
+use hyper_util::server::conn::auto;
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncSeekExt, AsyncWrite};
+use tokio_stream::StreamExt;
+use tokio_util::io::ReaderStream;
+use std::convert::Infallible;
 use std::future::Future;
+use std::ops::Deref;
+use std::path::PathBuf;
 use std::pin::Pin;
 use std::sync::{Arc, Mutex};
 use std::sync::OnceLock;
 use serde_json;
 use crate::config::config_path;
-use crate::api::controllers::icon::get_app_handle;
+use crate::constants::{FILE_STREAM_CHUNK_BYTES, MAX_JSON_BODY_BYTES};
+use crate::api::controllers::icon::get_paths;
+use crate::utils::token::authenticate_token;
 
 /// HTTP request information
 #[derive(Debug, Clone)]
 pub struct HttpRequest {
     pub method: String,
+    /// Path with the query string, e.g. `/api/part/1/stream?token=abc`
     pub path: String,
-    pub headers: Vec<String>,
-    pub body: Option<String>,
+    pub headers: HeaderMap,
+    pub body: RequestBody,
+    /// IP address of the client, filled in by the connection handler
+    pub remote_addr: Option<String>,
+}
+
+/// Streamed body of a request, as it arrives from the client
+pub type RequestStream = UnsyncBoxBody<Bytes, hyper::Error>;
+
+/// Body of a request, left on the connection until a handler reads it
+/// Handlers of small JSON bodies read it whole with `read_limited`, others take it as a stream
+#[derive(Clone, Default)]
+pub struct RequestBody {
+    stream: Arc<Mutex<Option<RequestStream>>>,
+    /// Length from the Content-Length header, if the client sent one
+    declared_length: Option<u64>,
+}
+
+impl RequestBody {
+    pub fn new(stream: RequestStream, declared_length: Option<u64>) -> Self {
+        Self {
+            stream: Arc::new(Mutex::new(Some(stream))),
+            declared_length,
+        }
+    }
+
+    /// Take the body as a stream, `None` if it was already taken or read
+    pub fn take_stream(&self) -> Option<RequestStream> {
+        self.stream.lock().unwrap().take()
+    }
+
+    /// Read the complete body, which is limited to `MAX_JSON_BODY_BYTES`, `None` if it is empty
+    /// Fails with the response to send, 413 for bodies over the limit
+    pub async fn read_limited(&self) -> Result<Option<Bytes>, HttpResponse> {
+        let too_large = || error_response(413, "Payload Too Large", "The request body is too large");
+        if self.declared_length.is_some_and(|length| length > MAX_JSON_BODY_BYTES as u64) {
+            return Err(too_large());
+        }
+
+        let stream = match self.take_stream() {
+            Some(stream) => stream,
+            None => return Ok(None),
+        };
+        match Limited::new(stream, MAX_JSON_BODY_BYTES).collect().await {
+            Ok(collected) => Ok(Some(collected.to_bytes()).filter(|body| !body.is_empty())),
+            Err(e) if e.is::<http_body_util::LengthLimitError>() => Err(too_large()),
+            Err(_) => Err(error_response(400, "Bad Request", "The request body could not be read")),
+        }
+    }
+}
+
+impl std::fmt::Debug for RequestBody {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("RequestBody")
+            .field("declared_length", &self.declared_length)
+            .finish_non_exhaustive()
+    }
+}
+
+/// HTTP request that passed token authentication
+/// Dereferences to the underlying `HttpRequest`
+#[derive(Debug, Clone)]
+pub struct AuthenticatedRequest {
+    pub request: HttpRequest,
+    /// The (plain) token the request was authenticated with
+    pub token: String,
+}
+
+impl Deref for AuthenticatedRequest {
+    type Target = HttpRequest;
+
+    fn deref(&self) -> &HttpRequest {
+        &self.request
+    }
+}
+
+/// A byte range of a file on disk that is streamed as the response body
+#[derive(Debug, Clone)]
+pub struct FileBody {
+    pub path: PathBuf,
+    pub offset: u64,
+    pub length: u64,
 }
 
 /// HTTP response builder
@@ -24,6 +119,7 @@ pub struct HttpResponse {
     headers: Vec<(String, String)>,
     body: Option<String>,
     binary_body: Option<Vec<u8>>,
+    file_body: Option<FileBody>,
 }
 
 impl HttpResponse {
@@ -33,6 +129,7 @@ impl HttpResponse {
             headers: Vec::new(),
             body: None,
             binary_body: None,
+            file_body: None,
         }
     }
 
@@ -57,109 +154,175 @@ impl HttpResponse {
         self
     }
 
+    /// Stream `length` bytes of the file at `path` starting at `offset` instead of buffering them
+    pub fn with_file_body(mut self, path: PathBuf, offset: u64, length: u64) -> Self {
+        self.file_body = Some(FileBody { path, offset, length });
+        self
+    }
+
     pub fn with_cors(mut self) -> Self {
         self.headers.push(("Access-Control-Allow-Origin".to_string(), "*".to_string()));
-        self.headers.push(("Access-Control-Allow-Methods".to_string(), "GET, POST, OPTIONS".to_string()));
-        self.headers.push(("Access-Control-Allow-Headers".to_string(), "content-type".to_string()));
+        self.headers.push(("Access-Control-Allow-Methods".to_string(), "GET, POST, DELETE, OPTIONS".to_string()));
+        self.headers.push(("Access-Control-Allow-Headers".to_string(), "content-type, range, authorization".to_string()));
         self
     }
 
-    pub async fn send(self, stream: &mut TlsStream<TcpStream>) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
-        let status_line = match self.status_code {
-            200 => "HTTP/1.1 200 OK",
-            400 => "HTTP/1.1 400 Bad Request",
-            401 => "HTTP/1.1 401 Unauthorized",
-            404 => "HTTP/1.1 404 Not Found",
-            500 => "HTTP/1.1 500 Internal Server Error",
-            503 => "HTTP/1.1 503 Service Unavailable",
-            _ => "HTTP/1.1 200 OK",
+    /// Build the hyper response, the file of a file body is opened here and streamed in chunks
+    pub async fn into_response(self) -> Response<ResponseBody> {
+        let body = match &self.file_body {
+            Some(file_body) => match open_file_body(file_body).await {
+                Ok(body) => body,
+                Err(e) => {
+                    eprintln!("Failed to open {:?} for streaming: {}", file_body.path, e);
+                    return HttpResponse::new(500)
+                        .with_cors()
+                        .with_body("Internal Server Error")
+                        .into_buffered_response();
+                }
+            },
+            None => return self.into_buffered_response(),
         };
+        self.response_with_body(body)
+    }
 
-        let mut response = format!("{}\r\n", status_line);
-        
-        // Add headers
-        for (key, value) in &self.headers {
-            response.push_str(&format!("{}: {}\r\n", key, value));
-        }
-        
-        // Add content length
-        let body_len = self.body.as_ref().map_or(0, |b| b.len()) + self.binary_body.as_ref().map_or(0, |b| b.len());
-        response.push_str(&format!("Content-Length: {}\r\n", body_len));
-        response.push_str("\r\n");
-        
-        // Send headers
-        stream.write_all(response.as_bytes()).await?;
-        
-        // Send body if present
-        if let Some(body) = self.body {
-            stream.write_all(body.as_bytes()).await?;
+    /// Build the hyper response of a response without a file body
+    fn into_buffered_response(mut self) -> Response<ResponseBody> {
+        let mut bytes = self.body.take().map(String::into_bytes).unwrap_or_default();
+        if let Some(binary_body) = self.binary_body.take() {
+            bytes.extend_from_slice(&binary_body);
         }
-        
-        // Send binary body if present
-        if let Some(binary_body) = self.binary_body {
-            stream.write_all(&binary_body).await?;
+        self.response_with_body(Full::new(Bytes::from(bytes)).map_err(|never| match never {}).boxed())
+    }
+
+    fn response_with_body(self, body: ResponseBody) -> Response<ResponseBody> {
+        let mut response = Response::new(body);
+        *response.status_mut() = StatusCode::from_u16(self.status_code).unwrap_or(StatusCode::INTERNAL_SERVER_ERROR);
+        for (key, value) in self.headers {
+            match (HeaderName::from_bytes(key.as_bytes()), HeaderValue::from_str(&value)) {
+                (Ok(name), Ok(value)) => {
+                    response.headers_mut().append(name, value);
+                }
+                _ => eprintln!("Skipping invalid response header {}: {}", key, value),
+            }
         }
-        
-        stream.flush().await?;
-        Ok(())
+        response
     }
 }
 
-/// Parse HTTP request from raw bytes
-pub fn parse_http_request(request: &str) -> Option<HttpRequest> {
-    let lines: Vec<&str> = request.lines().collect();
-    if lines.is_empty() {
-        return None;
+/// Body of the responses of the HTTPS server
+pub type ResponseBody = BoxBody<Bytes, std::io::Error>;
+
+/// Open the byte range of a file as a streamed body with a known length
+async fn open_file_body(file_body: &FileBody) -> std::io::Result<ResponseBody> {
+    let mut file = tokio::fs::File::open(&file_body.path).await?;
+    file.seek(std::io::SeekFrom::Start(file_body.offset)).await?;
+    let chunks = ReaderStream::with_capacity(file.take(file_body.length), FILE_STREAM_CHUNK_BYTES)
+        .map(|chunk| chunk.map(Frame::data));
+    Ok(LengthBody { inner: StreamBody::new(chunks), length: file_body.length }.boxed())
+}
+
+/// Body that announces its length up front, so streamed files are sent with a Content-Length
+struct LengthBody<B> {
+    inner: B,
+    length: u64,
+}
+
+impl<B: hyper::body::Body + Unpin> hyper::body::Body for LengthBody<B> {
+    type Data = B::Data;
+    type Error = B::Error;
+
+    fn poll_frame(
+        mut self: Pin<&mut Self>,
+        cx: &mut std::task::Context<'_>,
+    ) -> std::task::Poll<Option<Result<Frame<Self::Data>, Self::Error>>> {
+        Pin::new(&mut self.inner).poll_frame(cx)
     }
 
-    let request_line = lines[0];
-    let parts: Vec<&str> = request_line.split_whitespace().collect();
-    if parts.len() < 2 {
-        return None;
+    fn size_hint(&self) -> hyper::body::SizeHint {
+        hyper::body::SizeHint::with_exact(self.length)
     }
+}
 
-    let method = parts[0].to_string();
-    let path = parts[1].to_string();
-    
-    // Find headers
-    let mut headers = Vec::new();
-    let mut body_start = None;
-    
-    for (i, line) in lines.iter().enumerate() {
-        if line.is_empty() {
-            body_start = Some(i + 1);
-            break;
-        }
-        if i > 0 { // Skip the request line
-            headers.push(line.to_string());
+/// Get the value of a header by name (case-insensitive)
+pub fn get_header<'a>(headers: &'a HeaderMap, name: &str) -> Option<&'a str> {
+    headers.get(name).and_then(|value| value.to_str().ok()).map(str::trim)
+}
+
+/// Get the value of a query string parameter from a request path
+pub fn get_query_param<'a>(path: &'a str, name: &str) -> Option<&'a str> {
+    let (_, query_string) = path.split_once('?')?;
+    query_string.split('&')
+        .filter_map(|pair| pair.split_once('=').or(Some((pair, ""))))
+        .find(|(key, _)| *key == name)
+        .map(|(_, value)| value)
+}
+
+/// Get the non-empty path segment at the given position (query string excluded)
+pub fn get_path_segment(path: &str, segment: usize) -> Option<&str> {
+    let clean_path = path.split('?').next().unwrap_or("");
+    clean_path.split('/').nth(segment).filter(|value| !value.is_empty())
+}
+
+/// Parse the numeric ID at the given path segment (query string excluded)
+pub fn parse_path_id(path: &str, segment: usize) -> Option<i64> {
+    get_path_segment(path, segment).and_then(|id| id.parse::<i64>().ok())
+}
+
+/// Decode a percent-encoded query string value ("the%20office" and "the+office" become "the office")
+/// Invalid escapes are kept as they are, invalid UTF-8 is replaced
+pub fn decode_query_value(value: &str) -> String {
+    let hex = |byte: u8| (byte as char).to_digit(16).map(|digit| digit as u8);
+    let bytes = value.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        let escaped = match (bytes[i], bytes.get(i + 1), bytes.get(i + 2)) {
+            (b'%', Some(&high), Some(&low)) => hex(high).zip(hex(low)).map(|(high, low)| high * 16 + low),
+            _ => None,
+        };
+        match (escaped, bytes[i]) {
+            (Some(byte), _) => {
+                decoded.push(byte);
+                i += 3;
+            }
+            (None, b'+') => {
+                decoded.push(b' ');
+                i += 1;
+            }
+            (None, byte) => {
+                decoded.push(byte);
+                i += 1;
+            }
         }
     }
-    
-    // Extract body if present
-    let body = if let Some(start) = body_start {
-        if start < lines.len() {
-            Some(lines[start..].join("\n"))
-        } else {
-            None
-        }
-    } else {
-        None
-    };
+    String::from_utf8_lossy(&decoded).to_string()
+}
 
-    Some(HttpRequest {
-        method,
-        path,
-        headers,
-        body,
-    })
+/// Extract an auth token from the `Authorization: Bearer` header or the `token` query parameter
+pub fn extract_request_token(request: &HttpRequest) -> Option<&str> {
+    get_header(&request.headers, "authorization")
+        .and_then(|value| value.strip_prefix("Bearer "))
+        .or_else(|| get_query_param(&request.path, "token"))
+        .filter(|token| !token.is_empty())
+}
+
+/// Build the standard 401 response for requests without a valid token
+pub fn unauthorized_response() -> HttpResponse {
+    let response_body = serde_json::json!({
+        "success": false,
+        "error": "Unauthorized",
+        "message": "Invalid or missing authorization token"
+    });
+    
+    HttpResponse::new(401)
+        .with_cors()
+        .with_json_body(&response_body.to_string())
 }
 
 /// Extract user agent from headers
-pub fn extract_user_agent(headers: &[String]) -> String {
-    headers.iter()
-        .find(|line| line.to_lowercase().starts_with("user-agent:"))
-        .and_then(|line| line.splitn(2, ':').nth(1))
-        .map(|ua| ua.trim().to_string())
+pub fn extract_user_agent(headers: &HeaderMap) -> String {
+    get_header(headers, "user-agent")
+        .map(|ua| ua.to_string())
         .unwrap_or_else(|| "Unknown".to_string())
 }
 
@@ -179,8 +342,8 @@ async fn check_server_initialized() -> Result<bool, Box<dyn std::error::Error +
     }
     
     // Check file system
-    let app_handle = get_app_handle().ok_or("App handle not initialized")?;
-    let config_path = config_path(app_handle)
+    let paths = get_paths().ok_or("Paths not initialized")?;
+    let config_path = config_path(paths)
         .map_err(|e| {
             eprintln!("Failed to get config path: {}", e);
             std::io::Error::new(std::io::ErrorKind::Other, format!("{}", e))
@@ -199,12 +362,22 @@ async fn check_server_initialized() -> Result<bool, Box<dyn std::error::Error +
 /// Route handler function type
 pub type RouteHandler = fn(&HttpRequest) -> Pin<Box<dyn Future<Output = Result<HttpResponse, Box<dyn std::error::Error + Send + Sync>>> + Send + 'static>>;
 
+/// Route handler function type for routes that require a valid token
+pub type AuthenticatedRouteHandler = fn(&AuthenticatedRequest) -> Pin<Box<dyn Future<Output = Result<HttpResponse, Box<dyn std::error::Error + Send + Sync>>> + Send + 'static>>;
+
+/// Handler of a route, either open to everyone or protected by token auth
+#[derive(Clone, Copy)]
+pub enum RouteKind {
+    Public(RouteHandler),
+    Authenticated(AuthenticatedRouteHandler),
+}
+
 /// Route definition
 #[derive(Clone)]
 pub struct Route {
     pub method: String,
     pub path_pattern: String,
-    pub handler: RouteHandler,
+    pub handler: RouteKind,
 }
 
 /// Main router using standard Rust patterns
@@ -220,14 +393,46 @@ impl Router {
         }
     }
 
-    pub fn add_route(&mut self, method: &str, path_pattern: &str, handler: RouteHandler) {
+    /// Add a route that requires a valid token (`Authorization: Bearer` header or `token` query parameter)
+    pub fn add_route(&mut self, method: &str, path_pattern: &str, handler: AuthenticatedRouteHandler) {
+        self.routes.push(Route {
+            method: method.to_string(),
+            path_pattern: path_pattern.to_string(),
+            handler: RouteKind::Authenticated(handler),
+        });
+    }
+
+    /// Add a route that is reachable without a token
+    pub fn add_public_route(&mut self, method: &str, path_pattern: &str, handler: RouteHandler) {
         self.routes.push(Route {
             method: method.to_string(),
             path_pattern: path_pattern.to_string(),
-            handler,
+            handler: RouteKind::Public(handler),
         });
     }
 
+    /// Authenticate the request and dispatch it to the route handler
+    async fn dispatch(&self, route: &Route, request: &HttpRequest) -> Result<HttpResponse, Box<dyn std::error::Error + Send + Sync>> {
+        match route.handler {
+            RouteKind::Public(handler) => handler(request).await,
+            RouteKind::Authenticated(handler) => {
+                let token = match extract_request_token(request) {
+                    Some(token) => token.to_string(),
+                    None => return Ok(unauthorized_response()),
+                };
+                if !authenticate_token(&token, request.remote_addr.as_deref()).await? {
+                    return Ok(unauthorized_response());
+                }
+
+                let authenticated_request = AuthenticatedRequest {
+                    request: request.clone(),
+                    token,
+                };
+                handler(&authenticated_request).await
+            }
+        }
+    }
+
     pub async fn handle_request(&self, request: &HttpRequest) -> Result<HttpResponse, Box<dyn std::error::Error + Send + Sync>> {
         // Check if server is initialized before processing any request
         if !check_server_initialized().await? {
@@ -244,7 +449,7 @@ impl Router {
         
         for route in &self.routes {
             if route.method == request.method && self.matches_path(&route.path_pattern, &request.path) {
-                return match (route.handler)(request).await {
+                return match self.dispatch(route, request).await {
                     Ok(response) => Ok(response),
                     Err(e) => {
                         eprintln!("Handler error: {}", e);
@@ -269,7 +474,10 @@ impl Router {
     }
 
     fn matches_path(&self, pattern: &str, path: &str) -> bool {
-        if pattern == path {
+        // Ignore the query string when matching (prefix patterns still see the full path)
+        let path_without_query = path.split('?').next().unwrap_or(path);
+        
+        if pattern == path || pattern == path_without_query {
             return true;
         }
         
@@ -281,7 +489,7 @@ impl Router {
         
         // Handle path parameter patterns like /api/index/{index_id}/icon
         if pattern.contains("{") && pattern.contains("}") {
-            return self.matches_path_with_params(pattern, path);
+            return self.matches_path_with_params(pattern, path_without_query);
         }
         
         false
@@ -311,92 +519,191 @@ impl Router {
     }
 }
 
-/// Read a complete HTTP request from the TLS stream
-async fn read_complete_http_request(
-    tls_stream: &mut TlsStream<TcpStream>,
-) -> Result<String, Box<dyn std::error::Error + Send + Sync>> {
-    let mut buffer = Vec::new();
-    let mut temp_buffer = [0; 1024];
-    
-    loop {
-        let n = tls_stream.read(&mut temp_buffer).await?;
-        if n == 0 {
-            break;
-        }
-        
-        buffer.extend_from_slice(&temp_buffer[..n]);
-        
-        // Check if we have a complete HTTP request
-        let request_str = String::from_utf8_lossy(&buffer);
-        
-        // Look for the end of headers (double CRLF)
-        if let Some(header_end) = request_str.find("\r\n\r\n") {
-            let headers = &request_str[..header_end + 4];
-            
-            // Check if there's a Content-Length header
-            if let Some(content_length_start) = headers.find("Content-Length:") {
-                if let Some(content_length_end) = headers[content_length_start..].find("\r\n") {
-                    let content_length_str = &headers[content_length_start + 15..content_length_start + content_length_end];
-                    if let Ok(content_length) = content_length_str.trim().parse::<usize>() {
-                        let body_start = header_end + 4;
-                        let expected_total = body_start + content_length;
-                        
-                        if buffer.len() >= expected_total {
-                            // We have the complete request
-                            return Ok(String::from_utf8_lossy(&buffer[..expected_total]).to_string());
-                        }
-                        // Continue reading to get the complete body
-                    }
-                }
-            } else {
-                // No Content-Length header, assume request is complete after headers
-                return Ok(request_str.to_string());
-            }
-        }
-        
-        // Prevent infinite loops with very large requests
-        if buffer.len() > 1024 * 1024 * 100 { // 100MB limit
-            return Err("Request too large".into());
-        }
-    }
-    
-    if buffer.is_empty() {
-        Err("Empty request".into())
-    } else {
-        Ok(String::from_utf8_lossy(&buffer).to_string())
+/// Build a JSON error response with the given status code
+pub fn error_response(status_code: u16, error: &str, message: &str) -> HttpResponse {
+    let response_body = serde_json::json!({
+        "success": false,
+        "error": error,
+        "message": message
+    });
+
+    HttpResponse::new(status_code)
+        .with_cors()
+        .with_json_body(&response_body.to_string())
+}
+
+/// Split off the request head, the body is passed on unread
+fn into_http_request(request: Request<Incoming>, remote_addr: Option<String>) -> HttpRequest {
+    let (parts, body) = request.into_parts();
+
+    let declared_length = get_header(&parts.headers, CONTENT_LENGTH.as_str()).and_then(|length| length.parse::<u64>().ok());
+    let path = parts.uri.path_and_query()
+        .map(|path_and_query| path_and_query.as_str().to_string())
+        .unwrap_or_else(|| "/".to_string());
+
+    HttpRequest {
+        method: parts.method.as_str().to_string(),
+        path,
+        headers: parts.headers,
+        body: RequestBody::new(body.boxed_unsync(), declared_length),
+        remote_addr,
     }
 }
 
-/// Handle a single HTTPS connection using the router
-pub async fn handle_connection_with_router(
-    mut tls_stream: TlsStream<TcpStream>,
+/// Handle a single HTTP request using the router
+async fn handle_request_with_router(
+    request: Request<Incoming>,
     router: &Router,
-) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
-    
-    // Read the complete HTTP request
-    let request_str = match read_complete_http_request(&mut tls_stream).await {
-        Ok(req) => req,
-        Err(_e) => return Ok(()),
-    };
-    
-    // Parse HTTP request
-    let request = match parse_http_request(&request_str) {
-        Some(req) => req,
-        None => return Ok(()),
+    remote_addr: Option<String>,
+) -> Response<ResponseBody> {
+    let request = into_http_request(request, remote_addr);
+    let response = if request.method == "OPTIONS" {
+        // Handle CORS preflight
+        HttpResponse::new(200).with_cors()
+    } else {
+        router.handle_request(&request).await.unwrap_or_else(|e| {
+            eprintln!("Router error: {}", e);
+            error_response(500, "Internal server error", "An unexpected error occurred")
+        })
     };
-    
-    // Handle CORS preflight
-    if request.method == "OPTIONS" {
-        let response = HttpResponse::new(200)
-            .with_cors()
-            .with_body("");
-        response.send(&mut tls_stream).await?;
-        return Ok(());
+    // Read what is left of a small body the handler didn't read, so the connection can be kept alive
+    let _ = request.body.read_limited().await;
+
+    response.into_response().await
+}
+
+/// Serve the requests of a connection using the router
+/// Speaks HTTP/1.1 with keep-alive, or HTTP/2 when the client negotiated it with ALPN
+pub async fn handle_connection_with_router<S>(
+    stream: S,
+    remote_addr: Option<String>,
+    router: Arc<Router>,
+) -> Result<(), Box<dyn std::error::Error + Send + Sync>>
+where
+    S: AsyncRead + AsyncWrite + Unpin + Send + 'static,
+{
+    let service = service_fn(move |request| {
+        let router = router.clone();
+        let remote_addr = remote_addr.clone();
+        async move { Ok::<_, Infallible>(handle_request_with_router(request, &router, remote_addr).await) }
+    });
+
+    auto::Builder::new(TokioExecutor::new())
+        .serve_connection(TokioIo::new(stream), service)
+        .await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn request(path: &str, headers: &[&str]) -> HttpRequest {
+        HttpRequest {
+            method: "GET".to_string(),
+            path: path.to_string(),
+            headers: headers.iter()
+                .filter_map(|line| line.split_once(':'))
+                .map(|(name, value)| (HeaderName::from_bytes(name.as_bytes()).unwrap(), HeaderValue::from_str(value.trim()).unwrap()))
+                .collect(),
+            body: RequestBody::default(),
+            remote_addr: None,
+        }
+    }
+
+    #[test]
+    fn test_extract_request_token() {
+        assert_eq!(extract_request_token(&request("/api/item/1", &["Authorization: Bearer abc"])), Some("abc"));
+        assert_eq!(extract_request_token(&request("/api/item/1", &["authorization: Bearer abc"])), Some("abc"));
+        assert_eq!(extract_request_token(&request("/api/part/1/stream?token=xyz", &[])), Some("xyz"));
+        assert_eq!(extract_request_token(&request("/api/part/1/stream?a=1&token=xyz", &[])), Some("xyz"));
+        assert_eq!(extract_request_token(&request("/api/item/1?token=", &[])), None);
+        assert_eq!(extract_request_token(&request("/api/item/1", &["Authorization: Basic abc"])), None);
+        assert_eq!(extract_request_token(&request("/api/item/1", &[])), None);
+    }
+
+    #[test]
+    fn test_path_segments() {
+        assert_eq!(get_path_segment("/api/transcode/abc/master.m3u8?key=1", 4), Some("master.m3u8"));
+        assert_eq!(get_path_segment("/api/transcode/?key=1", 3), None);
+        assert_eq!(get_path_segment("/api/transcode", 3), None);
+        assert_eq!(parse_path_id("/api/part/42/stream?token=abc", 3), Some(42));
+        assert_eq!(parse_path_id("/api/part/abc/stream", 3), None);
+        assert_eq!(parse_path_id("/api/part//stream", 3), None);
+    }
+
+    #[test]
+    fn test_matches_path_ignores_query_string() {
+        let router = Router::new();
+        assert!(router.matches_path("/api/item/{item_id}", "/api/item/42?token=abc"));
+        assert!(router.matches_path("/api/ping", "/api/ping?token=abc"));
+        assert!(!router.matches_path("/api/item/{item_id}", "/api/item/42/stream"));
+    }
+
+    fn request_body(bytes: Vec<u8>, declared_length: Option<u64>) -> RequestBody {
+        RequestBody::new(Full::new(Bytes::from(bytes)).map_err(|never| match never {}).boxed_unsync(), declared_length)
+    }
+
+    #[tokio::test]
+    async fn test_request_body_read_limited() {
+        let body = request_body(b"{\"position_ms\":1}".to_vec(), None);
+        assert_eq!(body.read_limited().await.ok().flatten().as_deref(), Some(&b"{\"position_ms\":1}"[..]));
+        assert!(matches!(body.read_limited().await, Ok(None)));
+        assert!(body.take_stream().is_none());
+
+        assert!(matches!(request_body(Vec::new(), Some(0)).read_limited().await, Ok(None)));
+        assert!(matches!(RequestBody::default().read_limited().await, Ok(None)));
+
+        let too_large = request_body(vec![b' '; MAX_JSON_BODY_BYTES + 1], None).read_limited().await;
+        assert!(matches!(too_large, Err(response) if response.status_code == 413));
+        let declared_too_large = request_body(Vec::new(), Some(MAX_JSON_BODY_BYTES as u64 + 1)).read_limited().await;
+        assert!(matches!(declared_too_large, Err(response) if response.status_code == 413));
+    }
+
+    #[tokio::test]
+    async fn test_response_status_lines() {
+        for (status_code, reason) in [(200, "OK"), (409, "Conflict"), (413, "Payload Too Large"), (416, "Range Not Satisfiable")] {
+            let response = HttpResponse::new(status_code).into_response().await;
+            assert_eq!(response.status().as_u16(), status_code);
+            assert_eq!(response.status().canonical_reason(), Some(reason));
+        }
+    }
+
+    #[tokio::test]
+    async fn test_response_bodies_are_binary_safe() {
+        let bytes = vec![0x89, b'P', b'N', b'G', 0x00, 0xff];
+        let response = HttpResponse::new(200).with_binary_body(bytes.clone()).into_response().await;
+        assert_eq!(response.into_body().collect().await.unwrap().to_bytes(), bytes);
+
+        let path = std::env::temp_dir().join(format!("router_test_{}.bin", uuid::Uuid::new_v4()));
+        std::fs::write(&path, [0u8, 1, 2, 3, 4, 5, 6, 7]).unwrap();
+        let response = HttpResponse::new(206).with_file_body(path.clone(), 2, 3).into_response().await;
+        assert_eq!(hyper::body::Body::size_hint(response.body()).exact(), Some(3));
+        assert_eq!(response.into_body().collect().await.unwrap().to_bytes(), vec![2u8, 3, 4]);
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_connection_keep_alive() {
+        use tokio::io::AsyncWriteExt;
+
+        let (client, server) = tokio::io::duplex(64 * 1024);
+        tokio::spawn(handle_connection_with_router(server, None, Arc::new(Router::new())));
+        let (mut reader, mut writer) = tokio::io::split(client);
+
+        // Lowercase header names and bodies no handler reads on one connection
+        writer.write_all(concat!(
+            "OPTIONS /api/ping HTTP/1.1\r\nhost: localhost\r\n\r\n",
+            "OPTIONS /api/login HTTP/1.1\r\nhost: localhost\r\ntransfer-encoding: chunked\r\n\r\n5\r\nhello\r\n0\r\n\r\n",
+            "OPTIONS /api/login HTTP/1.1\r\nhost: localhost\r\ncontent-length: 5\r\n\r\nhello",
+            "OPTIONS /api/ping HTTP/1.1\r\nhost: localhost\r\n\r\n",
+        ).as_bytes()).await.unwrap();
+
+        let mut received = String::new();
+        let mut buffer = [0u8; 4096];
+        while received.matches("HTTP/1.1 200 OK").count() < 4 {
+            let n = reader.read(&mut buffer).await.unwrap();
+            assert!(n > 0, "connection closed early: {}", received);
+            received.push_str(&String::from_utf8_lossy(&buffer[..n]));
+        }
     }
-    
-    // Route the request
-    let response = router.handle_request(&request).await?;
-    response.send(&mut tls_stream).await?;
-    
-    Ok(())
 }
@@ -0,0 +1,92 @@
+use crate::api::responses::SessionResponse;
+use crate::utils::token::{get_active_sessions, revoke_session, revoke_all_sessions};
+use warp::reject::custom;
+
+// Custom error types for session operations
+#[derive(Debug)]
+pub struct SessionError;
+
+impl warp::reject::Reject for SessionError {}
+
+// Handler for listing active client sessions (HTTPS tokens)
+pub async fn handle_get_sessions() -> Result<impl warp::reply::Reply, warp::Rejection> {
+    let sessions: Vec<SessionResponse> = get_active_sessions().await
+        .map_err(|e| {
+            eprintln!("Failed to fetch sessions: {}", e);
+            custom(SessionError)
+        })?
+        .into_iter()
+        .map(SessionResponse::from)
+        .collect();
+    
+    Ok(warp::reply::with_status(
+        warp::reply::json(&serde_json::json!({
+            "sessions": sessions
+        })),
+        warp::http::StatusCode::OK,
+    ))
+}
+
+// Handler for revoking a single client session
+pub async fn handle_revoke_session(
+    session_id: String,
+) -> Result<impl warp::reply::Reply, warp::Rejection> {
+    let session_id = match session_id.parse::<i64>() {
+        Ok(id) => id,
+        Err(_) => {
+            return Ok(warp::reply::with_status(
+                warp::reply::json(&serde_json::json!({
+                    "success": false,
+                    "error": "Invalid session ID format"
+                })),
+                warp::http::StatusCode::BAD_REQUEST,
+            ));
+        }
+    };
+    
+    let revoked = revoke_session(session_id).await
+        .map_err(|e| {
+            eprintln!("Failed to revoke session: {}", e);
+            custom(SessionError)
+        })?;
+    
+    if !revoked {
+        return Ok(warp::reply::with_status(
+            warp::reply::json(&serde_json::json!({
+                "success": false,
+                "error": "Session not found"
+            })),
+            warp::http::StatusCode::NOT_FOUND,
+        ));
+    }
+    
+    println!("Session {} revoked", session_id);
+    
+    Ok(warp::reply::with_status(
+        warp::reply::json(&serde_json::json!({
+            "success": true,
+            "message": "Session revoked successfully"
+        })),
+        warp::http::StatusCode::OK,
+    ))
+}
+
+// Handler for revoking all client sessions
+pub async fn handle_revoke_all_sessions() -> Result<impl warp::reply::Reply, warp::Rejection> {
+    let revoked = revoke_all_sessions().await
+        .map_err(|e| {
+            eprintln!("Failed to revoke sessions: {}", e);
+            custom(SessionError)
+        })?;
+    
+    println!("{} session(s) revoked", revoked);
+    
+    Ok(warp::reply::with_status(
+        warp::reply::json(&serde_json::json!({
+            "success": true,
+            "message": "All sessions revoked successfully",
+            "revoked": revoked
+        })),
+        warp::http::StatusCode::OK,
+    ))
+}
@@ -1,12 +1,29 @@
 use std::sync::Arc;
 use tokio::sync::Mutex;
+#[cfg(feature = "desktop")]
 use tauri::AppHandle;
 use sqlx::SqlitePool;
+use crate::config::PathProvider;
 
-// Unified app state containing both database pool and HTTPS port information
+// Unified app state containing the data directory, database pool and HTTPS port information
 #[derive(Clone)]
 pub struct AppState {
-    pub app_handle: Arc<Mutex<Option<AppHandle>>>,
+    pub paths: Arc<dyn PathProvider>,
+    #[cfg(feature = "desktop")]
+    pub app_handle: Arc<Mutex<Option<AppHandle>>>, // Only for desktop integration (native dialogs)
     pub db_pool: SqlitePool,
     pub https_port: Arc<Mutex<Option<u16>>>,
 }
+
+impl AppState {
+    /// Create the state of a server without desktop integration
+    pub fn new(paths: Arc<dyn PathProvider>, db_pool: SqlitePool) -> Self {
+        Self {
+            paths,
+            #[cfg(feature = "desktop")]
+            app_handle: Arc::new(Mutex::new(None)),
+            db_pool,
+            https_port: Arc::new(Mutex::new(None)),
+        }
+    }
+}
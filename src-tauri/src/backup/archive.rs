@@ -0,0 +1,375 @@
+//! Backup archives of the server state
+//!
+//! An archive is a gzipped tar holding a manifest, a snapshot of the database taken with SQLite's
+//! online backup API (consistent while the server keeps writing), config.json and the index icons.
+//! Caches that are rebuilt on demand (thumbnails, artwork, transcodes) and the certificates, which
+//! are generated per machine, are left out.
+
+use anyhow::{anyhow, bail, Result};
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use libsqlite3_sys as ffi;
+use serde::{Deserialize, Serialize};
+use sqlx::sqlite::{SqliteConnectOptions, SqliteConnection};
+use sqlx::{ConnectOptions, Connection, SqlitePool};
+use std::ffi::{CStr, CString};
+use std::fs::File;
+use std::path::{Path, PathBuf};
+use tokio::sync::Mutex;
+
+/// Version of the archive layout, bumped when archives written now can't be read by older servers
+pub const BACKUP_FORMAT_VERSION: u32 = 1;
+
+/// Names of the entries inside an archive, and of the files they come from in the data directory
+pub const MANIFEST_NAME: &str = "manifest.json";
+pub const DATABASE_NAME: &str = "app.sqlite3";
+pub const CONFIG_NAME: &str = "config.json";
+pub const ICONS_NAME: &str = "icons";
+
+/// Directory of the archives inside the data directory
+pub const BACKUPS_DIR_NAME: &str = "backups";
+
+const ARCHIVE_EXTENSION: &str = ".tar.gz";
+
+/// How long the snapshot waits for a writer holding a lock on the database
+const SNAPSHOT_BUSY_TIMEOUT_MS: i32 = 5000;
+
+/// Only one backup is written at a time
+static BACKUP_LOCK: Mutex<()> = Mutex::const_new(());
+
+/// What a backup was made for, part of its file name
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BackupTrigger {
+    Manual,
+    Scheduled,
+    PreRestore,
+    Uploaded,
+}
+
+impl BackupTrigger {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            BackupTrigger::Manual => "manual",
+            BackupTrigger::Scheduled => "scheduled",
+            BackupTrigger::PreRestore => "pre-restore",
+            BackupTrigger::Uploaded => "uploaded",
+        }
+    }
+}
+
+/// Description of an archive, stored as its first entry
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BackupManifest {
+    pub format_version: u32,
+    pub created_at: i64,
+    pub schema_version: i64,
+    pub server_version: String,
+    pub trigger: String,
+}
+
+/// An archive in the backups directory
+#[derive(Debug, Clone, Serialize)]
+pub struct BackupInfo {
+    pub file_name: String,
+    pub size_bytes: u64,
+    pub created_at: i64,
+    pub trigger: Option<String>,
+}
+
+/// Get the backups directory of a data directory, creating it if needed
+pub fn backups_dir(data_dir: &Path) -> Result<PathBuf> {
+    let backups_dir = data_dir.join(BACKUPS_DIR_NAME);
+    std::fs::create_dir_all(&backups_dir)?;
+    Ok(backups_dir)
+}
+
+/// Resolve the path of an archive from its file name, rejecting anything that isn't a plain archive name
+pub fn backup_file_path(backups_dir: &Path, file_name: &str) -> Option<PathBuf> {
+    let is_plain_name = !file_name.starts_with('.')
+        && !file_name.contains(['/', '\\'])
+        && file_name.ends_with(ARCHIVE_EXTENSION)
+        && file_name.len() > ARCHIVE_EXTENSION.len();
+
+    is_plain_name.then(|| backups_dir.join(file_name))
+}
+
+/// Get the trigger encoded in an archive name (backup-YYYYMMDD-HHMMSS-{trigger}.tar.gz)
+fn trigger_from_file_name(file_name: &str) -> Option<&str> {
+    let stem = file_name.strip_suffix(ARCHIVE_EXTENSION)?.strip_prefix("backup-")?;
+    // Skip the date and time parts
+    let trigger = stem.splitn(3, '-').nth(2)?;
+    // Backups made within the same second get a counter appended
+    Some(match trigger.rsplit_once('-') {
+        Some((trigger, counter)) if counter.chars().all(|c| c.is_ascii_digit()) => trigger,
+        _ => trigger,
+    })
+}
+
+/// Get an unused archive name for a backup made now (backup-YYYYMMDD-HHMMSS-{trigger}.tar.gz)
+pub fn next_backup_file_name(backups_dir: &Path, trigger: BackupTrigger) -> String {
+    let created = chrono::Utc::now().format("%Y%m%d-%H%M%S");
+    let mut file_name = format!("backup-{}-{}{}", created, trigger.as_str(), ARCHIVE_EXTENSION);
+    let mut attempt = 1;
+    while backups_dir.join(&file_name).exists() {
+        attempt += 1;
+        file_name = format!("backup-{}-{}-{}{}", created, trigger.as_str(), attempt, ARCHIVE_EXTENSION);
+    }
+    file_name
+}
+
+/// Write a backup archive of the server state in `data_dir` to its backups directory
+pub async fn create_backup(pool: &SqlitePool, data_dir: &Path, trigger: BackupTrigger) -> Result<BackupInfo> {
+    let _guard = BACKUP_LOCK.lock().await;
+
+    let backups_dir = backups_dir(data_dir)?;
+    let now = chrono::Utc::now();
+    let file_name = next_backup_file_name(&backups_dir, trigger);
+
+    let archive_path = backups_dir.join(&file_name);
+    let partial_path = backups_dir.join(format!(".{}.partial", file_name));
+    let snapshot_path = backups_dir.join(format!(".{}.sqlite3", file_name));
+
+    snapshot_database(pool, &snapshot_path).await?;
+    // Read from the snapshot itself, so the manifest describes the database in the archive
+    let schema_version = match snapshot_schema_version(&snapshot_path).await {
+        Ok(schema_version) => schema_version,
+        Err(e) => {
+            let _ = tokio::fs::remove_file(&snapshot_path).await;
+            return Err(e);
+        }
+    };
+
+    let manifest = BackupManifest {
+        format_version: BACKUP_FORMAT_VERSION,
+        created_at: now.timestamp(),
+        schema_version,
+        server_version: env!("CARGO_PKG_VERSION").to_string(),
+        trigger: trigger.as_str().to_string(),
+    };
+
+    let data_dir = data_dir.to_path_buf();
+    let write_partial_path = partial_path.clone();
+    let write_snapshot_path = snapshot_path.clone();
+    let written = tokio::task::spawn_blocking(move || {
+        write_archive(&write_partial_path, &manifest, &write_snapshot_path, &data_dir)
+    })
+    .await
+    .map_err(|e| anyhow!("Backup task failed: {}", e))
+    .and_then(|result| result);
+
+    let _ = tokio::fs::remove_file(&snapshot_path).await;
+    if let Err(e) = written {
+        let _ = tokio::fs::remove_file(&partial_path).await;
+        return Err(e);
+    }
+
+    // Archives only appear under their final name once complete
+    tokio::fs::rename(&partial_path, &archive_path).await?;
+    let size_bytes = tokio::fs::metadata(&archive_path).await?.len();
+
+    Ok(BackupInfo {
+        file_name,
+        size_bytes,
+        created_at: now.timestamp(),
+        trigger: Some(trigger.as_str().to_string()),
+    })
+}
+
+/// Copy the database to `snapshot_path` with SQLite's online backup API
+/// The copy is taken from a single read transaction, so it is consistent even while other
+/// connections write (WAL mode lets them continue during the copy). Copying is a blocking call
+/// that can take a while for big libraries, so it runs on its own connection on a blocking thread
+async fn snapshot_database(pool: &SqlitePool, snapshot_path: &Path) -> Result<()> {
+    if snapshot_path.exists() {
+        tokio::fs::remove_file(snapshot_path).await?;
+    }
+    let source_path = CString::new(pool.connect_options().get_filename().to_string_lossy().as_bytes())?;
+    let snapshot_path = CString::new(snapshot_path.to_string_lossy().as_bytes())?;
+
+    tokio::task::spawn_blocking(move || copy_database(&source_path, &snapshot_path))
+        .await
+        .map_err(|e| anyhow!("Database backup task failed: {}", e))?
+}
+
+/// Copy the database at `source_path` to `snapshot_path` in one backup step
+fn copy_database(source_path: &CStr, snapshot_path: &CStr) -> Result<()> {
+    // SAFETY: both connections are opened and closed here, and the backup object is finished
+    // before either closes
+    unsafe {
+        let mut source: *mut ffi::sqlite3 = std::ptr::null_mut();
+        if ffi::sqlite3_open_v2(source_path.as_ptr(), &mut source, ffi::SQLITE_OPEN_READWRITE, std::ptr::null()) != ffi::SQLITE_OK {
+            let message = sqlite_error_message(source);
+            ffi::sqlite3_close(source);
+            bail!("Failed to open the database for backup: {}", message);
+        }
+        // Wait for writers holding a lock instead of failing the backup
+        ffi::sqlite3_busy_timeout(source, SNAPSHOT_BUSY_TIMEOUT_MS);
+
+        let mut destination: *mut ffi::sqlite3 = std::ptr::null_mut();
+        if ffi::sqlite3_open(snapshot_path.as_ptr(), &mut destination) != ffi::SQLITE_OK {
+            let message = sqlite_error_message(destination);
+            ffi::sqlite3_close(destination);
+            ffi::sqlite3_close(source);
+            bail!("Failed to create the database snapshot: {}", message);
+        }
+
+        let backup = ffi::sqlite3_backup_init(destination, c"main".as_ptr(), source, c"main".as_ptr());
+        if backup.is_null() {
+            let message = sqlite_error_message(destination);
+            ffi::sqlite3_close(destination);
+            ffi::sqlite3_close(source);
+            bail!("Failed to start the database backup: {}", message);
+        }
+
+        // -1 copies all pages in one step, inside one read transaction
+        let step_result = ffi::sqlite3_backup_step(backup, -1);
+        let finish_result = ffi::sqlite3_backup_finish(backup);
+        let message = sqlite_error_message(destination);
+        ffi::sqlite3_close(destination);
+        ffi::sqlite3_close(source);
+
+        if step_result != ffi::SQLITE_DONE || finish_result != ffi::SQLITE_OK {
+            bail!("Database backup failed: {}", message);
+        }
+    }
+
+    Ok(())
+}
+
+/// Get the schema version of a database, the highest migration applied to it
+pub(crate) async fn read_schema_version(connection: &mut SqliteConnection) -> Result<i64> {
+    let schema_version: Option<i64> = sqlx::query_scalar("SELECT MAX(version) FROM schema_migrations")
+        .fetch_one(connection)
+        .await
+        .map_err(|_| anyhow!("Database has no schema version"))?;
+
+    Ok(schema_version.unwrap_or(0))
+}
+
+/// Get the schema version of a database snapshot
+async fn snapshot_schema_version(snapshot_path: &Path) -> Result<i64> {
+    let mut connection = SqliteConnectOptions::new()
+        .filename(snapshot_path)
+        .read_only(true)
+        .connect()
+        .await?;
+
+    let schema_version = read_schema_version(&mut connection).await;
+    connection.close().await?;
+    schema_version
+}
+
+/// Get the last error message of a SQLite connection
+unsafe fn sqlite_error_message(db: *mut ffi::sqlite3) -> String {
+    if db.is_null() {
+        return "out of memory".to_string();
+    }
+    CStr::from_ptr(ffi::sqlite3_errmsg(db)).to_string_lossy().to_string()
+}
+
+/// Write the archive entries: manifest first, so it can be read without unpacking the rest
+fn write_archive(archive_path: &Path, manifest: &BackupManifest, snapshot_path: &Path, data_dir: &Path) -> Result<()> {
+    let file = File::create(archive_path)?;
+    let mut builder = tar::Builder::new(GzEncoder::new(file, Compression::default()));
+
+    let manifest_json = serde_json::to_vec_pretty(manifest)?;
+    let mut header = tar::Header::new_gnu();
+    header.set_size(manifest_json.len() as u64);
+    header.set_mode(0o644);
+    header.set_mtime(manifest.created_at.max(0) as u64);
+    header.set_cksum();
+    builder.append_data(&mut header, MANIFEST_NAME, manifest_json.as_slice())?;
+
+    builder.append_path_with_name(snapshot_path, DATABASE_NAME)?;
+
+    let config_path = data_dir.join(CONFIG_NAME);
+    if config_path.is_file() {
+        builder.append_path_with_name(&config_path, CONFIG_NAME)?;
+    }
+
+    let icons_dir = data_dir.join(ICONS_NAME);
+    if icons_dir.is_dir() {
+        builder.append_dir_all(ICONS_NAME, &icons_dir)?;
+    }
+
+    let file = builder.into_inner()?.finish()?;
+    file.sync_all()?;
+
+    Ok(())
+}
+
+/// List the archives in the backups directory, newest first
+pub async fn list_backups(data_dir: &Path) -> Result<Vec<BackupInfo>> {
+    let backups_dir = backups_dir(data_dir)?;
+    let mut backups = Vec::new();
+
+    let mut entries = tokio::fs::read_dir(&backups_dir).await?;
+    while let Some(entry) = entries.next_entry().await? {
+        let file_name = entry.file_name().to_string_lossy().to_string();
+        if backup_file_path(&backups_dir, &file_name).is_none() {
+            continue;
+        }
+        let metadata = entry.metadata().await?;
+        if !metadata.is_file() {
+            continue;
+        }
+
+        let created_at = metadata.modified().ok()
+            .and_then(|modified| modified.duration_since(std::time::UNIX_EPOCH).ok())
+            .map(|since_epoch| since_epoch.as_secs() as i64)
+            .unwrap_or(0);
+
+        backups.push(BackupInfo {
+            trigger: trigger_from_file_name(&file_name).map(|trigger| trigger.to_string()),
+            file_name,
+            size_bytes: metadata.len(),
+            created_at,
+        });
+    }
+
+    backups.sort_by(|a, b| b.created_at.cmp(&a.created_at).then_with(|| b.file_name.cmp(&a.file_name)));
+    Ok(backups)
+}
+
+/// Delete the oldest scheduled backups beyond `keep`; other backups are only deleted by hand
+/// Returns the number of deleted archives
+pub async fn prune_backups(data_dir: &Path, keep: u32) -> Result<usize> {
+    let backups_dir = backups_dir(data_dir)?;
+    let scheduled = list_backups(data_dir).await?
+        .into_iter()
+        .filter(|backup| backup.trigger.as_deref() == Some(BackupTrigger::Scheduled.as_str()));
+
+    let mut deleted = 0;
+    for backup in scheduled.skip(keep as usize) {
+        tokio::fs::remove_file(backups_dir.join(&backup.file_name)).await?;
+        deleted += 1;
+    }
+
+    Ok(deleted)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_backup_file_path() {
+        let backups_dir = Path::new("/data/backups");
+        assert_eq!(
+            backup_file_path(backups_dir, "backup-20260101-120000-manual.tar.gz"),
+            Some(backups_dir.join("backup-20260101-120000-manual.tar.gz"))
+        );
+        assert_eq!(backup_file_path(backups_dir, "../config.json"), None);
+        assert_eq!(backup_file_path(backups_dir, "../../etc/passwd.tar.gz"), None);
+        assert_eq!(backup_file_path(backups_dir, ".backup.tar.gz.partial"), None);
+        assert_eq!(backup_file_path(backups_dir, ".tar.gz"), None);
+    }
+
+    #[test]
+    fn test_trigger_from_file_name() {
+        assert_eq!(trigger_from_file_name("backup-20260101-120000-scheduled.tar.gz"), Some("scheduled"));
+        assert_eq!(trigger_from_file_name("backup-20260101-120000-pre-restore.tar.gz"), Some("pre-restore"));
+        assert_eq!(trigger_from_file_name("backup-20260101-120000-manual-2.tar.gz"), Some("manual"));
+        assert_eq!(trigger_from_file_name("server.tar.gz"), None);
+    }
+}
@@ -0,0 +1,7 @@
+pub mod archive;
+pub mod restore;
+pub mod schedule;
+
+pub use archive::*;
+pub use restore::*;
+pub use schedule::*;
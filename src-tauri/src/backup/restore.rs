@@ -0,0 +1,241 @@
+//! Restoring backup archives
+//!
+//! The database can't be swapped out under a running server, so a restore takes two steps: the
+//! archive is unpacked and checked into a pending directory, and the next start of the server
+//! moves it into place before the database is opened. Schema migrations then bring a database
+//! from an older server up to date.
+
+use anyhow::{anyhow, bail, Result};
+use flate2::read::GzDecoder;
+use sqlx::sqlite::SqliteConnectOptions;
+use sqlx::{ConnectOptions, Connection};
+use std::fs::File;
+use std::path::Path;
+use crate::backup::archive::{create_backup, read_schema_version, BackupManifest, BackupTrigger, BACKUP_FORMAT_VERSION, CONFIG_NAME, DATABASE_NAME, ICONS_NAME, MANIFEST_NAME};
+use crate::db::migrations::latest_schema_version;
+use crate::db::pool::connect_pool;
+
+/// Directory inside the data directory holding a checked restore until the next start
+pub const PENDING_RESTORE_DIR_NAME: &str = "restore-pending";
+
+/// Directory an archive is unpacked into while it is checked
+const STAGING_DIR_NAME: &str = ".restore-staging";
+
+/// Unpack and check an archive, then queue it to replace the server state on the next start
+/// A previously queued restore is replaced
+pub async fn stage_restore(archive_path: &Path, data_dir: &Path) -> Result<BackupManifest> {
+    let staging_dir = data_dir.join(STAGING_DIR_NAME);
+    if staging_dir.exists() {
+        tokio::fs::remove_dir_all(&staging_dir).await?;
+    }
+
+    let unpack_archive_path = archive_path.to_path_buf();
+    let unpack_staging_dir = staging_dir.clone();
+    let checked = match tokio::task::spawn_blocking(move || unpack_archive(&unpack_archive_path, &unpack_staging_dir)).await {
+        Ok(Ok(())) => check_staged_restore(&staging_dir).await,
+        Ok(Err(e)) => Err(e),
+        Err(e) => Err(anyhow!("Restore task failed: {}", e)),
+    };
+    let manifest = match checked {
+        Ok(manifest) => manifest,
+        Err(e) => {
+            let _ = tokio::fs::remove_dir_all(&staging_dir).await;
+            return Err(e);
+        }
+    };
+
+    let pending_dir = data_dir.join(PENDING_RESTORE_DIR_NAME);
+    if pending_dir.exists() {
+        tokio::fs::remove_dir_all(&pending_dir).await?;
+    }
+    tokio::fs::rename(&staging_dir, &pending_dir).await?;
+
+    Ok(manifest)
+}
+
+/// Unpack the entries of an archive that belong to a backup, ignoring anything else
+fn unpack_archive(archive_path: &Path, staging_dir: &Path) -> Result<()> {
+    std::fs::create_dir_all(staging_dir)?;
+    let mut archive = tar::Archive::new(GzDecoder::new(File::open(archive_path)?));
+
+    for entry in archive.entries().map_err(|e| anyhow!("Not a backup archive: {}", e))? {
+        let mut entry = entry.map_err(|e| anyhow!("Damaged backup archive: {}", e))?;
+
+        let entry_type = entry.header().entry_type();
+        if !entry_type.is_file() && !entry_type.is_dir() {
+            continue;
+        }
+        let path = entry.path()?.into_owned();
+        let is_backup_entry = path == Path::new(MANIFEST_NAME)
+            || path == Path::new(DATABASE_NAME)
+            || path == Path::new(CONFIG_NAME)
+            || path.starts_with(ICONS_NAME);
+        if !is_backup_entry {
+            continue;
+        }
+
+        // unpack_in refuses entries that would end up outside the staging directory
+        if !entry.unpack_in(staging_dir)? {
+            bail!("Backup archive contains an invalid path: {}", path.display());
+        }
+    }
+
+    Ok(())
+}
+
+/// Check that an unpacked archive can be restored by this server, returning its manifest
+async fn check_staged_restore(staging_dir: &Path) -> Result<BackupManifest> {
+    let manifest_json = tokio::fs::read_to_string(staging_dir.join(MANIFEST_NAME)).await
+        .map_err(|_| anyhow!("Backup archive has no manifest"))?;
+    let manifest: BackupManifest = serde_json::from_str(&manifest_json)
+        .map_err(|e| anyhow!("Backup archive has an invalid manifest: {}", e))?;
+    if manifest.format_version > BACKUP_FORMAT_VERSION {
+        bail!("Backup was made by a newer server (version {}), please update the server", manifest.server_version);
+    }
+
+    let database_path = staging_dir.join(DATABASE_NAME);
+    if !database_path.is_file() {
+        bail!("Backup archive has no database");
+    }
+
+    let schema_version = check_database(&database_path).await?;
+    if schema_version > latest_schema_version() {
+        bail!("Backup was made by a newer server (version {}), please update the server", manifest.server_version);
+    }
+
+    Ok(manifest)
+}
+
+/// Check the integrity of a restored database, returning its schema version
+async fn check_database(database_path: &Path) -> Result<i64> {
+    let mut connection = SqliteConnectOptions::new()
+        .filename(database_path)
+        .read_only(true)
+        .connect()
+        .await?;
+
+    let result: String = sqlx::query_scalar("PRAGMA quick_check")
+        .fetch_one(&mut connection)
+        .await?;
+    if result != "ok" {
+        bail!("Backup database is damaged: {}", result);
+    }
+
+    let schema_version = read_schema_version(&mut connection).await
+        .map_err(|_| anyhow!("Backup database has no schema version"))?;
+
+    connection.close().await?;
+    Ok(schema_version)
+}
+
+/// Move a queued restore into place, to be called on startup before the database is opened
+/// The current state is backed up first, so a restore can be undone by restoring that backup
+/// Returns the manifest of the restored backup, if there was one to restore
+pub async fn apply_pending_restore(data_dir: &Path) -> Result<Option<BackupManifest>> {
+    let pending_dir = data_dir.join(PENDING_RESTORE_DIR_NAME);
+    let manifest_path = pending_dir.join(MANIFEST_NAME);
+    if !manifest_path.is_file() {
+        return Ok(None);
+    }
+    let manifest: BackupManifest = serde_json::from_str(&tokio::fs::read_to_string(&manifest_path).await?)?;
+
+    let db_path = data_dir.join(DATABASE_NAME);
+    if db_path.exists() {
+        // Closing the pool checkpoints the WAL, so the database is a single file afterwards
+        let pool = connect_pool(&db_path).await?;
+        let backup = create_backup(&pool, data_dir, BackupTrigger::PreRestore).await;
+        pool.close().await;
+        println!("💾 Backed up the current state to {} before restoring", backup?.file_name);
+    }
+
+    for suffix in ["-wal", "-shm"] {
+        let path = data_dir.join(format!("{}{}", DATABASE_NAME, suffix));
+        if path.exists() {
+            tokio::fs::remove_file(&path).await?;
+        }
+    }
+    tokio::fs::rename(pending_dir.join(DATABASE_NAME), &db_path).await?;
+
+    let config_path = pending_dir.join(CONFIG_NAME);
+    if config_path.is_file() {
+        tokio::fs::rename(&config_path, data_dir.join(CONFIG_NAME)).await?;
+    }
+
+    let icons_dir = data_dir.join(ICONS_NAME);
+    if icons_dir.exists() {
+        tokio::fs::remove_dir_all(&icons_dir).await?;
+    }
+    let restored_icons_dir = pending_dir.join(ICONS_NAME);
+    if restored_icons_dir.is_dir() {
+        tokio::fs::rename(&restored_icons_dir, &icons_dir).await?;
+    }
+
+    tokio::fs::remove_dir_all(&pending_dir).await?;
+
+    Ok(Some(manifest))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::backup::archive::list_backups;
+    use crate::db::migrations::run_migrations;
+
+    #[tokio::test]
+    async fn test_backup_and_restore() {
+        let data_dir = std::env::temp_dir().join(format!("backup_test_{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(data_dir.join(ICONS_NAME)).unwrap();
+        std::fs::write(data_dir.join(CONFIG_NAME), r#"{"id":"server","name":"Before","password":"hash"}"#).unwrap();
+        std::fs::write(data_dir.join(ICONS_NAME).join("index_1.png"), b"icon").unwrap();
+
+        let db_path = data_dir.join(DATABASE_NAME);
+        let pool = connect_pool(&db_path).await.unwrap();
+        run_migrations(&pool, &db_path).await.unwrap();
+        sqlx::query("INSERT INTO profiles (name, color, created_at) VALUES ('Kids', '#ff0000', 0)")
+            .execute(&pool)
+            .await
+            .unwrap();
+
+        let backup = create_backup(&pool, &data_dir, BackupTrigger::Manual).await.unwrap();
+        pool.close().await;
+        assert_eq!(list_backups(&data_dir).await.unwrap().len(), 1);
+
+        // Change the state after the backup, the restore brings back the backed up one
+        std::fs::write(data_dir.join(CONFIG_NAME), r#"{"id":"server","name":"After","password":"hash"}"#).unwrap();
+        std::fs::remove_file(data_dir.join(ICONS_NAME).join("index_1.png")).unwrap();
+        let pool = connect_pool(&db_path).await.unwrap();
+        sqlx::query("DELETE FROM profiles").execute(&pool).await.unwrap();
+        pool.close().await;
+
+        let archive_path = data_dir.join("backups").join(&backup.file_name);
+        let manifest = stage_restore(&archive_path, &data_dir).await.unwrap();
+        assert_eq!(manifest.schema_version, latest_schema_version());
+
+        assert!(apply_pending_restore(&data_dir).await.unwrap().is_some());
+        assert!(apply_pending_restore(&data_dir).await.unwrap().is_none());
+
+        let pool = connect_pool(&db_path).await.unwrap();
+        let profiles: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM profiles").fetch_one(&pool).await.unwrap();
+        pool.close().await;
+        assert_eq!(profiles, 1);
+        assert!(std::fs::read_to_string(data_dir.join(CONFIG_NAME)).unwrap().contains("Before"));
+        assert!(data_dir.join(ICONS_NAME).join("index_1.png").exists());
+        // The state before the restore was backed up too
+        assert_eq!(list_backups(&data_dir).await.unwrap().len(), 2);
+
+        std::fs::remove_dir_all(&data_dir).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_stage_restore_rejects_invalid_archive() {
+        let data_dir = std::env::temp_dir().join(format!("backup_invalid_test_{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&data_dir).unwrap();
+        let archive_path = data_dir.join("not-a-backup.tar.gz");
+        std::fs::write(&archive_path, b"not a backup").unwrap();
+
+        assert!(stage_restore(&archive_path, &data_dir).await.is_err());
+        assert!(!data_dir.join(PENDING_RESTORE_DIR_NAME).exists());
+
+        std::fs::remove_dir_all(&data_dir).unwrap();
+    }
+}
@@ -0,0 +1,131 @@
+//! Automatic backups
+//!
+//! The backup process checks the schedule in config.json periodically and writes a scheduled
+//! backup once the last one is older than the interval, then deletes the scheduled backups
+//! beyond the number to keep.
+
+use anyhow::Result;
+use sqlx::SqlitePool;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use crate::backup::archive::{create_backup, list_backups, prune_backups, BackupTrigger, CONFIG_NAME};
+use crate::constants::BACKUP_CHECK_INTERVAL_SECS;
+use crate::models::config::{BackupSettings, Configuration};
+
+/// Longest interval accepted for automatic backups (30 days)
+const MAX_BACKUP_INTERVAL_HOURS: u32 = 30 * 24;
+
+/// Most automatic backups that can be kept
+const MAX_BACKUPS_KEPT: u32 = 100;
+
+impl BackupSettings {
+    /// Check that the interval and number of backups kept are in range
+    pub fn validate(&self) -> Result<(), String> {
+        if let Some(interval_hours) = self.interval_hours {
+            if !(1..=MAX_BACKUP_INTERVAL_HOURS).contains(&interval_hours) {
+                return Err(format!("intervalHours must be between 1 and {}", MAX_BACKUP_INTERVAL_HOURS));
+            }
+        }
+        if !(1..=MAX_BACKUPS_KEPT).contains(&self.keep) {
+            return Err(format!("keep must be between 1 and {}", MAX_BACKUPS_KEPT));
+        }
+        Ok(())
+    }
+}
+
+/// Check whether an automatic backup should be made now
+/// `last_backup_at` is the time of the newest scheduled backup, both are Unix timestamps
+pub fn is_backup_due(settings: &BackupSettings, last_backup_at: Option<i64>, now: i64) -> bool {
+    match settings.interval_hours {
+        Some(interval_hours) => last_backup_at.is_none_or(|last_backup_at| now - last_backup_at >= interval_hours as i64 * 60 * 60),
+        None => false,
+    }
+}
+
+/// Read the backup settings from config.json, `None` while the server isn't set up
+pub async fn read_backup_settings(data_dir: &Path) -> Result<Option<BackupSettings>> {
+    let config_path = data_dir.join(CONFIG_NAME);
+    if !config_path.exists() {
+        return Ok(None);
+    }
+
+    let config: Configuration = serde_json::from_str(&tokio::fs::read_to_string(&config_path).await?)?;
+    Ok(Some(config.backup))
+}
+
+/// Store the backup settings in config.json, returning false while the server isn't set up
+pub async fn save_backup_settings(data_dir: &Path, settings: BackupSettings) -> Result<bool> {
+    let config_path = data_dir.join(CONFIG_NAME);
+    if !config_path.exists() {
+        return Ok(false);
+    }
+
+    let mut config: Configuration = serde_json::from_str(&tokio::fs::read_to_string(&config_path).await?)?;
+    config.backup = settings;
+    tokio::fs::write(&config_path, serde_json::to_string_pretty(&config)?).await?;
+    Ok(true)
+}
+
+/// Background process that makes the automatic backups
+pub async fn start_backup_process(db_pool: SqlitePool, data_dir: PathBuf) {
+    let mut interval = tokio::time::interval(Duration::from_secs(BACKUP_CHECK_INTERVAL_SECS));
+
+    loop {
+        interval.tick().await;
+
+        if let Err(e) = run_scheduled_backup(&db_pool, &data_dir).await {
+            eprintln!("Scheduled backup failed: {}", e);
+        }
+    }
+}
+
+/// Make a scheduled backup if one is due, then prune the old ones
+async fn run_scheduled_backup(db_pool: &SqlitePool, data_dir: &Path) -> Result<()> {
+    let settings = match read_backup_settings(data_dir).await? {
+        Some(settings) => settings,
+        None => return Ok(()),
+    };
+
+    let last_backup_at = list_backups(data_dir).await?
+        .into_iter()
+        .find(|backup| backup.trigger.as_deref() == Some(BackupTrigger::Scheduled.as_str()))
+        .map(|backup| backup.created_at);
+    if !is_backup_due(&settings, last_backup_at, chrono::Utc::now().timestamp()) {
+        return Ok(());
+    }
+
+    let backup = create_backup(db_pool, data_dir, BackupTrigger::Scheduled).await?;
+    println!("💾 Created scheduled backup {}", backup.file_name);
+
+    let pruned = prune_backups(data_dir, settings.keep).await?;
+    if pruned > 0 {
+        println!("💾 Deleted {} old scheduled backup(s)", pruned);
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_is_backup_due() {
+        let daily = BackupSettings { interval_hours: Some(24), keep: 7 };
+        let now = 1_800_000_000;
+        assert!(is_backup_due(&daily, None, now));
+        assert!(is_backup_due(&daily, Some(now - 24 * 60 * 60), now));
+        assert!(!is_backup_due(&daily, Some(now - 23 * 60 * 60), now));
+
+        let disabled = BackupSettings { interval_hours: None, keep: 7 };
+        assert!(!is_backup_due(&disabled, None, now));
+    }
+
+    #[test]
+    fn test_validate_backup_settings() {
+        assert!(BackupSettings::default().validate().is_ok());
+        assert!(BackupSettings { interval_hours: None, keep: 3 }.validate().is_ok());
+        assert!(BackupSettings { interval_hours: Some(0), keep: 3 }.validate().is_err());
+        assert!(BackupSettings { interval_hours: Some(24), keep: 0 }.validate().is_err());
+    }
+}
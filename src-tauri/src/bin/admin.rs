@@ -0,0 +1,285 @@
+//! Administration of Index Media Server from the command line, for scripts and ops work without a desktop session
+//!
+//! Works on the data directory of the desktop app or the headless server, which may be running at the same time:
+//! index-media-server-admin --data-dir <path> index list
+
+use index_media_server_lib::admin;
+use index_media_server_lib::config::DataDir;
+use index_media_server_lib::constants::{DATA_DIR_ENV_VAR, DEFAULT_INDEX_ICON, DEFAULT_PROFILE_COLOR, TOKEN_IDLE_EXPIRY_SECS};
+use index_media_server_lib::db::repos::{IndexesRepo, ProfilesRepo, TokensRepo};
+use anyhow::{anyhow, bail, Result};
+use chrono::{DateTime, Local, Utc};
+use std::io::{BufRead, Write};
+use std::path::{Path, PathBuf};
+
+const USAGE: &str = "Usage: index-media-server-admin [--data-dir <path>] <command>
+
+Commands:
+  init --name <name> [--password <password>]
+                          Set up a new server (the password is read from stdin if not given)
+  password [--password <password>]
+                          Reset the password clients log in with
+  index list
+  index add --name <name> --folder <path> [--folder <path>...] [--type videos|photos|audio] [--icon <icon>]
+  index remove <id>
+  index scan <id>         Queue a scan, picked up by the running server
+  profile list
+  profile add --name <name> [--color <color>]
+  profile update <id> [--name <name>] [--color <color>]
+  profile remove <id>
+  token list              List the signed in clients
+  token revoke <id>       Sign out a client, or all of them with --all
+  classify <path>...      Show how the video files of a path are classified, without changing anything
+
+Options:
+  --data-dir <path>       Directory of the database and config.json (or set INDEX_MEDIA_SERVER_DATA_DIR)
+  -h, --help              Show this help";
+
+/// Arguments of a command: positional arguments and `--option value` pairs
+struct CommandArgs {
+    positional: Vec<String>,
+    options: Vec<(String, String)>,
+    flags: Vec<String>,
+}
+
+impl CommandArgs {
+    fn parse(args: Vec<String>) -> Result<Self> {
+        let mut positional = Vec::new();
+        let mut options = Vec::new();
+        let mut flags = Vec::new();
+
+        let mut args = args.into_iter();
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "--all" => flags.push(arg),
+                _ if arg.starts_with("--") => {
+                    let value = args.next().ok_or_else(|| anyhow!("{} needs a value", arg))?;
+                    options.push((arg, value));
+                }
+                _ => positional.push(arg),
+            }
+        }
+
+        Ok(Self { positional, options, flags })
+    }
+
+    /// Fail on options and flags the command doesn't know
+    fn allow(&self, options: &[&str], flags: &[&str]) -> Result<()> {
+        if let Some((option, _)) = self.options.iter().find(|(option, _)| !options.contains(&option.as_str())) {
+            bail!("Unknown option: {}", option);
+        }
+        if let Some(flag) = self.flags.iter().find(|flag| !flags.contains(&flag.as_str())) {
+            bail!("Unknown option: {}", flag);
+        }
+        Ok(())
+    }
+
+    /// Get the last value of an option
+    fn option(&self, name: &str) -> Option<&str> {
+        self.options.iter().rev().find(|(option, _)| option == name).map(|(_, value)| value.as_str())
+    }
+
+    /// Get all values of a repeatable option
+    fn option_values(&self, name: &str) -> Vec<String> {
+        self.options.iter().filter(|(option, _)| option == name).map(|(_, value)| value.clone()).collect()
+    }
+
+    fn flag(&self, name: &str) -> bool {
+        self.flags.iter().any(|flag| flag == name)
+    }
+
+    /// Get the single ID argument of a command
+    fn id(&self) -> Result<i64> {
+        match self.positional.as_slice() {
+            [id] => id.parse().map_err(|_| anyhow!("Invalid ID: {}", id)),
+            [] => bail!("An ID is required"),
+            _ => bail!("Expected a single ID"),
+        }
+    }
+}
+
+/// Read a password from the option, or from stdin so it doesn't end up in the shell history
+fn read_password(args: &CommandArgs) -> Result<String> {
+    if let Some(password) = args.option("--password") {
+        return Ok(password.to_string());
+    }
+
+    eprint!("Password: ");
+    std::io::stderr().flush()?;
+    let mut password = String::new();
+    std::io::stdin().lock().read_line(&mut password)?;
+    Ok(password.trim_end_matches(['\r', '\n']).to_string())
+}
+
+fn format_timestamp(timestamp: i64) -> String {
+    DateTime::<Utc>::from_timestamp(timestamp, 0)
+        .map(|time| time.with_timezone(&Local).format("%Y-%m-%d %H:%M").to_string())
+        .unwrap_or_else(|| "-".to_string())
+}
+
+async fn run(data_dir: Option<PathBuf>, command: &str, subcommand: Option<&str>, args: CommandArgs) -> Result<()> {
+    // Classifying only reads the given paths, so the data directory isn't touched
+    if command == "classify" {
+        args.allow(&[], &[])?;
+        if args.positional.is_empty() {
+            bail!("A path is required");
+        }
+        for path in &args.positional {
+            let files = admin::collect_video_files(Path::new(path))?;
+            if files.is_empty() {
+                println!("No video files in {}", path);
+            }
+            for file in files {
+                println!("{}", admin::describe_classification(&file));
+            }
+        }
+        return Ok(());
+    }
+
+    let paths = DataDir(data_dir.ok_or_else(|| anyhow!("--data-dir is required"))?);
+    let db_pool = admin::open_database(&paths).await?;
+
+    match (command, subcommand) {
+        ("init", None) => {
+            args.allow(&["--name", "--password"], &[])?;
+            let name = args.option("--name").ok_or_else(|| anyhow!("--name is required"))?;
+            let configuration = admin::init_server_config(&paths, name, &read_password(&args)?).await?;
+            println!("Server '{}' set up in {}", configuration.name, paths.0.display());
+        }
+        ("password", None) => {
+            args.allow(&["--password"], &[])?;
+            admin::reset_password(&paths, &read_password(&args)?).await?;
+            println!("Server password updated, signed in clients stay signed in (see `token revoke --all`)");
+        }
+        ("index", Some("list")) => {
+            args.allow(&[], &[])?;
+            for index in IndexesRepo::new(db_pool.clone()).get_all_indexes().await? {
+                let last_scanned = index.last_scanned_at_datetime()
+                    .map(|time| format_timestamp(time.timestamp()))
+                    .unwrap_or_else(|| "never".to_string());
+                println!("{}\t{}\t{}\t{} (last scanned: {})\t{}",
+                    index.id, index.name, index.r#type, index.scan_status, last_scanned, index.folders().join(", "));
+            }
+        }
+        ("index", Some("add")) => {
+            args.allow(&["--name", "--type", "--icon", "--folder"], &[])?;
+            let name = args.option("--name").ok_or_else(|| anyhow!("--name is required"))?;
+            let index = admin::add_index(
+                &db_pool,
+                name,
+                args.option("--type").unwrap_or("videos"),
+                args.option("--icon").unwrap_or(DEFAULT_INDEX_ICON),
+                args.option_values("--folder"),
+            ).await?;
+            println!("Index '{}' added with ID {} and queued for scanning", index.name, index.id);
+        }
+        ("index", Some("remove")) => {
+            args.allow(&[], &[])?;
+            let index = admin::remove_index(&db_pool, &paths, args.id()?).await?;
+            println!("Index '{}' removed", index.name);
+        }
+        ("index", Some("scan")) => {
+            args.allow(&[], &[])?;
+            let index = admin::queue_index_scan(&db_pool, args.id()?).await?;
+            println!("Index '{}' queued for scanning", index.name);
+        }
+        ("profile", Some("list")) => {
+            args.allow(&[], &[])?;
+            for profile in ProfilesRepo::new(db_pool.clone()).get_all_profiles().await? {
+                println!("{}\t{}\t{}", profile.id, profile.name, profile.color);
+            }
+        }
+        ("profile", Some("add")) => {
+            args.allow(&["--name", "--color"], &[])?;
+            let name = args.option("--name").ok_or_else(|| anyhow!("--name is required"))?;
+            let profile = admin::add_profile(&db_pool, name, args.option("--color").unwrap_or(DEFAULT_PROFILE_COLOR)).await?;
+            println!("Profile '{}' added with ID {}", profile.name, profile.id);
+        }
+        ("profile", Some("update")) => {
+            args.allow(&["--name", "--color"], &[])?;
+            let profile = admin::update_profile(&db_pool, args.id()?, args.option("--name"), args.option("--color")).await?;
+            println!("Profile {} updated: {} ({})", profile.id, profile.name, profile.color);
+        }
+        ("profile", Some("remove")) => {
+            args.allow(&[], &[])?;
+            let profile = admin::remove_profile(&db_pool, args.id()?).await?;
+            println!("Profile '{}' removed", profile.name);
+        }
+        ("token", Some("list")) => {
+            args.allow(&[], &[])?;
+            let active_since = Utc::now().timestamp() - TOKEN_IDLE_EXPIRY_SECS;
+            for session in TokensRepo::new(db_pool.clone()).get_active_sessions(active_since).await? {
+                println!("{}\t{}\t{}\tsigned in {}\tlast used {}",
+                    session.id,
+                    session.user_agent.as_deref().unwrap_or("-"),
+                    session.ip_address.as_deref().unwrap_or("-"),
+                    format_timestamp(session.created_at),
+                    format_timestamp(session.last_used_at));
+            }
+        }
+        ("token", Some("revoke")) => {
+            args.allow(&[], &["--all"])?;
+            let tokens_repo = TokensRepo::new(db_pool.clone());
+            if args.flag("--all") {
+                if !args.positional.is_empty() {
+                    bail!("Give either a session ID or --all");
+                }
+                let revoked = tokens_repo.delete_all_tokens().await?;
+                println!("Signed out {} clients", revoked);
+            } else {
+                let session_id = args.id()?;
+                if !tokens_repo.delete_session(session_id).await? {
+                    bail!("Session {} not found", session_id);
+                }
+                println!("Session {} signed out", session_id);
+            }
+        }
+        (command, Some(subcommand)) => bail!("Unknown command: {} {}", command, subcommand),
+        (command, None) => bail!("Unknown command: {}", command),
+    }
+
+    db_pool.close().await;
+    Ok(())
+}
+
+#[tokio::main]
+async fn main() {
+    let mut data_dir = std::env::var_os(DATA_DIR_ENV_VAR).map(PathBuf::from);
+    let mut args: Vec<String> = std::env::args().skip(1).collect();
+
+    if args.is_empty() || args.iter().any(|arg| arg == "-h" || arg == "--help") {
+        println!("{}", USAGE);
+        return;
+    }
+    if args.first().map(String::as_str) == Some("--data-dir") {
+        if args.len() < 2 {
+            eprintln!("--data-dir needs a value\n\n{}", USAGE);
+            std::process::exit(2);
+        }
+        data_dir = Some(PathBuf::from(args.remove(1)));
+        args.remove(0);
+    }
+
+    let Some(command) = (!args.is_empty()).then(|| args.remove(0)) else {
+        eprintln!("A command is required\n\n{}", USAGE);
+        std::process::exit(2);
+    };
+    // Commands on indexes, profiles and tokens take a subcommand
+    let subcommand = match command.as_str() {
+        "index" | "profile" | "token" if !args.is_empty() => Some(args.remove(0)),
+        "index" | "profile" | "token" => {
+            eprintln!("{} needs a subcommand\n\n{}", command, USAGE);
+            std::process::exit(2);
+        }
+        _ => None,
+    };
+
+    let result = match CommandArgs::parse(args) {
+        Ok(args) => run(data_dir, &command, subcommand.as_deref(), args).await,
+        Err(e) => Err(e),
+    };
+    if let Err(e) = result {
+        eprintln!("❌ {}", e);
+        std::process::exit(1);
+    }
+}
@@ -0,0 +1,137 @@
+//! Index Media Server without tray icon, webview or native dialogs, for NAS boxes and containers
+//!
+//! Build without the desktop feature so the binary doesn't link against GTK/WebKit:
+//! cargo build --release --no-default-features --bin index-media-server-headless
+
+use index_media_server_lib::{AppState, DEFAULT_HTTP_PORT, find_available_port, generate_secure_token, server};
+use index_media_server_lib::api::handlers::init_web_dir;
+use index_media_server_lib::config::{DataDir, PathProvider};
+use index_media_server_lib::constants::DATA_DIR_ENV_VAR;
+use std::net::{IpAddr, Ipv4Addr};
+use std::path::PathBuf;
+use std::sync::Arc;
+
+const USAGE: &str = "Usage: index-media-server-headless --data-dir <path> [options]
+
+Options:
+  --data-dir <path>    Directory of the database, config.json, icons and certificates
+                       (or set INDEX_MEDIA_SERVER_DATA_DIR)
+  --http-host <addr>   Loopback address of the admin web interface (default: 127.0.0.1)
+  --http-port <port>   Port of the admin web interface (default: first free port from 1420)
+  --web-dir <path>     Directory of the admin web interface files (default: ../localweb)
+  -h, --help           Show this help
+
+The admin web interface is plain HTTP with the access token in its URL, and can reset the password,
+download backups (which include the password hash) and restore them. It only listens on loopback,
+reach it from another machine through an SSH tunnel: ssh -L 1420:127.0.0.1:1420 <server>";
+
+/// Command line options of the headless server
+struct HeadlessOptions {
+    data_dir: PathBuf,
+    http_host: IpAddr,
+    http_port: Option<u16>,
+    web_dir: Option<PathBuf>,
+}
+
+/// Parse the command line, `None` when only the usage was asked for
+fn parse_args(mut args: impl Iterator<Item = String>) -> Result<Option<HeadlessOptions>, String> {
+    let mut data_dir = std::env::var_os(DATA_DIR_ENV_VAR).map(PathBuf::from);
+    let mut http_host = IpAddr::V4(Ipv4Addr::LOCALHOST);
+    let mut http_port = None;
+    let mut web_dir = None;
+
+    while let Some(arg) = args.next() {
+        let mut value = |name: &str| args.next().ok_or_else(|| format!("{} needs a value", name));
+        match arg.as_str() {
+            "--data-dir" => data_dir = Some(PathBuf::from(value("--data-dir")?)),
+            "--http-host" => {
+                http_host = value("--http-host")?.parse().map_err(|_| "--http-host must be an IP address".to_string())?;
+                if !http_host.is_loopback() {
+                    return Err("--http-host must be a loopback address, the admin web interface isn't encrypted".to_string());
+                }
+            }
+            "--http-port" => {
+                http_port = Some(value("--http-port")?.parse().map_err(|_| "--http-port must be a port number".to_string())?);
+            }
+            "--web-dir" => web_dir = Some(PathBuf::from(value("--web-dir")?)),
+            "-h" | "--help" => return Ok(None),
+            other => return Err(format!("Unknown option: {}", other)),
+        }
+    }
+
+    let data_dir = data_dir.ok_or("--data-dir is required")?;
+    Ok(Some(HeadlessOptions { data_dir, http_host, http_port, web_dir }))
+}
+
+/// Wait for Ctrl+C, or SIGTERM from a service manager or container runtime
+async fn shutdown_signal() {
+    #[cfg(unix)]
+    {
+        use tokio::signal::unix::{signal, SignalKind};
+        match signal(SignalKind::terminate()) {
+            Ok(mut terminate) => {
+                tokio::select! {
+                    _ = tokio::signal::ctrl_c() => {}
+                    _ = terminate.recv() => {}
+                }
+            }
+            Err(_) => {
+                let _ = tokio::signal::ctrl_c().await;
+            }
+        }
+    }
+    #[cfg(not(unix))]
+    {
+        let _ = tokio::signal::ctrl_c().await;
+    }
+}
+
+async fn run(options: HeadlessOptions) -> anyhow::Result<()> {
+    if let Some(web_dir) = options.web_dir {
+        init_web_dir(web_dir);
+    }
+
+    let paths: Arc<dyn PathProvider> = Arc::new(DataDir(options.data_dir));
+    println!("📁 Data directory: {}", paths.app_data_dir()?.display());
+
+    let db_pool = server::init_server(paths.clone()).await?;
+    let app_state = AppState::new(paths, db_pool.clone());
+
+    // An explicit port is used as is, so it can be published or forwarded
+    let http_port = match options.http_port {
+        Some(port) => port,
+        None => find_available_port(DEFAULT_HTTP_PORT).unwrap_or(DEFAULT_HTTP_PORT),
+    };
+
+    // Generate a secure token for access to the admin web interface
+    let startup_token = generate_secure_token();
+    server::start_server_tasks(app_state, options.http_host, http_port, startup_token.clone())?;
+
+    println!("🔑 Manage the server at http://localhost:{}?token={}", http_port, startup_token);
+
+    shutdown_signal().await;
+    println!("👋 Shutting down");
+    db_pool.close().await;
+
+    Ok(())
+}
+
+#[tokio::main]
+async fn main() {
+    let options = match parse_args(std::env::args().skip(1)) {
+        Ok(Some(options)) => options,
+        Ok(None) => {
+            println!("{}", USAGE);
+            return;
+        }
+        Err(e) => {
+            eprintln!("{}\n\n{}", e, USAGE);
+            std::process::exit(2);
+        }
+    };
+
+    if let Err(e) = run(options).await {
+        eprintln!("❌ {}", e);
+        std::process::exit(1);
+    }
+}
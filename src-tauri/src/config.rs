@@ -1,35 +1,72 @@
-use tauri::{path::BaseDirectory, Manager};
 use anyhow::Result;
+use std::path::PathBuf;
 
-/// Get the unified app data directory using Tauri's app data directory
-pub fn get_app_data_dir(app_handle: &tauri::AppHandle) -> Result<std::path::PathBuf> {
-    let dir = app_handle
-        .path()
-        .resolve("data", BaseDirectory::AppData)?; // e.g., ~/Library/Application Support/com.kalegd.index-media-server/data
+/// Resolves where the server keeps its state
+/// The desktop app uses Tauri's app data directory, the headless server a directory given on the command line
+pub trait PathProvider: Send + Sync {
+    /// Get the app data directory, which may not exist yet
+    fn app_data_dir(&self) -> Result<PathBuf>;
+}
+
+#[cfg(feature = "desktop")]
+impl PathProvider for tauri::AppHandle {
+    fn app_data_dir(&self) -> Result<PathBuf> {
+        use tauri::{path::BaseDirectory, Manager};
+        Ok(self.path().resolve("data", BaseDirectory::AppData)?) // e.g., ~/Library/Application Support/com.kalegd.index-media-server/data
+    }
+}
+
+/// A fixed app data directory, used by the headless server
+#[derive(Debug, Clone)]
+pub struct DataDir(pub PathBuf);
+
+impl PathProvider for DataDir {
+    fn app_data_dir(&self) -> Result<PathBuf> {
+        Ok(self.0.clone())
+    }
+}
+
+/// Get the unified app data directory
+pub fn get_app_data_dir(paths: &(impl PathProvider + ?Sized)) -> Result<PathBuf> {
+    let dir = paths.app_data_dir()?;
     std::fs::create_dir_all(&dir)?;
     Ok(dir)
 }
 
-/// Get the SQLite database path using Tauri's app data directory
-pub fn sqlite_path(app_handle: &tauri::AppHandle) -> Result<std::path::PathBuf> {
-    Ok(get_app_data_dir(app_handle)?.join("app.sqlite3"))
+/// Get the SQLite database path inside the app data directory
+pub fn sqlite_path(paths: &(impl PathProvider + ?Sized)) -> Result<PathBuf> {
+    Ok(get_app_data_dir(paths)?.join("app.sqlite3"))
 }
 
-/// Get the config.json path using Tauri's app data directory
-pub fn config_path(app_handle: &tauri::AppHandle) -> Result<std::path::PathBuf> {
-    Ok(get_app_data_dir(app_handle)?.join("config.json"))
+/// Get the config.json path inside the app data directory
+pub fn config_path(paths: &(impl PathProvider + ?Sized)) -> Result<PathBuf> {
+    Ok(get_app_data_dir(paths)?.join("config.json"))
 }
 
-/// Get the icons directory path using Tauri's app data directory
-pub fn icons_dir(app_handle: &tauri::AppHandle) -> Result<std::path::PathBuf> {
-    let icons_dir = get_app_data_dir(app_handle)?.join("icons");
+/// Get the icons directory path inside the app data directory
+pub fn icons_dir(paths: &(impl PathProvider + ?Sized)) -> Result<PathBuf> {
+    let icons_dir = get_app_data_dir(paths)?.join("icons");
     std::fs::create_dir_all(&icons_dir)?;
     Ok(icons_dir)
 }
 
-/// Get the certificates directory path using Tauri's app data directory
-pub fn certs_dir(app_handle: &tauri::AppHandle) -> Result<std::path::PathBuf> {
-    let certs_dir = get_app_data_dir(app_handle)?.join("certs");
+/// Get the certificates directory path inside the app data directory
+pub fn certs_dir(paths: &(impl PathProvider + ?Sized)) -> Result<PathBuf> {
+    let certs_dir = get_app_data_dir(paths)?.join("certs");
     std::fs::create_dir_all(&certs_dir)?;
     Ok(certs_dir)
 }
+
+/// Get the transcode cache directory path (HLS segments) inside the app data directory
+pub fn transcode_cache_dir(paths: &(impl PathProvider + ?Sized)) -> Result<PathBuf> {
+    let transcode_dir = get_app_data_dir(paths)?.join("transcode");
+    std::fs::create_dir_all(&transcode_dir)?;
+    Ok(transcode_dir)
+}
+
+/// Get the thumbnails directory path inside the app data directory
+pub fn thumbnails_dir(paths: &(impl PathProvider + ?Sized)) -> Result<PathBuf> {
+    let thumbnails_dir = get_app_data_dir(paths)?.join("thumbnails");
+    std::fs::create_dir_all(&thumbnails_dir)?;
+    Ok(thumbnails_dir)
+}
@@ -3,3 +3,116 @@ pub const DEFAULT_HTTPS_PORT: u16 = 8443;
 
 /// Default HTTP port for the Index Media Server  
 pub const DEFAULT_HTTP_PORT: u16 = 1420;
+
+/// Largest JSON request body the HTTPS API reads into memory, other bodies are streamed by their handlers
+pub const MAX_JSON_BODY_BYTES: usize = 64 * 1024;
+
+/// Size of the chunks files are streamed to clients in
+pub const FILE_STREAM_CHUNK_BYTES: usize = 64 * 1024;
+
+/// Tokens that have not been used for this long expire (30 days)
+pub const TOKEN_IDLE_EXPIRY_SECS: i64 = 30 * 24 * 60 * 60;
+
+/// Minimum time between `last_used_at` refreshes of a token
+pub const TOKEN_TOUCH_INTERVAL_SECS: i64 = 60;
+
+/// How often expired tokens are pruned from the database
+pub const TOKEN_PRUNE_INTERVAL_SECS: u64 = 60 * 60;
+
+/// ffprobe runs longer than this are killed and the file is recorded as failed to probe
+pub const FFPROBE_TIMEOUT_SECS: u64 = 60;
+
+/// Duration of each HLS segment produced by the transcoder
+pub const HLS_SEGMENT_DURATION_SECS: u64 = 6;
+
+/// Transcode sessions without requests for this long are stopped and removed
+pub const TRANSCODE_IDLE_TIMEOUT_SECS: u64 = 120;
+
+/// Transcode sessions that may run at once, each one is an ffmpeg process
+pub const TRANSCODE_MAX_SESSIONS: usize = 4;
+
+/// How often idle transcode sessions are cleaned up
+pub const TRANSCODE_CLEANUP_INTERVAL_SECS: u64 = 30;
+
+/// Quiet period after the last filesystem event before an incremental scan starts
+pub const WATCHER_DEBOUNCE_SECS: u64 = 3;
+
+/// Longest time filesystem events are held back while changes keep coming in (e.g. long copies)
+pub const WATCHER_MAX_DELAY_SECS: u64 = 30;
+
+/// How often the set of watched folders is synced with the configured indexes
+pub const WATCHER_REFRESH_INTERVAL_SECS: u64 = 30;
+
+/// Default number of video files examined and hashed concurrently during scans
+pub const DEFAULT_SCAN_CONCURRENCY: usize = 4;
+
+/// Number of database writes grouped into a single transaction during scans
+pub const SCAN_DB_BATCH_SIZE: usize = 500;
+
+/// Minimum time between persisted and broadcast scan progress updates
+pub const SCAN_PROGRESS_INTERVAL_MS: u64 = 500;
+
+/// Delay before the first automatic retry of a failed scan, doubled after each further failure
+pub const SCAN_RETRY_BASE_SECS: i64 = 60;
+
+/// Longest delay between automatic retries of a failing scan
+pub const SCAN_RETRY_MAX_SECS: i64 = 6 * 60 * 60;
+
+/// Consecutive failed scans after which an index is no longer retried automatically
+pub const SCAN_MAX_ATTEMPTS: i64 = 5;
+
+/// Number of scan runs of each kind (full and incremental) kept in the history of each index
+pub const SCAN_RUNS_KEPT: i64 = 50;
+
+/// Time without playback after which the server counts as idle for scheduled scans
+pub const SCAN_IDLE_AFTER_SECS: i64 = 15 * 60;
+
+/// Interval of schedules that only set `onlyWhenIdle`
+pub const DEFAULT_SCAN_SCHEDULE_INTERVAL_HOURS: u32 = 24;
+
+/// Longest side of generated photo thumbnails in pixels
+pub const PHOTO_THUMBNAIL_SIZE: u32 = 400;
+
+/// Longest side of cover art extracted from music files in pixels
+pub const MUSIC_COVER_SIZE: u32 = 600;
+
+/// Longest side of cached video artwork in pixels
+pub const ARTWORK_MAX_SIZE: u32 = 1920;
+
+/// Widths that resized artwork variants are generated at, so `?w=` can't fill the cache with arbitrary sizes
+pub const ARTWORK_WIDTHS: [u32; 5] = [160, 320, 480, 720, 1080];
+
+/// Position of the frame grabbed as thumbnail, as a fraction of the runtime
+pub const ARTWORK_FRAME_POSITION: f64 = 0.1;
+
+/// Default API and image server of the TMDB metadata agent (overridden by `TMDB_BASE_URL` and `TMDB_IMAGE_BASE_URL`)
+pub const DEFAULT_TMDB_BASE_URL: &str = "https://api.themoviedb.org/3";
+pub const DEFAULT_TMDB_IMAGE_BASE_URL: &str = "https://image.tmdb.org/t/p";
+
+/// Timeout of requests to metadata providers
+pub const METADATA_REQUEST_TIMEOUT_SECS: u64 = 15;
+
+/// Share of the runtime after which a reported playback position marks an item as watched
+pub const WATCHED_THRESHOLD: f64 = 0.9;
+
+/// Playback positions before this are not kept as resume points
+pub const RESUME_MIN_POSITION_MS: i64 = 30_000;
+
+/// Interval of automatic backups on new servers
+pub const DEFAULT_BACKUP_INTERVAL_HOURS: u32 = 24;
+
+/// Number of automatic backups kept on new servers
+pub const DEFAULT_BACKUPS_KEPT: u32 = 7;
+
+/// How often the automatic backup schedule is checked
+pub const BACKUP_CHECK_INTERVAL_SECS: u64 = 15 * 60;
+
+/// Environment variable with the data directory of the headless server and the admin tool
+pub const DATA_DIR_ENV_VAR: &str = "INDEX_MEDIA_SERVER_DATA_DIR";
+
+/// Media types of local indexes, as matched by the scanning process
+pub const INDEX_TYPES: [&str; 3] = ["videos", "photos", "audio"];
+
+/// Icon and profile color used when the admin tool is given none, same as the setup page
+pub const DEFAULT_INDEX_ICON: &str = "home";
+pub const DEFAULT_PROFILE_COLOR: &str = "#3B82F6";
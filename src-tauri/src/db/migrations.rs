@@ -0,0 +1,255 @@
+use sqlx::SqlitePool;
+use anyhow::{bail, Result};
+use std::path::{Path, PathBuf};
+
+/// A numbered schema migration embedded in the binary
+#[derive(Debug, Clone, Copy)]
+pub struct Migration {
+    pub version: i64,
+    pub name: &'static str,
+    pub sql: &'static str,
+}
+
+/// All migrations in version order; add new ones at the end and never edit applied ones
+pub const MIGRATIONS: &[Migration] = &[
+    Migration {
+        version: 1,
+        name: "initial_schema",
+        sql: include_str!("../../../migrations/0001_initial_schema.sql"),
+    },
+    Migration {
+        version: 2,
+        name: "token_activity",
+        sql: include_str!("../../../migrations/0002_token_activity.sql"),
+    },
+    Migration {
+        version: 3,
+        name: "video_probing",
+        sql: include_str!("../../../migrations/0003_video_probing.sql"),
+    },
+    Migration {
+        version: 4,
+        name: "scan_jobs",
+        sql: include_str!("../../../migrations/0004_scan_jobs.sql"),
+    },
+    Migration {
+        version: 5,
+        name: "scan_runs",
+        sql: include_str!("../../../migrations/0005_scan_runs.sql"),
+    },
+    Migration {
+        version: 6,
+        name: "photos",
+        sql: include_str!("../../../migrations/0006_photos.sql"),
+    },
+    Migration {
+        version: 7,
+        name: "music",
+        sql: include_str!("../../../migrations/0007_music.sql"),
+    },
+    Migration {
+        version: 8,
+        name: "video_artwork",
+        sql: include_str!("../../../migrations/0008_video_artwork.sql"),
+    },
+    Migration {
+        version: 9,
+        name: "metadata_refresh",
+        sql: include_str!("../../../migrations/0009_metadata_refresh.sql"),
+    },
+    Migration {
+        version: 10,
+        name: "nfo_import",
+        sql: include_str!("../../../migrations/0010_nfo_import.sql"),
+    },
+    Migration {
+        version: 11,
+        name: "subtitles",
+        sql: include_str!("../../../migrations/0011_subtitles.sql"),
+    },
+    Migration {
+        version: 12,
+        name: "watch_state",
+        sql: include_str!("../../../migrations/0012_watch_state.sql"),
+    },
+    Migration {
+        version: 13,
+        name: "video_search",
+        sql: include_str!("../../../migrations/0013_video_search.sql"),
+    },
+];
+
+/// Get the version the schema will have once all migrations are applied
+pub fn latest_schema_version() -> i64 {
+    MIGRATIONS.iter().map(|migration| migration.version).max().unwrap_or(0)
+}
+
+/// Bring the database schema up to date, applying each pending migration in its own transaction
+/// Refuses to run against a schema written by a newer server, and backs up existing databases
+/// next to `db_path` before changing them
+pub async fn run_migrations(pool: &SqlitePool, db_path: &Path) -> Result<()> {
+    sqlx::query(
+        "CREATE TABLE IF NOT EXISTS schema_migrations (
+           version    INTEGER PRIMARY KEY,
+           name       TEXT NOT NULL,
+           applied_at INTEGER NOT NULL
+         )"
+    )
+    .execute(pool)
+    .await?;
+    
+    let applied: Vec<i64> = sqlx::query_scalar("SELECT version FROM schema_migrations ORDER BY version")
+        .fetch_all(pool)
+        .await?;
+    let current_version = applied.last().copied().unwrap_or(0);
+    let latest_version = latest_schema_version();
+    if current_version > latest_version {
+        bail!(
+            "Database schema version {} is newer than this server supports (version {}), please update the server",
+            current_version,
+            latest_version
+        );
+    }
+    
+    let pending: Vec<&Migration> = MIGRATIONS.iter()
+        .filter(|migration| !applied.contains(&migration.version))
+        .collect();
+    if pending.is_empty() {
+        return Ok(());
+    }
+    
+    // A database without migrations but with tables was created by `init_schema` before migrations existed,
+    // it has the baseline schema that migration 1 creates idempotently
+    let is_legacy = applied.is_empty() && table_exists(pool, "tokens").await?;
+    if !applied.is_empty() || is_legacy {
+        let backup_path = backup_database(pool, db_path, current_version).await?;
+        println!("💾 Backed up the database to {} before migrating", backup_path.display());
+    }
+    
+    for migration in pending {
+        println!("🗄️  Applying database migration {} ({})", migration.version, migration.name);
+        let mut tx = pool.begin().await?;
+        
+        sqlx::raw_sql(migration.sql)
+            .execute(&mut *tx)
+            .await
+            .map_err(|e| anyhow::anyhow!("Migration {} ({}) failed: {}", migration.version, migration.name, e))?;
+        
+        sqlx::query("INSERT INTO schema_migrations (version, name, applied_at) VALUES (?, ?, ?)")
+            .bind(migration.version)
+            .bind(migration.name)
+            .bind(chrono::Utc::now().timestamp())
+            .execute(&mut *tx)
+            .await?;
+        
+        tx.commit().await?;
+    }
+    
+    Ok(())
+}
+
+/// Write a consistent copy of the database (WAL contents included) next to it
+/// Returns the path of the backup, `app.sqlite3.v{version}.bak`
+async fn backup_database(pool: &SqlitePool, db_path: &Path, version: i64) -> Result<PathBuf> {
+    let file_name = db_path.file_name().map(|name| name.to_string_lossy().to_string()).unwrap_or_default();
+    let backup_path = db_path.with_file_name(format!("{}.v{}.bak", file_name, version));
+    
+    // VACUUM INTO refuses to overwrite files
+    if backup_path.exists() {
+        tokio::fs::remove_file(&backup_path).await?;
+    }
+    sqlx::query("VACUUM INTO ?")
+        .bind(backup_path.to_string_lossy().to_string())
+        .execute(pool)
+        .await?;
+    
+    Ok(backup_path)
+}
+
+/// Check whether a table exists
+async fn table_exists(pool: &SqlitePool, table: &str) -> Result<bool> {
+    let count: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM sqlite_master WHERE type = 'table' AND name = ?")
+        .bind(table)
+        .fetch_one(pool)
+        .await?;
+    
+    Ok(count > 0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use sqlx::sqlite::SqlitePoolOptions;
+    
+    /// In-memory databases are per connection, so the pool must keep a single one
+    async fn memory_pool() -> SqlitePool {
+        SqlitePoolOptions::new()
+            .max_connections(1)
+            .connect("sqlite::memory:")
+            .await
+            .unwrap()
+    }
+
+    #[test]
+    fn test_migration_versions_are_sequential() {
+        for (i, migration) in MIGRATIONS.iter().enumerate() {
+            assert_eq!(migration.version, i as i64 + 1, "migration {} is out of order", migration.name);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_run_migrations() {
+        let pool = memory_pool().await;
+        let db_path = std::env::temp_dir().join("migrations_test.sqlite3");
+        
+        run_migrations(&pool, &db_path).await.unwrap();
+        // Running again on an up to date schema changes nothing
+        run_migrations(&pool, &db_path).await.unwrap();
+        
+        let versions: Vec<i64> = sqlx::query_scalar("SELECT version FROM schema_migrations")
+            .fetch_all(&pool)
+            .await
+            .unwrap();
+        assert_eq!(versions.last().copied(), Some(latest_schema_version()));
+        assert!(table_exists(&pool, "video_items").await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_run_migrations_upgrades_legacy_schema() {
+        let pool = memory_pool().await;
+        let db_path = std::env::temp_dir().join(format!("migrations_legacy_test_{}.sqlite3", uuid::Uuid::new_v4()));
+        
+        // The baseline schema as `init_schema` created it, without the migrations table
+        sqlx::raw_sql(MIGRATIONS[0].sql).execute(&pool).await.unwrap();
+        sqlx::query("INSERT INTO tokens (token, user_agent, created_at) VALUES ('abc', 'test', 0)")
+            .execute(&pool)
+            .await
+            .unwrap();
+        
+        run_migrations(&pool, &db_path).await.unwrap();
+        let _ = std::fs::remove_file(db_path.with_file_name(format!("{}.v0.bak", db_path.file_name().unwrap().to_string_lossy())));
+        
+        let last_used_at: Option<i64> = sqlx::query_scalar("SELECT last_used_at FROM tokens WHERE token = 'abc'")
+            .fetch_one(&pool)
+            .await
+            .unwrap();
+        assert_eq!(last_used_at, None);
+        assert!(table_exists(&pool, "watch_state").await.unwrap());
+        assert!(table_exists(&pool, "scan_runs").await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_run_migrations_refuses_newer_schema() {
+        let pool = memory_pool().await;
+        let db_path = std::env::temp_dir().join("migrations_newer_test.sqlite3");
+        run_migrations(&pool, &db_path).await.unwrap();
+        
+        sqlx::query("INSERT INTO schema_migrations (version, name, applied_at) VALUES (?, 'future', 0)")
+            .bind(latest_schema_version() + 1)
+            .execute(&pool)
+            .await
+            .unwrap();
+        
+        assert!(run_migrations(&pool, &db_path).await.is_err());
+    }
+}
@@ -1,7 +1,9 @@
 pub mod pool;
+pub mod migrations;
 pub mod models;
 pub mod repos;
 
 pub use pool::*;
+pub use migrations::*;
 pub use models::*;
 pub use repos::*;
@@ -1,6 +1,7 @@
 use serde::{Deserialize, Serialize};
 use chrono::{DateTime, Utc};
 use serde_json::Value;
+use crate::models::config::ScanSchedule;
 
 /// Token model for database storage
 #[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
@@ -8,15 +9,20 @@ pub struct Token {
     pub token: String,
     pub user_agent: String,
     pub created_at: i64, // Unix timestamp
+    pub last_used_at: Option<i64>, // Unix timestamp
+    pub ip_address: Option<String>,
 }
 
 impl Token {
     /// Create a new token instance
-    pub fn new(token: String, user_agent: String) -> Self {
+    pub fn new(token: String, user_agent: String, ip_address: Option<String>) -> Self {
+        let now = Utc::now().timestamp();
         Self {
             token,
             user_agent,
-            created_at: Utc::now().timestamp(),
+            created_at: now,
+            last_used_at: Some(now),
+            ip_address,
         }
     }
     
@@ -26,6 +32,16 @@ impl Token {
     }
 }
 
+/// Client session backed by a token (the token hash itself is never exposed)
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
+pub struct TokenSession {
+    pub id: i64, // SQLite rowid of the token
+    pub user_agent: Option<String>,
+    pub ip_address: Option<String>,
+    pub created_at: i64, // Unix timestamp
+    pub last_used_at: i64, // Unix timestamp (falls back to created_at)
+}
+
 /// Profile model for database storage
 #[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
 pub struct Profile {
@@ -98,6 +114,24 @@ impl Index {
         Ok(())
     }
     
+    /// Get the folders configured in metadata (empty if missing or invalid)
+    pub fn folders(&self) -> Vec<String> {
+        self.metadata_json()
+            .ok()
+            .and_then(|meta| meta.get("folders").and_then(|f| f.as_array()).cloned())
+            .map(|arr| arr.iter().filter_map(|v| v.as_str().map(|s| s.to_string())).collect())
+            .unwrap_or_default()
+    }
+    
+    /// Get the rescan schedule configured in metadata (disabled if missing or invalid)
+    pub fn scan_schedule(&self) -> ScanSchedule {
+        self.metadata_json()
+            .ok()
+            .and_then(|meta| meta.get("scan_schedule").cloned())
+            .and_then(|schedule| serde_json::from_value(schedule).ok())
+            .unwrap_or_default()
+    }
+    
     /// Get the last scanned time as a DateTime
     pub fn last_scanned_at_datetime(&self) -> Option<DateTime<Utc>> {
         if self.last_scanned_at > 0 {
@@ -239,6 +273,7 @@ pub struct VideoPart {
     pub part_index: i64,
     pub duration_ms: Option<i64>,
     pub fast_hash: Option<String>,
+    pub probed_at: Option<i64>, // Unix timestamp
     pub created_at: i64, // Unix timestamp
     pub updated_at: i64, // Unix timestamp
 }
@@ -256,6 +291,7 @@ impl VideoPart {
             part_index,
             duration_ms: None,
             fast_hash: None,
+            probed_at: None,
             created_at: now,
             updated_at: now,
         }
@@ -271,3 +307,234 @@ impl VideoPart {
         DateTime::from_timestamp(self.updated_at, 0).unwrap_or_else(|| Utc::now())
     }
 }
+
+/// Video stream model for database storage (audio/video/subtitle track of a part)
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
+pub struct VideoStream {
+    pub id: i64,
+    pub part_id: i64,
+    pub stream_index: i64,
+    pub kind: String, // 'video' | 'audio' | 'subtitle'
+    pub codec: Option<String>,
+    pub language: Option<String>,
+    pub title: Option<String>,
+    pub channels: Option<i64>,
+    pub width: Option<i64>,
+    pub height: Option<i64>,
+    pub is_default: i64, // 0 = false, 1 = true
+    pub is_forced: i64, // 0 = false, 1 = true
+}
+
+/// Video artwork model for database storage (a cached poster, backdrop or thumbnail of an item)
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
+pub struct VideoArtwork {
+    pub id: i64,
+    pub item_id: i64,
+    pub kind: String, // 'poster' | 'fanart' | 'thumb'
+    pub source: String, // 'sidecar' | 'embedded' | 'frame'
+    pub source_path: Option<String>,
+    pub source_mtime: Option<i64>,
+    pub width: Option<i64>,
+    pub height: Option<i64>,
+    pub created_at: i64, // Unix timestamp
+    pub updated_at: i64, // Unix timestamp
+}
+
+/// Subtitle model for database storage (a sidecar subtitle file or an extracted text stream of a part)
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
+pub struct Subtitle {
+    pub id: i64,
+    pub part_id: i64,
+    pub source: String, // 'sidecar' | 'embedded'
+    pub path: String,
+    pub stream_index: Option<i64>,
+    pub format: String, // 'srt' | 'ass' | 'ssa' | 'vtt'
+    pub language: Option<String>,
+    pub title: Option<String>,
+    pub is_default: i64, // 0 = false, 1 = true
+    pub is_forced: i64, // 0 = false, 1 = true
+    pub is_sdh: i64, // 0 = false, 1 = true
+    pub mtime: Option<i64>, // Unix timestamp
+    pub created_at: i64, // Unix timestamp
+    pub updated_at: i64, // Unix timestamp
+}
+
+/// Photo album model for database storage (a folder containing photos)
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
+pub struct PhotoAlbum {
+    pub id: i64,
+    pub index_id: i64,
+    pub parent_id: Option<i64>,
+    pub path: String,
+    pub title: String,
+    pub created_at: i64, // Unix timestamp
+    pub updated_at: i64, // Unix timestamp
+}
+
+/// Photo item model for database storage
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
+pub struct PhotoItem {
+    pub id: i64,
+    pub index_id: i64,
+    pub album_id: Option<i64>,
+    pub path: String,
+    pub size: Option<i64>,
+    pub mtime: Option<i64>,
+    pub fast_hash: Option<String>,
+    pub width: Option<i64>,
+    pub height: Option<i64>,
+    pub taken_at: i64, // Unix timestamp
+    pub camera_make: Option<String>,
+    pub camera_model: Option<String>,
+    pub latitude: Option<f64>,
+    pub longitude: Option<f64>,
+    pub orientation: Option<i64>,
+    pub has_thumbnail: i64, // 0 = false, 1 = true
+    pub added_at: i64, // Unix timestamp
+    pub updated_at: i64, // Unix timestamp
+}
+
+/// Music item model for database storage (artist, album or track)
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
+pub struct MusicItem {
+    pub id: i64,
+    pub index_id: i64,
+    pub r#type: String, // 'artist', 'album', 'track'
+    pub parent_id: Option<i64>,
+    pub title: String,
+    pub sort_title: Option<String>,
+    pub year: Option<i64>,
+    pub number: Option<i64>, // track number
+    pub disc_number: Option<i64>,
+    pub genre: Option<String>,
+    pub has_cover: i64, // 0 = false, 1 = true
+    pub added_at: i64, // Unix timestamp
+    pub updated_at: i64, // Unix timestamp
+}
+
+impl MusicItem {
+    /// Create a new music item instance
+    pub fn new(
+        index_id: i64,
+        r#type: String,
+        title: String,
+        parent_id: Option<i64>,
+        sort_title: Option<String>,
+    ) -> Self {
+        let now = Utc::now().timestamp();
+        Self {
+            id: 0, // Will be set by database
+            index_id,
+            r#type,
+            parent_id,
+            title,
+            sort_title,
+            year: None,
+            number: None,
+            disc_number: None,
+            genre: None,
+            has_cover: 0,
+            added_at: now,
+            updated_at: now,
+        }
+    }
+}
+
+/// Music file model for database storage (the audio file of a track)
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
+pub struct MusicFile {
+    pub id: i64,
+    pub item_id: i64,
+    pub index_id: i64,
+    pub path: String,
+    pub size: Option<i64>,
+    pub mtime: Option<i64>,
+    pub fast_hash: Option<String>,
+    pub duration_ms: Option<i64>,
+    pub codec: Option<String>,
+    pub sample_rate: Option<i64>,
+    pub channels: Option<i64>,
+    pub created_at: i64, // Unix timestamp
+    pub updated_at: i64, // Unix timestamp
+}
+
+/// Progress of the latest scan of an index
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
+pub struct ScanJob {
+    pub index_id: i64,
+    pub kind: String, // 'full' | 'incremental'
+    pub status: String, // 'scanning' | 'paused' | 'done' | 'failed' | 'cancelled'
+    pub files_discovered: i64,
+    pub files_processed: i64,
+    pub current_folder: Option<String>,
+    pub items_added: i64,
+    pub items_updated: i64,
+    pub items_removed: i64,
+    pub errors: i64,
+    pub started_at: i64, // Unix timestamp
+    pub updated_at: i64, // Unix timestamp
+    pub finished_at: Option<i64>, // Unix timestamp
+}
+
+impl ScanJob {
+    /// Create a new scan job that starts now
+    pub fn new(index_id: i64, kind: String) -> Self {
+        let now = Utc::now().timestamp();
+        Self {
+            index_id,
+            kind,
+            status: "scanning".to_string(),
+            files_discovered: 0,
+            files_processed: 0,
+            current_folder: None,
+            items_added: 0,
+            items_updated: 0,
+            items_removed: 0,
+            errors: 0,
+            started_at: now,
+            updated_at: now,
+            finished_at: None,
+        }
+    }
+    
+    /// Estimate the seconds left from the processing rate so far
+    /// The estimate grows while the walk is still discovering files
+    pub fn eta_secs(&self) -> Option<i64> {
+        if self.status != "scanning" || self.files_processed == 0 {
+            return None;
+        }
+        let elapsed = (self.updated_at - self.started_at).max(1);
+        let remaining = (self.files_discovered - self.files_processed).max(0);
+        Some(remaining * elapsed / self.files_processed)
+    }
+}
+
+/// A finished or running scan in the history of an index
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
+pub struct ScanRun {
+    pub id: i64,
+    pub index_id: i64,
+    pub kind: String, // 'full' | 'incremental'
+    pub outcome: String, // 'running' | 'done' | 'failed' | 'cancelled' | 'interrupted'
+    pub files_discovered: i64,
+    pub files_processed: i64,
+    pub items_added: i64,
+    pub items_updated: i64,
+    pub items_removed: i64,
+    pub errors: i64,
+    pub error_message: Option<String>,
+    pub started_at: i64, // Unix timestamp
+    pub finished_at: Option<i64>, // Unix timestamp
+}
+
+/// Watch state of a video item for a profile (reported for playable items, rolled up for seasons and shows)
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
+pub struct WatchState {
+    pub profile_id: i64,
+    pub item_id: i64,
+    pub position_ms: i64,
+    pub watched: i64, // 0 = false, 1 = true
+    pub play_count: i64,
+    pub last_watched_at: Option<i64>, // Unix timestamp
+    pub updated_at: i64, // Unix timestamp
+}
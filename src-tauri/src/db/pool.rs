@@ -20,16 +20,3 @@ pub async fn connect_pool(db_path: &std::path::Path) -> Result<SqlitePool> {
     
     Ok(pool)
 }
-
-/// Initialize the database schema
-pub async fn init_schema(pool: &SqlitePool) -> Result<()> {
-    // Read the schema.sql file
-    let schema_sql = include_str!("../../../schema.sql");
-    
-    // Execute the schema
-    sqlx::query(schema_sql)
-        .execute(pool)
-        .await?;
-    
-    Ok(())
-}
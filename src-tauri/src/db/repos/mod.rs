@@ -2,8 +2,18 @@ pub mod tokens_repo;
 pub mod profiles_repo;
 pub mod indexes_repo;
 pub mod video_repo;
+pub mod photo_repo;
+pub mod music_repo;
+pub mod scan_jobs_repo;
+pub mod scan_runs_repo;
+pub mod watch_state_repo;
 
 pub use tokens_repo::*;
 pub use profiles_repo::*;
 pub use indexes_repo::*;
 pub use video_repo::*;
+pub use photo_repo::*;
+pub use music_repo::*;
+pub use scan_jobs_repo::*;
+pub use scan_runs_repo::*;
+pub use watch_state_repo::*;
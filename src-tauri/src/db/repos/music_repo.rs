@@ -0,0 +1,271 @@
+use sqlx::SqlitePool;
+use anyhow::Result;
+use crate::db::models::{MusicFile, MusicItem};
+
+/// Repository for music database operations
+#[derive(Debug, Clone)]
+pub struct MusicRepo {
+    pool: SqlitePool,
+}
+
+impl MusicRepo {
+    pub fn new(pool: SqlitePool) -> Self {
+        Self { pool }
+    }
+    
+    // Music Items
+    
+    /// Add a music item, returning its ID
+    pub async fn add_music_item(&self, music_item: &MusicItem) -> Result<i64> {
+        let now = chrono::Utc::now().timestamp();
+        let result = sqlx::query(
+            "INSERT INTO music_items (index_id, type, parent_id, title, sort_title, year, number, disc_number, genre, has_cover, added_at, updated_at)
+             VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)"
+        )
+        .bind(music_item.index_id)
+        .bind(&music_item.r#type)
+        .bind(music_item.parent_id)
+        .bind(&music_item.title)
+        .bind(&music_item.sort_title)
+        .bind(music_item.year)
+        .bind(music_item.number)
+        .bind(music_item.disc_number)
+        .bind(&music_item.genre)
+        .bind(music_item.has_cover)
+        .bind(now)
+        .bind(now)
+        .execute(&self.pool)
+        .await?;
+        
+        Ok(result.last_insert_rowid())
+    }
+    
+    /// Update the tag information and parent of a music item
+    pub async fn update_music_item(&self, music_item: &MusicItem) -> Result<()> {
+        let now = chrono::Utc::now().timestamp();
+        sqlx::query(
+            "UPDATE music_items SET parent_id = ?, title = ?, sort_title = ?, year = ?, number = ?, disc_number = ?, genre = ?, updated_at = ?
+             WHERE id = ?"
+        )
+        .bind(music_item.parent_id)
+        .bind(&music_item.title)
+        .bind(&music_item.sort_title)
+        .bind(music_item.year)
+        .bind(music_item.number)
+        .bind(music_item.disc_number)
+        .bind(&music_item.genre)
+        .bind(now)
+        .bind(music_item.id)
+        .execute(&self.pool)
+        .await?;
+        
+        Ok(())
+    }
+    
+    /// Set whether cover art was extracted for a music item
+    pub async fn update_music_item_cover(&self, id: i64, has_cover: bool) -> Result<()> {
+        sqlx::query("UPDATE music_items SET has_cover = ? WHERE id = ?")
+            .bind(if has_cover { 1 } else { 0 })
+            .bind(id)
+            .execute(&self.pool)
+            .await?;
+        
+        Ok(())
+    }
+    
+    /// Find a music item by type, parent and title (titles are compared case-insensitively)
+    pub async fn find_music_item(&self, index_id: i64, r#type: &str, parent_id: Option<i64>, title: &str) -> Result<Option<MusicItem>> {
+        let music_item = sqlx::query_as::<_, MusicItem>(
+            "SELECT * FROM music_items WHERE index_id = ? AND type = ? AND parent_id IS ? AND title = ? COLLATE NOCASE LIMIT 1"
+        )
+        .bind(index_id)
+        .bind(r#type)
+        .bind(parent_id)
+        .bind(title)
+        .fetch_optional(&self.pool)
+        .await?;
+        
+        Ok(music_item)
+    }
+    
+    /// Get a page of music items of an index (optionally of one type) ordered by title
+    pub async fn get_music_items_page(&self, index_id: i64, r#type: Option<&str>, offset: i64, limit: i64) -> Result<Vec<MusicItem>> {
+        let music_items = if let Some(r#type) = r#type {
+            sqlx::query_as::<_, MusicItem>(
+                "SELECT * FROM music_items WHERE index_id = ? AND type = ?
+                 ORDER BY COALESCE(sort_title, title) COLLATE NOCASE ASC, id ASC LIMIT ? OFFSET ?"
+            )
+            .bind(index_id)
+            .bind(r#type)
+            .bind(limit)
+            .bind(offset)
+            .fetch_all(&self.pool)
+            .await?
+        } else {
+            sqlx::query_as::<_, MusicItem>(
+                "SELECT * FROM music_items WHERE index_id = ?
+                 ORDER BY COALESCE(sort_title, title) COLLATE NOCASE ASC, id ASC LIMIT ? OFFSET ?"
+            )
+            .bind(index_id)
+            .bind(limit)
+            .bind(offset)
+            .fetch_all(&self.pool)
+            .await?
+        };
+        
+        Ok(music_items)
+    }
+    
+    /// Count music items of an index, using the same filter as `get_music_items_page`
+    pub async fn count_music_items(&self, index_id: i64, r#type: Option<&str>) -> Result<i64> {
+        let count = if let Some(r#type) = r#type {
+            sqlx::query_scalar::<_, i64>("SELECT COUNT(*) FROM music_items WHERE index_id = ? AND type = ?")
+                .bind(index_id)
+                .bind(r#type)
+                .fetch_one(&self.pool)
+                .await?
+        } else {
+            sqlx::query_scalar::<_, i64>("SELECT COUNT(*) FROM music_items WHERE index_id = ?")
+                .bind(index_id)
+                .fetch_one(&self.pool)
+                .await?
+        };
+        
+        Ok(count)
+    }
+    
+    /// Get music item by ID
+    pub async fn get_music_item_by_id(&self, id: i64) -> Result<Option<MusicItem>> {
+        let music_item = sqlx::query_as::<_, MusicItem>("SELECT * FROM music_items WHERE id = ?")
+            .bind(id)
+            .fetch_optional(&self.pool)
+            .await?;
+        
+        Ok(music_item)
+    }
+    
+    /// Get the children of a music item: albums by year, tracks by disc and track number
+    pub async fn get_music_item_children(&self, parent_id: i64) -> Result<Vec<MusicItem>> {
+        let music_items = sqlx::query_as::<_, MusicItem>(
+            "SELECT * FROM music_items WHERE parent_id = ?
+             ORDER BY year ASC, disc_number ASC, number ASC, COALESCE(sort_title, title) COLLATE NOCASE ASC"
+        )
+        .bind(parent_id)
+        .fetch_all(&self.pool)
+        .await?;
+        
+        Ok(music_items)
+    }
+    
+    /// Delete artists and albums without children and tracks without a file
+    /// Returns the IDs of the deleted items
+    pub async fn delete_empty_music_items(&self, index_id: i64) -> Result<Vec<i64>> {
+        let mut deleted = Vec::new();
+        
+        // Deleting the last album of an artist leaves the artist empty, so repeat until nothing changes
+        loop {
+            let ids = sqlx::query_scalar::<_, i64>(
+                "DELETE FROM music_items WHERE index_id = ?
+                   AND ((type = 'track' AND id NOT IN (SELECT item_id FROM music_files))
+                     OR (type != 'track' AND id NOT IN (SELECT parent_id FROM music_items WHERE parent_id IS NOT NULL)))
+                 RETURNING id"
+            )
+            .bind(index_id)
+            .fetch_all(&self.pool)
+            .await?;
+            
+            if ids.is_empty() {
+                return Ok(deleted);
+            }
+            deleted.extend(ids);
+        }
+    }
+    
+    // Music Files
+    
+    /// Insert a music file, or update the file already stored at the same path
+    pub async fn save_music_file(&self, music_file: &MusicFile) -> Result<i64> {
+        let now = chrono::Utc::now().timestamp();
+        let id = sqlx::query_scalar::<_, i64>(
+            "INSERT INTO music_files (item_id, index_id, path, size, mtime, fast_hash, duration_ms, codec, sample_rate, channels, created_at, updated_at)
+             VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
+             ON CONFLICT(path) DO UPDATE SET
+               item_id = excluded.item_id,
+               index_id = excluded.index_id,
+               size = excluded.size,
+               mtime = excluded.mtime,
+               fast_hash = excluded.fast_hash,
+               duration_ms = excluded.duration_ms,
+               codec = excluded.codec,
+               sample_rate = excluded.sample_rate,
+               channels = excluded.channels,
+               updated_at = excluded.updated_at
+             RETURNING id"
+        )
+        .bind(music_file.item_id)
+        .bind(music_file.index_id)
+        .bind(&music_file.path)
+        .bind(music_file.size)
+        .bind(music_file.mtime)
+        .bind(&music_file.fast_hash)
+        .bind(music_file.duration_ms)
+        .bind(&music_file.codec)
+        .bind(music_file.sample_rate)
+        .bind(music_file.channels)
+        .bind(now)
+        .bind(now)
+        .fetch_one(&self.pool)
+        .await?;
+        
+        Ok(id)
+    }
+    
+    /// Get all music files of an index
+    pub async fn get_music_files_by_index(&self, index_id: i64) -> Result<Vec<MusicFile>> {
+        let music_files = sqlx::query_as::<_, MusicFile>("SELECT * FROM music_files WHERE index_id = ?")
+            .bind(index_id)
+            .fetch_all(&self.pool)
+            .await?;
+        
+        Ok(music_files)
+    }
+    
+    /// Get the file of a track
+    pub async fn get_music_file_by_item(&self, item_id: i64) -> Result<Option<MusicFile>> {
+        let music_file = sqlx::query_as::<_, MusicFile>("SELECT * FROM music_files WHERE item_id = ? LIMIT 1")
+            .bind(item_id)
+            .fetch_optional(&self.pool)
+            .await?;
+        
+        Ok(music_file)
+    }
+    
+    /// Mark unchanged music files as seen by the current scan in a single transaction
+    pub async fn touch_music_files(&self, ids: &[i64]) -> Result<()> {
+        let now = chrono::Utc::now().timestamp();
+        let mut tx = self.pool.begin().await?;
+        
+        for id in ids {
+            sqlx::query("UPDATE music_files SET updated_at = ? WHERE id = ?")
+                .bind(now)
+                .bind(id)
+                .execute(&mut *tx)
+                .await?;
+        }
+        
+        tx.commit().await?;
+        Ok(())
+    }
+    
+    /// Delete music files of an index that were not seen since the given time, returning the number of deleted files
+    /// Their tracks are removed by `delete_empty_music_items`
+    pub async fn delete_unseen_music_files(&self, index_id: i64, seen_since: i64) -> Result<u64> {
+        let result = sqlx::query("DELETE FROM music_files WHERE index_id = ? AND updated_at < ?")
+            .bind(index_id)
+            .bind(seen_since)
+            .execute(&self.pool)
+            .await?;
+        
+        Ok(result.rows_affected())
+    }
+}
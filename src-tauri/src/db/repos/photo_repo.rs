@@ -0,0 +1,253 @@
+use sqlx::SqlitePool;
+use anyhow::Result;
+use crate::db::models::{PhotoAlbum, PhotoItem};
+
+/// Repository for photo database operations
+#[derive(Debug, Clone)]
+pub struct PhotoRepo {
+    pool: SqlitePool,
+}
+
+impl PhotoRepo {
+    pub fn new(pool: SqlitePool) -> Self {
+        Self { pool }
+    }
+    
+    // Photo Albums
+    
+    /// Get the album of a folder, creating it if needed
+    pub async fn get_or_create_photo_album(&self, index_id: i64, parent_id: Option<i64>, path: &str, title: &str) -> Result<i64> {
+        let now = chrono::Utc::now().timestamp();
+        let id = sqlx::query_scalar::<_, i64>(
+            "INSERT INTO photo_albums (index_id, parent_id, path, title, created_at, updated_at) VALUES (?, ?, ?, ?, ?, ?)
+             ON CONFLICT(index_id, path) DO UPDATE SET parent_id = excluded.parent_id, updated_at = excluded.updated_at
+             RETURNING id"
+        )
+        .bind(index_id)
+        .bind(parent_id)
+        .bind(path)
+        .bind(title)
+        .bind(now)
+        .bind(now)
+        .fetch_one(&self.pool)
+        .await?;
+        
+        Ok(id)
+    }
+    
+    /// Get all albums of an index ordered by path
+    pub async fn get_photo_albums(&self, index_id: i64) -> Result<Vec<PhotoAlbum>> {
+        let photo_albums = sqlx::query_as::<_, PhotoAlbum>(
+            "SELECT * FROM photo_albums WHERE index_id = ? ORDER BY path COLLATE NOCASE ASC"
+        )
+        .bind(index_id)
+        .fetch_all(&self.pool)
+        .await?;
+        
+        Ok(photo_albums)
+    }
+    
+    /// Get photo album by ID
+    pub async fn get_photo_album_by_id(&self, id: i64) -> Result<Option<PhotoAlbum>> {
+        let photo_album = sqlx::query_as::<_, PhotoAlbum>("SELECT * FROM photo_albums WHERE id = ?")
+            .bind(id)
+            .fetch_optional(&self.pool)
+            .await?;
+        
+        Ok(photo_album)
+    }
+    
+    /// Get (album_id, photo_count, cover_photo_id) for each album of an index with photos
+    /// The cover is the most recently taken photo of the album, preferring photos with a thumbnail
+    pub async fn get_photo_album_stats(&self, index_id: i64) -> Result<Vec<(i64, i64, i64)>> {
+        let stats = sqlx::query_as::<_, (i64, i64, i64)>(
+            "SELECT album_id, COUNT(*),
+                    (SELECT cover.id FROM photo_items cover WHERE cover.album_id = photo_items.album_id
+                     ORDER BY cover.has_thumbnail DESC, cover.taken_at DESC, cover.id DESC LIMIT 1)
+             FROM photo_items
+             WHERE index_id = ? AND album_id IS NOT NULL
+             GROUP BY album_id"
+        )
+        .bind(index_id)
+        .fetch_all(&self.pool)
+        .await?;
+        
+        Ok(stats)
+    }
+    
+    /// Delete albums without photos or child albums, returns the number of deleted albums
+    pub async fn delete_empty_photo_albums(&self, index_id: i64) -> Result<u64> {
+        let mut deleted = 0;
+        
+        // Deleting a leaf album can leave its parent empty, so repeat until nothing changes
+        loop {
+            let result = sqlx::query(
+                "DELETE FROM photo_albums WHERE index_id = ?
+                   AND id NOT IN (SELECT album_id FROM photo_items WHERE album_id IS NOT NULL)
+                   AND id NOT IN (SELECT parent_id FROM photo_albums WHERE parent_id IS NOT NULL)"
+            )
+            .bind(index_id)
+            .execute(&self.pool)
+            .await?;
+            
+            if result.rows_affected() == 0 {
+                return Ok(deleted);
+            }
+            deleted += result.rows_affected();
+        }
+    }
+    
+    // Photo Items
+    
+    /// Insert a photo, or update the photo already stored at the same path
+    pub async fn save_photo_item(&self, photo_item: &PhotoItem) -> Result<i64> {
+        let now = chrono::Utc::now().timestamp();
+        let id = sqlx::query_scalar::<_, i64>(
+            "INSERT INTO photo_items (index_id, album_id, path, size, mtime, fast_hash, width, height, taken_at,
+                                      camera_make, camera_model, latitude, longitude, orientation, has_thumbnail, added_at, updated_at)
+             VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
+             ON CONFLICT(path) DO UPDATE SET
+               index_id = excluded.index_id,
+               album_id = excluded.album_id,
+               size = excluded.size,
+               mtime = excluded.mtime,
+               fast_hash = excluded.fast_hash,
+               width = excluded.width,
+               height = excluded.height,
+               taken_at = excluded.taken_at,
+               camera_make = excluded.camera_make,
+               camera_model = excluded.camera_model,
+               latitude = excluded.latitude,
+               longitude = excluded.longitude,
+               orientation = excluded.orientation,
+               has_thumbnail = excluded.has_thumbnail,
+               updated_at = excluded.updated_at
+             RETURNING id"
+        )
+        .bind(photo_item.index_id)
+        .bind(photo_item.album_id)
+        .bind(&photo_item.path)
+        .bind(photo_item.size)
+        .bind(photo_item.mtime)
+        .bind(&photo_item.fast_hash)
+        .bind(photo_item.width)
+        .bind(photo_item.height)
+        .bind(photo_item.taken_at)
+        .bind(&photo_item.camera_make)
+        .bind(&photo_item.camera_model)
+        .bind(photo_item.latitude)
+        .bind(photo_item.longitude)
+        .bind(photo_item.orientation)
+        .bind(photo_item.has_thumbnail)
+        .bind(now)
+        .bind(now)
+        .fetch_one(&self.pool)
+        .await?;
+        
+        Ok(id)
+    }
+    
+    /// Get all photos of an index
+    pub async fn get_photo_items_by_index(&self, index_id: i64) -> Result<Vec<PhotoItem>> {
+        let photo_items = sqlx::query_as::<_, PhotoItem>("SELECT * FROM photo_items WHERE index_id = ?")
+            .bind(index_id)
+            .fetch_all(&self.pool)
+            .await?;
+        
+        Ok(photo_items)
+    }
+    
+    /// Get a page of photos of an index (optionally of one album), most recently taken first
+    pub async fn get_photo_items_page(&self, index_id: i64, album_id: Option<i64>, offset: i64, limit: i64) -> Result<Vec<PhotoItem>> {
+        let photo_items = if let Some(album_id) = album_id {
+            sqlx::query_as::<_, PhotoItem>(
+                "SELECT * FROM photo_items WHERE index_id = ? AND album_id = ? ORDER BY taken_at DESC, id DESC LIMIT ? OFFSET ?"
+            )
+            .bind(index_id)
+            .bind(album_id)
+            .bind(limit)
+            .bind(offset)
+            .fetch_all(&self.pool)
+            .await?
+        } else {
+            sqlx::query_as::<_, PhotoItem>(
+                "SELECT * FROM photo_items WHERE index_id = ? ORDER BY taken_at DESC, id DESC LIMIT ? OFFSET ?"
+            )
+            .bind(index_id)
+            .bind(limit)
+            .bind(offset)
+            .fetch_all(&self.pool)
+            .await?
+        };
+        
+        Ok(photo_items)
+    }
+    
+    /// Count photos of an index, using the same filter as `get_photo_items_page`
+    pub async fn count_photo_items(&self, index_id: i64, album_id: Option<i64>) -> Result<i64> {
+        let count = if let Some(album_id) = album_id {
+            sqlx::query_scalar::<_, i64>("SELECT COUNT(*) FROM photo_items WHERE index_id = ? AND album_id = ?")
+                .bind(index_id)
+                .bind(album_id)
+                .fetch_one(&self.pool)
+                .await?
+        } else {
+            sqlx::query_scalar::<_, i64>("SELECT COUNT(*) FROM photo_items WHERE index_id = ?")
+                .bind(index_id)
+                .fetch_one(&self.pool)
+                .await?
+        };
+        
+        Ok(count)
+    }
+    
+    /// Get photo item by ID
+    pub async fn get_photo_item_by_id(&self, id: i64) -> Result<Option<PhotoItem>> {
+        let photo_item = sqlx::query_as::<_, PhotoItem>("SELECT * FROM photo_items WHERE id = ?")
+            .bind(id)
+            .fetch_optional(&self.pool)
+            .await?;
+        
+        Ok(photo_item)
+    }
+    
+    /// Mark unchanged photos as seen by the current scan in a single transaction
+    pub async fn touch_photo_items(&self, ids: &[i64]) -> Result<()> {
+        let now = chrono::Utc::now().timestamp();
+        let mut tx = self.pool.begin().await?;
+        
+        for id in ids {
+            sqlx::query("UPDATE photo_items SET updated_at = ? WHERE id = ?")
+                .bind(now)
+                .bind(id)
+                .execute(&mut *tx)
+                .await?;
+        }
+        
+        tx.commit().await?;
+        Ok(())
+    }
+    
+    /// Delete photos of an index that were not seen since the given time, returning them
+    pub async fn delete_unseen_photo_items(&self, index_id: i64, seen_since: i64) -> Result<Vec<PhotoItem>> {
+        let photo_items = sqlx::query_as::<_, PhotoItem>(
+            "DELETE FROM photo_items WHERE index_id = ? AND updated_at < ? RETURNING *"
+        )
+        .bind(index_id)
+        .bind(seen_since)
+        .fetch_all(&self.pool)
+        .await?;
+        
+        Ok(photo_items)
+    }
+    
+    /// Check whether any photo has the given content hash (so its thumbnail is still used)
+    pub async fn photo_hash_exists(&self, fast_hash: &str) -> Result<bool> {
+        let count = sqlx::query_scalar::<_, i64>("SELECT COUNT(*) FROM photo_items WHERE fast_hash = ?")
+            .bind(fast_hash)
+            .fetch_one(&self.pool)
+            .await?;
+        
+        Ok(count > 0)
+    }
+}
@@ -0,0 +1,63 @@
+use sqlx::SqlitePool;
+use anyhow::Result;
+use crate::db::models::ScanJob;
+
+/// Repository for scan job database operations
+#[derive(Debug, Clone)]
+pub struct ScanJobsRepo {
+    pool: SqlitePool,
+}
+
+impl ScanJobsRepo {
+    pub fn new(pool: SqlitePool) -> Self {
+        Self { pool }
+    }
+    
+    /// Insert or replace the scan job of an index
+    pub async fn save_scan_job(&self, scan_job: &ScanJob) -> Result<()> {
+        sqlx::query(
+            "INSERT INTO scan_jobs (index_id, kind, status, files_discovered, files_processed, current_folder, items_added, items_updated, items_removed, errors, started_at, updated_at, finished_at)
+             VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
+             ON CONFLICT(index_id) DO UPDATE SET
+               kind = excluded.kind,
+               status = excluded.status,
+               files_discovered = excluded.files_discovered,
+               files_processed = excluded.files_processed,
+               current_folder = excluded.current_folder,
+               items_added = excluded.items_added,
+               items_updated = excluded.items_updated,
+               items_removed = excluded.items_removed,
+               errors = excluded.errors,
+               started_at = excluded.started_at,
+               updated_at = excluded.updated_at,
+               finished_at = excluded.finished_at"
+        )
+        .bind(scan_job.index_id)
+        .bind(&scan_job.kind)
+        .bind(&scan_job.status)
+        .bind(scan_job.files_discovered)
+        .bind(scan_job.files_processed)
+        .bind(&scan_job.current_folder)
+        .bind(scan_job.items_added)
+        .bind(scan_job.items_updated)
+        .bind(scan_job.items_removed)
+        .bind(scan_job.errors)
+        .bind(scan_job.started_at)
+        .bind(scan_job.updated_at)
+        .bind(scan_job.finished_at)
+        .execute(&self.pool)
+        .await?;
+        
+        Ok(())
+    }
+    
+    /// Get the latest scan job of an index
+    pub async fn get_scan_job(&self, index_id: i64) -> Result<Option<ScanJob>> {
+        let scan_job = sqlx::query_as::<_, ScanJob>("SELECT * FROM scan_jobs WHERE index_id = ?")
+            .bind(index_id)
+            .fetch_optional(&self.pool)
+            .await?;
+        
+        Ok(scan_job)
+    }
+}
@@ -0,0 +1,113 @@
+use sqlx::SqlitePool;
+use anyhow::Result;
+use crate::db::models::{ScanJob, ScanRun};
+
+/// Repository for scan history database operations
+#[derive(Debug, Clone)]
+pub struct ScanRunsRepo {
+    pool: SqlitePool,
+}
+
+impl ScanRunsRepo {
+    pub fn new(pool: SqlitePool) -> Self {
+        Self { pool }
+    }
+    
+    /// Record the start of a scan, returning the run ID
+    pub async fn start_scan_run(&self, index_id: i64, kind: &str, started_at: i64) -> Result<i64> {
+        let result = sqlx::query(
+            "INSERT INTO scan_runs (index_id, kind, outcome, started_at) VALUES (?, ?, 'running', ?)"
+        )
+        .bind(index_id)
+        .bind(kind)
+        .bind(started_at)
+        .execute(&self.pool)
+        .await?;
+        
+        Ok(result.last_insert_rowid())
+    }
+    
+    /// Record the outcome and final counters of a scan
+    pub async fn finish_scan_run(&self, id: i64, outcome: &str, scan_job: &ScanJob, error_message: Option<&str>) -> Result<()> {
+        sqlx::query(
+            "UPDATE scan_runs SET outcome = ?, files_discovered = ?, files_processed = ?, items_added = ?, items_updated = ?,
+             items_removed = ?, errors = ?, error_message = ?, finished_at = ? WHERE id = ?"
+        )
+        .bind(outcome)
+        .bind(scan_job.files_discovered)
+        .bind(scan_job.files_processed)
+        .bind(scan_job.items_added)
+        .bind(scan_job.items_updated)
+        .bind(scan_job.items_removed)
+        .bind(scan_job.errors)
+        .bind(error_message)
+        .bind(scan_job.finished_at)
+        .bind(id)
+        .execute(&self.pool)
+        .await?;
+        
+        Ok(())
+    }
+    
+    /// Mark runs started before the given time that never finished as interrupted
+    /// Returns the number of interrupted runs
+    pub async fn interrupt_running_scan_runs(&self, started_before: i64) -> Result<u64> {
+        let result = sqlx::query(
+            "UPDATE scan_runs SET outcome = 'interrupted', finished_at = strftime('%s','now')
+             WHERE outcome = 'running' AND started_at < ?"
+        )
+        .bind(started_before)
+        .execute(&self.pool)
+        .await?;
+        
+        Ok(result.rows_affected())
+    }
+    
+    /// Get the most recent scan runs of an index, newest first
+    pub async fn get_scan_runs(&self, index_id: i64, limit: i64) -> Result<Vec<ScanRun>> {
+        let scan_runs = sqlx::query_as::<_, ScanRun>(
+            "SELECT * FROM scan_runs WHERE index_id = ? ORDER BY started_at DESC, id DESC LIMIT ?"
+        )
+        .bind(index_id)
+        .bind(limit)
+        .fetch_all(&self.pool)
+        .await?;
+        
+        Ok(scan_runs)
+    }
+    
+    /// Get the number of failed or interrupted full scans since the last completed or cancelled one,
+    /// and when the latest of them finished
+    pub async fn get_consecutive_failures(&self, index_id: i64) -> Result<(i64, Option<i64>)> {
+        let failures = sqlx::query_as::<_, (i64, Option<i64>)>(
+            "SELECT COUNT(*), MAX(finished_at) FROM scan_runs
+             WHERE index_id = ? AND kind = 'full' AND outcome IN ('failed','interrupted')
+               AND id > COALESCE((SELECT MAX(id) FROM scan_runs
+                                  WHERE index_id = ? AND kind = 'full' AND outcome IN ('done','cancelled')), 0)"
+        )
+        .bind(index_id)
+        .bind(index_id)
+        .fetch_one(&self.pool)
+        .await?;
+        
+        Ok(failures)
+    }
+    
+    /// Delete all but the most recent runs of a kind of an index
+    /// Kinds are pruned separately, so frequent incremental runs don't push full scans out of the history
+    pub async fn prune_scan_runs(&self, index_id: i64, kind: &str, keep: i64) -> Result<()> {
+        sqlx::query(
+            "DELETE FROM scan_runs WHERE index_id = ? AND kind = ? AND id NOT IN
+             (SELECT id FROM scan_runs WHERE index_id = ? AND kind = ? ORDER BY id DESC LIMIT ?)"
+        )
+        .bind(index_id)
+        .bind(kind)
+        .bind(index_id)
+        .bind(kind)
+        .bind(keep)
+        .execute(&self.pool)
+        .await?;
+        
+        Ok(())
+    }
+}
@@ -1,6 +1,6 @@
 use sqlx::SqlitePool;
 use anyhow::Result;
-use crate::db::models::Token;
+use crate::db::models::{Token, TokenSession};
 
 /// Repository for token database operations
 #[derive(Debug)]
@@ -14,33 +14,87 @@ impl TokensRepo {
     }
     
     /// Add a new token to the database
-    pub async fn add_token(&self, token: String, user_agent: String) -> Result<()> {
-        let token_model = Token::new(token, user_agent);
+    pub async fn add_token(&self, token: String, user_agent: String, ip_address: Option<String>) -> Result<()> {
+        let token_model = Token::new(token, user_agent, ip_address);
         
         sqlx::query(
-            "INSERT INTO tokens (token, user_agent, created_at) VALUES (?, ?, ?)"
+            "INSERT INTO tokens (token, user_agent, created_at, last_used_at, ip_address) VALUES (?, ?, ?, ?, ?)"
         )
         .bind(&token_model.token)
         .bind(&token_model.user_agent)
         .bind(token_model.created_at)
+        .bind(token_model.last_used_at)
+        .bind(&token_model.ip_address)
         .execute(&self.pool)
         .await?;
         
         Ok(())
     }
     
-    /// Check if a token exists in the database
-    pub async fn token_exists(&self, token: &str) -> Result<bool> {
+    /// Check if a token exists in the database and was used after `active_since`
+    pub async fn token_exists(&self, token: &str, active_since: i64) -> Result<bool> {
         let result = sqlx::query_scalar::<_, i64>(
-            "SELECT COUNT(*) FROM tokens WHERE token = ?"
+            "SELECT COUNT(*) FROM tokens WHERE token = ? AND COALESCE(last_used_at, created_at) >= ?"
         )
         .bind(token)
+        .bind(active_since)
         .fetch_one(&self.pool)
         .await?;
         
         Ok(result > 0)
     }
     
+    /// Refresh the last use of a token, skipping the write if it was refreshed after `touched_since`
+    pub async fn touch_token(&self, token: &str, used_at: i64, ip_address: Option<&str>, touched_since: i64) -> Result<()> {
+        sqlx::query(
+            "UPDATE tokens SET last_used_at = ?, ip_address = COALESCE(?, ip_address)
+             WHERE token = ? AND (COALESCE(last_used_at, created_at) < ? OR ip_address IS NOT ?)"
+        )
+        .bind(used_at)
+        .bind(ip_address)
+        .bind(token)
+        .bind(touched_since)
+        .bind(ip_address)
+        .execute(&self.pool)
+        .await?;
+        
+        Ok(())
+    }
+    
+    /// Get all sessions that were used after `active_since`, most recently used first
+    pub async fn get_active_sessions(&self, active_since: i64) -> Result<Vec<TokenSession>> {
+        let sessions = sqlx::query_as::<_, TokenSession>(
+            "SELECT rowid AS id, user_agent, ip_address, created_at, COALESCE(last_used_at, created_at) AS last_used_at
+             FROM tokens
+             WHERE COALESCE(last_used_at, created_at) >= ?
+             ORDER BY COALESCE(last_used_at, created_at) DESC"
+        )
+        .bind(active_since)
+        .fetch_all(&self.pool)
+        .await?;
+        
+        Ok(sessions)
+    }
+    
+    /// Delete the token backing a session
+    pub async fn delete_session(&self, session_id: i64) -> Result<bool> {
+        let result = sqlx::query("DELETE FROM tokens WHERE rowid = ?")
+            .bind(session_id)
+            .execute(&self.pool)
+            .await?;
+        
+        Ok(result.rows_affected() > 0)
+    }
+    
+    /// Delete all tokens
+    pub async fn delete_all_tokens(&self) -> Result<u64> {
+        let result = sqlx::query("DELETE FROM tokens")
+            .execute(&self.pool)
+            .await?;
+        
+        Ok(result.rows_affected())
+    }
+    
     /// Get all tokens (for debugging/admin purposes)
     pub async fn get_all_tokens(&self) -> Result<Vec<Token>> {
         let tokens = sqlx::query_as::<_, Token>("SELECT * FROM tokens ORDER BY created_at DESC")
@@ -69,4 +123,14 @@ impl TokensRepo {
         
         Ok(result.rows_affected())
     }
+    
+    /// Delete tokens that have not been used since the specified timestamp
+    pub async fn delete_expired_tokens(&self, unused_since: i64) -> Result<u64> {
+        let result = sqlx::query("DELETE FROM tokens WHERE COALESCE(last_used_at, created_at) < ?")
+            .bind(unused_since)
+            .execute(&self.pool)
+            .await?;
+        
+        Ok(result.rows_affected())
+    }
 }
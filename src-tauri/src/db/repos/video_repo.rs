@@ -1,17 +1,72 @@
-use sqlx::SqlitePool;
+use sqlx::pool::PoolConnection;
+use sqlx::{Connection, Sqlite, SqliteConnection, SqlitePool, Transaction};
 use anyhow::Result;
-use crate::db::models::{VideoItem, VideoVersion, VideoPart};
+use crate::db::models::{VideoItem, VideoVersion, VideoPart, VideoStream, VideoArtwork, Subtitle};
+use crate::utils::probe::ProbeResult;
 use serde_json::Value;
+use std::collections::HashMap;
+use std::ops::{Deref, DerefMut};
+use tokio::sync::{Mutex, MutexGuard};
 
 /// Repository for video-related database operations
 #[derive(Debug)]
 pub struct VideoRepo {
     pool: SqlitePool,
+    /// Set for repos from `begin`, whose queries all run in this transaction
+    transaction: Option<Mutex<Transaction<'static, Sqlite>>>,
+}
+
+/// Connection a query runs on
+enum RepoConnection<'a> {
+    Pool(PoolConnection<Sqlite>),
+    Transaction(MutexGuard<'a, Transaction<'static, Sqlite>>),
+}
+
+impl Deref for RepoConnection<'_> {
+    type Target = SqliteConnection;
+
+    fn deref(&self) -> &SqliteConnection {
+        match self {
+            RepoConnection::Pool(connection) => connection,
+            RepoConnection::Transaction(transaction) => transaction,
+        }
+    }
+}
+
+impl DerefMut for RepoConnection<'_> {
+    fn deref_mut(&mut self) -> &mut SqliteConnection {
+        match self {
+            RepoConnection::Pool(connection) => connection,
+            RepoConnection::Transaction(transaction) => transaction,
+        }
+    }
 }
 
 impl VideoRepo {
     pub fn new(pool: SqlitePool) -> Self {
-        Self { pool }
+        Self { pool, transaction: None }
+    }
+    
+    /// Get a repo running all its queries in a new transaction, written by `commit`
+    /// Reads see the writes made through the repo before they are committed
+    pub async fn begin(&self) -> Result<Self> {
+        let transaction = self.pool.begin().await?;
+        Ok(Self { pool: self.pool.clone(), transaction: Some(Mutex::new(transaction)) })
+    }
+    
+    /// Commit the transaction of a repo from `begin`
+    pub async fn commit(self) -> Result<()> {
+        if let Some(transaction) = self.transaction {
+            transaction.into_inner().commit().await?;
+        }
+        Ok(())
+    }
+    
+    async fn connection(&self) -> Result<RepoConnection<'_>> {
+        Ok(match &self.transaction {
+            Some(transaction) => RepoConnection::Transaction(transaction.lock().await),
+            None => RepoConnection::Pool(self.pool.acquire().await?),
+        })
     }
     
     // Video Items
@@ -36,7 +91,7 @@ impl VideoRepo {
         .bind(video_item.latest_added_at)
         .bind(video_item.created_at)
         .bind(video_item.updated_at)
-        .execute(&self.pool)
+        .execute(&mut *self.connection().await?)
         .await?;
         
         Ok(result.last_insert_rowid())
@@ -62,7 +117,7 @@ impl VideoRepo {
         .bind(now)
         .bind(now)
         .bind(now)
-        .execute(&self.pool)
+        .execute(&mut *self.connection().await?)
         .await?;
         
         Ok(result.last_insert_rowid())
@@ -74,7 +129,7 @@ impl VideoRepo {
             "SELECT * FROM video_items WHERE index_id = ? ORDER BY latest_added_at DESC"
         )
         .bind(index_id)
-        .fetch_all(&self.pool)
+        .fetch_all(&mut *self.connection().await?)
         .await?;
         
         Ok(video_items)
@@ -87,17 +142,69 @@ impl VideoRepo {
         )
         .bind(index_id)
         .bind(r#type)
-        .fetch_all(&self.pool)
+        .fetch_all(&mut *self.connection().await?)
         .await?;
         
         Ok(video_items)
     }
     
+    /// Get a page of video items for an index, optionally filtered by type
+    /// Without a type filter only top-level items (no parent) are returned
+    /// `sort` must be one of "title", "year" or "latest_added"
+    pub async fn get_video_items_page(&self, index_id: i64, r#type: Option<&str>, sort: &str, offset: i64, limit: i64) -> Result<Vec<VideoItem>> {
+        let order_by = match sort {
+            "year" => "year DESC, COALESCE(sort_title, title) COLLATE NOCASE ASC",
+            "latest_added" => "latest_added_at DESC, id DESC",
+            _ => "COALESCE(sort_title, title) COLLATE NOCASE ASC, year ASC",
+        };
+        
+        let video_items = if let Some(item_type) = r#type {
+            sqlx::query_as::<_, VideoItem>(&format!(
+                "SELECT * FROM video_items WHERE index_id = ? AND type = ? ORDER BY {} LIMIT ? OFFSET ?", order_by
+            ))
+            .bind(index_id)
+            .bind(item_type)
+            .bind(limit)
+            .bind(offset)
+            .fetch_all(&mut *self.connection().await?)
+            .await?
+        } else {
+            sqlx::query_as::<_, VideoItem>(&format!(
+                "SELECT * FROM video_items WHERE index_id = ? AND parent_id IS NULL ORDER BY {} LIMIT ? OFFSET ?", order_by
+            ))
+            .bind(index_id)
+            .bind(limit)
+            .bind(offset)
+            .fetch_all(&mut *self.connection().await?)
+            .await?
+        };
+        
+        Ok(video_items)
+    }
+    
+    /// Count video items for an index, using the same filter as `get_video_items_page`
+    pub async fn count_video_items(&self, index_id: i64, r#type: Option<&str>) -> Result<i64> {
+        let count = if let Some(item_type) = r#type {
+            sqlx::query_scalar::<_, i64>("SELECT COUNT(*) FROM video_items WHERE index_id = ? AND type = ?")
+                .bind(index_id)
+                .bind(item_type)
+                .fetch_one(&mut *self.connection().await?)
+                .await?
+        } else {
+            sqlx::query_scalar::<_, i64>("SELECT COUNT(*) FROM video_items WHERE index_id = ? AND parent_id IS NULL")
+                .bind(index_id)
+                .fetch_one(&mut *self.connection().await?)
+                .await?
+        };
+        
+        Ok(count)
+    }
+    
     /// Get video item by ID
     pub async fn get_video_item_by_id(&self, id: i64) -> Result<Option<VideoItem>> {
         let video_item = sqlx::query_as::<_, VideoItem>("SELECT * FROM video_items WHERE id = ?")
             .bind(id)
-            .fetch_optional(&self.pool)
+            .fetch_optional(&mut *self.connection().await?)
             .await?;
         
         Ok(video_item)
@@ -109,12 +216,102 @@ impl VideoRepo {
             "SELECT * FROM video_items WHERE parent_id = ? ORDER BY number ASC, title ASC"
         )
         .bind(parent_id)
-        .fetch_all(&self.pool)
+        .fetch_all(&mut *self.connection().await?)
+        .await?;
+        
+        Ok(video_items)
+    }
+    
+    /// Get a video item and all its descendants except extras, deepest first (episodes before their season and show)
+    pub async fn get_video_item_subtree(&self, item_id: i64) -> Result<Vec<VideoItem>> {
+        let video_items = sqlx::query_as::<_, VideoItem>(
+            "WITH RECURSIVE subtree(id, depth) AS (
+               SELECT id, 0 FROM video_items WHERE id = ?
+               UNION ALL
+               SELECT vi.id, s.depth + 1 FROM video_items vi
+               JOIN subtree s ON vi.parent_id = s.id
+               WHERE vi.type != 'extra'
+             )
+             SELECT vi.* FROM subtree s
+             JOIN video_items vi ON vi.id = s.id
+             ORDER BY s.depth DESC, vi.number ASC"
+        )
+        .bind(item_id)
+        .fetch_all(&mut *self.connection().await?)
         .await?;
         
         Ok(video_items)
     }
     
+    /// Get movies and shows of an index that were never matched with metadata agents, and shows with episodes added since
+    /// Items described by a local NFO file are left out, the NFO is authoritative
+    pub async fn get_video_items_needing_metadata(&self, index_id: i64) -> Result<Vec<VideoItem>> {
+        let video_items = sqlx::query_as::<_, VideoItem>(
+            "SELECT * FROM video_items vi
+             WHERE vi.index_id = ? AND vi.type IN ('movie', 'show')
+               AND json_extract(vi.metadata, '$.agent') IS NOT 'nfo'
+               AND (vi.metadata_refreshed_at IS NULL
+                 OR EXISTS (SELECT 1 FROM video_items season
+                            JOIN video_items episode ON episode.parent_id = season.id
+                            WHERE season.parent_id = vi.id AND episode.created_at > vi.metadata_refreshed_at))
+             ORDER BY vi.id ASC"
+        )
+        .bind(index_id)
+        .fetch_all(&mut *self.connection().await?)
+        .await?;
+        
+        Ok(video_items)
+    }
+    
+    /// Update the title, sort title, year and metadata of a video item
+    pub async fn update_video_item_metadata(&self, id: i64, title: &str, sort_title: Option<String>, year: Option<i64>, metadata: &Value) -> Result<()> {
+        sqlx::query("UPDATE video_items SET title = ?, sort_title = ?, year = ?, metadata = ?, updated_at = ? WHERE id = ?")
+            .bind(title)
+            .bind(sort_title)
+            .bind(year)
+            .bind(metadata.to_string())
+            .bind(chrono::Utc::now().timestamp())
+            .bind(id)
+            .execute(&mut *self.connection().await?)
+            .await?;
+        
+        Ok(())
+    }
+    
+    /// Mark a video item as matched with metadata agents now, or as needing a match (None)
+    pub async fn update_video_item_metadata_refreshed(&self, id: i64, refreshed_at: Option<i64>) -> Result<()> {
+        sqlx::query("UPDATE video_items SET metadata_refreshed_at = ? WHERE id = ?")
+            .bind(refreshed_at)
+            .bind(id)
+            .execute(&mut *self.connection().await?)
+            .await?;
+        
+        Ok(())
+    }
+    
+    /// Get the modification times of the NFO files imported for the items of an index
+    pub async fn get_video_item_nfo_mtimes(&self, index_id: i64) -> Result<Vec<(i64, i64)>> {
+        let mtimes = sqlx::query_as::<_, (i64, i64)>(
+            "SELECT id, nfo_mtime FROM video_items WHERE index_id = ? AND nfo_mtime IS NOT NULL"
+        )
+        .bind(index_id)
+        .fetch_all(&mut *self.connection().await?)
+        .await?;
+        
+        Ok(mtimes)
+    }
+    
+    /// Record the modification time of the NFO file imported for a video item, or that it has none (None)
+    pub async fn update_video_item_nfo_mtime(&self, id: i64, nfo_mtime: Option<i64>) -> Result<()> {
+        sqlx::query("UPDATE video_items SET nfo_mtime = ? WHERE id = ?")
+            .bind(nfo_mtime)
+            .bind(id)
+            .execute(&mut *self.connection().await?)
+            .await?;
+        
+        Ok(())
+    }
+    
     // Video Versions
     
     /// Add a new video version
@@ -136,7 +333,7 @@ impl VideoRepo {
         .bind(&video_version.probe_version)
         .bind(video_version.created_at)
         .bind(video_version.updated_at)
-        .execute(&self.pool)
+        .execute(&mut *self.connection().await?)
         .await?;
         
         Ok(result.last_insert_rowid())
@@ -148,7 +345,7 @@ impl VideoRepo {
             "SELECT * FROM video_versions WHERE item_id = ? ORDER BY created_at ASC"
         )
         .bind(item_id)
-        .fetch_all(&self.pool)
+        .fetch_all(&mut *self.connection().await?)
         .await?;
         
         Ok(video_versions)
@@ -172,7 +369,7 @@ impl VideoRepo {
         .bind(&video_part.fast_hash)
         .bind(video_part.created_at)
         .bind(video_part.updated_at)
-        .execute(&self.pool)
+        .execute(&mut *self.connection().await?)
         .await?;
         
         Ok(result.last_insert_rowid())
@@ -184,7 +381,23 @@ impl VideoRepo {
             "SELECT * FROM video_parts WHERE version_id = ? ORDER BY part_index ASC"
         )
         .bind(version_id)
-        .fetch_all(&self.pool)
+        .fetch_all(&mut *self.connection().await?)
+        .await?;
+        
+        Ok(video_parts)
+    }
+    
+    /// Get all video parts of an index
+    pub async fn get_video_parts_by_index(&self, index_id: i64) -> Result<Vec<VideoPart>> {
+        let video_parts = sqlx::query_as::<_, VideoPart>(
+            "SELECT vp.*
+             FROM video_parts vp
+             JOIN video_versions vv ON vv.id = vp.version_id
+             JOIN video_items vi ON vi.id = vv.item_id
+             WHERE vi.index_id = ?"
+        )
+        .bind(index_id)
+        .fetch_all(&mut *self.connection().await?)
         .await?;
         
         Ok(video_parts)
@@ -194,12 +407,33 @@ impl VideoRepo {
     pub async fn get_video_part_by_path(&self, path: &str) -> Result<Option<VideoPart>> {
         let video_part = sqlx::query_as::<_, VideoPart>("SELECT * FROM video_parts WHERE path = ?")
             .bind(path)
-            .fetch_optional(&self.pool)
+            .fetch_optional(&mut *self.connection().await?)
             .await?;
         
         Ok(video_part)
     }
     
+    /// Get video parts of an index at a path or anywhere below it (when the path is a folder)
+    pub async fn get_video_parts_under_path(&self, index_id: i64, path: &str) -> Result<Vec<VideoPart>> {
+        let folder_prefix = format!("{}{}", path.trim_end_matches(std::path::MAIN_SEPARATOR), std::path::MAIN_SEPARATOR);
+        let video_parts = sqlx::query_as::<_, VideoPart>(
+            "SELECT vp.*
+             FROM video_parts vp
+             JOIN video_versions vv ON vv.id = vp.version_id
+             JOIN video_items vi ON vi.id = vv.item_id
+             WHERE vi.index_id = ?
+               AND (vp.path = ? OR substr(vp.path, 1, ?) = ?)"
+        )
+        .bind(index_id)
+        .bind(path)
+        .bind(folder_prefix.chars().count() as i64)
+        .bind(&folder_prefix)
+        .fetch_all(&mut *self.connection().await?)
+        .await?;
+        
+        Ok(video_parts)
+    }
+    
     /// Get video parts by size and fast_hash
     pub async fn get_video_parts_by_size_and_hash(&self, size: i64, fast_hash: &str) -> Result<Vec<VideoPart>> {
         let video_parts = sqlx::query_as::<_, VideoPart>(
@@ -207,7 +441,7 @@ impl VideoRepo {
         )
         .bind(size)
         .bind(fast_hash)
-        .fetch_all(&self.pool)
+        .fetch_all(&mut *self.connection().await?)
         .await?;
         
         Ok(video_parts)
@@ -220,7 +454,28 @@ impl VideoRepo {
         )
         .bind(index_id)
         .bind(title)
-        .fetch_all(&self.pool)
+        .fetch_all(&mut *self.connection().await?)
+        .await?;
+        
+        Ok(video_items)
+    }
+    
+    /// Search video items of a type with an FTS5 match expression, best matches first
+    /// Titles weigh most, then alternative titles, cast names and the overview
+    pub async fn search_video_items(&self, match_query: &str, index_id: Option<i64>, item_type: &str, limit: i64) -> Result<Vec<VideoItem>> {
+        let video_items = sqlx::query_as::<_, VideoItem>(
+            "SELECT vi.* FROM video_items_fts
+             JOIN video_items vi ON vi.id = video_items_fts.rowid
+             WHERE video_items_fts MATCH ? AND vi.type = ? AND (? IS NULL OR vi.index_id = ?)
+             ORDER BY bm25(video_items_fts, 10.0, 8.0, 6.0, 1.0, 2.0), vi.sort_title ASC, vi.title ASC
+             LIMIT ?"
+        )
+        .bind(match_query)
+        .bind(item_type)
+        .bind(index_id)
+        .bind(index_id)
+        .bind(limit)
+        .fetch_all(&mut *self.connection().await?)
         .await?;
         
         Ok(video_items)
@@ -232,7 +487,7 @@ impl VideoRepo {
             "SELECT * FROM video_items WHERE parent_id = ?"
         )
         .bind(parent_id)
-        .fetch_all(&self.pool)
+        .fetch_all(&mut *self.connection().await?)
         .await?;
         
         Ok(video_items)
@@ -245,7 +500,7 @@ impl VideoRepo {
         )
         .bind(parent_id)
         .bind(number)
-        .fetch_all(&self.pool)
+        .fetch_all(&mut *self.connection().await?)
         .await?;
         
         Ok(video_items)
@@ -256,7 +511,7 @@ impl VideoRepo {
         sqlx::query("UPDATE video_parts SET updated_at = ? WHERE id = ?")
             .bind(chrono::Utc::now().timestamp())
             .bind(id)
-            .execute(&self.pool)
+            .execute(&mut *self.connection().await?)
             .await?;
         
         Ok(())
@@ -269,7 +524,27 @@ impl VideoRepo {
             .bind(mtime)
             .bind(chrono::Utc::now().timestamp())
             .bind(id)
-            .execute(&self.pool)
+            .execute(&mut *self.connection().await?)
+            .await?;
+        
+        Ok(())
+    }
+    
+    /// Update the size, mtime and fast_hash of a part whose file content changed
+    /// A different size or hash clears `probed_at`, so the new content is probed again even if its mtime is older
+    pub async fn update_video_part_file(&self, id: i64, size: i64, mtime: i64, fast_hash: String) -> Result<()> {
+        sqlx::query(
+            "UPDATE video_parts
+             SET probed_at = CASE WHEN size IS NOT ?1 OR fast_hash IS NOT ?3 THEN NULL ELSE probed_at END,
+                 size = ?1, mtime = ?2, fast_hash = ?3, updated_at = ?4
+             WHERE id = ?5"
+        )
+            .bind(size)
+            .bind(mtime)
+            .bind(&fast_hash)
+            .bind(chrono::Utc::now().timestamp())
+            .bind(id)
+            .execute(&mut *self.connection().await?)
             .await?;
         
         Ok(())
@@ -320,7 +595,7 @@ impl VideoRepo {
         .bind(&video_version.probe_version)
         .bind(video_version.created_at)
         .bind(video_version.updated_at)
-        .execute(&self.pool)
+        .execute(&mut *self.connection().await?)
         .await?;
         
         Ok(result.last_insert_rowid())
@@ -346,6 +621,7 @@ impl VideoRepo {
             part_index,
             duration_ms,
             fast_hash,
+            probed_at: None,
             created_at: chrono::Utc::now().timestamp(),
             updated_at: chrono::Utc::now().timestamp(),
         };
@@ -362,7 +638,7 @@ impl VideoRepo {
         .bind(&video_part.fast_hash)
         .bind(video_part.created_at)
         .bind(video_part.updated_at)
-        .execute(&self.pool)
+        .execute(&mut *self.connection().await?)
         .await?;
         
         Ok(result.last_insert_rowid())
@@ -372,7 +648,7 @@ impl VideoRepo {
     pub async fn delete_video_item(&self, id: i64) -> Result<()> {
         sqlx::query("DELETE FROM video_items WHERE id = ?")
             .bind(id)
-            .execute(&self.pool)
+            .execute(&mut *self.connection().await?)
             .await?;
         
         Ok(())
@@ -382,7 +658,7 @@ impl VideoRepo {
     pub async fn delete_video_version(&self, id: i64) -> Result<()> {
         sqlx::query("DELETE FROM video_versions WHERE id = ?")
             .bind(id)
-            .execute(&self.pool)
+            .execute(&mut *self.connection().await?)
             .await?;
         
         Ok(())
@@ -392,7 +668,7 @@ impl VideoRepo {
     pub async fn delete_video_part(&self, id: i64) -> Result<()> {
         sqlx::query("DELETE FROM video_parts WHERE id = ?")
             .bind(id)
-            .execute(&self.pool)
+            .execute(&mut *self.connection().await?)
             .await?;
         
         Ok(())
@@ -405,7 +681,7 @@ impl VideoRepo {
         )
         .bind(index_id)
         .bind(source_path)
-        .fetch_all(&self.pool)
+        .fetch_all(&mut *self.connection().await?)
         .await?;
         
         Ok(video_items)
@@ -416,7 +692,7 @@ impl VideoRepo {
         sqlx::query("UPDATE video_items SET source_path = ?, updated_at = strftime('%s','now') WHERE id = ?")
             .bind(&source_path)
             .bind(id)
-            .execute(&self.pool)
+            .execute(&mut *self.connection().await?)
             .await?;
         
         Ok(())
@@ -426,7 +702,7 @@ impl VideoRepo {
     pub async fn get_video_part_by_id(&self, id: i64) -> Result<Option<VideoPart>> {
         let video_part = sqlx::query_as::<_, VideoPart>("SELECT * FROM video_parts WHERE id = ?")
             .bind(id)
-            .fetch_optional(&self.pool)
+            .fetch_optional(&mut *self.connection().await?)
             .await?;
         
         Ok(video_part)
@@ -436,7 +712,7 @@ impl VideoRepo {
     pub async fn get_video_version_by_id(&self, id: i64) -> Result<Option<VideoVersion>> {
         let video_version = sqlx::query_as::<_, VideoVersion>("SELECT * FROM video_versions WHERE id = ?")
             .bind(id)
-            .fetch_optional(&self.pool)
+            .fetch_optional(&mut *self.connection().await?)
             .await?;
         
         Ok(video_version)
@@ -447,7 +723,7 @@ impl VideoRepo {
         sqlx::query("UPDATE video_versions SET item_id = ?, updated_at = strftime('%s','now') WHERE id = ?")
             .bind(item_id)
             .bind(id)
-            .execute(&self.pool)
+            .execute(&mut *self.connection().await?)
             .await?;
         
         Ok(())
@@ -458,9 +734,404 @@ impl VideoRepo {
         sqlx::query("UPDATE video_parts SET version_id = ?, updated_at = strftime('%s','now') WHERE id = ?")
             .bind(version_id)
             .bind(id)
-            .execute(&self.pool)
+            .execute(&mut *self.connection().await?)
+            .await?;
+        
+        Ok(())
+    }
+    
+    // Probing
+    
+    /// Get IDs of versions in an index that have parts which were never probed or changed since
+    pub async fn get_video_version_ids_needing_probe(&self, index_id: i64) -> Result<Vec<i64>> {
+        let version_ids = sqlx::query_scalar::<_, i64>(
+            "SELECT DISTINCT vv.id
+             FROM video_parts vp
+             JOIN video_versions vv ON vv.id = vp.version_id
+             JOIN video_items vi ON vi.id = vv.item_id
+             WHERE vi.index_id = ?
+               AND (vp.probed_at IS NULL OR vp.probed_at < vp.mtime)"
+        )
+        .bind(index_id)
+        .fetch_all(&mut *self.connection().await?)
+        .await?;
+        
+        Ok(version_ids)
+    }
+    
+    /// Store the probed duration of a part and mark it as probed
+    pub async fn update_video_part_probe(&self, id: i64, duration_ms: Option<i64>) -> Result<()> {
+        let now = chrono::Utc::now().timestamp();
+        sqlx::query("UPDATE video_parts SET duration_ms = ?, probed_at = ?, updated_at = ? WHERE id = ?")
+            .bind(duration_ms)
+            .bind(now)
+            .bind(now)
+            .bind(id)
+            .execute(&mut *self.connection().await?)
             .await?;
         
         Ok(())
     }
+    
+    /// Store the probed technical details of a version
+    /// `runtime_ms` is the total of all parts, the other details come from the probe of the first part
+    pub async fn update_video_version_probe(&self, id: i64, probe: &ProbeResult, runtime_ms: Option<i64>, probe_version: &str) -> Result<()> {
+        sqlx::query(
+            "UPDATE video_versions
+             SET container = ?, resolution = ?, hdr = ?, audio_channels = ?, bitrate = ?, runtime_ms = ?, probe_version = ?, updated_at = ?
+             WHERE id = ?"
+        )
+        .bind(&probe.container)
+        .bind(&probe.resolution)
+        .bind(if probe.hdr { 1 } else { 0 })
+        .bind(probe.audio_channels)
+        .bind(probe.bitrate)
+        .bind(runtime_ms)
+        .bind(probe_version)
+        .bind(chrono::Utc::now().timestamp())
+        .bind(id)
+        .execute(&mut *self.connection().await?)
+        .await?;
+        
+        Ok(())
+    }
+    
+    // Video Streams
+    
+    /// Replace all streams of a part with the given ones
+    pub async fn replace_video_streams(&self, part_id: i64, streams: &[VideoStream]) -> Result<()> {
+        let mut connection = self.connection().await?;
+        let mut tx = connection.begin().await?;
+        
+        sqlx::query("DELETE FROM video_streams WHERE part_id = ?")
+            .bind(part_id)
+            .execute(&mut *tx)
+            .await?;
+        
+        for stream in streams {
+            sqlx::query(
+                "INSERT INTO video_streams (part_id, stream_index, kind, codec, language, title, channels, width, height, is_default, is_forced)
+                 VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)"
+            )
+            .bind(part_id)
+            .bind(stream.stream_index)
+            .bind(&stream.kind)
+            .bind(&stream.codec)
+            .bind(&stream.language)
+            .bind(&stream.title)
+            .bind(stream.channels)
+            .bind(stream.width)
+            .bind(stream.height)
+            .bind(stream.is_default)
+            .bind(stream.is_forced)
+            .execute(&mut *tx)
+            .await?;
+        }
+        
+        tx.commit().await?;
+        Ok(())
+    }
+    
+    /// Get streams of a part in container order
+    pub async fn get_video_streams_by_part(&self, part_id: i64) -> Result<Vec<VideoStream>> {
+        let streams = sqlx::query_as::<_, VideoStream>(
+            "SELECT * FROM video_streams WHERE part_id = ? ORDER BY stream_index ASC"
+        )
+        .bind(part_id)
+        .fetch_all(&mut *self.connection().await?)
+        .await?;
+        
+        Ok(streams)
+    }
+    
+    // Video Artwork
+    
+    /// Get the first part of every item of an index that has files, as (item ID, path, duration in ms)
+    /// The first part of the first version stands for the item when extracting artwork
+    pub async fn get_video_item_first_parts(&self, index_id: i64) -> Result<Vec<(i64, String, Option<i64>)>> {
+        let parts = sqlx::query_as::<_, (i64, String, Option<i64>)>(
+            "SELECT vv.item_id, vp.path, vp.duration_ms
+             FROM video_parts vp
+             JOIN video_versions vv ON vv.id = vp.version_id
+             JOIN video_items vi ON vi.id = vv.item_id
+             WHERE vi.index_id = ?
+             ORDER BY vv.item_id ASC, vv.id ASC, vp.part_index ASC"
+        )
+        .bind(index_id)
+        .fetch_all(&mut *self.connection().await?)
+        .await?;
+        
+        let mut first_parts: Vec<(i64, String, Option<i64>)> = Vec::new();
+        for part in parts {
+            if first_parts.last().map(|last| last.0) != Some(part.0) {
+                first_parts.push(part);
+            }
+        }
+        
+        Ok(first_parts)
+    }
+    
+    /// Get IDs of items in an index whose embedded artwork was never extracted or whose files changed since
+    pub async fn get_video_item_ids_needing_artwork(&self, index_id: i64) -> Result<Vec<i64>> {
+        let item_ids = sqlx::query_scalar::<_, i64>(
+            "SELECT DISTINCT vi.id
+             FROM video_items vi
+             JOIN video_versions vv ON vv.item_id = vi.id
+             JOIN video_parts vp ON vp.version_id = vv.id
+             WHERE vi.index_id = ?
+               AND (vi.artwork_checked_at IS NULL
+                 OR vp.mtime > vi.artwork_checked_at
+                 OR vp.probed_at > vi.artwork_checked_at)"
+        )
+        .bind(index_id)
+        .fetch_all(&mut *self.connection().await?)
+        .await?;
+        
+        Ok(item_ids)
+    }
+    
+    /// Mark the embedded artwork of an item as extracted now, or as needing extraction (None)
+    pub async fn update_video_item_artwork_checked(&self, id: i64, checked_at: Option<i64>) -> Result<()> {
+        sqlx::query("UPDATE video_items SET artwork_checked_at = ? WHERE id = ?")
+            .bind(checked_at)
+            .bind(id)
+            .execute(&mut *self.connection().await?)
+            .await?;
+        
+        Ok(())
+    }
+    
+    /// Get the IDs of all video items, across indexes
+    pub async fn get_all_video_item_ids(&self) -> Result<Vec<i64>> {
+        let item_ids = sqlx::query_scalar::<_, i64>("SELECT id FROM video_items")
+            .fetch_all(&mut *self.connection().await?)
+            .await?;
+        
+        Ok(item_ids)
+    }
+    
+    /// Get all artwork of the items of an index
+    pub async fn get_video_artwork_by_index(&self, index_id: i64) -> Result<Vec<VideoArtwork>> {
+        let artwork = sqlx::query_as::<_, VideoArtwork>(
+            "SELECT va.* FROM video_artwork va
+             JOIN video_items vi ON vi.id = va.item_id
+             WHERE vi.index_id = ?"
+        )
+        .bind(index_id)
+        .fetch_all(&mut *self.connection().await?)
+        .await?;
+        
+        Ok(artwork)
+    }
+    
+    /// Get all artwork of an item
+    pub async fn get_video_artwork_by_item(&self, item_id: i64) -> Result<Vec<VideoArtwork>> {
+        let artwork = sqlx::query_as::<_, VideoArtwork>("SELECT * FROM video_artwork WHERE item_id = ? ORDER BY kind ASC")
+            .bind(item_id)
+            .fetch_all(&mut *self.connection().await?)
+            .await?;
+        
+        Ok(artwork)
+    }
+    
+    /// Get all artwork of several items at once, keyed by item ID (items without artwork are left out)
+    pub async fn get_video_artwork_by_items(&self, item_ids: &[i64]) -> Result<HashMap<i64, Vec<VideoArtwork>>> {
+        let mut artwork_by_item: HashMap<i64, Vec<VideoArtwork>> = HashMap::new();
+        if item_ids.is_empty() {
+            return Ok(artwork_by_item);
+        }
+        
+        let sql = format!(
+            "SELECT * FROM video_artwork WHERE item_id IN ({}) ORDER BY kind ASC",
+            vec!["?"; item_ids.len()].join(", ")
+        );
+        let mut query = sqlx::query_as::<_, VideoArtwork>(&sql);
+        for item_id in item_ids {
+            query = query.bind(item_id);
+        }
+        for artwork in query.fetch_all(&mut *self.connection().await?).await? {
+            artwork_by_item.entry(artwork.item_id).or_default().push(artwork);
+        }
+        
+        Ok(artwork_by_item)
+    }
+    
+    /// Get one kind of artwork of an item
+    pub async fn get_video_artwork(&self, item_id: i64, kind: &str) -> Result<Option<VideoArtwork>> {
+        let artwork = sqlx::query_as::<_, VideoArtwork>("SELECT * FROM video_artwork WHERE item_id = ? AND kind = ?")
+            .bind(item_id)
+            .bind(kind)
+            .fetch_optional(&mut *self.connection().await?)
+            .await?;
+        
+        Ok(artwork)
+    }
+    
+    /// Insert artwork, or replace the artwork of the same kind already stored for the item
+    pub async fn save_video_artwork(&self, artwork: &VideoArtwork) -> Result<()> {
+        let now = chrono::Utc::now().timestamp();
+        sqlx::query(
+            "INSERT INTO video_artwork (item_id, kind, source, source_path, source_mtime, width, height, created_at, updated_at)
+             VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?)
+             ON CONFLICT(item_id, kind) DO UPDATE SET
+               source = excluded.source,
+               source_path = excluded.source_path,
+               source_mtime = excluded.source_mtime,
+               width = excluded.width,
+               height = excluded.height,
+               updated_at = excluded.updated_at"
+        )
+        .bind(artwork.item_id)
+        .bind(&artwork.kind)
+        .bind(&artwork.source)
+        .bind(&artwork.source_path)
+        .bind(artwork.source_mtime)
+        .bind(artwork.width)
+        .bind(artwork.height)
+        .bind(now)
+        .bind(now)
+        .execute(&mut *self.connection().await?)
+        .await?;
+        
+        Ok(())
+    }
+    
+    /// Delete one kind of artwork of an item
+    pub async fn delete_video_artwork(&self, item_id: i64, kind: &str) -> Result<()> {
+        sqlx::query("DELETE FROM video_artwork WHERE item_id = ? AND kind = ?")
+            .bind(item_id)
+            .bind(kind)
+            .execute(&mut *self.connection().await?)
+            .await?;
+        
+        Ok(())
+    }
+    
+    // Subtitles
+    
+    /// Get the sidecar subtitles of the parts of an index
+    pub async fn get_sidecar_subtitles_by_index(&self, index_id: i64) -> Result<Vec<Subtitle>> {
+        let subtitles = sqlx::query_as::<_, Subtitle>(
+            "SELECT s.* FROM subtitles s
+             JOIN video_parts vp ON vp.id = s.part_id
+             JOIN video_versions vv ON vv.id = vp.version_id
+             JOIN video_items vi ON vi.id = vv.item_id
+             WHERE vi.index_id = ? AND s.source = 'sidecar'"
+        )
+        .bind(index_id)
+        .fetch_all(&mut *self.connection().await?)
+        .await?;
+        
+        Ok(subtitles)
+    }
+    
+    /// Get the subtitles of a part, embedded tracks first in container order
+    pub async fn get_subtitles_by_part(&self, part_id: i64) -> Result<Vec<Subtitle>> {
+        let subtitles = sqlx::query_as::<_, Subtitle>(
+            "SELECT * FROM subtitles WHERE part_id = ? ORDER BY source ASC, stream_index ASC, path ASC"
+        )
+        .bind(part_id)
+        .fetch_all(&mut *self.connection().await?)
+        .await?;
+        
+        Ok(subtitles)
+    }
+    
+    /// Get subtitle by ID
+    pub async fn get_subtitle_by_id(&self, id: i64) -> Result<Option<Subtitle>> {
+        let subtitle = sqlx::query_as::<_, Subtitle>("SELECT * FROM subtitles WHERE id = ?")
+            .bind(id)
+            .fetch_optional(&mut *self.connection().await?)
+            .await?;
+        
+        Ok(subtitle)
+    }
+    
+    /// Insert a subtitle, or update the one already stored for the same file of the part
+    pub async fn save_subtitle(&self, subtitle: &Subtitle) -> Result<()> {
+        let now = chrono::Utc::now().timestamp();
+        sqlx::query(
+            "INSERT INTO subtitles (part_id, source, path, stream_index, format, language, title, is_default, is_forced, is_sdh, mtime, created_at, updated_at)
+             VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
+             ON CONFLICT(part_id, path) DO UPDATE SET
+               source = excluded.source,
+               stream_index = excluded.stream_index,
+               format = excluded.format,
+               language = excluded.language,
+               title = excluded.title,
+               is_default = excluded.is_default,
+               is_forced = excluded.is_forced,
+               is_sdh = excluded.is_sdh,
+               mtime = excluded.mtime,
+               updated_at = excluded.updated_at"
+        )
+        .bind(subtitle.part_id)
+        .bind(&subtitle.source)
+        .bind(&subtitle.path)
+        .bind(subtitle.stream_index)
+        .bind(&subtitle.format)
+        .bind(&subtitle.language)
+        .bind(&subtitle.title)
+        .bind(subtitle.is_default)
+        .bind(subtitle.is_forced)
+        .bind(subtitle.is_sdh)
+        .bind(subtitle.mtime)
+        .bind(now)
+        .bind(now)
+        .execute(&mut *self.connection().await?)
+        .await?;
+        
+        Ok(())
+    }
+    
+    /// Replace the embedded subtitles of a part with the given ones
+    pub async fn replace_embedded_subtitles(&self, part_id: i64, subtitles: &[Subtitle]) -> Result<()> {
+        let mut connection = self.connection().await?;
+        let mut tx = connection.begin().await?;
+        
+        sqlx::query("DELETE FROM subtitles WHERE part_id = ? AND source = 'embedded'")
+            .bind(part_id)
+            .execute(&mut *tx)
+            .await?;
+        
+        for subtitle in subtitles {
+            sqlx::query(
+                "INSERT INTO subtitles (part_id, source, path, stream_index, format, language, title, is_default, is_forced, is_sdh)
+                 VALUES (?, 'embedded', ?, ?, ?, ?, ?, ?, ?, ?)"
+            )
+            .bind(part_id)
+            .bind(&subtitle.path)
+            .bind(subtitle.stream_index)
+            .bind(&subtitle.format)
+            .bind(&subtitle.language)
+            .bind(&subtitle.title)
+            .bind(subtitle.is_default)
+            .bind(subtitle.is_forced)
+            .bind(subtitle.is_sdh)
+            .execute(&mut *tx)
+            .await?;
+        }
+        
+        tx.commit().await?;
+        Ok(())
+    }
+    
+    /// Delete a subtitle
+    pub async fn delete_subtitle(&self, id: i64) -> Result<()> {
+        sqlx::query("DELETE FROM subtitles WHERE id = ?")
+            .bind(id)
+            .execute(&mut *self.connection().await?)
+            .await?;
+        
+        Ok(())
+    }
+    
+    /// Get the IDs of all video parts, across indexes
+    pub async fn get_all_video_part_ids(&self) -> Result<Vec<i64>> {
+        let part_ids = sqlx::query_scalar::<_, i64>("SELECT id FROM video_parts")
+            .fetch_all(&mut *self.connection().await?)
+            .await?;
+        
+        Ok(part_ids)
+    }
 }
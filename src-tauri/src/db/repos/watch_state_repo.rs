@@ -0,0 +1,185 @@
+use sqlx::SqlitePool;
+use anyhow::Result;
+use crate::db::models::{VideoItem, WatchState};
+use std::collections::HashMap;
+
+/// Repository for per-profile watch state database operations
+#[derive(Debug, Clone)]
+pub struct WatchStateRepo {
+    pool: SqlitePool,
+}
+
+impl WatchStateRepo {
+    pub fn new(pool: SqlitePool) -> Self {
+        Self { pool }
+    }
+    
+    /// Get the watch state of an item for a profile
+    pub async fn get_watch_state(&self, profile_id: i64, item_id: i64) -> Result<Option<WatchState>> {
+        let watch_state = sqlx::query_as::<_, WatchState>(
+            "SELECT * FROM watch_state WHERE profile_id = ? AND item_id = ?"
+        )
+        .bind(profile_id)
+        .bind(item_id)
+        .fetch_optional(&self.pool)
+        .await?;
+        
+        Ok(watch_state)
+    }
+    
+    /// Get the watch state of several items for a profile, keyed by item ID (items never watched are left out)
+    pub async fn get_watch_states(&self, profile_id: i64, item_ids: &[i64]) -> Result<HashMap<i64, WatchState>> {
+        if item_ids.is_empty() {
+            return Ok(HashMap::new());
+        }
+        
+        let sql = format!(
+            "SELECT * FROM watch_state WHERE profile_id = ? AND item_id IN ({})",
+            vec!["?"; item_ids.len()].join(", ")
+        );
+        let mut query = sqlx::query_as::<_, WatchState>(&sql).bind(profile_id);
+        for item_id in item_ids {
+            query = query.bind(item_id);
+        }
+        let watch_states = query.fetch_all(&self.pool).await?;
+        
+        Ok(watch_states.into_iter().map(|watch_state| (watch_state.item_id, watch_state)).collect())
+    }
+    
+    /// Store the resume point of an item, keeping its watched flag and play count
+    pub async fn save_progress(&self, profile_id: i64, item_id: i64, position_ms: i64) -> Result<()> {
+        let now = chrono::Utc::now().timestamp();
+        sqlx::query(
+            "INSERT INTO watch_state (profile_id, item_id, position_ms, last_watched_at, updated_at)
+             VALUES (?, ?, ?, ?, ?)
+             ON CONFLICT(profile_id, item_id) DO UPDATE SET
+               position_ms = excluded.position_ms,
+               last_watched_at = excluded.last_watched_at,
+               updated_at = excluded.updated_at"
+        )
+        .bind(profile_id)
+        .bind(item_id)
+        .bind(position_ms)
+        .bind(now)
+        .bind(now)
+        .execute(&self.pool)
+        .await?;
+        
+        Ok(())
+    }
+    
+    /// Mark an item as watched (counting a play) or unwatched, clearing its resume point either way
+    pub async fn set_watched(&self, profile_id: i64, item_id: i64, watched: bool) -> Result<()> {
+        let now = chrono::Utc::now().timestamp();
+        let query = if watched {
+            "INSERT INTO watch_state (profile_id, item_id, position_ms, watched, play_count, last_watched_at, updated_at)
+             VALUES (?, ?, 0, 1, 1, ?, ?)
+             ON CONFLICT(profile_id, item_id) DO UPDATE SET
+               position_ms = 0,
+               watched = 1,
+               play_count = play_count + 1,
+               last_watched_at = excluded.last_watched_at,
+               updated_at = excluded.updated_at"
+        } else {
+            "INSERT INTO watch_state (profile_id, item_id, position_ms, watched, last_watched_at, updated_at)
+             VALUES (?, ?, 0, 0, ?, ?)
+             ON CONFLICT(profile_id, item_id) DO UPDATE SET
+               position_ms = 0,
+               watched = 0,
+               updated_at = excluded.updated_at"
+        };
+        
+        sqlx::query(query)
+            .bind(profile_id)
+            .bind(item_id)
+            .bind(now)
+            .bind(now)
+            .execute(&self.pool)
+            .await?;
+        
+        Ok(())
+    }
+    
+    /// Recompute the watch state of a season or show from its children (extras excluded)
+    /// It is watched once every child is, and was last watched when its latest child was
+    pub async fn refresh_parent_watch_state(&self, profile_id: i64, parent_id: i64) -> Result<()> {
+        let now = chrono::Utc::now().timestamp();
+        sqlx::query(
+            "INSERT INTO watch_state (profile_id, item_id, watched, last_watched_at, updated_at)
+             SELECT ?, ?,
+                    CASE WHEN COUNT(*) > 0 AND SUM(COALESCE(w.watched, 0)) = COUNT(*) THEN 1 ELSE 0 END,
+                    MAX(w.last_watched_at), ?
+             FROM video_items c
+             LEFT JOIN watch_state w ON w.item_id = c.id AND w.profile_id = ?
+             WHERE c.parent_id = ? AND c.type != 'extra'
+             ON CONFLICT(profile_id, item_id) DO UPDATE SET
+               watched = excluded.watched,
+               last_watched_at = excluded.last_watched_at,
+               updated_at = excluded.updated_at"
+        )
+        .bind(profile_id)
+        .bind(parent_id)
+        .bind(now)
+        .bind(profile_id)
+        .bind(parent_id)
+        .execute(&self.pool)
+        .await?;
+        
+        Ok(())
+    }
+    
+    /// Get the items a profile stopped watching part way through, most recently watched first
+    pub async fn get_continue_watching(&self, profile_id: i64, limit: i64) -> Result<Vec<VideoItem>> {
+        let video_items = sqlx::query_as::<_, VideoItem>(
+            "SELECT vi.* FROM watch_state w
+             JOIN video_items vi ON vi.id = w.item_id
+             WHERE w.profile_id = ? AND w.position_ms > 0
+             ORDER BY w.last_watched_at DESC
+             LIMIT ?"
+        )
+        .bind(profile_id)
+        .bind(limit)
+        .fetch_all(&self.pool)
+        .await?;
+        
+        Ok(video_items)
+    }
+    
+    /// Get the next episode of each show a profile is watching, most recently watched show first
+    /// The next episode follows the last watched one in season and episode number order; it must be
+    /// unwatched and not started (those are in Continue Watching). Specials (season 0) are skipped.
+    pub async fn get_next_up(&self, profile_id: i64, limit: i64) -> Result<Vec<VideoItem>> {
+        let video_items = sqlx::query_as::<_, VideoItem>(
+            "WITH last_watched AS (
+               SELECT se.parent_id AS show_id, se.number AS season_number, ep.number AS episode_number, w.last_watched_at,
+                      ROW_NUMBER() OVER (PARTITION BY se.parent_id ORDER BY w.last_watched_at DESC, se.number DESC, ep.number DESC) AS row_rank
+               FROM watch_state w
+               JOIN video_items ep ON ep.id = w.item_id AND ep.type = 'episode'
+               JOIN video_items se ON se.id = ep.parent_id AND se.type = 'season'
+               WHERE w.profile_id = ? AND w.watched = 1
+             ),
+             candidates AS (
+               SELECT ep.*, lw.last_watched_at AS show_last_watched_at,
+                      ROW_NUMBER() OVER (PARTITION BY lw.show_id ORDER BY se.number ASC, ep.number ASC) AS row_rank
+               FROM last_watched lw
+               JOIN video_items se ON se.parent_id = lw.show_id AND se.type = 'season'
+               JOIN video_items ep ON ep.parent_id = se.id AND ep.type = 'episode'
+               LEFT JOIN watch_state w ON w.item_id = ep.id AND w.profile_id = ?
+               WHERE lw.row_rank = 1 AND se.number > 0
+                 AND (se.number > lw.season_number OR (se.number = lw.season_number AND ep.number > lw.episode_number))
+                 AND COALESCE(w.watched, 0) = 0 AND COALESCE(w.position_ms, 0) = 0
+             )
+             SELECT * FROM candidates
+             WHERE row_rank = 1
+             ORDER BY show_last_watched_at DESC
+             LIMIT ?"
+        )
+        .bind(profile_id)
+        .bind(profile_id)
+        .bind(limit)
+        .fetch_all(&self.pool)
+        .await?;
+        
+        Ok(video_items)
+    }
+}
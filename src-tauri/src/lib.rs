@@ -8,9 +8,16 @@ pub mod db;
 pub mod config;
 pub mod scanning;
 pub mod scanning_process;
+pub mod transcoding;
+pub mod metadata;
+pub mod backup;
+pub mod server;
+pub mod admin;
 
 // Re-export commonly used types and functions
-pub use api::folders::{handle_select_folders, select_folders};
+pub use api::folders::handle_select_folders;
+#[cfg(feature = "desktop")]
+pub use api::folders::select_folders;
 pub use api::config::{handle_save_configuration, handle_get_configuration, handle_update_server_password, handle_update_server_name};
 pub use api::handlers::{handle_static_file, handle_ping, handle_connect_code};
 pub use api::http::start_http_server;
@@ -24,6 +31,7 @@ pub use utils::token::generate_secure_token;
 // Re-export error types for custom rejection handling
 pub use api::config::{ConfigNotFoundError, ConfigGetError};
 
+#[cfg(feature = "desktop")]
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
 pub fn run() {
     tauri::Builder::default()
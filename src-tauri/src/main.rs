@@ -1,6 +1,7 @@
 #![cfg_attr(not(debug_assertions), windows_subsystem = "windows")]
 
-use index_media_server_lib::{AppState, DEFAULT_HTTP_PORT, find_available_port, start_http_server, start_https_server, generate_secure_token, config, db, utils, scanning_process};
+use index_media_server_lib::{AppState, DEFAULT_HTTP_PORT, find_available_port, generate_secure_token, server};
+use index_media_server_lib::config::PathProvider;
 
 use tauri::{
   menu::{Menu, MenuItem},
@@ -9,6 +10,7 @@ use tauri::{
 };
 use tauri_plugin_dialog::DialogExt;
 use tauri_plugin_opener::OpenerExt;
+use std::net::Ipv4Addr;
 use std::sync::Arc;
 use tokio::sync::Mutex;
 
@@ -21,35 +23,17 @@ fn main() {
     .plugin(tauri_plugin_dialog::init())
     .invoke_handler(tauri::generate_handler![index_media_server_lib::select_folders])
     .setup(move |app| {
+      let paths: Arc<dyn PathProvider> = Arc::new(app.handle().clone());
+
       // Initialize database and create app state
       let app_state = tauri::async_runtime::block_on(async {
-        let db_path = config::sqlite_path(app.handle())?;
-        let db_pool = db::pool::connect_pool(&db_path).await?;
-        db::pool::init_schema(&db_pool).await?;
-        
-        
-        // Initialize token repository
-        utils::token::init_token_repo(db_pool.clone());
-        
-        // Initialize icon app handle for HTTPS server
-        index_media_server_lib::api::controllers::icon::init_icon_app_handle(app.handle().clone());
-        
-        // Initialize auth app handle for HTTPS server
-        index_media_server_lib::api::controllers::auth::init_auth_app_handle(app.handle().clone());
-        
-        // Initialize auth database pool for HTTPS server
-        index_media_server_lib::api::controllers::auth::init_auth_db_pool(db_pool.clone());
+        let db_pool = server::init_server(paths.clone()).await?;
         
-        let app_handle = Arc::new(Mutex::new(Some(app.handle().clone())));
-        let https_port = Arc::new(Mutex::new(None));
         Ok::<AppState, anyhow::Error>(AppState {
-          app_handle,
-          db_pool,
-          https_port,
+          app_handle: Arc::new(Mutex::new(Some(app.handle().clone()))),
+          ..AppState::new(paths, db_pool)
         })
       })?;
-      
-      let app_state_clone = app_state.clone();
 
       // Find an available HTTP port
       let http_port = match find_available_port(DEFAULT_HTTP_PORT) {
@@ -63,33 +47,11 @@ fn main() {
       // Generate a secure token for local access
       let startup_token = generate_secure_token();
 
-      // Start HTTP server for browser communication and static file serving
-      let app_state_http = app_state_clone.clone();
-      let startup_token_http = startup_token.clone();
-      tauri::async_runtime::spawn(async move {
-        if let Err(e) = start_http_server(http_port, app_state_http, startup_token_http).await {
-          eprintln!("Failed to start HTTP server: {}", e);
-        }
-      });
-
-      // Start HTTPS server for network access
-      let app_state_https = app_state_clone.clone();
-      tauri::async_runtime::spawn(async move {
-        match start_https_server(app_state_https).await {
-          Ok(port) => {
-            println!("✅ HTTPS server started successfully on port {}", port);
-          }
-          Err(e) => {
-            eprintln!("Failed to start HTTPS server: {}", e);
-          }
-        }
-      });
-
-      // Start background scanning process
-      let app_state_scanning = app_state_clone.clone();
-      tauri::async_runtime::spawn(async move {
-        scanning_process::start_scanning_process(app_state_scanning).await;
-      });
+      // Start the HTTP and HTTPS servers and background processes on Tauri's runtime
+      let startup_token_server = startup_token.clone();
+      tauri::async_runtime::block_on(async move {
+        server::start_server_tasks(app_state, Ipv4Addr::LOCALHOST.into(), http_port, startup_token_server)
+      })?;
 
       // Hide Dock icon as we won't have windows
       #[cfg(target_os = "macos")]
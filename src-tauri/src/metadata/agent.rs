@@ -0,0 +1,188 @@
+use async_trait::async_trait;
+use once_cell::sync::Lazy;
+use regex::Regex;
+use serde::Serialize;
+use std::collections::HashMap;
+
+/// Bracketed tags in folder and file names ("[imdbid-tt0133093]", "{tmdb-603}", "[1080p]")
+static BRACKET_TAG: Lazy<Regex> = Lazy::new(|| Regex::new(r"[\[{][^\]}]*[\]}]").unwrap());
+
+/// A release year in parentheses at the end of a title
+static TITLE_YEAR: Lazy<Regex> = Lazy::new(|| Regex::new(r"^(.*?)\s*\((\d{4})\)\s*$").unwrap());
+
+/// Leading articles moved to the end of sort titles
+const SORT_ARTICLES: [&str; 3] = ["The ", "A ", "An "];
+
+/// What is known locally about a movie or show to match it with a provider
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct MatchQuery {
+    pub title: String,
+    pub year: Option<i64>,
+    /// Provider IDs keyed by provider ("imdb", "tmdb", "tvdb")
+    pub external_ids: HashMap<String, String>,
+}
+
+/// A cast member of a movie or show
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct CastMember {
+    pub name: String,
+    pub character: Option<String>,
+    pub profile_url: Option<String>,
+}
+
+/// Details of a matched movie or show
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct MediaMetadata {
+    /// Provider IDs keyed by provider ("imdb", "tmdb", "tvdb")
+    pub external_ids: HashMap<String, String>,
+    pub title: String,
+    pub original_title: Option<String>,
+    pub overview: Option<String>,
+    pub tagline: Option<String>,
+    pub genres: Vec<String>,
+    pub cast: Vec<CastMember>,
+    /// Average rating out of 10
+    pub rating: Option<f64>,
+    pub vote_count: Option<i64>,
+    /// Release date (movies) or first air date (shows), as "YYYY-MM-DD"
+    pub release_date: Option<String>,
+    pub poster_url: Option<String>,
+    pub fanart_url: Option<String>,
+}
+
+impl MediaMetadata {
+    /// Get the year of the release date
+    pub fn year(&self) -> Option<i64> {
+        self.release_date.as_deref()?.get(..4)?.parse::<i64>().ok()
+    }
+}
+
+/// Details of a matched episode
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct EpisodeMetadata {
+    pub number: i64,
+    pub title: Option<String>,
+    pub overview: Option<String>,
+    pub air_date: Option<String>,
+    pub rating: Option<f64>,
+    pub thumb_url: Option<String>,
+}
+
+/// Details of a matched season with its episodes
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct SeasonMetadata {
+    pub number: i64,
+    pub title: Option<String>,
+    pub overview: Option<String>,
+    pub air_date: Option<String>,
+    pub poster_url: Option<String>,
+    pub episodes: Vec<EpisodeMetadata>,
+}
+
+/// Details of a matched show with the requested seasons
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ShowMetadata {
+    pub show: MediaMetadata,
+    pub seasons: Vec<SeasonMetadata>,
+}
+
+/// A metadata provider that movies and shows can be matched with
+#[async_trait]
+pub trait MetadataAgent: Send + Sync {
+    /// Short name stored with matched items ("tmdb")
+    fn name(&self) -> &'static str;
+
+    /// Match a movie, returning None when the provider has no good match
+    async fn match_movie(&self, query: &MatchQuery) -> Result<Option<MediaMetadata>, anyhow::Error>;
+
+    /// Match a show and fetch the given seasons, returning None when the provider has no good match
+    async fn match_show(&self, query: &MatchQuery, seasons: &[i64]) -> Result<Option<ShowMetadata>, anyhow::Error>;
+}
+
+/// Get the agents configured through the environment, in the order they are tried
+pub fn configured_agents() -> Vec<Box<dyn MetadataAgent>> {
+    let mut agents: Vec<Box<dyn MetadataAgent>> = Vec::new();
+    if let Some(tmdb) = crate::metadata::TmdbAgent::from_env() {
+        agents.push(Box::new(tmdb));
+    }
+    agents
+}
+
+/// Build a match query from the names of an item (title, folder name, file name)
+/// External IDs and the year are taken from the first name that has them
+pub fn build_match_query(title: &str, year: Option<i64>, names: &[&str]) -> MatchQuery {
+    let (clean_title, title_year) = clean_title_and_year(title);
+    let mut query = MatchQuery {
+        title: clean_title,
+        year: year.or(title_year),
+        external_ids: HashMap::new(),
+    };
+
+    for name in std::iter::once(title).chain(names.iter().copied()) {
+        for (provider, id) in crate::utils::video_classifier::parse_external_ids(name) {
+            query.external_ids.entry(provider).or_insert_with(|| id.trim().to_string());
+        }
+        if query.year.is_none() {
+            query.year = clean_title_and_year(name).1;
+        }
+    }
+
+    query
+}
+
+/// Remove bracketed tags and the release year from a title, returning the year separately
+/// "The Matrix (1999) {tmdb-603}" becomes ("The Matrix", Some(1999))
+pub fn clean_title_and_year(title: &str) -> (String, Option<i64>) {
+    let without_tags = BRACKET_TAG.replace_all(title, " ");
+    let without_tags = without_tags.trim();
+
+    if let Some(caps) = TITLE_YEAR.captures(without_tags) {
+        let name = caps.get(1).map(|m| m.as_str().trim()).unwrap_or("");
+        let year = caps.get(2).and_then(|m| m.as_str().parse::<i64>().ok());
+        // A title that is only a year in parentheses keeps it as its title
+        if !name.is_empty() {
+            return (name.to_string(), year);
+        }
+    }
+
+    (without_tags.to_string(), None)
+}
+
+/// Build the sort title of a title by moving a leading article to the end ("Matrix, The")
+/// Returns None when the title has no leading article
+pub fn sort_title_for(title: &str) -> Option<String> {
+    SORT_ARTICLES.iter().find_map(|article| {
+        let rest = title.strip_prefix(article)?;
+        (!rest.is_empty()).then(|| format!("{}, {}", rest, article.trim_end()))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_clean_title_and_year() {
+        assert_eq!(clean_title_and_year("The Matrix (1999) {tmdb-603}"), ("The Matrix".to_string(), Some(1999)));
+        assert_eq!(clean_title_and_year("Some Show [tvdbid-12345]"), ("Some Show".to_string(), None));
+        // Only years in parentheses count, titles may end with a number
+        assert_eq!(clean_title_and_year("Blade Runner 2049"), ("Blade Runner 2049".to_string(), None));
+        assert_eq!(clean_title_and_year("(2019)"), ("(2019)".to_string(), None));
+    }
+
+    #[test]
+    fn test_build_match_query() {
+        let query = build_match_query("Heat", None, &["Heat (1995) [imdbid-tt0113277]", "Heat.1995.1080p"]);
+        assert_eq!(query.title, "Heat");
+        assert_eq!(query.year, Some(1995));
+        assert_eq!(query.external_ids.get("imdb").map(String::as_str), Some("tt0113277"));
+    }
+
+    #[test]
+    fn test_sort_title_for() {
+        assert_eq!(sort_title_for("The Matrix"), Some("Matrix, The".to_string()));
+        assert_eq!(sort_title_for("An American Werewolf in London"), Some("American Werewolf in London, An".to_string()));
+        assert_eq!(sort_title_for("Theodore Rex"), None);
+        assert_eq!(sort_title_for("The "), None);
+    }
+}
@@ -0,0 +1,14 @@
+//! Metadata agents
+//!
+//! An agent matches movies and shows against an online provider, by external
+//! ID when the file or folder names carry one (`{tmdb-603}`, `[imdbid-tt0133093]`)
+//! and by title and year otherwise. Matched details are merged into the
+//! `metadata` JSON of video items by `refresh`.
+
+pub mod agent;
+pub mod refresh;
+pub mod tmdb;
+
+pub use agent::*;
+pub use refresh::*;
+pub use tmdb::*;
@@ -0,0 +1,183 @@
+use crate::db::models::VideoItem;
+use crate::db::repos::VideoRepo;
+use crate::metadata::agent::{build_match_query, configured_agents, sort_title_for, EpisodeMetadata, MediaMetadata, MetadataAgent, SeasonMetadata};
+use serde_json::{Map, Value};
+use std::path::Path;
+
+/// Match new movies and shows of an index (and shows with new episodes) with the configured agents
+/// Returns the number of matched items
+pub async fn refresh_index_metadata(video_repo: &VideoRepo, index_id: i64) -> Result<usize, anyhow::Error> {
+    let agents = configured_agents();
+    if agents.is_empty() {
+        return Ok(0);
+    }
+
+    let items = video_repo.get_video_items_needing_metadata(index_id).await?;
+    if items.is_empty() {
+        return Ok(0);
+    }
+
+    println!("🏷️  Matching metadata of {} item(s)...", items.len());
+
+    let mut matched_items = 0;
+    for item in items {
+        match refresh_item_metadata(video_repo, &agents, &item).await {
+            Ok(true) => matched_items += 1,
+            Ok(false) => println!("🤷 No metadata match for '{}'", item.title),
+            Err(e) => {
+                // Not marked as refreshed, so it is retried by the next scan
+                eprintln!("❌ Failed to match metadata of '{}': {}", item.title, e);
+                continue;
+            }
+        }
+        // Items without a match aren't looked up again until they get new content or are refreshed manually
+        video_repo.update_video_item_metadata_refreshed(item.id, Some(chrono::Utc::now().timestamp())).await?;
+    }
+
+    Ok(matched_items)
+}
+
+/// Match a movie or show with the first agent that knows it and store the result
+/// Returns false when no agent has a match (or the item is not a movie or show)
+pub async fn refresh_item_metadata(video_repo: &VideoRepo, agents: &[Box<dyn MetadataAgent>], item: &VideoItem) -> Result<bool, anyhow::Error> {
+    // Folder and file names may carry the external IDs and year the title lacks
+    let mut names: Vec<String> = Vec::new();
+    if let Some(folder_name) = item.source_path.as_deref().and_then(|path| Path::new(path).file_name()) {
+        names.push(folder_name.to_string_lossy().to_string());
+    }
+    if item.r#type == "movie" {
+        if let Some(version) = video_repo.get_video_versions_by_item(item.id).await?.first() {
+            if let Some(part) = video_repo.get_video_parts_by_version(version.id).await?.first() {
+                if let Some(stem) = Path::new(&part.path).file_stem() {
+                    names.push(stem.to_string_lossy().to_string());
+                }
+            }
+        }
+    }
+    let names: Vec<&str> = names.iter().map(String::as_str).collect();
+    let mut query = build_match_query(&item.title, item.year, &names);
+
+    // IDs pinned by an NFO file that only holds a provider URL
+    let metadata = metadata_object(&item.metadata);
+    for provider in ["imdb", "tmdb", "tvdb"] {
+        if let Some(id) = metadata.get(&format!("{}_id", provider)).and_then(Value::as_str) {
+            query.external_ids.insert(provider.to_string(), id.to_string());
+        }
+    }
+
+    for agent in agents {
+        match item.r#type.as_str() {
+            "movie" => {
+                if let Some(movie) = agent.match_movie(&query).await? {
+                    let mut metadata = metadata_object(&item.metadata);
+                    merge_media_metadata(&mut metadata, agent.name(), &movie, "release_date");
+                    let year = movie.year().or(item.year);
+                    video_repo.update_video_item_metadata(item.id, &item.title, sort_title_for(&item.title), year, &Value::Object(metadata)).await?;
+                    return Ok(true);
+                }
+            }
+            "show" => {
+                let seasons = video_repo.get_video_items_by_parent(item.id).await?;
+                let season_numbers: Vec<i64> = seasons.iter().filter(|season| season.r#type == "season").filter_map(|season| season.number).collect();
+
+                if let Some(show) = agent.match_show(&query, &season_numbers).await? {
+                    let mut metadata = metadata_object(&item.metadata);
+                    merge_media_metadata(&mut metadata, agent.name(), &show.show, "first_air_date");
+                    let year = show.show.year().or(item.year);
+                    video_repo.update_video_item_metadata(item.id, &item.title, sort_title_for(&item.title), year, &Value::Object(metadata)).await?;
+
+                    for season in seasons.iter().filter(|season| season.r#type == "season") {
+                        if let Some(season_metadata) = show.seasons.iter().find(|matched| Some(matched.number) == season.number) {
+                            apply_season_metadata(video_repo, season, season_metadata).await?;
+                        }
+                    }
+                    return Ok(true);
+                }
+            }
+            _ => return Ok(false),
+        }
+    }
+
+    Ok(false)
+}
+
+/// Store the details of a season and of its episodes
+async fn apply_season_metadata(video_repo: &VideoRepo, season: &VideoItem, season_metadata: &SeasonMetadata) -> Result<(), anyhow::Error> {
+    let mut metadata = metadata_object(&season.metadata);
+    set_optional(&mut metadata, "title", season_metadata.title.clone());
+    set_optional(&mut metadata, "overview", season_metadata.overview.clone());
+    set_optional(&mut metadata, "air_date", season_metadata.air_date.clone());
+    set_optional(&mut metadata, "poster_url", season_metadata.poster_url.clone());
+    let year = season_metadata.air_date.as_deref().and_then(date_year).or(season.year);
+    video_repo.update_video_item_metadata(season.id, &season.title, season.sort_title.clone(), year, &Value::Object(metadata)).await?;
+
+    for episode in video_repo.get_video_items_by_parent(season.id).await? {
+        if episode.r#type != "episode" {
+            continue;
+        }
+        if let Some(episode_metadata) = season_metadata.episodes.iter().find(|matched| Some(matched.number) == episode.number) {
+            apply_episode_metadata(video_repo, &episode, episode_metadata).await?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Store the details of an episode, replacing its placeholder title ("Episode 3") with the real one
+async fn apply_episode_metadata(video_repo: &VideoRepo, episode: &VideoItem, episode_metadata: &EpisodeMetadata) -> Result<(), anyhow::Error> {
+    let mut metadata = metadata_object(&episode.metadata);
+    set_optional(&mut metadata, "title", episode_metadata.title.clone());
+    set_optional(&mut metadata, "overview", episode_metadata.overview.clone());
+    set_optional(&mut metadata, "air_date", episode_metadata.air_date.clone());
+    set_optional(&mut metadata, "rating", episode_metadata.rating.map(Value::from));
+    set_optional(&mut metadata, "thumb_url", episode_metadata.thumb_url.clone());
+
+    let is_placeholder = episode.number.is_some_and(|number| episode.title == format!("Episode {}", number));
+    let title = match (&episode_metadata.title, is_placeholder) {
+        (Some(title), true) => title.clone(),
+        _ => episode.title.clone(),
+    };
+    let year = episode_metadata.air_date.as_deref().and_then(date_year).or(episode.year);
+    video_repo.update_video_item_metadata(episode.id, &title, episode.sort_title.clone(), year, &Value::Object(metadata)).await?;
+
+    Ok(())
+}
+
+/// Merge the details of a movie or show into item metadata, keeping keys set by the scanner
+fn merge_media_metadata(metadata: &mut Map<String, Value>, agent_name: &str, matched: &MediaMetadata, date_key: &str) {
+    metadata.insert("agent".to_string(), Value::from(agent_name));
+    for (provider, id) in &matched.external_ids {
+        metadata.insert(format!("{}_id", provider), Value::from(id.clone()));
+    }
+    metadata.insert("matched_title".to_string(), Value::from(matched.title.clone()));
+    set_optional(metadata, "original_title", matched.original_title.clone());
+    set_optional(metadata, "overview", matched.overview.clone());
+    set_optional(metadata, "tagline", matched.tagline.clone());
+    metadata.insert("genres".to_string(), Value::from(matched.genres.clone()));
+    metadata.insert("cast".to_string(), serde_json::to_value(&matched.cast).unwrap_or(Value::Array(Vec::new())));
+    set_optional(metadata, "rating", matched.rating.map(Value::from));
+    set_optional(metadata, "vote_count", matched.vote_count.map(Value::from));
+    set_optional(metadata, date_key, matched.release_date.clone());
+    set_optional(metadata, "poster_url", matched.poster_url.clone());
+    set_optional(metadata, "fanart_url", matched.fanart_url.clone());
+}
+
+/// Set a metadata key when the agent has a value for it (keys set by the scanner, like `air_date`, stay otherwise)
+pub(crate) fn set_optional<T: Into<Value>>(metadata: &mut Map<String, Value>, key: &str, value: Option<T>) {
+    if let Some(value) = value {
+        metadata.insert(key.to_string(), value.into());
+    }
+}
+
+/// Parse the metadata JSON of an item into an object (invalid or non-object metadata starts empty)
+pub(crate) fn metadata_object(metadata: &str) -> Map<String, Value> {
+    match serde_json::from_str::<Value>(metadata) {
+        Ok(Value::Object(map)) => map,
+        _ => Map::new(),
+    }
+}
+
+/// Get the year of a "YYYY-MM-DD" date
+pub(crate) fn date_year(date: &str) -> Option<i64> {
+    date.get(..4)?.parse::<i64>().ok()
+}
@@ -0,0 +1,453 @@
+use crate::constants::{DEFAULT_TMDB_BASE_URL, DEFAULT_TMDB_IMAGE_BASE_URL, METADATA_REQUEST_TIMEOUT_SECS};
+use crate::metadata::agent::{CastMember, EpisodeMetadata, MatchQuery, MediaMetadata, MetadataAgent, SeasonMetadata, ShowMetadata};
+use async_trait::async_trait;
+use serde::de::DeserializeOwned;
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::time::Duration;
+
+/// Number of cast members kept per movie or show
+const CAST_LIMIT: usize = 20;
+
+/// Image sizes requested from the image server
+const POSTER_SIZE: &str = "w500";
+const FANART_SIZE: &str = "original";
+const PROFILE_SIZE: &str = "w185";
+const STILL_SIZE: &str = "w300";
+
+/// Metadata agent for The Movie Database API (v3) or any server implementing the same endpoints
+pub struct TmdbAgent {
+    client: reqwest::Client,
+    api_key: String,
+    base_url: String,
+    image_base_url: String,
+}
+
+/// Whether a lookup is for a movie or a show
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum MediaKind {
+    Movie,
+    Show,
+}
+
+impl MediaKind {
+    fn path(self) -> &'static str {
+        match self {
+            MediaKind::Movie => "movie",
+            MediaKind::Show => "tv",
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct TmdbSearchResults {
+    #[serde(default)]
+    results: Vec<TmdbSearchResult>,
+}
+
+#[derive(Debug, Deserialize)]
+struct TmdbSearchResult {
+    id: i64,
+    title: Option<String>,
+    name: Option<String>,
+    original_title: Option<String>,
+    original_name: Option<String>,
+    release_date: Option<String>,
+    first_air_date: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct TmdbFindResults {
+    #[serde(default)]
+    movie_results: Vec<TmdbSearchResult>,
+    #[serde(default)]
+    tv_results: Vec<TmdbSearchResult>,
+}
+
+#[derive(Debug, Deserialize)]
+struct TmdbGenre {
+    name: String,
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct TmdbCredits {
+    #[serde(default)]
+    cast: Vec<TmdbCastMember>,
+}
+
+#[derive(Debug, Deserialize)]
+struct TmdbCastMember {
+    name: String,
+    character: Option<String>,
+    profile_path: Option<String>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct TmdbExternalIds {
+    imdb_id: Option<String>,
+    tvdb_id: Option<i64>,
+}
+
+/// Movie or show details (movies use `title`/`release_date`, shows `name`/`first_air_date`)
+#[derive(Debug, Deserialize)]
+struct TmdbDetails {
+    id: i64,
+    imdb_id: Option<String>,
+    title: Option<String>,
+    name: Option<String>,
+    original_title: Option<String>,
+    original_name: Option<String>,
+    overview: Option<String>,
+    tagline: Option<String>,
+    #[serde(default)]
+    genres: Vec<TmdbGenre>,
+    vote_average: Option<f64>,
+    vote_count: Option<i64>,
+    release_date: Option<String>,
+    first_air_date: Option<String>,
+    poster_path: Option<String>,
+    backdrop_path: Option<String>,
+    #[serde(default)]
+    credits: TmdbCredits,
+    #[serde(default)]
+    external_ids: TmdbExternalIds,
+}
+
+#[derive(Debug, Deserialize)]
+struct TmdbSeason {
+    season_number: i64,
+    name: Option<String>,
+    overview: Option<String>,
+    air_date: Option<String>,
+    poster_path: Option<String>,
+    #[serde(default)]
+    episodes: Vec<TmdbEpisode>,
+}
+
+#[derive(Debug, Deserialize)]
+struct TmdbEpisode {
+    episode_number: i64,
+    name: Option<String>,
+    overview: Option<String>,
+    air_date: Option<String>,
+    vote_average: Option<f64>,
+    still_path: Option<String>,
+}
+
+impl TmdbAgent {
+    /// Create an agent for the API at `base_url` (e.g. "https://api.themoviedb.org/3")
+    /// `api_key` may be a v3 API key or a v4 read access token
+    pub fn new(api_key: String, base_url: String, image_base_url: String) -> Result<Self, anyhow::Error> {
+        let client = reqwest::Client::builder()
+            .timeout(Duration::from_secs(METADATA_REQUEST_TIMEOUT_SECS))
+            .build()?;
+
+        Ok(Self {
+            client,
+            api_key,
+            base_url: base_url.trim_end_matches('/').to_string(),
+            image_base_url: image_base_url.trim_end_matches('/').to_string(),
+        })
+    }
+
+    /// Create an agent from `TMDB_API_KEY`, `TMDB_BASE_URL` and `TMDB_IMAGE_BASE_URL`
+    /// Returns None when no API key is configured
+    pub fn from_env() -> Option<Self> {
+        let api_key = std::env::var("TMDB_API_KEY").ok().filter(|key| !key.trim().is_empty())?;
+        let base_url = std::env::var("TMDB_BASE_URL").unwrap_or_else(|_| DEFAULT_TMDB_BASE_URL.to_string());
+        let image_base_url = std::env::var("TMDB_IMAGE_BASE_URL").unwrap_or_else(|_| DEFAULT_TMDB_IMAGE_BASE_URL.to_string());
+
+        match Self::new(api_key.trim().to_string(), base_url, image_base_url) {
+            Ok(agent) => Some(agent),
+            Err(e) => {
+                eprintln!("❌ Failed to create TMDB agent: {}", e);
+                None
+            }
+        }
+    }
+
+    /// GET a JSON resource of the API, returning None when it doesn't exist
+    async fn get_json<T: DeserializeOwned>(&self, path: &str, params: &[(&str, String)]) -> Result<Option<T>, anyhow::Error> {
+        let mut request = self.client.get(format!("{}{}", self.base_url, path)).query(params);
+        // v4 read access tokens are JWTs, v3 API keys are plain hex strings
+        request = if self.api_key.contains('.') {
+            request.bearer_auth(&self.api_key)
+        } else {
+            request.query(&[("api_key", &self.api_key)])
+        };
+
+        let response = request.send().await?;
+        if response.status() == reqwest::StatusCode::NOT_FOUND {
+            return Ok(None);
+        }
+        let response = response.error_for_status()?;
+        Ok(Some(response.json::<T>().await?))
+    }
+
+    /// Build the URL of an image of the given size
+    fn image_url(&self, path: Option<String>, size: &str) -> Option<String> {
+        non_empty(path).map(|path| format!("{}/{}{}", self.image_base_url, size, path))
+    }
+
+    /// Find the TMDB ID of a movie or show, by external ID first and by title and year otherwise
+    async fn find_id(&self, kind: MediaKind, query: &MatchQuery) -> Result<Option<i64>, anyhow::Error> {
+        if let Some(id) = query.external_ids.get("tmdb").and_then(|id| id.parse::<i64>().ok()) {
+            return Ok(Some(id));
+        }
+
+        let lookups = [("imdb", "imdb_id"), ("tvdb", "tvdb_id")];
+        for (provider, external_source) in lookups {
+            let external_id = match query.external_ids.get(provider) {
+                Some(external_id) => external_id,
+                None => continue,
+            };
+            let path = format!("/find/{}", external_id);
+            if let Some(found) = self.get_json::<TmdbFindResults>(&path, &[("external_source", external_source.to_string())]).await? {
+                let results = if kind == MediaKind::Movie { found.movie_results } else { found.tv_results };
+                if let Some(result) = results.first() {
+                    return Ok(Some(result.id));
+                }
+            }
+        }
+
+        let mut params = vec![("query", query.title.clone())];
+        if let Some(year) = query.year {
+            let year_param = if kind == MediaKind::Movie { "year" } else { "first_air_date_year" };
+            params.push((year_param, year.to_string()));
+        }
+        let path = format!("/search/{}", kind.path());
+        let results = self.get_json::<TmdbSearchResults>(&path, &params).await?.map(|found| found.results).unwrap_or_default();
+
+        Ok(pick_search_result(&results, query))
+    }
+
+    /// Fetch the details of a movie or show with its credits and external IDs
+    async fn get_details(&self, kind: MediaKind, id: i64) -> Result<Option<MediaMetadata>, anyhow::Error> {
+        let path = format!("/{}/{}", kind.path(), id);
+        let details = match self.get_json::<TmdbDetails>(&path, &[("append_to_response", "credits,external_ids".to_string())]).await? {
+            Some(details) => details,
+            None => return Ok(None),
+        };
+
+        let mut external_ids = HashMap::new();
+        external_ids.insert("tmdb".to_string(), details.id.to_string());
+        if let Some(imdb_id) = non_empty(details.imdb_id).or(non_empty(details.external_ids.imdb_id)) {
+            external_ids.insert("imdb".to_string(), imdb_id);
+        }
+        if let Some(tvdb_id) = details.external_ids.tvdb_id {
+            external_ids.insert("tvdb".to_string(), tvdb_id.to_string());
+        }
+
+        let cast = details.credits.cast.into_iter()
+            .take(CAST_LIMIT)
+            .map(|member| CastMember {
+                name: member.name,
+                character: non_empty(member.character),
+                profile_url: self.image_url(member.profile_path, PROFILE_SIZE),
+            })
+            .collect();
+
+        Ok(Some(MediaMetadata {
+            external_ids,
+            title: details.title.or(details.name).unwrap_or_default(),
+            original_title: non_empty(details.original_title.or(details.original_name)),
+            overview: non_empty(details.overview),
+            tagline: non_empty(details.tagline),
+            genres: details.genres.into_iter().map(|genre| genre.name).collect(),
+            cast,
+            rating: details.vote_average.filter(|_| details.vote_count.unwrap_or(0) > 0),
+            vote_count: details.vote_count,
+            release_date: non_empty(details.release_date.or(details.first_air_date)),
+            poster_url: self.image_url(details.poster_path, POSTER_SIZE),
+            fanart_url: self.image_url(details.backdrop_path, FANART_SIZE),
+        }))
+    }
+
+    /// Fetch a season of a show with its episodes
+    async fn get_season(&self, show_id: i64, season_number: i64) -> Result<Option<SeasonMetadata>, anyhow::Error> {
+        let path = format!("/tv/{}/season/{}", show_id, season_number);
+        let season = match self.get_json::<TmdbSeason>(&path, &[]).await? {
+            Some(season) => season,
+            None => return Ok(None),
+        };
+
+        let episodes = season.episodes.into_iter()
+            .map(|episode| EpisodeMetadata {
+                number: episode.episode_number,
+                title: non_empty(episode.name),
+                overview: non_empty(episode.overview),
+                air_date: non_empty(episode.air_date),
+                rating: episode.vote_average.filter(|rating| *rating > 0.0),
+                thumb_url: self.image_url(episode.still_path, STILL_SIZE),
+            })
+            .collect();
+
+        Ok(Some(SeasonMetadata {
+            number: season.season_number,
+            title: non_empty(season.name),
+            overview: non_empty(season.overview),
+            air_date: non_empty(season.air_date),
+            poster_url: self.image_url(season.poster_path, POSTER_SIZE),
+            episodes,
+        }))
+    }
+}
+
+#[async_trait]
+impl MetadataAgent for TmdbAgent {
+    fn name(&self) -> &'static str {
+        "tmdb"
+    }
+
+    async fn match_movie(&self, query: &MatchQuery) -> Result<Option<MediaMetadata>, anyhow::Error> {
+        match self.find_id(MediaKind::Movie, query).await? {
+            Some(id) => self.get_details(MediaKind::Movie, id).await,
+            None => Ok(None),
+        }
+    }
+
+    async fn match_show(&self, query: &MatchQuery, seasons: &[i64]) -> Result<Option<ShowMetadata>, anyhow::Error> {
+        let id = match self.find_id(MediaKind::Show, query).await? {
+            Some(id) => id,
+            None => return Ok(None),
+        };
+        let show = match self.get_details(MediaKind::Show, id).await? {
+            Some(show) => show,
+            None => return Ok(None),
+        };
+
+        let mut show_seasons = Vec::new();
+        for season_number in seasons {
+            if let Some(season) = self.get_season(id, *season_number).await? {
+                show_seasons.push(season);
+            }
+        }
+
+        Ok(Some(ShowMetadata { show, seasons: show_seasons }))
+    }
+}
+
+/// Pick the search result that matches the query best
+/// A matching title and year wins, then a matching year (releases can be a year apart across countries), then
+/// a matching title. Without any of these only the top result of a search without year is trusted.
+fn pick_search_result(results: &[TmdbSearchResult], query: &MatchQuery) -> Option<i64> {
+    let wanted_title = normalize_title(&query.title);
+
+    let score = |result: &TmdbSearchResult| {
+        let titles = [&result.title, &result.name, &result.original_title, &result.original_name];
+        let title_matches = titles.iter().any(|title| title.as_deref().map(normalize_title).as_deref() == Some(wanted_title.as_str()));
+        let year = result.release_date.as_deref().or(result.first_air_date.as_deref())
+            .and_then(|date| date.get(..4)?.parse::<i64>().ok());
+        let year_score = match (query.year, year) {
+            (Some(wanted), Some(year)) if wanted == year => 2,
+            (Some(wanted), Some(year)) if (wanted - year).abs() == 1 => 1,
+            _ => 0,
+        };
+        year_score * 2 + if title_matches { 1 } else { 0 }
+    };
+
+    let best = results.iter()
+        .map(|result| (score(result), result.id))
+        .enumerate()
+        .max_by_key(|(position, (score, _))| (*score, std::cmp::Reverse(*position)))
+        .map(|(_, best)| best);
+
+    match best {
+        Some((score, id)) if score > 0 => Some(id),
+        _ if query.year.is_none() => results.first().map(|result| result.id),
+        _ => None,
+    }
+}
+
+/// Lowercase a title and drop punctuation so "Spider-Man: No Way Home" matches "spider man no way home"
+fn normalize_title(title: &str) -> String {
+    title.chars()
+        .map(|c| if c.is_alphanumeric() { c.to_ascii_lowercase() } else { ' ' })
+        .collect::<String>()
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Treat empty strings from the API as missing values
+fn non_empty(value: Option<String>) -> Option<String> {
+    value.filter(|value| !value.trim().is_empty())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use warp::Filter;
+
+    fn result(id: i64, title: &str, release_date: &str) -> TmdbSearchResult {
+        TmdbSearchResult {
+            id,
+            title: Some(title.to_string()),
+            name: None,
+            original_title: None,
+            original_name: None,
+            release_date: Some(release_date.to_string()),
+            first_air_date: None,
+        }
+    }
+
+    #[test]
+    fn test_pick_search_result() {
+        let results = vec![
+            result(1, "Heat", "2013-06-28"),
+            result(2, "Heat", "1995-12-15"),
+            result(3, "Heat Wave", "1995-03-01"),
+        ];
+        let query = |year: Option<i64>| MatchQuery { title: "Heat".to_string(), year, ..Default::default() };
+
+        assert_eq!(pick_search_result(&results, &query(Some(1995))), Some(2));
+        assert_eq!(pick_search_result(&results, &query(Some(1996))), Some(2));
+        assert_eq!(pick_search_result(&results, &query(None)), Some(1));
+        assert_eq!(pick_search_result(&[result(4, "Other", "2001-01-01")], &query(Some(1995))), None);
+    }
+
+    #[tokio::test]
+    async fn test_match_movie_with_mock_server() {
+        let search = warp::path!("3" / "search" / "movie")
+            .and(warp::query::<HashMap<String, String>>())
+            .map(|params: HashMap<String, String>| {
+                assert_eq!(params.get("api_key").map(String::as_str), Some("test-key"));
+                assert_eq!(params.get("query").map(String::as_str), Some("The Matrix"));
+                warp::reply::json(&serde_json::json!({
+                    "results": [
+                        { "id": 603, "title": "The Matrix", "release_date": "1999-03-30" }
+                    ]
+                }))
+            });
+        let details = warp::path!("3" / "movie" / i64).map(|id: i64| {
+            warp::reply::json(&serde_json::json!({
+                "id": id,
+                "imdb_id": "tt0133093",
+                "title": "The Matrix",
+                "overview": "A hacker learns the truth.",
+                "tagline": "",
+                "genres": [{ "id": 28, "name": "Action" }],
+                "vote_average": 8.2,
+                "vote_count": 100,
+                "release_date": "1999-03-30",
+                "poster_path": "/poster.jpg",
+                "backdrop_path": null,
+                "credits": { "cast": [{ "name": "Keanu Reeves", "character": "Neo", "profile_path": null }] }
+            }))
+        });
+        let (addr, server) = warp::serve(search.or(details)).bind_ephemeral(([127, 0, 0, 1], 0));
+        tokio::spawn(server);
+
+        let agent = TmdbAgent::new("test-key".to_string(), format!("http://{}/3", addr), "http://images/t/p".to_string()).unwrap();
+        let query = MatchQuery { title: "The Matrix".to_string(), year: Some(1999), ..Default::default() };
+        let movie = agent.match_movie(&query).await.unwrap().unwrap();
+
+        assert_eq!(movie.external_ids.get("tmdb").map(String::as_str), Some("603"));
+        assert_eq!(movie.external_ids.get("imdb").map(String::as_str), Some("tt0133093"));
+        assert_eq!(movie.genres, vec!["Action".to_string()]);
+        assert_eq!(movie.tagline, None);
+        assert_eq!(movie.year(), Some(1999));
+        assert_eq!(movie.poster_url.as_deref(), Some("http://images/t/p/w500/poster.jpg"));
+        assert_eq!(movie.cast[0].character.as_deref(), Some("Neo"));
+    }
+}
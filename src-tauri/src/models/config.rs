@@ -1,4 +1,5 @@
 use serde::{Deserialize, Serialize};
+use crate::constants::{DEFAULT_BACKUPS_KEPT, DEFAULT_BACKUP_INTERVAL_HOURS};
 
 // Configuration data structures - now only contains server-level settings
 #[derive(Debug, Serialize, Deserialize)]
@@ -6,6 +7,8 @@ pub struct Configuration {
     pub id: String,
     pub name: String,
     pub password: String,
+    #[serde(default)]
+    pub backup: BackupSettings,
 }
 
 // Configuration response structure that excludes the password field
@@ -91,4 +94,43 @@ pub struct IndexUpdateRequest {
     #[serde(rename = "customIconFile")]
     pub custom_icon_file: Option<String>, // Base64 encoded image data
     pub folders: Vec<String>,
+    #[serde(rename = "scanSchedule", default)]
+    pub scan_schedule: Option<ScanSchedule>, // Keeps the current schedule when missing
+}
+
+// Periodic rescan schedule of an index, stored in the index metadata
+// All fields empty (the default) means the index is only scanned on request
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ScanSchedule {
+    #[serde(rename = "intervalHours", default)]
+    pub interval_hours: Option<u32>, // Rescan once the last scan is this old
+    #[serde(rename = "windowStart", default)]
+    pub window_start: Option<String>, // "HH:MM" local time, scans only start inside the window
+    #[serde(rename = "windowEnd", default)]
+    pub window_end: Option<String>, // "HH:MM" local time, may be before windowStart to span midnight
+    #[serde(rename = "onlyWhenIdle", default)]
+    pub only_when_idle: bool, // Only start when nothing has been played for a while
+}
+
+// Automatic backup schedule of the server, stored in config.json
+// A missing intervalHours turns automatic backups off; backups made on request are never pruned
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BackupSettings {
+    #[serde(rename = "intervalHours", default)]
+    pub interval_hours: Option<u32>, // Back up once the last automatic backup is this old
+    #[serde(default = "default_backups_kept")]
+    pub keep: u32, // Number of automatic backups kept, older ones are deleted
+}
+
+fn default_backups_kept() -> u32 {
+    DEFAULT_BACKUPS_KEPT
+}
+
+impl Default for BackupSettings {
+    fn default() -> Self {
+        Self {
+            interval_hours: Some(DEFAULT_BACKUP_INTERVAL_HOURS),
+            keep: DEFAULT_BACKUPS_KEPT,
+        }
+    }
 }
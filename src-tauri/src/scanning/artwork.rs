@@ -0,0 +1,467 @@
+//! Video artwork extraction
+//!
+//! Posters, backdrops and thumbnails of video items come from sidecar images
+//! next to the media first (`poster.jpg`, `folder.jpg`, `fanart.jpg`,
+//! `<name>-thumb.jpg`, ...). Without a sidecar, the embedded cover art of the
+//! first file (MP4 `covr` atom or MKV image attachment) becomes the poster and
+//! a frame grabbed at 10% of the runtime becomes the thumbnail, both with a
+//! local ffmpeg. Images are normalized to JPEG in the app data cache, resized
+//! variants are generated when they are first requested.
+
+use crate::constants::{ARTWORK_FRAME_POSITION, ARTWORK_MAX_SIZE, ARTWORK_WIDTHS};
+use crate::db::models::{VideoArtwork, VideoItem};
+use crate::db::repos::VideoRepo;
+use crate::transcoding::session::ffmpeg_binary;
+use crate::utils::image::{create_thumbnail, image_dimensions, video_artwork_dir, video_artwork_path, video_artwork_root};
+use crate::utils::probe::{ffprobe_version, probe_media_file};
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use tokio::process::Command;
+use tokio::sync::OnceCell;
+
+/// Kinds of artwork stored for video items
+pub const ARTWORK_KINDS: [&str; 3] = ["poster", "fanart", "thumb"];
+
+/// Image extensions of sidecar artwork
+const SIDECAR_EXTENSIONS: [&str; 4] = ["jpg", "jpeg", "png", "webp"];
+
+/// Whether ffmpeg could be run, checked once
+static FFMPEG_AVAILABLE: OnceCell<bool> = OnceCell::const_new();
+
+/// A sidecar image found for an item
+struct SidecarImage {
+    item_id: i64,
+    kind: &'static str,
+    path: PathBuf,
+    mtime: i64,
+}
+
+/// Where a sidecar image may be, as a folder and a lowercase file name (without extension)
+#[derive(Debug, PartialEq)]
+struct SidecarCandidate {
+    kind: &'static str,
+    folder: PathBuf,
+    name: String,
+}
+
+/// Import sidecar artwork and extract embedded artwork for the items of an index
+/// Returns the number of artwork images stored
+pub async fn extract_index_artwork(video_repo: &VideoRepo, index_id: i64) -> Result<usize, anyhow::Error> {
+    if video_artwork_root().is_none() {
+        return Ok(0);
+    }
+
+    let items = video_repo.get_video_items_by_index(index_id).await?;
+    let first_parts: HashMap<i64, (String, Option<i64>)> = video_repo.get_video_item_first_parts(index_id).await?
+        .into_iter()
+        .map(|(item_id, path, duration_ms)| (item_id, (path, duration_ms)))
+        .collect();
+    let stored: HashMap<(i64, String), VideoArtwork> = video_repo.get_video_artwork_by_index(index_id).await?
+        .into_iter()
+        .map(|artwork| ((artwork.item_id, artwork.kind.clone()), artwork))
+        .collect();
+    let mut needing_check: HashSet<i64> = video_repo.get_video_item_ids_needing_artwork(index_id).await?.into_iter().collect();
+
+    // Seasons have no folder of their own, their sidecars are in the show folder
+    let item_paths: HashMap<i64, String> = items.iter()
+        .filter_map(|item| Some((item.id, item.source_path.clone()?)))
+        .collect();
+    let candidates: Vec<(i64, Vec<SidecarCandidate>)> = items.iter()
+        .map(|item| {
+            let show_path = item.parent_id.and_then(|parent_id| item_paths.get(&parent_id)).map(String::as_str);
+            let first_part = first_parts.get(&item.id).map(|(path, _)| path.as_str());
+            (item.id, sidecar_candidates(item, show_path, first_part))
+        })
+        .collect();
+    let sidecars = tokio::task::spawn_blocking(move || find_sidecar_images(candidates)).await?;
+
+    let mut stored_count = 0;
+    let mut sidecar_kinds: HashSet<(i64, &'static str)> = HashSet::new();
+    for sidecar in sidecars {
+        sidecar_kinds.insert((sidecar.item_id, sidecar.kind));
+
+        let unchanged = stored.get(&(sidecar.item_id, sidecar.kind.to_string())).is_some_and(|artwork| {
+            artwork.source == "sidecar"
+                && artwork.source_path.as_deref() == Some(sidecar.path.to_string_lossy().as_ref())
+                && artwork.source_mtime == Some(sidecar.mtime)
+        });
+        if unchanged {
+            continue;
+        }
+
+        let (item_id, kind) = (sidecar.item_id, sidecar.kind);
+        let source = sidecar.path.clone();
+        match tokio::task::spawn_blocking(move || import_artwork_image(&source, item_id, kind)).await? {
+            Ok((width, height)) => {
+                video_repo.save_video_artwork(&new_artwork(item_id, kind, "sidecar", Some(&sidecar.path), Some(sidecar.mtime), width, height)).await?;
+                stored_count += 1;
+            }
+            Err(e) => eprintln!("⚠️  Failed to import artwork {}: {}", sidecar.path.display(), e),
+        }
+    }
+
+    // Sidecars that were removed take their artwork with them, embedded artwork may replace it
+    for artwork in stored.values() {
+        if artwork.source == "sidecar" && !sidecar_kinds.iter().any(|(item_id, kind)| *item_id == artwork.item_id && *kind == artwork.kind) {
+            remove_artwork(video_repo, artwork.item_id, &artwork.kind).await?;
+            needing_check.insert(artwork.item_id);
+        }
+    }
+
+    let needing_check: Vec<i64> = needing_check.into_iter().filter(|item_id| first_parts.contains_key(item_id)).collect();
+    if !needing_check.is_empty() {
+        if ffmpeg_available().await {
+            println!("🖼️  Extracting artwork of {} video item(s)...", needing_check.len());
+            for item_id in needing_check {
+                let (path, duration_ms) = &first_parts[&item_id];
+                let skip: Vec<&str> = ARTWORK_KINDS.into_iter().filter(|kind| sidecar_kinds.contains(&(item_id, *kind))).collect();
+                match extract_media_artwork(video_repo, item_id, Path::new(path), *duration_ms, &skip, &stored).await {
+                    Ok(count) => stored_count += count,
+                    Err(e) => eprintln!("❌ Failed to extract artwork of {}: {}", path, e),
+                }
+                // Failures are not retried until the file changes
+                video_repo.update_video_item_artwork_checked(item_id, Some(chrono::Utc::now().timestamp())).await?;
+            }
+        } else {
+            println!("⚠️  ffmpeg not found, skipping artwork extraction for {} item(s)", needing_check.len());
+        }
+    }
+
+    remove_orphaned_artwork(video_repo).await?;
+
+    Ok(stored_count)
+}
+
+/// Check whether ffmpeg can be run
+pub(crate) async fn ffmpeg_available() -> bool {
+    *FFMPEG_AVAILABLE.get_or_init(|| async {
+        Command::new(ffmpeg_binary())
+            .arg("-version")
+            .output()
+            .await
+            .is_ok_and(|output| output.status.success())
+    }).await
+}
+
+/// List the places sidecar artwork of an item may be, most specific first
+fn sidecar_candidates(item: &VideoItem, show_path: Option<&str>, first_part: Option<&str>) -> Vec<SidecarCandidate> {
+    let mut candidates = Vec::new();
+    let mut add = |kind: &'static str, folder: &Path, name: String| {
+        candidates.push(SidecarCandidate { kind, folder: folder.to_path_buf(), name: name.to_lowercase() });
+    };
+
+    // Images named after the file ("Movie (2010)-poster.jpg", "S01E01-thumb.jpg")
+    if let Some(part_path) = first_part.map(Path::new) {
+        if let (Some(folder), Some(stem)) = (part_path.parent(), part_path.file_stem()) {
+            let stem = stem.to_string_lossy();
+            for kind in ARTWORK_KINDS {
+                add(kind, folder, format!("{}-{}", stem, kind));
+            }
+        }
+    }
+
+    // Images of the movie or show folder
+    if matches!(item.r#type.as_str(), "movie" | "show") {
+        if let Some(folder) = item.source_path.as_deref().map(Path::new) {
+            for name in ["poster", "folder", "cover"] {
+                add("poster", folder, name.to_string());
+            }
+            for name in ["fanart", "backdrop"] {
+                add("fanart", folder, name.to_string());
+            }
+        }
+    }
+
+    // Season images in the show folder ("season01-poster.jpg", "season-specials-poster.jpg")
+    if let (Some(number), Some(folder), "season") = (item.number, show_path.map(Path::new), item.r#type.as_str()) {
+        for kind in ["poster", "fanart"] {
+            add(kind, folder, format!("season{:02}-{}", number, kind));
+            if number == 0 {
+                add(kind, folder, format!("season-specials-{}", kind));
+            }
+        }
+    }
+
+    candidates
+}
+
+/// Find the first existing sidecar image of each kind for each item, listing every folder once
+/// File names are compared case-insensitively
+/// This does blocking file IO, so call it from a blocking thread
+fn find_sidecar_images(candidates: Vec<(i64, Vec<SidecarCandidate>)>) -> Vec<SidecarImage> {
+    let mut folders: HashMap<PathBuf, HashMap<String, PathBuf>> = HashMap::new();
+    let mut found = Vec::new();
+
+    for (item_id, item_candidates) in candidates {
+        let mut found_kinds: HashSet<&str> = HashSet::new();
+        for candidate in item_candidates {
+            if found_kinds.contains(candidate.kind) {
+                continue;
+            }
+
+            let images = folders.entry(candidate.folder.clone()).or_insert_with(|| list_folder_images(&candidate.folder));
+            let image = match images.get(&candidate.name) {
+                Some(image) => image,
+                None => continue,
+            };
+            let mtime = match image.metadata().and_then(|metadata| metadata.modified()) {
+                Ok(modified) => modified.duration_since(std::time::UNIX_EPOCH).map(|d| d.as_secs() as i64).unwrap_or(0),
+                Err(_) => continue,
+            };
+
+            found_kinds.insert(candidate.kind);
+            found.push(SidecarImage { item_id, kind: candidate.kind, path: image.clone(), mtime });
+        }
+    }
+
+    found
+}
+
+/// List the images of a folder keyed by lowercase file name without extension
+fn list_folder_images(folder: &Path) -> HashMap<String, PathBuf> {
+    let mut images = HashMap::new();
+    let entries = match std::fs::read_dir(folder) {
+        Ok(entries) => entries,
+        Err(_) => return images,
+    };
+
+    for path in entries.flatten().map(|entry| entry.path()) {
+        let is_image = path.extension()
+            .and_then(|ext| ext.to_str())
+            .is_some_and(|ext| SIDECAR_EXTENSIONS.contains(&ext.to_lowercase().as_str()));
+        if let (true, Some(stem)) = (is_image, path.file_stem()) {
+            images.entry(stem.to_string_lossy().to_lowercase()).or_insert(path);
+        }
+    }
+
+    images
+}
+
+/// Extract the embedded cover art and a frame of a file, unless sidecar artwork of that kind exists
+/// Returns the number of artwork images stored
+async fn extract_media_artwork(
+    video_repo: &VideoRepo,
+    item_id: i64,
+    path: &Path,
+    duration_ms: Option<i64>,
+    skip: &[&str],
+    stored: &HashMap<(i64, String), VideoArtwork>
+) -> Result<usize, anyhow::Error> {
+    let mut stored_count = 0;
+    let probe = match ffprobe_version().await {
+        Some(_) => Some(probe_media_file(path).await?),
+        None => None,
+    };
+
+    if !skip.contains(&"poster") {
+        let cover_stream_index = probe.as_ref().and_then(|probe| probe.cover_stream_index);
+        let extracted = match cover_stream_index {
+            Some(stream_index) => {
+                let args = vec!["-map".to_string(), format!("0:{}", stream_index)];
+                extract_with_ffmpeg(item_id, "poster", path, None, args).await?
+            }
+            None => None,
+        };
+        match extracted {
+            Some((width, height)) => {
+                video_repo.save_video_artwork(&new_artwork(item_id, "poster", "embedded", None, None, width, height)).await?;
+                stored_count += 1;
+            }
+            // The cover art was removed from the file
+            None if probe.is_some() && stored.get(&(item_id, "poster".to_string())).is_some_and(|artwork| artwork.source == "embedded") => {
+                remove_artwork(video_repo, item_id, "poster").await?;
+            }
+            None => {}
+        }
+    }
+
+    if !skip.contains(&"thumb") {
+        let duration_ms = duration_ms.or_else(|| probe.as_ref().and_then(|probe| probe.duration_ms));
+        let position_secs = duration_ms.map(|ms| ms as f64 / 1000.0 * ARTWORK_FRAME_POSITION).unwrap_or(0.0);
+        if let Some((width, height)) = extract_with_ffmpeg(item_id, "thumb", path, Some(position_secs), Vec::new()).await? {
+            video_repo.save_video_artwork(&new_artwork(item_id, "thumb", "frame", None, None, width, height)).await?;
+            stored_count += 1;
+        }
+    }
+
+    Ok(stored_count)
+}
+
+/// Write a single frame of a file with ffmpeg and import it as artwork
+/// Returns the dimensions of the stored image, or None when ffmpeg produced no image
+async fn extract_with_ffmpeg(
+    item_id: i64,
+    kind: &'static str,
+    input: &Path,
+    position_secs: Option<f64>,
+    extra_args: Vec<String>
+) -> Result<Option<(u32, u32)>, anyhow::Error> {
+    let dir = video_artwork_dir(item_id).ok_or_else(|| anyhow::anyhow!("Artwork directory not initialized"))?;
+    tokio::fs::create_dir_all(&dir).await?;
+    let frame_path = dir.join(format!("{}.extract.jpg", kind));
+
+    let mut args: Vec<String> = vec!["-hide_banner".into(), "-loglevel".into(), "error".into(), "-nostdin".into()];
+    if let Some(position_secs) = position_secs {
+        args.extend(["-ss".into(), format!("{:.3}", position_secs)]);
+    }
+    args.extend(["-i".into(), input.to_string_lossy().to_string()]);
+    args.extend(extra_args);
+    args.extend(["-frames:v".into(), "1".into(), "-q:v".into(), "2".into(), "-y".into()]);
+    args.push(frame_path.to_string_lossy().to_string());
+
+    let output = Command::new(ffmpeg_binary())
+        .args(&args)
+        .kill_on_drop(true)
+        .output()
+        .await?;
+
+    if !output.status.success() || !frame_path.is_file() {
+        let _ = tokio::fs::remove_file(&frame_path).await;
+        return Ok(None);
+    }
+
+    let source = frame_path.clone();
+    let result = tokio::task::spawn_blocking(move || import_artwork_image(&source, item_id, kind)).await?;
+    let _ = tokio::fs::remove_file(&frame_path).await;
+    result.map(Some)
+}
+
+/// Store an image as the artwork of an item, dropping resized variants of the previous one
+/// Returns the dimensions of the stored image
+/// This does blocking file IO and decoding, so call it from a blocking thread
+fn import_artwork_image(source: &Path, item_id: i64, kind: &str) -> Result<(u32, u32), anyhow::Error> {
+    let destination = video_artwork_path(item_id, kind, None).ok_or_else(|| anyhow::anyhow!("Artwork directory not initialized"))?;
+    remove_artwork_variants(item_id, kind);
+    create_thumbnail(source, &destination, ARTWORK_MAX_SIZE, None)?;
+    image_dimensions(&destination)
+}
+
+/// Remove the resized variants of an item's artwork
+fn remove_artwork_variants(item_id: i64, kind: &str) {
+    for width in ARTWORK_WIDTHS {
+        if let Some(path) = video_artwork_path(item_id, kind, Some(width)) {
+            let _ = std::fs::remove_file(path);
+        }
+    }
+}
+
+/// Delete artwork of an item along with its cached images
+async fn remove_artwork(video_repo: &VideoRepo, item_id: i64, kind: &str) -> Result<(), anyhow::Error> {
+    video_repo.delete_video_artwork(item_id, kind).await?;
+
+    let kind_owned = kind.to_string();
+    tokio::task::spawn_blocking(move || {
+        remove_artwork_variants(item_id, &kind_owned);
+        if let Some(path) = video_artwork_path(item_id, &kind_owned, None) {
+            let _ = std::fs::remove_file(path);
+        }
+    }).await?;
+
+    Ok(())
+}
+
+/// Remove the cached artwork of items that no longer exist (their rows are deleted with the item)
+async fn remove_orphaned_artwork(video_repo: &VideoRepo) -> Result<(), anyhow::Error> {
+    let root = match video_artwork_root() {
+        Some(root) => root,
+        None => return Ok(()),
+    };
+    let item_ids: HashSet<i64> = video_repo.get_all_video_item_ids().await?.into_iter().collect();
+
+    tokio::task::spawn_blocking(move || {
+        let entries = match std::fs::read_dir(&root) {
+            Ok(entries) => entries,
+            Err(_) => return,
+        };
+        for entry in entries.flatten() {
+            let orphaned = entry.file_name().to_str()
+                .and_then(|name| name.parse::<i64>().ok())
+                .is_some_and(|item_id| !item_ids.contains(&item_id));
+            if orphaned {
+                let _ = std::fs::remove_dir_all(entry.path());
+            }
+        }
+    }).await?;
+
+    Ok(())
+}
+
+/// Pick the width of the resized variant to serve for a requested width
+/// Returns None when the original image should be served
+pub fn artwork_variant_width(requested: u32, original_width: Option<i64>) -> Option<u32> {
+    let width = ARTWORK_WIDTHS.into_iter().find(|width| *width >= requested)?;
+    // Images are never scaled up
+    match original_width {
+        Some(original_width) if original_width <= width as i64 => None,
+        _ => Some(width),
+    }
+}
+
+/// Build an artwork row for saving
+fn new_artwork(item_id: i64, kind: &str, source: &str, source_path: Option<&Path>, source_mtime: Option<i64>, width: u32, height: u32) -> VideoArtwork {
+    VideoArtwork {
+        id: 0, // Will be set by database
+        item_id,
+        kind: kind.to_string(),
+        source: source.to_string(),
+        source_path: source_path.map(|path| path.to_string_lossy().to_string()),
+        source_mtime,
+        width: Some(width as i64),
+        height: Some(height as i64),
+        created_at: 0, // Will be set by database
+        updated_at: 0, // Will be set by database
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::Value;
+
+    fn item(r#type: &str, source_path: Option<&str>, number: Option<i64>) -> VideoItem {
+        let mut item = VideoItem::new(1, r#type.to_string(), "Title".to_string(), None, source_path.map(String::from), Value::Null);
+        item.number = number;
+        item
+    }
+
+    #[test]
+    fn test_sidecar_candidates_movie() {
+        let movie = item("movie", Some("/movies/Heat (1995)"), None);
+        let candidates = sidecar_candidates(&movie, None, Some("/movies/Heat (1995)/Heat (1995).mkv"));
+        let names: Vec<(&str, &str)> = candidates.iter().map(|c| (c.kind, c.name.as_str())).collect();
+
+        assert_eq!(names, vec![
+            ("poster", "heat (1995)-poster"),
+            ("fanart", "heat (1995)-fanart"),
+            ("thumb", "heat (1995)-thumb"),
+            ("poster", "poster"),
+            ("poster", "folder"),
+            ("poster", "cover"),
+            ("fanart", "fanart"),
+            ("fanart", "backdrop"),
+        ]);
+        assert!(candidates.iter().all(|c| c.folder == Path::new("/movies/Heat (1995)")));
+    }
+
+    #[test]
+    fn test_sidecar_candidates_season() {
+        let specials = item("season", None, Some(0));
+        let names: Vec<String> = sidecar_candidates(&specials, Some("/tv/Show"), None).into_iter().map(|c| c.name).collect();
+        assert_eq!(names, vec!["season00-poster", "season-specials-poster", "season00-fanart", "season-specials-fanart"]);
+
+        // Episodes only have images named after their file
+        let episode = item("episode", None, Some(3));
+        let candidates = sidecar_candidates(&episode, None, Some("/tv/Show/Season 1/S01E03.mkv"));
+        assert_eq!(candidates.len(), 3);
+        assert_eq!(candidates[2], SidecarCandidate { kind: "thumb", folder: PathBuf::from("/tv/Show/Season 1"), name: "s01e03-thumb".to_string() });
+    }
+
+    #[test]
+    fn test_artwork_variant_width() {
+        assert_eq!(artwork_variant_width(300, Some(1000)), Some(320));
+        assert_eq!(artwork_variant_width(320, None), Some(320));
+        assert_eq!(artwork_variant_width(2000, Some(1920)), None);
+        // Never scale up a small original
+        assert_eq!(artwork_variant_width(700, Some(600)), None);
+    }
+}
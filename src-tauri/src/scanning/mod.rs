@@ -1,5 +1,25 @@
 pub mod video_scanning;
 pub mod temp_files;
+pub mod probing;
+pub mod subtitles;
+pub mod nfo;
+pub mod artwork;
+pub mod pipeline;
+pub mod progress;
+pub mod schedule;
+pub mod watcher;
+pub mod photo_scanning;
+pub mod music_scanning;
 
 pub use video_scanning::*;
 pub use temp_files::*;
+pub use probing::*;
+pub use subtitles::*;
+pub use nfo::*;
+pub use artwork::*;
+pub use pipeline::*;
+pub use progress::*;
+pub use schedule::*;
+pub use watcher::*;
+pub use photo_scanning::*;
+pub use music_scanning::*;
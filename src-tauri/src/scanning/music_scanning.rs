@@ -0,0 +1,409 @@
+//! Music index scanning
+//!
+//! Music files are walked like videos. Their tags are read on the blocking pool
+//! and each file becomes a track, grouped under an album and an artist. The
+//! first embedded cover art found for an album becomes the album cover.
+
+use crate::api::state::AppState;
+use crate::constants::{MUSIC_COVER_SIZE, SCAN_DB_BATCH_SIZE};
+use crate::db::models::{Index, MusicFile, MusicItem};
+use crate::db::repos::{IndexesRepo, MusicRepo};
+use crate::scanning::pipeline::{walk_folder, scan_concurrency, WalkEntry};
+use crate::scanning::video_scanning::finish_scan;
+use crate::scanning::{ScanProgress, ScanCancelled};
+use crate::utils::audio_tags::{read_audio_tags, AudioTags};
+use crate::utils::hash::calculate_fast_hash;
+use crate::utils::image::{create_thumbnail_from_bytes, music_cover_path};
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use tokio::sync::mpsc;
+use tokio::task::JoinHandle;
+
+/// Music file extensions to look for
+pub const MUSIC_EXTENSIONS: [&str; 9] = [
+    "mp3", "flac", "ogg", "oga", "opus", "m4a", "m4b", "aac", "wav"
+];
+
+/// Titles used when a file has no artist or album tag
+const UNKNOWN_ARTIST: &str = "Unknown Artist";
+const UNKNOWN_ALBUM: &str = "Unknown Album";
+
+/// Walk entries buffered between the folder walk and processing
+const WALK_CHANNEL_CAPACITY: usize = 1024;
+
+/// Music files already known for an index, keyed by path
+type KnownFiles = HashMap<String, MusicFile>;
+
+/// Result of examining a music file off the async runtime
+enum PreparedTrack {
+    /// Same path, size and mtime as a known file
+    Unchanged { file_id: i64 },
+    /// New or changed file with its tags
+    Changed(Box<ChangedTrack>),
+}
+
+/// A new or changed music file, read but not stored yet
+struct ChangedTrack {
+    path: String,
+    size: i64,
+    mtime: i64,
+    fast_hash: String,
+    tags: AudioTags,
+}
+
+/// Artists and albums looked up or created during a scan
+#[derive(Default)]
+struct MusicItemCache {
+    /// Item IDs keyed by (type, parent ID, lowercase title)
+    ids: HashMap<(String, Option<i64>, String), i64>,
+    /// Albums whose cover was written during this scan
+    covers_written: HashSet<i64>,
+}
+
+/// Check if a path has one of the music file extensions
+pub fn is_music_file(path: &Path) -> bool {
+    path.extension()
+        .and_then(|ext| ext.to_str())
+        .is_some_and(|ext| MUSIC_EXTENSIONS.contains(&ext.to_lowercase().as_str()))
+}
+
+/// Scan a music index
+pub async fn scan_music_index(indexes_repo: &IndexesRepo, index: &Index, app_state: &AppState) -> Result<(), anyhow::Error> {
+    let mut progress = ScanProgress::start(&app_state.db_pool, index.id, "full").await;
+
+    let result = run_music_index_scan(indexes_repo, index, app_state, &mut progress).await;
+    if finish_scan(&mut progress, &result).await {
+        println!("🛑 Cancelled scan for index '{}' (ID: {})", index.name, index.id);
+        indexes_repo.update_scan_status(index.id, "done".to_string()).await?;
+        return Ok(());
+    }
+    result
+}
+
+/// Full scan of all folders of a music index, reporting progress as it goes
+async fn run_music_index_scan(
+    indexes_repo: &IndexesRepo,
+    index: &Index,
+    app_state: &AppState,
+    progress: &mut ScanProgress
+) -> Result<(), anyhow::Error> {
+    println!("🔍 Scanning music index '{}' (ID: {})", index.name, index.id);
+
+    let folders = index.folders();
+    if folders.is_empty() {
+        println!("⚠️  No folders configured for index '{}'", index.name);
+        return Ok(());
+    }
+
+    let pre_scan_timestamp = chrono::Utc::now().timestamp();
+    let music_repo = MusicRepo::new(app_state.db_pool.clone());
+
+    // Files already in the index, so unchanged files skip hashing and tag reading
+    let known_files: Arc<KnownFiles> = Arc::new(music_repo.get_music_files_by_index(index.id).await?
+        .into_iter()
+        .map(|file| (file.path.clone(), file))
+        .collect());
+    let mut cache = MusicItemCache::default();
+    let mut total_tracks = 0;
+
+    // Files below a folder that failed to scan weren't seen, so the scan fails before cleanup removes them
+    let mut folder_error = None;
+
+    for folder_path in folders {
+        progress.checkpoint().await?;
+        println!("📂 Scanning folder: {}", folder_path);
+        progress.set_folder(&folder_path);
+
+        match scan_music_folder(Path::new(&folder_path), &music_repo, index.id, &known_files, &mut cache, progress).await {
+            Ok(track_count) => {
+                println!("✅ Found {} track(s) in folder: {}", track_count, folder_path);
+                total_tracks += track_count;
+            }
+            Err(e) if e.is::<ScanCancelled>() => return Err(e),
+            Err(e) => {
+                eprintln!("❌ Error scanning folder '{}': {}", folder_path, e);
+                progress.error();
+                folder_error.get_or_insert_with(|| format!("Folder '{}' could not be scanned: {}", folder_path, e));
+            }
+        }
+    }
+
+    println!("🎵 Total tracks found: {}", total_tracks);
+
+    // A cancelled scan didn't see every file, so it must stop before cleanup removes unseen tracks
+    progress.checkpoint().await?;
+    if let Some(folder_error) = folder_error {
+        return Err(anyhow::anyhow!(folder_error));
+    }
+
+    match cleanup_deleted_tracks(&music_repo, index.id, pre_scan_timestamp).await {
+        Ok(0) => {}
+        Ok(deleted_tracks) => {
+            println!("🗑️  Cleanup complete: {} tracks deleted", deleted_tracks);
+            progress.items_removed(deleted_tracks as usize);
+        }
+        Err(e) => eprintln!("❌ Error during cleanup: {}", e),
+    }
+
+    let now = chrono::Utc::now().timestamp();
+    indexes_repo.update_scan_status_with_timestamp(index.id, "done".to_string(), Some(now)).await?;
+
+    println!("✅ Completed scan for music index '{}' (ID: {})", index.name, index.id);
+
+    Ok(())
+}
+
+/// Scan a folder for music files, reading up to `scan_concurrency()` files at once
+/// Returns the number of tracks found
+async fn scan_music_folder(
+    folder: &Path,
+    music_repo: &MusicRepo,
+    index_id: i64,
+    known_files: &Arc<KnownFiles>,
+    cache: &mut MusicItemCache,
+    progress: &mut ScanProgress
+) -> Result<usize, anyhow::Error> {
+    if !folder.is_dir() {
+        return Err(anyhow::anyhow!("Folder does not exist or is not a directory: {}", folder.display()));
+    }
+
+    let (entry_sender, mut entry_receiver) = mpsc::channel::<WalkEntry>(WALK_CHANNEL_CAPACITY);
+    let root = folder.to_path_buf();
+    let walker = tokio::task::spawn_blocking(move || {
+        walk_folder(&root, is_music_file, |entry| entry_sender.blocking_send(entry).is_ok())
+    });
+
+    let concurrency = scan_concurrency();
+    let mut pending: VecDeque<(PathBuf, JoinHandle<Result<PreparedTrack, anyhow::Error>>)> = VecDeque::new();
+    let mut unchanged_ids: Vec<i64> = Vec::new();
+    let mut track_count = 0;
+    let mut walking = true;
+
+    let result: Result<(), anyhow::Error> = async {
+        loop {
+            progress.checkpoint().await?;
+
+            while walking && pending.len() < concurrency {
+                match entry_receiver.recv().await {
+                    Some(WalkEntry::File(path)) => {
+                        progress.file_discovered();
+                        let known_files = Arc::clone(known_files);
+                        let prepare_path = path.clone();
+                        let handle = tokio::task::spawn_blocking(move || prepare_music_file(prepare_path, &known_files));
+                        pending.push_back((path, handle));
+                    }
+                    // Tracks are grouped by their tags, not by folder
+                    Some(WalkEntry::FolderDone(_)) => {}
+                    None => walking = false,
+                }
+            }
+
+            let (path, handle) = match pending.pop_front() {
+                Some(entry) => entry,
+                None => return Ok(()),
+            };
+
+            progress.file_processed();
+            if let Some(parent) = path.parent() {
+                progress.set_folder(&parent.to_string_lossy());
+            }
+
+            match handle.await? {
+                Ok(PreparedTrack::Unchanged { file_id }) => {
+                    unchanged_ids.push(file_id);
+                    if unchanged_ids.len() >= SCAN_DB_BATCH_SIZE {
+                        music_repo.touch_music_files(&unchanged_ids).await?;
+                        unchanged_ids.clear();
+                    }
+                    track_count += 1;
+                }
+                Ok(PreparedTrack::Changed(track)) => {
+                    let known_file = known_files.get(&track.path);
+                    match save_track(music_repo, index_id, cache, known_file, *track).await {
+                        Ok(()) => {
+                            if known_file.is_some() {
+                                progress.item_updated();
+                            } else {
+                                println!("🎵 {}", path.display());
+                                progress.item_added();
+                            }
+                            track_count += 1;
+                        }
+                        Err(e) => {
+                            eprintln!("❌ Failed to save track {}: {}", path.display(), e);
+                            progress.error();
+                        }
+                    }
+                }
+                Err(e) => {
+                    eprintln!("❌ Failed to process music file {}: {}", path.display(), e);
+                    progress.error();
+                }
+            }
+            progress.report().await;
+        }
+    }.await;
+
+    // Always write pending touches, otherwise cleanup would treat those files as deleted
+    if !unchanged_ids.is_empty() {
+        music_repo.touch_music_files(&unchanged_ids).await?;
+    }
+    result?;
+    walker.await??;
+
+    Ok(track_count)
+}
+
+/// Read the file information and tags of a music file, unless it matches a known file
+/// This does blocking file IO, so call it from a blocking thread
+fn prepare_music_file(path: PathBuf, known_files: &KnownFiles) -> Result<PreparedTrack, anyhow::Error> {
+    let metadata = path.metadata()?;
+    let size = metadata.len() as i64;
+    let mtime = metadata.modified()?.duration_since(std::time::UNIX_EPOCH)?.as_secs() as i64;
+    let path_str = path.to_string_lossy().to_string();
+
+    if let Some(file) = known_files.get(&path_str) {
+        if file.size == Some(size) && file.mtime == Some(mtime) {
+            return Ok(PreparedTrack::Unchanged { file_id: file.id });
+        }
+    }
+
+    let fast_hash = calculate_fast_hash(&path)?;
+    // Files symphonia can't read are still indexed, titled after the file name
+    let tags = read_audio_tags(&path).unwrap_or_else(|e| {
+        eprintln!("⚠️  Failed to read tags of {}: {}", path.display(), e);
+        AudioTags::default()
+    });
+
+    Ok(PreparedTrack::Changed(Box::new(ChangedTrack { path: path_str, size, mtime, fast_hash, tags })))
+}
+
+/// Store a new or changed track under its artist and album, with its file and the album cover
+async fn save_track(
+    music_repo: &MusicRepo,
+    index_id: i64,
+    cache: &mut MusicItemCache,
+    known_file: Option<&MusicFile>,
+    track: ChangedTrack
+) -> Result<(), anyhow::Error> {
+    let ChangedTrack { path, size, mtime, fast_hash, mut tags } = track;
+
+    // Albums are grouped under the album artist, so compilations aren't split across track artists
+    let artist_title = tags.album_artist.clone()
+        .or_else(|| tags.artist.clone())
+        .unwrap_or_else(|| UNKNOWN_ARTIST.to_string());
+    let artist = MusicItem::new(index_id, "artist".to_string(), artist_title, None, tags.sort_artist.clone());
+    let artist_id = get_or_add_music_item(music_repo, cache, artist).await?;
+
+    let album_title = tags.album.clone().unwrap_or_else(|| UNKNOWN_ALBUM.to_string());
+    let mut album = MusicItem::new(index_id, "album".to_string(), album_title, Some(artist_id), tags.sort_album.clone());
+    album.year = tags.year;
+    album.genre = tags.genre.clone();
+    let album_id = get_or_add_music_item(music_repo, cache, album).await?;
+
+    let track_title = tags.title.clone().unwrap_or_else(|| {
+        Path::new(&path).file_stem().map(|stem| stem.to_string_lossy().to_string()).unwrap_or_default()
+    });
+    let mut track_item = MusicItem::new(index_id, "track".to_string(), track_title, Some(album_id), tags.sort_title.clone());
+    track_item.year = tags.year;
+    track_item.number = tags.track_number;
+    track_item.disc_number = tags.disc_number;
+    track_item.genre = tags.genre.clone();
+
+    // A changed file keeps its track, so its ID stays stable for clients
+    let item_id = match known_file {
+        Some(file) => {
+            track_item.id = file.item_id;
+            music_repo.update_music_item(&track_item).await?;
+            file.item_id
+        }
+        None => music_repo.add_music_item(&track_item).await?,
+    };
+
+    music_repo.save_music_file(&MusicFile {
+        id: 0, // Will be set by database
+        item_id,
+        index_id,
+        path,
+        size: Some(size),
+        mtime: Some(mtime),
+        fast_hash: Some(fast_hash),
+        duration_ms: tags.duration_ms,
+        codec: tags.codec.take(),
+        sample_rate: tags.sample_rate,
+        channels: tags.channels,
+        created_at: 0, // Will be set by database
+        updated_at: 0, // Will be set by database
+    }).await?;
+
+    if let Some(cover) = tags.cover.take() {
+        if cache.covers_written.insert(album_id) {
+            save_album_cover(music_repo, album_id, cover).await;
+        }
+    }
+
+    Ok(())
+}
+
+/// Get the ID of an artist or album, adding it when it doesn't exist yet
+async fn get_or_add_music_item(music_repo: &MusicRepo, cache: &mut MusicItemCache, music_item: MusicItem) -> Result<i64, anyhow::Error> {
+    let key = (music_item.r#type.clone(), music_item.parent_id, music_item.title.to_lowercase());
+    if let Some(id) = cache.ids.get(&key) {
+        return Ok(*id);
+    }
+
+    let id = match music_repo.find_music_item(music_item.index_id, &music_item.r#type, music_item.parent_id, &music_item.title).await? {
+        Some(existing) => {
+            // Fill in information that earlier tracks didn't have
+            if (existing.year.is_none() && music_item.year.is_some())
+                || (existing.genre.is_none() && music_item.genre.is_some())
+                || (existing.sort_title.is_none() && music_item.sort_title.is_some()) {
+                music_repo.update_music_item(&MusicItem {
+                    sort_title: existing.sort_title.clone().or(music_item.sort_title),
+                    year: existing.year.or(music_item.year),
+                    genre: existing.genre.clone().or(music_item.genre),
+                    ..existing.clone()
+                }).await?;
+            }
+            existing.id
+        }
+        None => music_repo.add_music_item(&music_item).await?,
+    };
+
+    cache.ids.insert(key, id);
+    Ok(id)
+}
+
+/// Write the cover art of an album, logging failures (a missing cover doesn't fail the track)
+async fn save_album_cover(music_repo: &MusicRepo, album_id: i64, cover: Vec<u8>) {
+    let cover_path = match music_cover_path(album_id) {
+        Some(cover_path) => cover_path,
+        None => return,
+    };
+
+    match tokio::task::spawn_blocking(move || create_thumbnail_from_bytes(&cover, &cover_path, MUSIC_COVER_SIZE)).await {
+        Ok(Ok(())) => {
+            if let Err(e) = music_repo.update_music_item_cover(album_id, true).await {
+                eprintln!("❌ Failed to mark cover of album {}: {}", album_id, e);
+            }
+        }
+        Ok(Err(e)) => eprintln!("⚠️  Failed to save cover of album {}: {}", album_id, e),
+        Err(e) => eprintln!("⚠️  Failed to save cover of album {}: {}", album_id, e),
+    }
+}
+
+/// Remove music files that weren't seen during the scan, then tracks, albums and artists left empty
+/// Returns the number of deleted tracks
+async fn cleanup_deleted_tracks(music_repo: &MusicRepo, index_id: i64, pre_scan_timestamp: i64) -> Result<u64, anyhow::Error> {
+    let deleted_files = music_repo.delete_unseen_music_files(index_id, pre_scan_timestamp).await?;
+
+    // Covers are stored by album ID, so remove the ones of deleted albums (other IDs have no cover file)
+    for item_id in music_repo.delete_empty_music_items(index_id).await? {
+        if let Some(cover_path) = music_cover_path(item_id) {
+            let _ = tokio::fs::remove_file(cover_path).await;
+        }
+    }
+
+    Ok(deleted_files)
+}
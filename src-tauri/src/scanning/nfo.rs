@@ -0,0 +1,214 @@
+//! Local NFO import
+//!
+//! Kodi-style NFO files next to the media describe movies (`movie.nfo` in the
+//! movie folder or `<file name>.nfo`), shows (`tvshow.nfo`) and episodes
+//! (`<file name>.nfo`). They are the authoritative offline metadata source:
+//! their titles replace the ones derived from folder and file names, and items
+//! described by one are left alone by metadata agents. NFO files that only
+//! hold a provider URL pin the match of the agents instead. Files are read
+//! again when their modification time changes.
+
+use crate::db::models::VideoItem;
+use crate::db::repos::VideoRepo;
+use crate::metadata::agent::sort_title_for;
+use crate::metadata::refresh::{metadata_object, set_optional};
+use crate::utils::nfo::{parse_nfo, NfoMetadata};
+use serde_json::Value;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+/// Value of the `agent` metadata key of items described by an NFO file
+const NFO_AGENT: &str = "nfo";
+
+/// Import new or changed NFO files of the movies, shows and episodes of an index
+/// Returns the number of items updated from an NFO file
+pub async fn import_index_nfo(video_repo: &VideoRepo, index_id: i64) -> Result<usize, anyhow::Error> {
+    let items: Vec<VideoItem> = video_repo.get_video_items_by_index(index_id).await?
+        .into_iter()
+        .filter(|item| matches!(item.r#type.as_str(), "movie" | "show" | "episode"))
+        .collect();
+    let first_parts: HashMap<i64, String> = video_repo.get_video_item_first_parts(index_id).await?
+        .into_iter()
+        .map(|(item_id, path, _)| (item_id, path))
+        .collect();
+    let imported: HashMap<i64, i64> = video_repo.get_video_item_nfo_mtimes(index_id).await?.into_iter().collect();
+
+    let candidates: Vec<(i64, Vec<PathBuf>)> = items.iter()
+        .map(|item| (item.id, nfo_candidates(item, first_parts.get(&item.id).map(String::as_str))))
+        .collect();
+    let found = tokio::task::spawn_blocking(move || find_nfo_files(candidates)).await?;
+
+    let mut imported_items = 0;
+    for item in &items {
+        match (found.get(&item.id), imported.get(&item.id)) {
+            (Some((_, mtime)), Some(imported_mtime)) if mtime == imported_mtime => {}
+            (Some((path, mtime)), _) => {
+                match import_item_nfo(video_repo, item, path).await {
+                    Ok(true) => imported_items += 1,
+                    Ok(false) => println!("🤷 No {} found in NFO file {}", item.r#type, path.display()),
+                    Err(e) => {
+                        // Not recorded, so it is read again by the next scan
+                        eprintln!("❌ Failed to import NFO file {}: {}", path.display(), e);
+                        continue;
+                    }
+                }
+                video_repo.update_video_item_nfo_mtime(item.id, Some(*mtime)).await?;
+            }
+            (None, Some(_)) => forget_item_nfo(video_repo, item).await?,
+            (None, None) => {}
+        }
+    }
+
+    Ok(imported_items)
+}
+
+/// List the places the NFO file of an item may be, most specific first
+fn nfo_candidates(item: &VideoItem, first_part: Option<&str>) -> Vec<PathBuf> {
+    let mut candidates = Vec::new();
+
+    // NFO named after the file ("Heat (1995).nfo", "S01E03.nfo")
+    if matches!(item.r#type.as_str(), "movie" | "episode") {
+        if let Some(part_path) = first_part.map(Path::new) {
+            candidates.push(part_path.with_extension("nfo"));
+        }
+    }
+
+    match (item.r#type.as_str(), item.source_path.as_deref().map(Path::new)) {
+        ("movie", Some(folder)) => candidates.push(folder.join("movie.nfo")),
+        ("show", Some(folder)) => candidates.push(folder.join("tvshow.nfo")),
+        _ => {}
+    }
+
+    candidates
+}
+
+/// Find the first existing NFO file of each item with its modification time
+/// This does blocking file IO, so call it from a blocking thread
+fn find_nfo_files(candidates: Vec<(i64, Vec<PathBuf>)>) -> HashMap<i64, (PathBuf, i64)> {
+    let mut found = HashMap::new();
+
+    for (item_id, item_candidates) in candidates {
+        for candidate in item_candidates {
+            let mtime = match candidate.metadata().and_then(|metadata| metadata.modified()) {
+                Ok(modified) => modified.duration_since(std::time::UNIX_EPOCH).map(|d| d.as_secs() as i64).unwrap_or(0),
+                Err(_) => continue,
+            };
+            found.insert(item_id, (candidate, mtime));
+            break;
+        }
+    }
+
+    found
+}
+
+/// Read the NFO file of an item and store its details
+/// Returns false when the file describes nothing of the item's kind
+async fn import_item_nfo(video_repo: &VideoRepo, item: &VideoItem, path: &Path) -> Result<bool, anyhow::Error> {
+    // Older scrapers wrote Latin-1 files, which are read lossily
+    let bytes = tokio::fs::read(path).await?;
+    let entries = parse_nfo(&String::from_utf8_lossy(&bytes))?;
+    let nfo = match pick_entry(item, &entries) {
+        Some(nfo) => nfo,
+        None => return Ok(false),
+    };
+
+    let mut metadata = metadata_object(&item.metadata);
+    for (provider, id) in &nfo.external_ids {
+        metadata.insert(format!("{}_id", provider), Value::from(id.clone()));
+    }
+    set_optional(&mut metadata, "original_title", nfo.original_title.clone());
+    set_optional(&mut metadata, "overview", nfo.plot.clone());
+    set_optional(&mut metadata, "tagline", nfo.tagline.clone());
+    if !nfo.genres.is_empty() {
+        metadata.insert("genres".to_string(), Value::from(nfo.genres.clone()));
+    }
+    set_optional(&mut metadata, "rating", nfo.rating.map(Value::from));
+    set_optional(&mut metadata, "vote_count", nfo.votes.map(Value::from));
+    let date_key = match item.r#type.as_str() {
+        "movie" => "release_date",
+        "show" => "first_air_date",
+        _ => "air_date",
+    };
+    set_optional(&mut metadata, date_key, nfo.premiered.clone());
+
+    if nfo.kind.is_empty() {
+        // Only a provider URL: let the agents match again with the pinned IDs
+        video_repo.update_video_item_metadata_refreshed(item.id, None).await?;
+    } else {
+        metadata.insert("agent".to_string(), Value::from(NFO_AGENT));
+    }
+
+    // Loose movies are found again by their title on rescans, so they keep the one from the file name
+    let keeps_title = item.r#type == "movie" && item.source_path.is_none();
+    let (title, sort_title) = match (&nfo.title, keeps_title) {
+        (Some(title), false) => (title.clone(), nfo.sort_title.clone().or_else(|| sort_title_for(title))),
+        _ => (item.title.clone(), nfo.sort_title.clone().or_else(|| item.sort_title.clone())),
+    };
+    let year = nfo.year.or(item.year);
+    video_repo.update_video_item_metadata(item.id, &title, sort_title, year, &Value::Object(metadata)).await?;
+
+    Ok(true)
+}
+
+/// Pick the NFO entry describing an item
+/// Multi-episode files are matched by episode number, URL-only files match any item
+fn pick_entry<'a>(item: &VideoItem, entries: &'a [NfoMetadata]) -> Option<&'a NfoMetadata> {
+    let kind = match item.r#type.as_str() {
+        "movie" => "movie",
+        "show" => "tvshow",
+        _ => "episodedetails",
+    };
+    let matching: Vec<&NfoMetadata> = entries.iter().filter(|entry| entry.kind == kind || entry.kind.is_empty()).collect();
+
+    matching.iter()
+        .find(|entry| item.r#type == "episode" && entry.episode.is_some() && entry.episode == item.number)
+        .or(matching.first())
+        .copied()
+}
+
+/// Hand an item whose NFO file was removed back to the metadata agents
+async fn forget_item_nfo(video_repo: &VideoRepo, item: &VideoItem) -> Result<(), anyhow::Error> {
+    let mut metadata = metadata_object(&item.metadata);
+    if metadata.get("agent").and_then(Value::as_str) == Some(NFO_AGENT) {
+        metadata.remove("agent");
+        video_repo.update_video_item_metadata(item.id, &item.title, item.sort_title.clone(), item.year, &Value::Object(metadata)).await?;
+    }
+    video_repo.update_video_item_metadata_refreshed(item.id, None).await?;
+    video_repo.update_video_item_nfo_mtime(item.id, None).await?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn item(r#type: &str, source_path: Option<&str>, number: Option<i64>) -> VideoItem {
+        let mut item = VideoItem::new(1, r#type.to_string(), "Title".to_string(), None, source_path.map(String::from), Value::Null);
+        item.number = number;
+        item
+    }
+
+    #[test]
+    fn test_nfo_candidates() {
+        let movie = item("movie", Some("/movies/Heat (1995)"), None);
+        assert_eq!(nfo_candidates(&movie, Some("/movies/Heat (1995)/Heat (1995).mkv")), vec![
+            PathBuf::from("/movies/Heat (1995)/Heat (1995).nfo"),
+            PathBuf::from("/movies/Heat (1995)/movie.nfo"),
+        ]);
+
+        let show = item("show", Some("/tv/Show"), None);
+        assert_eq!(nfo_candidates(&show, None), vec![PathBuf::from("/tv/Show/tvshow.nfo")]);
+    }
+
+    #[test]
+    fn test_pick_entry() {
+        let entries = parse_nfo("<episodedetails><title>Part One</title><episode>1</episode></episodedetails>\
+                                 <episodedetails><title>Part Two</title><episode>2</episode></episodedetails>").unwrap();
+
+        let second = item("episode", None, Some(2));
+        assert_eq!(pick_entry(&second, &entries).and_then(|nfo| nfo.title.as_deref()), Some("Part Two"));
+        // Episode entries don't describe a show
+        assert_eq!(pick_entry(&item("show", Some("/tv/Show"), None), &entries), None);
+    }
+}
@@ -0,0 +1,356 @@
+//! Photo index scanning
+//!
+//! Photos are walked like videos. Their EXIF data is read and a thumbnail is
+//! generated on the blocking pool, and every folder of the index becomes an
+//! album (nested like the folders).
+
+use crate::api::state::AppState;
+use crate::constants::{PHOTO_THUMBNAIL_SIZE, SCAN_DB_BATCH_SIZE};
+use crate::db::models::{Index, PhotoItem};
+use crate::db::repos::{IndexesRepo, PhotoRepo};
+use crate::scanning::pipeline::{walk_folder, scan_concurrency, WalkEntry};
+use crate::scanning::video_scanning::finish_scan;
+use crate::scanning::{ScanProgress, ScanCancelled};
+use crate::utils::exif::read_photo_metadata;
+use crate::utils::hash::calculate_fast_hash;
+use crate::utils::image::{create_thumbnail, photo_thumbnail_path};
+use std::collections::{HashMap, VecDeque};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use tokio::sync::mpsc;
+use tokio::task::JoinHandle;
+
+/// Photo file extensions to look for
+pub const PHOTO_EXTENSIONS: [&str; 10] = [
+    "jpg", "jpeg", "png", "gif", "webp", "tif", "tiff", "bmp", "heic", "heif"
+];
+
+/// Walk entries buffered between the folder walk and processing
+const WALK_CHANNEL_CAPACITY: usize = 1024;
+
+/// Photos already known for an index, keyed by path
+type KnownPhotos = HashMap<String, PhotoItem>;
+
+/// Album IDs of the folders seen during a scan
+type AlbumIds = HashMap<PathBuf, i64>;
+
+/// Result of examining a photo off the async runtime
+enum PreparedPhoto {
+    /// Same path, size and mtime as a known photo
+    Unchanged { photo_id: i64 },
+    /// New or changed photo, without its album yet
+    Changed(Box<PhotoItem>),
+}
+
+/// Check if a path has one of the photo file extensions
+pub fn is_photo_file(path: &Path) -> bool {
+    path.extension()
+        .and_then(|ext| ext.to_str())
+        .is_some_and(|ext| PHOTO_EXTENSIONS.contains(&ext.to_lowercase().as_str()))
+}
+
+/// Scan a photo index
+pub async fn scan_photo_index(indexes_repo: &IndexesRepo, index: &Index, app_state: &AppState) -> Result<(), anyhow::Error> {
+    let mut progress = ScanProgress::start(&app_state.db_pool, index.id, "full").await;
+
+    let result = run_photo_index_scan(indexes_repo, index, app_state, &mut progress).await;
+    if finish_scan(&mut progress, &result).await {
+        println!("🛑 Cancelled scan for index '{}' (ID: {})", index.name, index.id);
+        indexes_repo.update_scan_status(index.id, "done".to_string()).await?;
+        return Ok(());
+    }
+    result
+}
+
+/// Full scan of all folders of a photo index, reporting progress as it goes
+async fn run_photo_index_scan(
+    indexes_repo: &IndexesRepo,
+    index: &Index,
+    app_state: &AppState,
+    progress: &mut ScanProgress
+) -> Result<(), anyhow::Error> {
+    println!("🔍 Scanning photo index '{}' (ID: {})", index.name, index.id);
+
+    let folders = index.folders();
+    if folders.is_empty() {
+        println!("⚠️  No folders configured for index '{}'", index.name);
+        return Ok(());
+    }
+
+    let pre_scan_timestamp = chrono::Utc::now().timestamp();
+    let photo_repo = PhotoRepo::new(app_state.db_pool.clone());
+
+    // Photos already in the index, so unchanged files skip hashing and decoding
+    let known_photos: Arc<KnownPhotos> = Arc::new(photo_repo.get_photo_items_by_index(index.id).await?
+        .into_iter()
+        .map(|photo| (photo.path.clone(), photo))
+        .collect());
+    let mut album_ids = AlbumIds::new();
+    let mut total_photos = 0;
+
+    // Files below a folder that failed to scan weren't seen, so the scan fails before cleanup removes them
+    let mut folder_error = None;
+
+    for folder_path in folders {
+        progress.checkpoint().await?;
+        println!("📂 Scanning folder: {}", folder_path);
+        progress.set_folder(&folder_path);
+
+        match scan_photo_folder(Path::new(&folder_path), &photo_repo, index.id, &known_photos, &mut album_ids, progress).await {
+            Ok(photo_count) => {
+                println!("✅ Found {} photo(s) in folder: {}", photo_count, folder_path);
+                total_photos += photo_count;
+            }
+            Err(e) if e.is::<ScanCancelled>() => return Err(e),
+            Err(e) => {
+                eprintln!("❌ Error scanning folder '{}': {}", folder_path, e);
+                progress.error();
+                folder_error.get_or_insert_with(|| format!("Folder '{}' could not be scanned: {}", folder_path, e));
+            }
+        }
+    }
+
+    println!("📷 Total photos found: {}", total_photos);
+
+    // A cancelled scan didn't see every file, so it must stop before cleanup removes unseen photos
+    progress.checkpoint().await?;
+    if let Some(folder_error) = folder_error {
+        return Err(anyhow::anyhow!(folder_error));
+    }
+
+    match cleanup_deleted_photos(&photo_repo, index.id, pre_scan_timestamp).await {
+        Ok((0, _)) => {}
+        Ok((deleted_photos, deleted_albums)) => {
+            println!("🗑️  Cleanup complete: {} photos, {} albums deleted", deleted_photos, deleted_albums);
+            progress.items_removed(deleted_photos);
+        }
+        Err(e) => eprintln!("❌ Error during cleanup: {}", e),
+    }
+
+    let now = chrono::Utc::now().timestamp();
+    indexes_repo.update_scan_status_with_timestamp(index.id, "done".to_string(), Some(now)).await?;
+
+    println!("✅ Completed scan for photo index '{}' (ID: {})", index.name, index.id);
+
+    Ok(())
+}
+
+/// Scan a folder for photos, preparing up to `scan_concurrency()` photos at once
+/// Returns the number of photos found
+async fn scan_photo_folder(
+    folder: &Path,
+    photo_repo: &PhotoRepo,
+    index_id: i64,
+    known_photos: &Arc<KnownPhotos>,
+    album_ids: &mut AlbumIds,
+    progress: &mut ScanProgress
+) -> Result<usize, anyhow::Error> {
+    if !folder.is_dir() {
+        return Err(anyhow::anyhow!("Folder does not exist or is not a directory: {}", folder.display()));
+    }
+
+    let (entry_sender, mut entry_receiver) = mpsc::channel::<WalkEntry>(WALK_CHANNEL_CAPACITY);
+    let root = folder.to_path_buf();
+    let walker = tokio::task::spawn_blocking(move || {
+        walk_folder(&root, is_photo_file, |entry| entry_sender.blocking_send(entry).is_ok())
+    });
+
+    let concurrency = scan_concurrency();
+    let mut pending: VecDeque<(PathBuf, JoinHandle<Result<PreparedPhoto, anyhow::Error>>)> = VecDeque::new();
+    let mut unchanged_ids: Vec<i64> = Vec::new();
+    let mut photo_count = 0;
+    let mut walking = true;
+
+    let result: Result<(), anyhow::Error> = async {
+        loop {
+            progress.checkpoint().await?;
+
+            while walking && pending.len() < concurrency {
+                match entry_receiver.recv().await {
+                    Some(WalkEntry::File(path)) => {
+                        progress.file_discovered();
+                        let known_photos = Arc::clone(known_photos);
+                        let prepare_path = path.clone();
+                        let handle = tokio::task::spawn_blocking(move || prepare_photo_file(prepare_path, &known_photos));
+                        pending.push_back((path, handle));
+                    }
+                    // Albums follow the folders of the photos, so finished folders need no handling
+                    Some(WalkEntry::FolderDone(_)) => {}
+                    None => walking = false,
+                }
+            }
+
+            let (path, handle) = match pending.pop_front() {
+                Some(entry) => entry,
+                None => return Ok(()),
+            };
+
+            progress.file_processed();
+            if let Some(parent) = path.parent() {
+                progress.set_folder(&parent.to_string_lossy());
+            }
+
+            match handle.await? {
+                Ok(PreparedPhoto::Unchanged { photo_id }) => {
+                    unchanged_ids.push(photo_id);
+                    if unchanged_ids.len() >= SCAN_DB_BATCH_SIZE {
+                        photo_repo.touch_photo_items(&unchanged_ids).await?;
+                        unchanged_ids.clear();
+                    }
+                    photo_count += 1;
+                }
+                Ok(PreparedPhoto::Changed(mut photo)) => {
+                    let album_folder = path.parent().unwrap_or(folder);
+                    photo.album_id = Some(get_album_id(album_ids, photo_repo, index_id, folder, album_folder).await?);
+                    photo.index_id = index_id;
+
+                    photo_repo.save_photo_item(&photo).await?;
+                    if known_photos.contains_key(&photo.path) {
+                        progress.item_updated();
+                    } else {
+                        println!("📷 {}", path.display());
+                        progress.item_added();
+                    }
+                    photo_count += 1;
+                }
+                Err(e) => {
+                    eprintln!("❌ Failed to process photo {}: {}", path.display(), e);
+                    progress.error();
+                }
+            }
+            progress.report().await;
+        }
+    }.await;
+
+    // Always write pending touches, otherwise cleanup would treat those photos as deleted
+    if !unchanged_ids.is_empty() {
+        photo_repo.touch_photo_items(&unchanged_ids).await?;
+    }
+    result?;
+    walker.await??;
+
+    Ok(photo_count)
+}
+
+/// Read the file information and EXIF data of a photo and generate its thumbnail,
+/// unless it matches a known photo
+/// This does blocking file IO and decoding, so call it from a blocking thread
+fn prepare_photo_file(path: PathBuf, known_photos: &KnownPhotos) -> Result<PreparedPhoto, anyhow::Error> {
+    let metadata = path.metadata()?;
+    let size = metadata.len() as i64;
+    let mtime = metadata.modified()?.duration_since(std::time::UNIX_EPOCH)?.as_secs() as i64;
+    let path_str = path.to_string_lossy().to_string();
+
+    if let Some(photo) = known_photos.get(&path_str) {
+        if photo.size == Some(size) && photo.mtime == Some(mtime) {
+            return Ok(PreparedPhoto::Unchanged { photo_id: photo.id });
+        }
+    }
+
+    let fast_hash = calculate_fast_hash(&path)?;
+    let exif = read_photo_metadata(&path).unwrap_or_else(|e| {
+        eprintln!("⚠️  Failed to read EXIF data of {}: {}", path.display(), e);
+        Default::default()
+    });
+
+    // Report the dimensions as displayed, rotated orientations swap width and height
+    let dimensions = image::image_dimensions(&path).ok().map(|(width, height)| {
+        match exif.orientation {
+            Some(5..=8) => (height as i64, width as i64),
+            _ => (width as i64, height as i64),
+        }
+    });
+
+    // Thumbnails are keyed by content, so a moved or copied photo reuses the existing one
+    let has_thumbnail = match photo_thumbnail_path(&fast_hash) {
+        Some(thumbnail_path) if thumbnail_path.exists() => true,
+        Some(thumbnail_path) => match create_thumbnail(&path, &thumbnail_path, PHOTO_THUMBNAIL_SIZE, exif.orientation) {
+            Ok(()) => true,
+            Err(e) => {
+                eprintln!("⚠️  Failed to create thumbnail of {}: {}", path.display(), e);
+                false
+            }
+        },
+        None => false,
+    };
+
+    let now = chrono::Utc::now().timestamp();
+    Ok(PreparedPhoto::Changed(Box::new(PhotoItem {
+        id: 0, // Will be set by database
+        index_id: 0, // Set by the caller
+        album_id: None, // Set by the caller
+        path: path_str,
+        size: Some(size),
+        mtime: Some(mtime),
+        fast_hash: Some(fast_hash),
+        width: dimensions.map(|(width, _)| width),
+        height: dimensions.map(|(_, height)| height),
+        taken_at: exif.taken_at.unwrap_or(mtime),
+        camera_make: exif.camera_make,
+        camera_model: exif.camera_model,
+        latitude: exif.latitude,
+        longitude: exif.longitude,
+        orientation: exif.orientation.map(|orientation| orientation as i64),
+        has_thumbnail: if has_thumbnail { 1 } else { 0 },
+        added_at: now,
+        updated_at: now,
+    })))
+}
+
+/// Get the album of a folder, creating it and its missing parent albums up to the index folder
+async fn get_album_id(
+    album_ids: &mut AlbumIds,
+    photo_repo: &PhotoRepo,
+    index_id: i64,
+    root: &Path,
+    folder: &Path
+) -> Result<i64, anyhow::Error> {
+    if let Some(album_id) = album_ids.get(folder) {
+        return Ok(*album_id);
+    }
+
+    // Create the missing albums from the top down, so each one can reference its parent
+    let missing_folders: Vec<PathBuf> = folder.ancestors()
+        .take_while(|ancestor| ancestor.starts_with(root) && !album_ids.contains_key(*ancestor))
+        .map(Path::to_path_buf)
+        .collect();
+
+    for album_folder in missing_folders.into_iter().rev() {
+        let parent_id = album_folder.parent()
+            .filter(|parent| parent.starts_with(root))
+            .and_then(|parent| album_ids.get(parent))
+            .copied();
+        let title = album_folder.file_name()
+            .map(|name| name.to_string_lossy().to_string())
+            .unwrap_or_else(|| album_folder.to_string_lossy().to_string());
+
+        let album_id = photo_repo.get_or_create_photo_album(index_id, parent_id, &album_folder.to_string_lossy(), &title).await?;
+        album_ids.insert(album_folder, album_id);
+    }
+
+    album_ids.get(folder).copied()
+        .ok_or_else(|| anyhow::anyhow!("Folder is outside of the index folder: {}", folder.display()))
+}
+
+/// Remove photos that weren't seen during the scan, their unused thumbnails and empty albums
+/// Returns (deleted_photos, deleted_albums)
+async fn cleanup_deleted_photos(photo_repo: &PhotoRepo, index_id: i64, pre_scan_timestamp: i64) -> Result<(usize, u64), anyhow::Error> {
+    let deleted_photos = photo_repo.delete_unseen_photo_items(index_id, pre_scan_timestamp).await?;
+
+    for photo in &deleted_photos {
+        let fast_hash = match &photo.fast_hash {
+            Some(fast_hash) => fast_hash,
+            None => continue,
+        };
+        // Another copy of the photo may still use the thumbnail
+        if photo_repo.photo_hash_exists(fast_hash).await? {
+            continue;
+        }
+        if let Some(thumbnail_path) = photo_thumbnail_path(fast_hash) {
+            let _ = tokio::fs::remove_file(thumbnail_path).await;
+        }
+    }
+
+    let deleted_albums = photo_repo.delete_empty_photo_albums(index_id).await?;
+    Ok((deleted_photos.len(), deleted_albums))
+}
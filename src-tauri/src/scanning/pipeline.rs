@@ -0,0 +1,167 @@
+//! Stages of the scanning pipeline
+//!
+//! Folders are walked on a blocking thread, and file metadata and fast hashes are
+//! computed on the blocking pool with bounded concurrency. Classification and
+//! item creation stay in `video_scanning`, which consumes the walk in order so
+//! source path tracking behaves like a sequential scan, and writes the files
+//! that are prepared in batched transactions.
+
+use crate::constants::DEFAULT_SCAN_CONCURRENCY;
+use crate::db::models::VideoPart;
+use crate::db::repos::VideoRepo;
+use crate::utils::hash::calculate_fast_hash;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+/// An entry produced by the folder walk, in processing order
+#[derive(Debug, Clone, PartialEq)]
+pub enum WalkEntry {
+    /// A file to process
+    File(PathBuf),
+    /// A folder and all of its subfolders have been walked
+    FolderDone(String),
+}
+
+/// Parts already known for an index, keyed by path
+pub type KnownParts = HashMap<String, VideoPart>;
+
+/// Result of examining a video file off the async runtime
+#[derive(Debug, Clone)]
+pub struct PreparedFile {
+    pub path: PathBuf,
+    pub size: i64,
+    pub mtime: i64,
+    pub status: FileStatus,
+}
+
+/// Whether a file needs processing
+#[derive(Debug, Clone)]
+pub enum FileStatus {
+    /// Same path, size and mtime as a known part (hashing was skipped)
+    Unchanged { part_id: i64 },
+    /// New, changed or moved file with its fast hash
+    Changed { fast_hash: String },
+}
+
+/// Get the number of files hashed concurrently (`SCAN_CONCURRENCY` overrides the default)
+pub fn scan_concurrency() -> usize {
+    std::env::var("SCAN_CONCURRENCY")
+        .ok()
+        .and_then(|value| value.parse::<usize>().ok())
+        .filter(|concurrency| *concurrency > 0)
+        .unwrap_or(DEFAULT_SCAN_CONCURRENCY)
+}
+
+/// Walk a folder depth-first, emitting the included files of each folder before its subfolders
+/// and `FolderDone` once a folder and everything below it was emitted (the order `SourcePathTracker` relies on)
+/// Stops early when `emit` returns false; unreadable subfolders are skipped
+/// This does blocking file IO, so call it from a blocking thread
+pub fn walk_folder(root: &Path, include: impl Fn(&Path) -> bool, mut emit: impl FnMut(WalkEntry) -> bool) -> Result<(), anyhow::Error> {
+    enum Step {
+        Walk(PathBuf),
+        Done(String),
+    }
+
+    let mut stack = vec![Step::Walk(root.to_path_buf())];
+
+    while let Some(step) = stack.pop() {
+        let current_dir = match step {
+            Step::Walk(dir) => dir,
+            Step::Done(dir) => {
+                if !emit(WalkEntry::FolderDone(dir)) {
+                    return Ok(());
+                }
+                continue;
+            }
+        };
+
+        let entries = match std::fs::read_dir(&current_dir) {
+            Ok(entries) => entries,
+            Err(e) if current_dir != root => {
+                eprintln!("❌ Skipping unreadable folder '{}': {}", current_dir.display(), e);
+                continue;
+            }
+            Err(e) => return Err(e.into()),
+        };
+
+        let mut subdirs = Vec::new();
+        let mut files = Vec::new();
+        for entry in entries.flatten() {
+            let entry_path = entry.path();
+            if entry_path.is_dir() {
+                subdirs.push(entry_path);
+            } else if entry_path.is_file() && include(&entry_path) {
+                files.push(entry_path);
+            }
+        }
+
+        for file in files {
+            if !emit(WalkEntry::File(file)) {
+                return Ok(());
+            }
+        }
+
+        // Pushed first so it is popped after all subfolders (LIFO stack)
+        stack.push(Step::Done(current_dir.to_string_lossy().to_string()));
+        stack.extend(subdirs.into_iter().map(Step::Walk));
+    }
+
+    Ok(())
+}
+
+/// Read the size and mtime of a file and hash it unless it matches a known part
+/// This does blocking file IO, so call it from a blocking thread
+pub fn prepare_video_file(path: PathBuf, known_parts: &KnownParts) -> Result<PreparedFile, anyhow::Error> {
+    let metadata = path.metadata()?;
+    let size = metadata.len() as i64;
+    let mtime = metadata.modified()?.duration_since(std::time::UNIX_EPOCH)?.as_secs() as i64;
+
+    if let Some(part) = known_parts.get(path.to_string_lossy().as_ref()) {
+        if part.size == Some(size) && part.mtime == Some(mtime) {
+            return Ok(PreparedFile { path, size, mtime, status: FileStatus::Unchanged { part_id: part.id } });
+        }
+    }
+
+    let fast_hash = calculate_fast_hash(&path)?;
+    Ok(PreparedFile { path, size, mtime, status: FileStatus::Changed { fast_hash } })
+}
+
+/// Load the parts of an index for the unchanged file check
+pub async fn load_known_parts(video_repo: &VideoRepo, index_id: i64) -> Result<KnownParts, anyhow::Error> {
+    Ok(video_repo.get_video_parts_by_index(index_id).await?
+        .into_iter()
+        .map(|part| (part.path.clone(), part))
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::scanning::video_scanning::is_video_file;
+
+    #[test]
+    fn test_walk_folder_order() {
+        let root = std::env::temp_dir().join(format!("index_media_server_walk_{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&root);
+        std::fs::create_dir_all(root.join("Show/Season 1")).unwrap();
+        std::fs::write(root.join("Show/Season 1/Show S01E01.mkv"), b"").unwrap();
+        std::fs::write(root.join("Show/Season 1/notes.txt"), b"").unwrap();
+        std::fs::write(root.join("Show/Show S00E01.mkv"), b"").unwrap();
+
+        let mut entries = Vec::new();
+        walk_folder(&root, is_video_file, |entry| {
+            entries.push(entry);
+            true
+        }).unwrap();
+        std::fs::remove_dir_all(&root).unwrap();
+
+        let path = |relative: &str| root.join(relative);
+        assert_eq!(entries, vec![
+            WalkEntry::File(path("Show/Show S00E01.mkv")),
+            WalkEntry::File(path("Show/Season 1/Show S01E01.mkv")),
+            WalkEntry::FolderDone(path("Show/Season 1").to_string_lossy().to_string()),
+            WalkEntry::FolderDone(path("Show").to_string_lossy().to_string()),
+            WalkEntry::FolderDone(root.to_string_lossy().to_string()),
+        ]);
+    }
+}
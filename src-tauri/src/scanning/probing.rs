@@ -0,0 +1,91 @@
+use crate::db::models::VideoStream;
+use crate::db::repos::VideoRepo;
+use crate::scanning::subtitles::extract_embedded_subtitles;
+use crate::utils::probe::{ffprobe_version, probe_media_file, ProbeResult};
+use std::path::Path;
+
+/// Probe new or changed video parts of an index and store their technical details
+/// Versions are probed as a whole so multi-part runtimes stay consistent
+pub async fn probe_index_parts(video_repo: &VideoRepo, index_id: i64) -> Result<usize, anyhow::Error> {
+    let version_ids = video_repo.get_video_version_ids_needing_probe(index_id).await?;
+    if version_ids.is_empty() {
+        return Ok(0);
+    }
+
+    let probe_version = match ffprobe_version().await {
+        Some(version) => version,
+        None => {
+            println!("⚠️  ffprobe not found, skipping media probing for {} version(s)", version_ids.len());
+            return Ok(0);
+        }
+    };
+
+    println!("🔬 Probing {} video version(s) with {}...", version_ids.len(), probe_version);
+
+    let mut probed_parts = 0;
+    for version_id in version_ids {
+        match probe_version_parts(video_repo, version_id, &probe_version).await {
+            Ok(count) => probed_parts += count,
+            Err(e) => eprintln!("❌ Failed to probe video version {}: {}", version_id, e),
+        }
+    }
+
+    Ok(probed_parts)
+}
+
+/// Probe all parts of a version and update the version with the combined result
+async fn probe_version_parts(video_repo: &VideoRepo, version_id: i64, probe_version: &str) -> Result<usize, anyhow::Error> {
+    let parts = video_repo.get_video_parts_by_version(version_id).await?;
+
+    let mut first_result: Option<ProbeResult> = None;
+    let mut runtime_ms: Option<i64> = None;
+
+    for part in &parts {
+        let result = match probe_media_file(Path::new(&part.path)).await {
+            Ok(result) => result,
+            Err(e) => {
+                // Still mark the part as probed so broken files aren't retried on every scan
+                eprintln!("❌ Failed to probe {}: {}", part.path, e);
+                video_repo.update_video_part_probe(part.id, None).await?;
+                continue;
+            }
+        };
+
+        let streams: Vec<VideoStream> = result.streams.iter().map(|stream| VideoStream {
+            id: 0, // Will be set by database
+            part_id: part.id,
+            stream_index: stream.stream_index,
+            kind: stream.kind.clone(),
+            codec: stream.codec.clone(),
+            language: stream.language.clone(),
+            title: stream.title.clone(),
+            channels: stream.channels,
+            width: stream.width,
+            height: stream.height,
+            is_default: if stream.is_default { 1 } else { 0 },
+            is_forced: if stream.is_forced { 1 } else { 0 },
+        }).collect();
+
+        video_repo.replace_video_streams(part.id, &streams).await?;
+        video_repo.update_video_part_probe(part.id, result.duration_ms).await?;
+
+        // Text subtitle tracks are extracted so players can load them like sidecar files
+        if let Err(e) = extract_embedded_subtitles(video_repo, part, &result.streams).await {
+            eprintln!("❌ Failed to extract subtitles of {}: {}", part.path, e);
+        }
+
+        if let Some(duration_ms) = result.duration_ms {
+            runtime_ms = Some(runtime_ms.unwrap_or(0) + duration_ms);
+        }
+        if first_result.is_none() {
+            first_result = Some(result);
+        }
+    }
+
+    // Parts are ordered by part_index, so the first successful probe describes the version
+    if let Some(result) = first_result {
+        video_repo.update_video_version_probe(version_id, &result, runtime_ms, probe_version).await?;
+    }
+
+    Ok(parts.len())
+}
@@ -0,0 +1,303 @@
+//! Scan progress tracking and control
+//!
+//! Each scan keeps a `ScanJob` up to date, persists it in `scan_jobs` and
+//! broadcasts it to subscribers (the Server-Sent Events stream), throttled
+//! so large scans don't flood the database or clients. The scan is recorded
+//! in the `scan_runs` history when it finishes.
+//!
+//! Running scans can be paused, resumed and cancelled from the API. Scans
+//! check for these requests between files, so they stop at a safe point.
+//!
+//! Scans of the same index never interleave, and only one scan works at a
+//! time. A paused scan gives up its turn, so other indexes are scanned while
+//! it waits to be resumed.
+
+use crate::constants::{SCAN_PROGRESS_INTERVAL_MS, SCAN_RUNS_KEPT};
+use crate::db::models::ScanJob;
+use crate::db::repos::{ScanJobsRepo, ScanRunsRepo};
+use once_cell::sync::Lazy;
+use sqlx::SqlitePool;
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::sync::{broadcast, watch, Mutex, OwnedMutexGuard, Semaphore, SemaphorePermit};
+
+/// Number of progress updates buffered for slow subscribers (older ones are skipped)
+const SCAN_EVENTS_CAPACITY: usize = 64;
+
+/// Broadcast channel of scan progress updates for all indexes
+static SCAN_EVENTS: Lazy<broadcast::Sender<ScanJob>> = Lazy::new(|| broadcast::channel(SCAN_EVENTS_CAPACITY).0);
+
+/// Controls of the running scans, keyed by index ID
+static SCAN_CONTROLS: Lazy<std::sync::Mutex<HashMap<i64, Arc<ScanControl>>>> = Lazy::new(|| std::sync::Mutex::new(HashMap::new()));
+
+/// Held by a scan while it runs, one per index
+static INDEX_SCAN_LOCKS: Lazy<std::sync::Mutex<HashMap<i64, Arc<Mutex<()>>>>> = Lazy::new(|| std::sync::Mutex::new(HashMap::new()));
+
+/// Turn of the scan doing work, which paused scans give up
+static SCAN_TURN: Semaphore = Semaphore::const_new(1);
+
+/// Wait until no other scan of the index is running
+async fn lock_index_scanning(index_id: i64) -> OwnedMutexGuard<()> {
+    let lock = Arc::clone(INDEX_SCAN_LOCKS.lock().unwrap().entry(index_id).or_default());
+    lock.lock_owned().await
+}
+
+/// Wait until no other scan is working
+async fn take_scan_turn() -> SemaphorePermit<'static> {
+    // The semaphore is never closed
+    SCAN_TURN.acquire().await.expect("Scan turn semaphore closed")
+}
+
+/// Subscribe to progress updates of all scans
+pub fn subscribe_scan_events() -> broadcast::Receiver<ScanJob> {
+    SCAN_EVENTS.subscribe()
+}
+
+/// Error returned by a scan that stopped because it was cancelled
+#[derive(Debug)]
+pub struct ScanCancelled;
+
+impl std::fmt::Display for ScanCancelled {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Scan cancelled")
+    }
+}
+
+impl std::error::Error for ScanCancelled {}
+
+/// Pause and cancellation requests for a running scan
+struct ScanControl {
+    cancelled: AtomicBool,
+    paused: watch::Sender<bool>,
+}
+
+fn scan_control(index_id: i64) -> Option<Arc<ScanControl>> {
+    SCAN_CONTROLS.lock().unwrap().get(&index_id).cloned()
+}
+
+/// Ask the running scan of an index to stop
+/// Returns false when the index isn't being scanned
+pub fn cancel_scan(index_id: i64) -> bool {
+    match scan_control(index_id) {
+        Some(control) => {
+            control.cancelled.store(true, Ordering::SeqCst);
+            // Wake up a paused scan so it can stop
+            control.paused.send_replace(false);
+            true
+        }
+        None => false,
+    }
+}
+
+/// Pause or resume the running scan of an index
+/// Returns false when the index isn't being scanned
+pub fn set_scan_paused(index_id: i64, paused: bool) -> bool {
+    match scan_control(index_id) {
+        Some(control) => {
+            control.paused.send_replace(paused);
+            true
+        }
+        None => false,
+    }
+}
+
+/// Check if pausing the running scan of an index was requested
+pub fn is_scan_paused(index_id: i64) -> bool {
+    scan_control(index_id).is_some_and(|control| *control.paused.borrow())
+}
+
+/// Progress of a running scan
+pub struct ScanProgress {
+    job: ScanJob,
+    repo: ScanJobsRepo,
+    runs_repo: ScanRunsRepo,
+    run_id: Option<i64>,
+    control: Arc<ScanControl>,
+    last_report: Instant,
+    turn: Option<SemaphorePermit<'static>>,
+    _index_guard: OwnedMutexGuard<()>,
+}
+
+impl ScanProgress {
+    /// Start tracking a new scan of an index ("full" or "incremental")
+    /// Waits for other scans of the index to finish, and for the turn to scan
+    pub async fn start(pool: &SqlitePool, index_id: i64, kind: &str) -> Self {
+        let index_guard = lock_index_scanning(index_id).await;
+        let turn = take_scan_turn().await;
+
+        let control = Arc::new(ScanControl {
+            cancelled: AtomicBool::new(false),
+            paused: watch::Sender::new(false),
+        });
+        SCAN_CONTROLS.lock().unwrap().insert(index_id, Arc::clone(&control));
+
+        let job = ScanJob::new(index_id, kind.to_string());
+        let runs_repo = ScanRunsRepo::new(pool.clone());
+        let run_id = match runs_repo.start_scan_run(index_id, kind, job.started_at).await {
+            Ok(run_id) => Some(run_id),
+            Err(e) => {
+                eprintln!("❌ Failed to record scan run for index {}: {}", index_id, e);
+                None
+            }
+        };
+
+        let mut progress = Self {
+            job,
+            repo: ScanJobsRepo::new(pool.clone()),
+            runs_repo,
+            run_id,
+            control,
+            last_report: Instant::now(),
+            turn: Some(turn),
+            _index_guard: index_guard,
+        };
+        progress.save_and_publish().await;
+        progress
+    }
+
+    /// Set the folder being scanned
+    pub fn set_folder(&mut self, folder: &str) {
+        if self.job.current_folder.as_deref() != Some(folder) {
+            self.job.current_folder = Some(folder.to_string());
+        }
+    }
+
+    /// A video file was found by the folder walk
+    pub fn file_discovered(&mut self) {
+        self.job.files_discovered += 1;
+    }
+
+    /// A video file was handled (whatever the outcome)
+    pub fn file_processed(&mut self) {
+        self.job.files_processed += 1;
+    }
+
+    /// A new video file was added
+    pub fn item_added(&mut self) {
+        self.job.items_added += 1;
+    }
+
+    /// A changed or moved video file was updated
+    pub fn item_updated(&mut self) {
+        self.job.items_updated += 1;
+    }
+
+    /// Deleted video files were removed
+    pub fn items_removed(&mut self, count: usize) {
+        self.job.items_removed += count as i64;
+    }
+
+    /// A file or folder failed to scan
+    pub fn error(&mut self) {
+        self.job.errors += 1;
+    }
+
+    /// Persist and broadcast the progress if the last report is old enough
+    pub async fn report(&mut self) {
+        if self.last_report.elapsed() >= Duration::from_millis(SCAN_PROGRESS_INTERVAL_MS) {
+            self.save_and_publish().await;
+        }
+    }
+
+    /// Wait while the scan is paused, and fail with `ScanCancelled` once it was cancelled
+    /// Call this between units of work, where stopping leaves the index consistent
+    pub async fn checkpoint(&mut self) -> Result<(), anyhow::Error> {
+        if *self.control.paused.borrow() {
+            println!("⏸️  Scan of index {} paused", self.job.index_id);
+            // Let other scans work meanwhile
+            self.turn = None;
+            self.job.status = "paused".to_string();
+            self.save_and_publish().await;
+
+            let mut paused = self.control.paused.subscribe();
+            // The sender lives in self.control, so this only returns once resumed or cancelled
+            let _ = paused.wait_for(|paused| !*paused).await;
+            if self.control.cancelled.load(Ordering::SeqCst) {
+                return Err(ScanCancelled.into());
+            }
+
+            self.turn = Some(take_scan_turn().await);
+            println!("▶️  Scan of index {} resumed", self.job.index_id);
+            self.job.status = "scanning".to_string();
+            self.save_and_publish().await;
+        }
+
+        if self.control.cancelled.load(Ordering::SeqCst) {
+            return Err(ScanCancelled.into());
+        }
+        Ok(())
+    }
+
+    /// Mark the scan as finished ("done", "failed" or "cancelled"), report it and record it in the history
+    pub async fn finish(&mut self, status: &str, error_message: Option<&str>) {
+        SCAN_CONTROLS.lock().unwrap().remove(&self.job.index_id);
+
+        self.job.status = status.to_string();
+        self.job.current_folder = None;
+        self.job.finished_at = Some(chrono::Utc::now().timestamp());
+        self.save_and_publish().await;
+
+        if let Some(run_id) = self.run_id {
+            if let Err(e) = self.runs_repo.finish_scan_run(run_id, status, &self.job, error_message).await {
+                eprintln!("❌ Failed to record scan run for index {}: {}", self.job.index_id, e);
+            }
+            if let Err(e) = self.runs_repo.prune_scan_runs(self.job.index_id, &self.job.kind, SCAN_RUNS_KEPT).await {
+                eprintln!("❌ Failed to prune scan runs for index {}: {}", self.job.index_id, e);
+            }
+        }
+        self.turn = None;
+    }
+
+    async fn save_and_publish(&mut self) {
+        self.job.updated_at = chrono::Utc::now().timestamp();
+        self.last_report = Instant::now();
+
+        // Progress is informational, so failing to store it never fails the scan
+        if let Err(e) = self.repo.save_scan_job(&self.job).await {
+            eprintln!("❌ Failed to save scan progress for index {}: {}", self.job.index_id, e);
+        }
+        // Sending only fails when nobody is subscribed
+        let _ = SCAN_EVENTS.send(self.job.clone());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::migrations::run_migrations;
+    use crate::db::pool::connect_pool;
+
+    #[tokio::test]
+    async fn test_paused_scan_gives_up_its_turn() {
+        let data_dir = std::env::temp_dir().join(format!("scan_progress_test_{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&data_dir).unwrap();
+        let db_path = data_dir.join("app.sqlite3");
+        let pool = connect_pool(&db_path).await.unwrap();
+        run_migrations(&pool, &db_path).await.unwrap();
+        // IDs no other test scans, the locks are shared by the whole process
+        let (paused_index, other_index) = (9_001, 9_002);
+
+        let mut paused_scan = ScanProgress::start(&pool, paused_index, "full").await;
+        assert!(set_scan_paused(paused_index, true));
+        let paused_task = tokio::spawn(async move {
+            let result = paused_scan.checkpoint().await;
+            paused_scan.finish("cancelled", None).await;
+            result
+        });
+
+        // Another index is scanned while the first scan is paused
+        let mut other_scan = tokio::time::timeout(Duration::from_secs(5), ScanProgress::start(&pool, other_index, "incremental"))
+            .await
+            .expect("scan waited for the paused scan");
+        assert!(is_scan_paused(paused_index));
+        other_scan.finish("done", None).await;
+
+        assert!(cancel_scan(paused_index));
+        assert!(paused_task.await.unwrap().unwrap_err().is::<ScanCancelled>());
+
+        pool.close().await;
+        std::fs::remove_dir_all(&data_dir).unwrap();
+    }
+}
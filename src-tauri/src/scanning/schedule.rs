@@ -0,0 +1,144 @@
+//! Periodic rescan schedules
+//!
+//! A schedule combines an interval, a daily time window in local time and an
+//! "only when idle" flag. The scanning process checks the schedules of all
+//! indexes each cycle and queues the ones that are due.
+
+use crate::constants::DEFAULT_SCAN_SCHEDULE_INTERVAL_HOURS;
+use crate::models::config::ScanSchedule;
+use chrono::{Duration, NaiveDateTime, NaiveTime};
+
+/// Longest interval accepted for a schedule (30 days)
+const MAX_SCAN_INTERVAL_HOURS: u32 = 30 * 24;
+
+impl ScanSchedule {
+    /// Whether the schedule scans the index at all
+    pub fn is_enabled(&self) -> bool {
+        self.interval_hours.is_some() || self.window_start.is_some() || self.only_when_idle
+    }
+
+    /// Check that the interval is in range and the window has a valid start and end
+    pub fn validate(&self) -> Result<(), String> {
+        if let Some(interval_hours) = self.interval_hours {
+            if !(1..=MAX_SCAN_INTERVAL_HOURS).contains(&interval_hours) {
+                return Err(format!("intervalHours must be between 1 and {}", MAX_SCAN_INTERVAL_HOURS));
+            }
+        }
+
+        match (&self.window_start, &self.window_end) {
+            (None, None) => Ok(()),
+            (Some(start), Some(end)) => {
+                parse_window_time(start).ok_or("windowStart must be a time formatted as HH:MM")?;
+                parse_window_time(end).ok_or("windowEnd must be a time formatted as HH:MM")?;
+                Ok(())
+            }
+            _ => Err("windowStart and windowEnd must be set together".to_string()),
+        }
+    }
+
+    /// Get the window as (start, end), if one is set
+    fn window(&self) -> Option<(NaiveTime, NaiveTime)> {
+        Some((
+            parse_window_time(self.window_start.as_deref()?)?,
+            parse_window_time(self.window_end.as_deref()?)?,
+        ))
+    }
+}
+
+/// Parse a window time formatted as "HH:MM"
+fn parse_window_time(time: &str) -> Option<NaiveTime> {
+    NaiveTime::parse_from_str(time.trim(), "%H:%M").ok()
+}
+
+/// Check whether a time lies inside a window (windows ending before they start span midnight)
+fn in_window(time: NaiveTime, start: NaiveTime, end: NaiveTime) -> bool {
+    if start == end {
+        true
+    } else if start < end {
+        time >= start && time < end
+    } else {
+        time >= start || time < end
+    }
+}
+
+/// Check whether a scheduled index should be queued now
+/// `now` and `last_scanned_at` are local times; `idle` tells whether playback has been idle long enough
+pub fn is_scan_due(schedule: &ScanSchedule, last_scanned_at: Option<NaiveDateTime>, now: NaiveDateTime, idle: bool) -> bool {
+    if !schedule.is_enabled() || (schedule.only_when_idle && !idle) {
+        return false;
+    }
+
+    let window = schedule.window();
+    if let Some((start, end)) = window {
+        if !in_window(now.time(), start, end) {
+            return false;
+        }
+    }
+
+    let last_scanned_at = match last_scanned_at {
+        Some(last_scanned_at) => last_scanned_at,
+        None => return true,
+    };
+
+    match (schedule.interval_hours, window) {
+        (Some(interval_hours), _) => now - last_scanned_at >= Duration::hours(interval_hours as i64),
+        // A window without interval scans once each time the window opens
+        (None, Some((start, _))) => {
+            let opened_today = now.date().and_time(start);
+            let window_opened_at = if now >= opened_today { opened_today } else { opened_today - Duration::days(1) };
+            last_scanned_at < window_opened_at
+        }
+        (None, None) => now - last_scanned_at >= Duration::hours(DEFAULT_SCAN_SCHEDULE_INTERVAL_HOURS as i64),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn at(time: &str) -> NaiveDateTime {
+        NaiveDateTime::parse_from_str(time, "%Y-%m-%d %H:%M").unwrap()
+    }
+
+    #[test]
+    fn test_is_scan_due_interval() {
+        let schedule = ScanSchedule { interval_hours: Some(6), ..Default::default() };
+
+        assert!(is_scan_due(&schedule, None, at("2024-05-01 12:00"), false));
+        assert!(!is_scan_due(&schedule, Some(at("2024-05-01 08:00")), at("2024-05-01 12:00"), false));
+        assert!(is_scan_due(&schedule, Some(at("2024-05-01 06:00")), at("2024-05-01 12:00"), false));
+        assert!(!is_scan_due(&ScanSchedule::default(), None, at("2024-05-01 12:00"), true));
+    }
+
+    #[test]
+    fn test_is_scan_due_window() {
+        let schedule = ScanSchedule {
+            window_start: Some("23:00".to_string()),
+            window_end: Some("05:00".to_string()),
+            ..Default::default()
+        };
+
+        // Outside of the window
+        assert!(!is_scan_due(&schedule, Some(at("2024-04-29 23:30")), at("2024-05-01 12:00"), false));
+        // Once per night, also after midnight
+        assert!(is_scan_due(&schedule, Some(at("2024-04-30 23:30")), at("2024-05-01 23:10"), false));
+        assert!(!is_scan_due(&schedule, Some(at("2024-04-30 23:30")), at("2024-05-01 02:00"), false));
+        assert!(is_scan_due(&schedule, Some(at("2024-04-30 12:00")), at("2024-05-01 02:00"), false));
+
+        let idle_schedule = ScanSchedule { only_when_idle: true, ..schedule };
+        assert!(!is_scan_due(&idle_schedule, Some(at("2024-04-30 12:00")), at("2024-05-01 02:00"), false));
+        assert!(is_scan_due(&idle_schedule, Some(at("2024-04-30 12:00")), at("2024-05-01 02:00"), true));
+    }
+
+    #[test]
+    fn test_validate_schedule() {
+        assert!(ScanSchedule { interval_hours: Some(6), ..Default::default() }.validate().is_ok());
+        assert!(ScanSchedule { interval_hours: Some(0), ..Default::default() }.validate().is_err());
+        assert!(ScanSchedule { window_start: Some("01:00".to_string()), ..Default::default() }.validate().is_err());
+        assert!(ScanSchedule {
+            window_start: Some("1am".to_string()),
+            window_end: Some("05:00".to_string()),
+            ..Default::default()
+        }.validate().is_err());
+    }
+}
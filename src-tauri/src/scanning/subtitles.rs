@@ -0,0 +1,301 @@
+//! Subtitle discovery
+//!
+//! Sidecar subtitles are text files next to a video named after it
+//! (`Movie.en.srt`, `Movie.en.forced.ass`), or in a `Subs` folder next to it,
+//! either named after the video or, in release folders, per video
+//! (`Subs/<video name>/2_English.srt`) or for the only video of the folder
+//! (`Subs/2_English.srt`). Language, forced and SDH flags come from the name
+//! tags. Text streams inside the container are extracted to the app data cache
+//! with ffmpeg when a part is probed, so players can load them the same way.
+
+use crate::db::models::{Subtitle, VideoPart};
+use crate::db::repos::VideoRepo;
+use crate::scanning::artwork::ffmpeg_available;
+use crate::scanning::video_scanning::is_video_file;
+use crate::transcoding::session::ffmpeg_binary;
+use crate::utils::image::{extracted_subtitle_path, extracted_subtitles_dir, extracted_subtitles_root};
+use crate::utils::probe::ProbedStream;
+use crate::utils::subtitles::{normalize_language, parse_sidecar_name, parse_subtitle_tags, subtitle_format, SubtitleTags};
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use tokio::process::Command;
+
+/// Names of the folders holding the subtitles of the videos next to them (compared case-insensitively)
+const SUBTITLE_FOLDERS: [&str; 2] = ["subs", "subtitles"];
+
+/// Text subtitle codecs that can be extracted, as (codec, stored format, ffmpeg encoder)
+const TEXT_SUBTITLE_CODECS: [(&str, &str, &str); 6] = [
+    ("subrip", "srt", "srt"),
+    ("srt", "srt", "srt"),
+    ("mov_text", "srt", "srt"),
+    ("ass", "ass", "ass"),
+    ("ssa", "ass", "ass"),
+    ("webvtt", "vtt", "webvtt"),
+];
+
+/// Link new or changed sidecar subtitles to the parts of an index and drop the ones that were removed
+/// Returns the number of subtitles stored
+pub async fn import_index_subtitles(video_repo: &VideoRepo, index_id: i64) -> Result<usize, anyhow::Error> {
+    let parts: Vec<(i64, PathBuf)> = video_repo.get_video_parts_by_index(index_id).await?
+        .into_iter()
+        .map(|part| (part.id, PathBuf::from(part.path)))
+        .collect();
+    let mut stored: HashMap<(i64, String), Subtitle> = video_repo.get_sidecar_subtitles_by_index(index_id).await?
+        .into_iter()
+        .map(|subtitle| ((subtitle.part_id, subtitle.path.clone()), subtitle))
+        .collect();
+
+    // Finding sidecars is blocking file IO
+    let found = tokio::task::spawn_blocking(move || find_sidecar_subtitles(&parts)).await?;
+
+    let mut stored_count = 0;
+    for subtitle in found {
+        let unchanged = stored.remove(&(subtitle.part_id, subtitle.path.clone()))
+            .is_some_and(|existing| existing.mtime == subtitle.mtime);
+        if !unchanged {
+            video_repo.save_subtitle(&subtitle).await?;
+            stored_count += 1;
+        }
+    }
+
+    // Sidecars that weren't found again were removed or renamed
+    for subtitle in stored.into_values() {
+        video_repo.delete_subtitle(subtitle.id).await?;
+    }
+
+    remove_orphaned_subtitles(video_repo).await?;
+
+    Ok(stored_count)
+}
+
+/// Find the sidecar subtitles of parts, listing every folder once
+/// This does blocking file IO, so call it from a blocking thread
+fn find_sidecar_subtitles(parts: &[(i64, PathBuf)]) -> Vec<Subtitle> {
+    let mut folders: HashMap<PathBuf, FolderListing> = HashMap::new();
+    let mut found = Vec::new();
+
+    for (part_id, part_path) in parts {
+        let (folder, stem) = match (part_path.parent(), part_path.file_stem()) {
+            (Some(folder), Some(stem)) => (folder, stem.to_string_lossy().to_string()),
+            _ => continue,
+        };
+        let listing = folders.entry(folder.to_path_buf()).or_insert_with(|| FolderListing::read(folder));
+
+        for file in listing.subtitles.iter().chain(&listing.subtitle_folder_files) {
+            if let Some(tags) = parse_sidecar_name(&stem, &file_stem(file)) {
+                found.extend(sidecar_subtitle(*part_id, file, tags));
+            }
+        }
+
+        // Release folders: "Subs/<video name>/2_English.srt", or "Subs/2_English.srt" for the only video
+        if let Some(video_folder) = listing.subtitle_subfolders.get(&stem.to_lowercase()) {
+            for file in list_subtitle_files(video_folder) {
+                found.extend(sidecar_subtitle(*part_id, &file, parse_subtitle_tags(&file_stem(&file))));
+            }
+        }
+        if listing.video_count == 1 {
+            for file in &listing.subtitle_folder_files {
+                if parse_sidecar_name(&stem, &file_stem(file)).is_none() {
+                    found.extend(sidecar_subtitle(*part_id, file, parse_subtitle_tags(&file_stem(file))));
+                }
+            }
+        }
+    }
+
+    found
+}
+
+/// Subtitle files and subtitle folders of a folder holding videos
+#[derive(Default)]
+struct FolderListing {
+    video_count: usize,
+    /// Subtitle files next to the videos
+    subtitles: Vec<PathBuf>,
+    /// Subtitle files directly inside the `Subs` folder
+    subtitle_folder_files: Vec<PathBuf>,
+    /// Folders inside the `Subs` folder keyed by lowercase name
+    subtitle_subfolders: HashMap<String, PathBuf>,
+}
+
+impl FolderListing {
+    fn read(folder: &Path) -> Self {
+        let mut listing = FolderListing::default();
+        let entries = match std::fs::read_dir(folder) {
+            Ok(entries) => entries,
+            Err(_) => return listing,
+        };
+
+        for path in entries.flatten().map(|entry| entry.path()) {
+            let name = path.file_name().map(|name| name.to_string_lossy().to_lowercase()).unwrap_or_default();
+            if path.is_dir() && SUBTITLE_FOLDERS.contains(&name.as_str()) {
+                listing.subtitle_folder_files.extend(list_subtitle_files(&path));
+                for subfolder in std::fs::read_dir(&path).into_iter().flatten().flatten().map(|entry| entry.path()).filter(|path| path.is_dir()) {
+                    let subfolder_name = subfolder.file_name().map(|name| name.to_string_lossy().to_lowercase()).unwrap_or_default();
+                    listing.subtitle_subfolders.insert(subfolder_name, subfolder);
+                }
+            } else if subtitle_format(&path).is_some() {
+                listing.subtitles.push(path);
+            } else if is_video_file(&path) {
+                listing.video_count += 1;
+            }
+        }
+
+        listing.subtitles.sort();
+        listing.subtitle_folder_files.sort();
+        listing
+    }
+}
+
+/// List the subtitle files of a folder, sorted by name
+fn list_subtitle_files(folder: &Path) -> Vec<PathBuf> {
+    let mut files: Vec<PathBuf> = match std::fs::read_dir(folder) {
+        Ok(entries) => entries.flatten().map(|entry| entry.path()).filter(|path| subtitle_format(path).is_some()).collect(),
+        Err(_) => Vec::new(),
+    };
+    files.sort();
+    files
+}
+
+/// Get the file name of a path without extension
+fn file_stem(path: &Path) -> String {
+    path.file_stem().map(|stem| stem.to_string_lossy().to_string()).unwrap_or_default()
+}
+
+/// Build the sidecar subtitle of a part, None when the file can't be read
+fn sidecar_subtitle(part_id: i64, path: &Path, tags: SubtitleTags) -> Option<Subtitle> {
+    let format = subtitle_format(path)?;
+    let modified = path.metadata().and_then(|metadata| metadata.modified()).ok()?;
+    let mtime = modified.duration_since(std::time::UNIX_EPOCH).map(|d| d.as_secs() as i64).unwrap_or(0);
+    Some(new_subtitle(part_id, "sidecar", path, None, format, tags, Some(mtime)))
+}
+
+/// Extract the text subtitle streams of a probed part to the app data cache
+/// Image subtitles (PGS, VobSub) can't be converted to text and are left to transcoding
+/// Returns the number of extracted subtitles
+pub async fn extract_embedded_subtitles(video_repo: &VideoRepo, part: &VideoPart, streams: &[ProbedStream]) -> Result<usize, anyhow::Error> {
+    let text_streams: Vec<(&ProbedStream, &str, &str)> = streams.iter()
+        .filter(|stream| stream.kind == "subtitle")
+        .filter_map(|stream| {
+            let codec = stream.codec.as_deref()?;
+            TEXT_SUBTITLE_CODECS.iter()
+                .find(|(name, _, _)| *name == codec)
+                .map(|(_, format, encoder)| (stream, *format, *encoder))
+        })
+        .collect();
+
+    // Streams of the previous probe may be gone or renumbered
+    if let Some(dir) = extracted_subtitles_dir(part.id) {
+        let _ = tokio::fs::remove_dir_all(&dir).await;
+    }
+    if text_streams.is_empty() || !ffmpeg_available().await {
+        video_repo.replace_embedded_subtitles(part.id, &[]).await?;
+        return Ok(0);
+    }
+
+    let mut subtitles = Vec::new();
+    for (stream, format, encoder) in text_streams {
+        let destination = match extracted_subtitle_path(part.id, stream.stream_index, format) {
+            Some(destination) => destination,
+            None => break,
+        };
+        if let Some(dir) = destination.parent() {
+            tokio::fs::create_dir_all(dir).await?;
+        }
+
+        let output = Command::new(ffmpeg_binary())
+            .args(["-hide_banner", "-loglevel", "error", "-nostdin", "-i"])
+            .arg(&part.path)
+            .args(["-map", &format!("0:{}", stream.stream_index), "-c:s", encoder, "-y"])
+            .arg(&destination)
+            .kill_on_drop(true)
+            .output()
+            .await?;
+        if !output.status.success() || !destination.is_file() {
+            eprintln!("❌ Failed to extract subtitle stream {} of {}: {}", stream.stream_index, part.path, String::from_utf8_lossy(&output.stderr).trim());
+            let _ = tokio::fs::remove_file(&destination).await;
+            continue;
+        }
+
+        let tags = SubtitleTags {
+            language: stream.language.as_deref().map(|language| normalize_language(language).unwrap_or_else(|| language.to_lowercase())),
+            title: stream.title.clone(),
+            is_default: stream.is_default,
+            is_forced: stream.is_forced,
+            is_sdh: stream.is_hearing_impaired,
+        };
+        subtitles.push(new_subtitle(part.id, "embedded", &destination, Some(stream.stream_index), format, tags, None));
+    }
+
+    video_repo.replace_embedded_subtitles(part.id, &subtitles).await?;
+    Ok(subtitles.len())
+}
+
+/// Remove extracted subtitles of parts that no longer exist
+async fn remove_orphaned_subtitles(video_repo: &VideoRepo) -> Result<(), anyhow::Error> {
+    let root = match extracted_subtitles_root() {
+        Some(root) if root.is_dir() => root,
+        _ => return Ok(()),
+    };
+    let part_ids: HashSet<i64> = video_repo.get_all_video_part_ids().await?.into_iter().collect();
+
+    let mut entries = tokio::fs::read_dir(&root).await?;
+    while let Some(entry) = entries.next_entry().await? {
+        let is_orphan = entry.file_name().to_str()
+            .and_then(|name| name.parse::<i64>().ok())
+            .is_some_and(|part_id| !part_ids.contains(&part_id));
+        if is_orphan {
+            let _ = tokio::fs::remove_dir_all(entry.path()).await;
+        }
+    }
+
+    Ok(())
+}
+
+/// Build a subtitle of a part from its name tags or stream details
+fn new_subtitle(part_id: i64, source: &str, path: &Path, stream_index: Option<i64>, format: &str, tags: SubtitleTags, mtime: Option<i64>) -> Subtitle {
+    let now = chrono::Utc::now().timestamp();
+    Subtitle {
+        id: 0, // Will be set by database
+        part_id,
+        source: source.to_string(),
+        path: path.to_string_lossy().to_string(),
+        stream_index,
+        format: format.to_string(),
+        language: tags.language,
+        title: tags.title,
+        is_default: if tags.is_default { 1 } else { 0 },
+        is_forced: if tags.is_forced { 1 } else { 0 },
+        is_sdh: if tags.is_sdh { 1 } else { 0 },
+        mtime,
+        created_at: now,
+        updated_at: now,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_find_sidecar_subtitles() {
+        let root = std::env::temp_dir().join(format!("subtitle_discovery_{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&root);
+        std::fs::create_dir_all(root.join("Subs/Movie")).unwrap();
+        for file in ["Movie.mkv", "Movie.en.srt", "Movie.en.forced.ass", "Other.srt", "Subs/Movie/2_English.srt", "Subs/3_French.srt"] {
+            std::fs::write(root.join(file), b"x").unwrap();
+        }
+
+        let found = find_sidecar_subtitles(&[(1, root.join("Movie.mkv"))]);
+        let names: Vec<(String, Option<&str>, i64)> = found.iter()
+            .map(|subtitle| (subtitle.path.trim_start_matches(root.to_str().unwrap()).to_string(), subtitle.language.as_deref(), subtitle.is_forced))
+            .collect();
+        let _ = std::fs::remove_dir_all(&root);
+
+        assert_eq!(names, vec![
+            ("/Movie.en.forced.ass".to_string(), Some("eng"), 1),
+            ("/Movie.en.srt".to_string(), Some("eng"), 0),
+            ("/Subs/Movie/2_English.srt".to_string(), Some("eng"), 0),
+            ("/Subs/3_French.srt".to_string(), Some("fre"), 0),
+        ]);
+    }
+}
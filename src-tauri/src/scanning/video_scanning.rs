@@ -1,13 +1,69 @@
 use crate::api::state::AppState;
+use crate::metadata::refresh_index_metadata;
+use crate::constants::SCAN_DB_BATCH_SIZE;
 use crate::db::repos::{IndexesRepo, VideoRepo};
-use crate::utils::hash::calculate_fast_hash;
 use crate::utils::video_classifier::{classify_path, MediaType, classify_movie_extra, classify_show_extra, MovieExtra, ShowExtra, GenericInfo};
-use crate::scanning::{TempFileManager, SourcePathTracker, TempVideoItem, TempExtraItem};
+use crate::scanning::{TempFileManager, SourcePathTracker, TempVideoItem, TempExtraItem, ScanProgress, ScanCancelled, probe_index_parts, import_index_subtitles, import_index_nfo, extract_index_artwork};
+use crate::scanning::pipeline::{
+    walk_folder, prepare_video_file, load_known_parts, scan_concurrency, KnownParts, PreparedFile, FileStatus, WalkEntry,
+};
+use std::collections::{HashMap, HashSet, VecDeque};
 use std::path::{Path, PathBuf};
+use std::sync::Arc;
 use serde_json::Value;
+use tokio::sync::mpsc;
+use tokio::task::JoinHandle;
+
+/// Walk entries buffered between the folder walk and processing
+const WALK_CHANNEL_CAPACITY: usize = 1024;
+
+/// Video file extensions to look for
+pub const VIDEO_EXTENSIONS: [&str; 11] = [
+    "mp4", "mkv", "avi", "mov", "wmv", "flv", "ts", "m2ts", "webm", "mpeg", "mpg"
+];
+
+/// Check if a path has one of the video file extensions
+pub fn is_video_file(path: &Path) -> bool {
+    path.extension()
+        .and_then(|ext| ext.to_str())
+        .is_some_and(|ext| VIDEO_EXTENSIONS.contains(&ext.to_lowercase().as_str()))
+}
 
 /// Scan a single index (depth-first search for video files)
 pub async fn scan_video_index(indexes_repo: &IndexesRepo, index: &crate::db::models::Index, app_state: &AppState) -> Result<(), anyhow::Error> {
+    let mut progress = ScanProgress::start(&app_state.db_pool, index.id, "full").await;
+    
+    let result = run_video_index_scan(indexes_repo, index, app_state, &mut progress).await;
+    if finish_scan(&mut progress, &result).await {
+        // Cancelled scans keep last_scanned_at, the partial scan is only recorded in the scan history
+        println!("🛑 Cancelled scan for index '{}' (ID: {})", index.name, index.id);
+        indexes_repo.update_scan_status(index.id, "done".to_string()).await?;
+        return Ok(());
+    }
+    result
+}
+
+/// Finish the scan progress with the outcome of a scan
+/// Returns true when the scan was cancelled
+pub(crate) async fn finish_scan(progress: &mut ScanProgress, result: &Result<(), anyhow::Error>) -> bool {
+    match result {
+        Ok(()) => progress.finish("done", None).await,
+        Err(e) if e.is::<ScanCancelled>() => {
+            progress.finish("cancelled", None).await;
+            return true;
+        }
+        Err(e) => progress.finish("failed", Some(&e.to_string())).await,
+    }
+    false
+}
+
+/// Full scan of all folders of an index, reporting progress as it goes
+async fn run_video_index_scan(
+    indexes_repo: &IndexesRepo,
+    index: &crate::db::models::Index,
+    app_state: &AppState,
+    progress: &mut ScanProgress
+) -> Result<(), anyhow::Error> {
     println!("🔍 Scanning index '{}' (ID: {})", index.name, index.id);
     
     // Initialize temporary file manager and cleanup any existing files
@@ -19,19 +75,7 @@ pub async fn scan_video_index(indexes_repo: &IndexesRepo, index: &crate::db::mod
     let mut source_tracker = SourcePathTracker::new();
     
     // Parse metadata to get folders
-    let folders = if let Ok(meta) = index.metadata_json() {
-        if let Some(folders_array) = meta.get("folders") {
-            if let Some(arr) = folders_array.as_array() {
-                arr.iter().filter_map(|v| v.as_str().map(|s| s.to_string())).collect()
-            } else {
-                Vec::new()
-            }
-        } else {
-            Vec::new()
-        }
-    } else {
-        Vec::new()
-    };
+    let folders = index.folders();
     
     if folders.is_empty() {
         println!("⚠️  No folders configured for index '{}'", index.name);
@@ -42,47 +86,126 @@ pub async fn scan_video_index(indexes_repo: &IndexesRepo, index: &crate::db::mod
     
     let pre_scan_timestamp = chrono::Utc::now().timestamp();
     let mut total_videos = 0;
+    let mut total_unchanged = 0;
     
     // Create video repository for database operations
     let video_repo = VideoRepo::new(app_state.db_pool.clone());
     
+    // Parts already in the index, so unchanged files can skip hashing
+    let known_parts = Arc::new(load_known_parts(&video_repo, index.id).await?);
+    
+    // Files below a folder that failed to scan weren't seen, so they must not be cleaned up,
+    // and the scan fails once the other folders are done so it is retried with backoff
+    let mut folder_error = None;
+    
     // Process each folder
-    for folder_path in folders {
+    for folder_path in &folders {
+        progress.checkpoint().await?;
         println!("📂 Scanning folder: {}", folder_path);
+        progress.set_folder(folder_path);
         
-        match scan_folder_recursive(&folder_path, &video_repo, index.id, &mut temp_manager, &mut source_tracker).await {
-            Ok(video_count) => {
-                println!("✅ Found {} video(s) in folder: {}", video_count, folder_path);
+        let mut scan = FolderScan {
+            video_repo: &video_repo,
+            index_id: index.id,
+            known_parts: &known_parts,
+            temp_manager: &mut temp_manager,
+            source_tracker: &mut source_tracker,
+            progress,
+        };
+        match scan_folder_recursive(folder_path, &mut scan).await {
+            Ok((video_count, unchanged_count)) => {
+                println!("✅ Found {} video(s) in folder: {} ({} unchanged)", video_count, folder_path, unchanged_count);
                 total_videos += video_count;
+                total_unchanged += unchanged_count;
             }
+            Err(e) if e.is::<ScanCancelled>() => return Err(e),
             Err(e) => {
                 eprintln!("❌ Error scanning folder '{}': {}", folder_path, e);
+                progress.error();
+                folder_error.get_or_insert_with(|| format!("Folder '{}' could not be scanned: {}", folder_path, e));
                 // Continue with other folders even if one fails
             }
         }
         
         // Remove the folder we just processed from source path tracking
         // This prevents false conflicts when processing subsequent folders
-        source_tracker.remove_source_path(&folder_path);
+        source_tracker.remove_source_path(folder_path);
     }
     
-    println!("🎬 Total videos found: {}", total_videos);
+    println!("🎬 Total videos found: {} ({} unchanged, {} hashed)", total_videos, total_unchanged, total_videos - total_unchanged);
     
     // Process any remaining temporary files (for content without source paths)
     println!("📝 Processing remaining temporary files...");
     process_temp_files(&mut temp_manager, &video_repo, index.id, "").await?;
     
+    // A cancelled scan didn't see every file, so it must stop before cleanup removes unseen parts
+    progress.checkpoint().await?;
+    
     // Clean up deleted files from database
     println!("🧹 Cleaning up deleted files from database...");
-    let cleanup_result = cleanup_deleted_files(&video_repo, index.id, pre_scan_timestamp).await;
-    match cleanup_result {
-        Ok((deleted_parts, deleted_versions, deleted_items)) => {
-            println!("🗑️  Cleanup complete: {} parts, {} versions, {} items deleted", 
-                     deleted_parts, deleted_versions, deleted_items);
+    if let Err(reason) = check_cleanup_safe(&folders, folder_error.is_some()).await {
+        eprintln!("⚠️  Skipping cleanup of deleted files: {}", reason);
+    } else {
+        match cleanup_deleted_files(&video_repo, index.id, pre_scan_timestamp, None).await {
+            Ok((deleted_parts, deleted_versions, deleted_items)) => {
+                println!("🗑️  Cleanup complete: {} parts, {} versions, {} items deleted", 
+                         deleted_parts, deleted_versions, deleted_items);
+                progress.items_removed(deleted_parts);
+            }
+            Err(e) => {
+                eprintln!("❌ Error during cleanup: {}", e);
+                // Continue anyway - cleanup errors shouldn't stop the scan
+            }
         }
+    }
+    
+    // Probe new or changed files for technical details (codecs, resolution, duration)
+    match probe_index_parts(&video_repo, index.id).await {
+        Ok(0) => {}
+        Ok(probed_parts) => println!("🔬 Probed {} video part(s)", probed_parts),
+        Err(e) => {
+            eprintln!("❌ Error during media probing: {}", e);
+            // Continue anyway - probing errors shouldn't fail the scan
+        }
+    }
+    
+    // Link sidecar subtitles to their parts
+    match import_index_subtitles(&video_repo, index.id).await {
+        Ok(0) => {}
+        Ok(subtitle_count) => println!("💬 Stored {} subtitle(s)", subtitle_count),
+        Err(e) => {
+            eprintln!("❌ Error during subtitle discovery: {}", e);
+            // Continue anyway - subtitle errors shouldn't fail the scan
+        }
+    }
+    
+    // Import local NFO files, before metadata agents so they stay authoritative
+    match import_index_nfo(&video_repo, index.id).await {
+        Ok(0) => {}
+        Ok(imported_items) => println!("📄 Imported NFO metadata of {} item(s)", imported_items),
+        Err(e) => {
+            eprintln!("❌ Error during NFO import: {}", e);
+            // Continue anyway - unreadable NFO files shouldn't fail the scan
+        }
+    }
+    
+    // Import sidecar artwork and extract embedded cover art and thumbnails
+    match extract_index_artwork(&video_repo, index.id).await {
+        Ok(0) => {}
+        Ok(artwork_count) => println!("🖼️  Stored {} artwork image(s)", artwork_count),
         Err(e) => {
-            eprintln!("❌ Error during cleanup: {}", e);
-            // Continue anyway - cleanup errors shouldn't stop the scan
+            eprintln!("❌ Error during artwork extraction: {}", e);
+            // Continue anyway - artwork errors shouldn't fail the scan
+        }
+    }
+    
+    // Match new movies and shows with the configured metadata agents
+    match refresh_index_metadata(&video_repo, index.id).await {
+        Ok(0) => {}
+        Ok(matched_items) => println!("🏷️  Matched metadata of {} item(s)", matched_items),
+        Err(e) => {
+            eprintln!("❌ Error during metadata matching: {}", e);
+            // Continue anyway - provider errors shouldn't fail the scan
         }
     }
     
@@ -90,6 +213,10 @@ pub async fn scan_video_index(indexes_repo: &IndexesRepo, index: &crate::db::mod
     temp_manager.cleanup()?;
     println!("🧹 Cleaned up temporary files");
     
+    if let Some(folder_error) = folder_error {
+        return Err(anyhow::anyhow!(folder_error));
+    }
+    
     // Update status to done and set last_scanned_at to current time
     let now = chrono::Utc::now().timestamp();
     indexes_repo.update_scan_status_with_timestamp(index.id, "done".to_string(), Some(now)).await?;
@@ -99,14 +226,202 @@ pub async fn scan_video_index(indexes_repo: &IndexesRepo, index: &crate::db::mod
     Ok(())
 }
 
-/// Recursively scan a folder for video files using depth-first search
-async fn scan_folder_recursive(
-    folder_path: &str, 
-    video_repo: &VideoRepo, 
+/// Incrementally scan changed paths of an index (used by the filesystem watcher)
+/// Paths that still exist are (re)processed, so moved files go through the usual migration logic;
+/// parts under changed paths that were not seen again are removed afterwards
+pub async fn scan_video_paths(index: &crate::db::models::Index, changed_paths: &[PathBuf], app_state: &AppState) -> Result<(), anyhow::Error> {
+    let mut progress = ScanProgress::start(&app_state.db_pool, index.id, "incremental").await;
+    
+    let result = run_video_paths_scan(index, changed_paths, app_state, &mut progress).await;
+    if finish_scan(&mut progress, &result).await {
+        println!("🛑 Cancelled incremental scan for index '{}' (ID: {})", index.name, index.id);
+        return Ok(());
+    }
+    result
+}
+
+/// Incremental scan of changed paths, reporting progress as it goes
+async fn run_video_paths_scan(
+    index: &crate::db::models::Index,
+    changed_paths: &[PathBuf],
+    app_state: &AppState,
+    progress: &mut ScanProgress
+) -> Result<(), anyhow::Error> {
+    println!("👀 Incremental scan of {} changed path(s) in index '{}' (ID: {})", changed_paths.len(), index.name, index.id);
+    
+    let mut temp_manager = TempFileManager::new(index.id)?;
+    temp_manager.cleanup_existing_files()?;
+    let mut source_tracker = SourcePathTracker::new();
+    let video_repo = VideoRepo::new(app_state.db_pool.clone());
+    let known_parts = Arc::new(load_known_parts(&video_repo, index.id).await?);
+    
+    let pre_scan_timestamp = chrono::Utc::now().timestamp();
+    let mut total_videos = 0;
+    let mut total_unchanged = 0;
+    // A changed path that failed to scan wasn't seen, so its parts must not be cleaned up
+    let mut path_failed = false;
+    
+    // Process existing paths first, so renames are found by hash before their old paths are cleaned up
+    for path in changed_paths.iter().filter(|path| path.exists()) {
+        progress.checkpoint().await?;
+        let path_str = path.to_string_lossy().to_string();
+        
+        if path.is_dir() {
+            println!("📂 Scanning changed folder: {}", path_str);
+            progress.set_folder(&path_str);
+            let mut scan = FolderScan {
+                video_repo: &video_repo,
+                index_id: index.id,
+                known_parts: &known_parts,
+                temp_manager: &mut temp_manager,
+                source_tracker: &mut source_tracker,
+                progress,
+            };
+            match scan_folder_recursive(&path_str, &mut scan).await {
+                Ok((video_count, unchanged_count)) => {
+                    total_videos += video_count;
+                    total_unchanged += unchanged_count;
+                }
+                Err(e) if e.is::<ScanCancelled>() => return Err(e),
+                Err(e) => {
+                    eprintln!("❌ Error scanning folder '{}': {}", path_str, e);
+                    progress.error();
+                    path_failed = true;
+                }
+            }
+        } else if is_video_file(path) {
+            println!("🎥 Scanning changed file: {}", path_str);
+            progress.file_discovered();
+            let result = scan_changed_file(path, &video_repo, index.id, &known_parts, &mut temp_manager, &mut source_tracker).await;
+            progress.file_processed();
+            match result {
+                Ok((outcome, skipped_hashing)) => {
+                    total_videos += 1;
+                    if skipped_hashing {
+                        total_unchanged += 1;
+                    }
+                    record_file_outcome(progress, outcome);
+                }
+                Err(e) => {
+                    eprintln!("❌ Failed to process video file {}: {}", path_str, e);
+                    progress.error();
+                    path_failed = true;
+                }
+            }
+            progress.report().await;
+        } else {
+            continue;
+        }
+        
+        // The changed path may sit below its source path (e.g. a new season folder or a single episode),
+        // so complete the tracked source path here; extras attach to the item owning a parent folder
+        let source_path = match source_tracker.get_source_path().cloned() {
+            Some(source_path) => {
+                source_tracker.remove_source_path(&source_path);
+                source_path
+            }
+            None => find_owning_source_path(&video_repo, index.id, path).await?.unwrap_or_default(),
+        };
+        process_temp_files(&mut temp_manager, &video_repo, index.id, &source_path).await?;
+    }
+    
+    println!("🎬 Videos found: {} ({} unchanged, {} hashed)", total_videos, total_unchanged, total_videos - total_unchanged);
+    progress.checkpoint().await?;
+    
+    // Remove parts under the changed paths that weren't seen during this scan (deleted, moved away or replaced)
+    if let Err(reason) = check_cleanup_safe(&index.folders(), path_failed).await {
+        eprintln!("⚠️  Skipping cleanup of deleted files: {}", reason);
+    } else {
+        match cleanup_deleted_files(&video_repo, index.id, pre_scan_timestamp, Some(changed_paths)).await {
+            Ok((0, _, _)) => {}
+            Ok((deleted_parts, deleted_versions, deleted_items)) => {
+                println!("🗑️  Cleanup complete: {} parts, {} versions, {} items deleted", deleted_parts, deleted_versions, deleted_items);
+                progress.items_removed(deleted_parts);
+            }
+            Err(e) => eprintln!("❌ Error during cleanup: {}", e),
+        }
+    }
+    
+    // Probe new or changed files for technical details (codecs, resolution, duration)
+    match probe_index_parts(&video_repo, index.id).await {
+        Ok(0) => {}
+        Ok(probed_parts) => println!("🔬 Probed {} video part(s)", probed_parts),
+        Err(e) => eprintln!("❌ Error during media probing: {}", e),
+    }
+    
+    match import_index_subtitles(&video_repo, index.id).await {
+        Ok(0) => {}
+        Ok(subtitle_count) => println!("💬 Stored {} subtitle(s)", subtitle_count),
+        Err(e) => eprintln!("❌ Error during subtitle discovery: {}", e),
+    }
+    
+    match import_index_nfo(&video_repo, index.id).await {
+        Ok(0) => {}
+        Ok(imported_items) => println!("📄 Imported NFO metadata of {} item(s)", imported_items),
+        Err(e) => eprintln!("❌ Error during NFO import: {}", e),
+    }
+    
+    match extract_index_artwork(&video_repo, index.id).await {
+        Ok(0) => {}
+        Ok(artwork_count) => println!("🖼️  Stored {} artwork image(s)", artwork_count),
+        Err(e) => eprintln!("❌ Error during artwork extraction: {}", e),
+    }
+    
+    match refresh_index_metadata(&video_repo, index.id).await {
+        Ok(0) => {}
+        Ok(matched_items) => println!("🏷️  Matched metadata of {} item(s)", matched_items),
+        Err(e) => eprintln!("❌ Error during metadata matching: {}", e),
+    }
+    
+    temp_manager.cleanup()?;
+    println!("✅ Completed incremental scan for index '{}' (ID: {})", index.name, index.id);
+    
+    Ok(())
+}
+
+/// Scan a single changed video file
+/// Returns the outcome and whether hashing was skipped, so only fast path files count as unchanged like in folder scans
+async fn scan_changed_file(
+    path: &Path,
+    video_repo: &VideoRepo,
     index_id: i64,
+    known_parts: &Arc<KnownParts>,
     temp_manager: &mut TempFileManager,
     source_tracker: &mut SourcePathTracker
-) -> Result<usize, anyhow::Error> {
+) -> Result<(FileOutcome, bool), anyhow::Error> {
+    let prepare_path = path.to_path_buf();
+    let prepare_known_parts = Arc::clone(known_parts);
+    let prepared = tokio::task::spawn_blocking(move || prepare_video_file(prepare_path, &prepare_known_parts)).await??;
+    let skipped_hashing = matches!(prepared.status, FileStatus::Unchanged { .. });
+    let outcome = process_video_file(prepared, video_repo, index_id, temp_manager, source_tracker).await?;
+    Ok((outcome, skipped_hashing))
+}
+
+/// Find the source path of an existing item that a path belongs to, by walking up its folders
+async fn find_owning_source_path(video_repo: &VideoRepo, index_id: i64, path: &Path) -> Result<Option<String>, anyhow::Error> {
+    for ancestor in path.ancestors() {
+        let ancestor_str = ancestor.to_string_lossy();
+        if !video_repo.get_video_items_by_source_path(index_id, &ancestor_str).await?.is_empty() {
+            return Ok(Some(ancestor_str.to_string()));
+        }
+    }
+    Ok(None)
+}
+
+/// What a folder scan works with, shared by its stages
+struct FolderScan<'a> {
+    video_repo: &'a VideoRepo,
+    index_id: i64,
+    known_parts: &'a Arc<KnownParts>,
+    temp_manager: &'a mut TempFileManager,
+    source_tracker: &'a mut SourcePathTracker,
+    progress: &'a mut ScanProgress,
+}
+
+/// Recursively scan a folder for video files using depth-first search
+/// Walking and hashing run ahead on blocking threads while files are processed here in walk order
+/// Returns (video_count, unchanged_count) where unchanged videos took the fast path
+async fn scan_folder_recursive(folder_path: &str, scan: &mut FolderScan<'_>) -> Result<(usize, usize), anyhow::Error> {
     let path = Path::new(folder_path);
     
     if !path.exists() {
@@ -117,133 +432,287 @@ async fn scan_folder_recursive(
         return Err(anyhow::anyhow!("Path is not a directory: {}", folder_path));
     }
     
-    let mut video_count = 0;
-    let mut dirs_to_process = vec![path.to_path_buf()];
-    
-    // Video file extensions to look for
-    let video_extensions = [
-        "mp4", "mkv", "avi", "mov", "wmv", "flv", "ts", "m2ts", "webm", "mpeg", "mpg"
-    ];
-    
-    // Process files in folder order before going deeper (breadth-first for files, then depth-first for dirs)
-    while let Some(current_dir) = dirs_to_process.pop() {
-        if current_dir.to_string_lossy().starts_with("REMOVE_FROM_TRACKER:") {
-            let path_str = current_dir.to_string_lossy();
-            let path_to_remove = path_str.strip_prefix("REMOVE_FROM_TRACKER:").unwrap();
-            
-            // If we successfully removed a source path, process temporary files
-            if source_tracker.remove_source_path(path_to_remove) {
-                println!("📝 Processing temporary files for completed source path: {}", path_to_remove);
-                process_temp_files(temp_manager, video_repo, index_id, path_to_remove).await?;
-                println!("✅ Temporary files processed for completed source path: {}", path_to_remove);
-            }
-            continue;
+    // Walk stage: emits files and finished folders in depth-first order
+    let (entry_sender, mut entry_receiver) = mpsc::channel::<WalkEntry>(WALK_CHANNEL_CAPACITY);
+    let root = path.to_path_buf();
+    let walker = tokio::task::spawn_blocking(move || {
+        walk_folder(&root, is_video_file, |entry| entry_sender.blocking_send(entry).is_ok())
+    });
+    
+    let counts = process_walk_entries(&mut entry_receiver, scan).await?;
+    walker.await??;
+    
+    Ok(counts)
+}
+
+/// A walk entry whose file is being prepared on the blocking pool
+enum PendingEntry {
+    File(PathBuf, JoinHandle<Result<PreparedFile, anyhow::Error>>),
+    FolderDone(String),
+}
+
+impl PendingEntry {
+    /// Whether the entry can be processed without waiting
+    fn is_ready(&self) -> bool {
+        match self {
+            PendingEntry::File(_, handle) => handle.is_finished(),
+            PendingEntry::FolderDone(_) => true,
         }
-        let mut entries = tokio::fs::read_dir(&current_dir).await?;
-        let mut subdirs = Vec::new();
-        
-        // First pass: collect all entries and separate files from directories
-        let mut file_entries = Vec::new();
-        while let Some(entry) = entries.next_entry().await? {
-            let entry_path = entry.path();
+    }
+    
+    /// Wait until the file of the entry is prepared
+    async fn ready(self) -> Result<ReadyEntry, anyhow::Error> {
+        Ok(match self {
+            PendingEntry::File(path, handle) => ReadyEntry::File(path, handle.await?),
+            PendingEntry::FolderDone(folder) => ReadyEntry::FolderDone(folder),
+        })
+    }
+}
+
+/// A walk entry ready for the database stage
+enum ReadyEntry {
+    File(PathBuf, Result<PreparedFile, anyhow::Error>),
+    FolderDone(String),
+}
+
+/// Walk entries between the folder walk and the database stage, with up to `scan_concurrency()` files hashing ahead
+struct WalkQueue<'a> {
+    entry_receiver: &'a mut mpsc::Receiver<WalkEntry>,
+    pending: VecDeque<PendingEntry>,
+    files_in_flight: usize,
+    concurrency: usize,
+    walking: bool,
+}
+
+impl WalkQueue<'_> {
+    /// Start preparing files until the concurrency limit is reached
+    /// Only waits for the walk when `wait` is set, otherwise takes the entries already walked
+    async fn fill(&mut self, known_parts: &Arc<KnownParts>, progress: &mut ScanProgress, wait: bool) {
+        while self.walking && self.files_in_flight < self.concurrency {
+            let entry = if wait {
+                self.entry_receiver.recv().await
+            } else {
+                match self.entry_receiver.try_recv() {
+                    Ok(entry) => Some(entry),
+                    Err(mpsc::error::TryRecvError::Empty) => return,
+                    Err(mpsc::error::TryRecvError::Disconnected) => None,
+                }
+            };
             
-            if entry_path.is_dir() {
-                subdirs.push(entry_path);
-            } else if entry_path.is_file() {
-                file_entries.push(entry_path);
+            match entry {
+                Some(WalkEntry::File(file_path)) => {
+                    progress.file_discovered();
+                    let known_parts = Arc::clone(known_parts);
+                    let prepare_path = file_path.clone();
+                    let handle = tokio::task::spawn_blocking(move || prepare_video_file(prepare_path, &known_parts));
+                    self.pending.push_back(PendingEntry::File(file_path, handle));
+                    self.files_in_flight += 1;
+                }
+                Some(WalkEntry::FolderDone(folder)) => self.pending.push_back(PendingEntry::FolderDone(folder)),
+                None => self.walking = false,
             }
         }
+    }
+    
+    /// Take the next entry in walk order, waiting for its file to be prepared
+    async fn next(&mut self) -> Result<Option<ReadyEntry>, anyhow::Error> {
+        let entry = match self.pending.pop_front() {
+            Some(entry) => entry,
+            None => return Ok(None),
+        };
+        if let PendingEntry::File(..) = entry {
+            self.files_in_flight -= 1;
+        }
+        entry.ready().await.map(Some)
+    }
+    
+    /// Take the next entry in walk order if it can be processed without waiting
+    async fn next_ready(&mut self) -> Result<Option<ReadyEntry>, anyhow::Error> {
+        if !self.pending.front().is_some_and(PendingEntry::is_ready) {
+            return Ok(None);
+        }
+        self.next().await
+    }
+}
+
+/// Consume walk entries in order, writing the files that are prepared in batched transactions
+/// A transaction is never held while waiting for the walk, hashing or a pause, so other writers aren't held up
+async fn process_walk_entries(entry_receiver: &mut mpsc::Receiver<WalkEntry>, scan: &mut FolderScan<'_>) -> Result<(usize, usize), anyhow::Error> {
+    let mut queue = WalkQueue {
+        entry_receiver,
+        pending: VecDeque::new(),
+        files_in_flight: 0,
+        concurrency: scan_concurrency(),
+        walking: true,
+    };
+    let mut video_count = 0;
+    let mut unchanged_count = 0;
+    
+    loop {
+        // Pausing holds the walk back too, since it blocks once the channel is full
+        scan.progress.checkpoint().await?;
         
-        // Process all files in the current directory first
-        for entry_path in file_entries {
-            // Check if it's a video file
-            if let Some(extension) = entry_path.extension() {
-                if let Some(ext_str) = extension.to_str() {
-                    let ext_lower = ext_str.to_lowercase();
-                    if video_extensions.contains(&ext_lower.as_str()) {
-                        // Classify the video file
-                        let classified = classify_path(entry_path.to_string_lossy().as_ref());
-                        println!("🎥 {} -> {:?}", 
-                            entry_path.file_name().unwrap_or_default().to_string_lossy(),
-                            classified.media_type
-                        );
+        // Hashing stage
+        queue.fill(scan.known_parts, scan.progress, true).await;
+        let mut next_entry = match queue.next().await? {
+            Some(entry) => entry,
+            None => break,
+        };
+        
+        // Database stage: the entries that are ready are written in one transaction
+        let batch_repo = scan.video_repo.begin().await?;
+        let mut batched = 0;
+        let result: Result<(), anyhow::Error> = async {
+            loop {
+                match next_entry {
+                    ReadyEntry::FolderDone(path_to_remove) => {
+                        // If we successfully removed a source path, process temporary files
+                        if scan.source_tracker.remove_source_path(&path_to_remove) {
+                            println!("📝 Processing temporary files for completed source path: {}", path_to_remove);
+                            process_temp_files(scan.temp_manager, &batch_repo, scan.index_id, &path_to_remove).await?;
+                            println!("✅ Temporary files processed for completed source path: {}", path_to_remove);
+                        }
+                    }
+                    ReadyEntry::File(entry_path, prepared) => {
+                        scan.progress.file_processed();
+                        if let Some(folder) = entry_path.parent() {
+                            scan.progress.set_folder(&folder.to_string_lossy());
+                        }
                         
-                        match classified.media_type {
-                            MediaType::Extra => {
-                                if let Some(extra) = classified.extra {
-                                    println!("   🎬 Extra: {}", extra.path);
-                                }
-                            }
-                            MediaType::TvEpisode => {
-                                if let Some(tv) = classified.tv_episode {
-                                    println!("   📺 TV: {} S{}E{} (year: {:?})", 
-                                        tv.show_name,
-                                        tv.season,
-                                        tv.episode,
-                                        tv.year.map_or("None".to_string(), |y| y.to_string())
-                                    );
-                                }
-                            }
-                            MediaType::Movie => {
-                                if let Some(movie) = classified.movie {
-                                    println!("   📽️  Movie: {} ({})", 
-                                        movie.title, 
-                                        movie.year.map_or("Unknown".to_string(), |y| y.to_string())
-                                    );
-                                }
+                        match prepared {
+                            // Unchanged parts are only marked as seen
+                            Ok(PreparedFile { status: FileStatus::Unchanged { part_id }, .. }) => {
+                                batch_repo.update_video_part_updated_at(part_id).await?;
+                                video_count += 1;
+                                unchanged_count += 1;
                             }
-                            MediaType::Generic => {
-                                if let Some(generic) = classified.generic {
-                                    println!("   📄 Generic: {}", generic.title);
+                            Ok(prepared) => {
+                                // Classification stage
+                                log_classification(&entry_path);
+                                
+                                // Process the video file with temporary file system
+                                match process_video_file(prepared, &batch_repo, scan.index_id, scan.temp_manager, scan.source_tracker).await {
+                                    Ok(outcome) => {
+                                        println!("🎥 {}", entry_path.display());
+                                        video_count += 1;
+                                        record_file_outcome(scan.progress, outcome);
+                                    }
+                                    Err(e) => {
+                                        eprintln!("❌ Failed to process video file {}: {}", entry_path.display(), e);
+                                        scan.progress.error();
+                                    }
                                 }
                             }
-                        }
-                        // Process the video file with temporary file system
-                        match process_video_file(&entry_path, video_repo, index_id, temp_manager, source_tracker).await {
-                            Ok(()) => {
-                                println!("🎥 {}", entry_path.display());
-                                video_count += 1;
-                            }
                             Err(e) => {
                                 eprintln!("❌ Failed to process video file {}: {}", entry_path.display(), e);
+                                scan.progress.error();
                             }
                         }
                     }
                 }
+                
+                batched += 1;
+                if batched >= SCAN_DB_BATCH_SIZE {
+                    return Ok(());
+                }
+                // Keep the hashing stage busy without waiting for the walk
+                queue.fill(scan.known_parts, scan.progress, false).await;
+                next_entry = match queue.next_ready().await? {
+                    Some(entry) => entry,
+                    None => return Ok(()),
+                };
             }
-        }
-        
-        // Push removal signal FIRST so it gets processed LAST (LIFO stack)
-        // This ensures we remove the source path after processing all subdirectories
-        let removal_signal = format!("REMOVE_FROM_TRACKER:{}", current_dir.to_string_lossy());
-        dirs_to_process.push(PathBuf::from(removal_signal));
+        }.await;
         
-        // Then add subdirectories to stack for later processing
-        for subdir in subdirs {
-            dirs_to_process.push(subdir);
-        }
+        // A failed batch is rolled back when its repo is dropped; the folder then fails the scan, so cleanup is skipped
+        result?;
+        batch_repo.commit().await?;
+        scan.progress.report().await;
     }
     
-    Ok(video_count)
+    Ok((video_count, unchanged_count))
 }
 
-/// Process a single video file and either update database or add to temporary files
+/// Count the outcome of a processed video file in the scan progress
+fn record_file_outcome(progress: &mut ScanProgress, outcome: FileOutcome) {
+    match outcome {
+        FileOutcome::Unchanged => {}
+        FileOutcome::Added => progress.item_added(),
+        FileOutcome::Updated => progress.item_updated(),
+    }
+}
+
+/// Print how a new or changed video file is classified
+fn log_classification(entry_path: &Path) {
+    let classified = classify_path(entry_path.to_string_lossy().as_ref());
+    println!("🎥 {} -> {:?}", 
+        entry_path.file_name().unwrap_or_default().to_string_lossy(),
+        classified.media_type
+    );
+    
+    match classified.media_type {
+        MediaType::Extra => {
+            if let Some(extra) = classified.extra {
+                println!("   🎬 Extra: {}", extra.path);
+            }
+        }
+        MediaType::TvEpisode => {
+            if let Some(tv) = classified.tv_episode {
+                println!("   📺 TV: {} S{}E{} (year: {:?})", 
+                    tv.show_name,
+                    tv.season,
+                    tv.episode,
+                    tv.year.map_or("None".to_string(), |y| y.to_string())
+                );
+            }
+        }
+        MediaType::Movie => {
+            if let Some(movie) = classified.movie {
+                println!("   📽️  Movie: {} ({})", 
+                    movie.title, 
+                    movie.year.map_or("Unknown".to_string(), |y| y.to_string())
+                );
+            }
+        }
+        MediaType::Generic => {
+            if let Some(generic) = classified.generic {
+                println!("   📄 Generic: {}", generic.title);
+            }
+        }
+    }
+}
+
+/// What processing a video file did to the index
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum FileOutcome {
+    /// Same file as in the last scan
+    Unchanged,
+    /// New file (added now or queued in temporary files)
+    Added,
+    /// Moved or changed file of an existing part
+    Updated,
+}
+
+/// Process a single prepared video file and either update database or add to temporary files
 async fn process_video_file(
-    file_path: &Path, 
+    prepared: PreparedFile, 
     video_repo: &VideoRepo, 
     index_id: i64,
     temp_manager: &mut TempFileManager,
     source_tracker: &mut SourcePathTracker
-) -> Result<(), anyhow::Error> {
-    // Get file metadata
-    let metadata = file_path.metadata()?;
-    let file_size = metadata.len() as i64;
-    let mtime = metadata.modified()?.duration_since(std::time::UNIX_EPOCH)?.as_secs() as i64;
-    
-    // Calculate fast hash
-    let fast_hash = calculate_fast_hash(file_path).await?;
+) -> Result<FileOutcome, anyhow::Error> {
+    let PreparedFile { path, size: file_size, mtime, status } = prepared;
+    let fast_hash = match status {
+        FileStatus::Unchanged { part_id } => {
+            video_repo.update_video_part_updated_at(part_id).await?;
+            return Ok(FileOutcome::Unchanged);
+        }
+        FileStatus::Changed { fast_hash } => fast_hash,
+    };
     
+    let file_path = path.as_path();
     let file_path_str = file_path.to_string_lossy().to_string();
+    let part_at_path = video_repo.get_video_part_by_path(&file_path_str).await?;
     
     // Check if video_part exists with same size + fast_hash
     let existing_parts = video_repo.get_video_parts_by_size_and_hash(file_size, &fast_hash).await?;
@@ -251,8 +720,9 @@ async fn process_video_file(
     if let Some(existing_part) = existing_parts.first() {
         // Video part exists, check if path is the same
         if existing_part.path == file_path_str {
-            // Same path, just update updated_at
-            video_repo.update_video_part_updated_at(existing_part.id).await?;
+            // Same path and content, refresh mtime so the next scan takes the fast path
+            video_repo.update_video_part_path(existing_part.id, file_path_str, mtime).await?;
+            return Ok(FileOutcome::Unchanged);
         } else {
             // Different path - check if this is a source path change that requires migration
             let classified = classify_path(&file_path_str);
@@ -307,7 +777,13 @@ async fn process_video_file(
             // Update path and updated_at
             video_repo.update_video_part_path(existing_part.id, file_path_str, mtime).await?;
         }
-        return Ok(());
+        return Ok(FileOutcome::Updated);
+    }
+    
+    // File at a known path was replaced with new content, keep the part and store its new signature
+    if let Some(existing_part) = part_at_path {
+        video_repo.update_video_part_file(existing_part.id, file_size, mtime, fast_hash).await?;
+        return Ok(FileOutcome::Updated);
     }
     
     // Video part doesn't exist, classify the file
@@ -325,7 +801,7 @@ async fn process_video_file(
             };
             temp_manager.add_extra(temp_extra)?;
         }
-        return Ok(());
+        return Ok(FileOutcome::Added);
     }
     
     // Handle movies, TV episodes, and generic content
@@ -380,7 +856,7 @@ async fn process_video_file(
         } else {
             return Err(anyhow::anyhow!("Movie without source_path found within source_path structure"));
         }
-        return Ok(());
+        return Ok(FileOutcome::Added);
     }
     
     // Add to temporary files for later processing
@@ -396,7 +872,7 @@ async fn process_video_file(
     };
     temp_manager.add_new_content(temp_item)?;
     
-    Ok(())
+    Ok(FileOutcome::Added)
 }
 
 /// Check if a movie matches its folder name (ignoring case, spaces, and dots)
@@ -1115,12 +1591,29 @@ async fn move_video_part_to_item(
     Ok(())
 }
 
+/// Check that removing the parts that weren't seen can't wipe files that are only out of reach
+/// An unplugged disk or a dropped network share makes every file below it look deleted, so
+/// cleanup only runs when all folders of the index can be read and every scanned path was walked
+async fn check_cleanup_safe(folders: &[String], scan_failed: bool) -> Result<(), String> {
+    if scan_failed {
+        return Err("a path failed to scan".to_string());
+    }
+    for folder in folders {
+        if let Err(e) = tokio::fs::read_dir(folder).await {
+            return Err(format!("folder '{}' can't be read ({})", folder, e));
+        }
+    }
+    Ok(())
+}
+
 /// Clean up deleted files from the database
+/// Only parts at or below `paths` are checked when given (the changed paths of an incremental scan)
 /// Returns (deleted_parts_count, deleted_versions_count, deleted_items_count)
 async fn cleanup_deleted_files(
     video_repo: &VideoRepo, 
     index_id: i64, 
-    pre_scan_timestamp: i64
+    pre_scan_timestamp: i64,
+    paths: Option<&[PathBuf]>
 ) -> Result<(usize, usize, usize), anyhow::Error> {
     let mut deleted_parts = 0;
     let mut deleted_versions = 0;
@@ -1128,6 +1621,46 @@ async fn cleanup_deleted_files(
     
     // Get all video items for this index
     let video_items = video_repo.get_video_items_by_index(index_id).await?;
+    let parent_ids: HashMap<i64, Option<i64>> = video_items.iter().map(|item| (item.id, item.parent_id)).collect();
+    
+    // Parts in scope, and the items owning them along with their parents, which may be left empty
+    let (scoped_part_ids, scoped_item_ids) = match paths {
+        Some(paths) => {
+            let mut part_ids = HashSet::new();
+            let mut version_ids = HashSet::new();
+            for path in paths {
+                for video_part in video_repo.get_video_parts_under_path(index_id, &path.to_string_lossy()).await? {
+                    part_ids.insert(video_part.id);
+                    version_ids.insert(video_part.version_id);
+                }
+            }
+            let mut item_ids = HashSet::new();
+            for version_id in version_ids {
+                if let Some(video_version) = video_repo.get_video_version_by_id(version_id).await? {
+                    let mut item_id = Some(video_version.item_id);
+                    while let Some(id) = item_id.filter(|id| item_ids.insert(*id)) {
+                        item_id = parent_ids.get(&id).copied().flatten();
+                    }
+                }
+            }
+            (Some(part_ids), Some(item_ids))
+        }
+        None => (None, None),
+    };
+    
+    // Children first, so parents left without children are deleted in the same pass
+    let depth = |mut id: i64| {
+        let mut depth = 0;
+        while let Some(parent_id) = parent_ids.get(&id).copied().flatten() {
+            depth += 1;
+            id = parent_id;
+        }
+        depth
+    };
+    let mut video_items: Vec<_> = video_items.into_iter()
+        .filter(|item| scoped_item_ids.as_ref().is_none_or(|item_ids| item_ids.contains(&item.id)))
+        .collect();
+    video_items.sort_by_key(|item| std::cmp::Reverse(depth(item.id)));
     
     for video_item in video_items {
         // Get all video versions for this item
@@ -1139,7 +1672,8 @@ async fn cleanup_deleted_files(
             
             // Check each video part
             for video_part in video_parts {
-                if video_part.updated_at < pre_scan_timestamp {
+                let in_scope = scoped_part_ids.as_ref().is_none_or(|part_ids| part_ids.contains(&video_part.id));
+                if in_scope && video_part.updated_at < pre_scan_timestamp {
                     // This part wasn't updated during scanning, so it was deleted
                     println!("🗑️  Deleting video part: {}", video_part.path);
                     video_repo.delete_video_part(video_part.id).await?;
@@ -1168,3 +1702,53 @@ async fn cleanup_deleted_files(
     
     Ok((deleted_parts, deleted_versions, deleted_items))
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::DataDir;
+    use crate::db::migrations::run_migrations;
+    use crate::db::pool::connect_pool;
+    
+    #[tokio::test]
+    async fn test_scan_changed_file_counts_only_fast_path_as_unchanged() {
+        let data_dir = std::env::temp_dir().join(format!("video_scanning_test_{}", uuid::Uuid::new_v4()));
+        let movie_folder = data_dir.join("Movies").join("Heat (1995)");
+        std::fs::create_dir_all(&movie_folder).unwrap();
+        let movie_path = movie_folder.join("Heat (1995).mkv");
+        std::fs::write(&movie_path, vec![7u8; 4096]).unwrap();
+        
+        let db_path = data_dir.join("app.sqlite3");
+        let pool = connect_pool(&db_path).await.unwrap();
+        run_migrations(&pool, &db_path).await.unwrap();
+        let app_state = AppState::new(Arc::new(DataDir(data_dir.clone())), pool.clone());
+        let indexes_repo = IndexesRepo::new(pool.clone());
+        let video_repo = VideoRepo::new(pool.clone());
+        
+        let metadata = serde_json::json!({ "folders": [data_dir.join("Movies").to_string_lossy()] });
+        let index_id = indexes_repo.add_index("Movies".to_string(), "videos".to_string(), None, metadata).await.unwrap();
+        let index = indexes_repo.get_index_by_id(index_id).await.unwrap().unwrap();
+        scan_video_paths(&index, std::slice::from_ref(&movie_path), &app_state).await.unwrap();
+        assert!(video_repo.get_video_part_by_path(&movie_path.to_string_lossy()).await.unwrap().is_some());
+        
+        // Touched but identical file is hashed, so it must not count as unchanged
+        let touched = std::time::SystemTime::now() + std::time::Duration::from_secs(60);
+        std::fs::File::options().write(true).open(&movie_path).unwrap().set_modified(touched).unwrap();
+        let mut temp_manager = TempFileManager::new(index_id).unwrap();
+        let mut source_tracker = SourcePathTracker::new();
+        let known_parts = Arc::new(load_known_parts(&video_repo, index_id).await.unwrap());
+        let (outcome, skipped_hashing) = scan_changed_file(&movie_path, &video_repo, index_id, &known_parts, &mut temp_manager, &mut source_tracker).await.unwrap();
+        assert_eq!(outcome, FileOutcome::Unchanged);
+        assert!(!skipped_hashing);
+        
+        // The refreshed mtime lets the next scan take the fast path
+        let known_parts = Arc::new(load_known_parts(&video_repo, index_id).await.unwrap());
+        let (outcome, skipped_hashing) = scan_changed_file(&movie_path, &video_repo, index_id, &known_parts, &mut temp_manager, &mut source_tracker).await.unwrap();
+        assert_eq!(outcome, FileOutcome::Unchanged);
+        assert!(skipped_hashing);
+        
+        temp_manager.cleanup().unwrap();
+        pool.close().await;
+        std::fs::remove_dir_all(&data_dir).unwrap();
+    }
+}
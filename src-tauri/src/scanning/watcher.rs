@@ -0,0 +1,200 @@
+//! Filesystem watcher for real-time incremental rescans
+//!
+//! Watches every folder of every video index and, once changes settle down,
+//! runs an incremental scan of just the affected paths.
+
+use crate::api::state::AppState;
+use crate::constants::{WATCHER_DEBOUNCE_SECS, WATCHER_MAX_DELAY_SECS, WATCHER_REFRESH_INTERVAL_SECS};
+use crate::db::repos::IndexesRepo;
+use crate::scanning::video_scanning::{is_video_file, scan_video_paths};
+use crate::utils::subtitles::is_subtitle_file;
+use notify::event::ModifyKind;
+use notify::{Event, EventKind, RecommendedWatcher, RecursiveMode, Watcher};
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use tokio::sync::mpsc;
+use tokio::time::Instant;
+
+/// Background process that watches index folders and rescans changed paths
+pub async fn start_watcher_process(app_state: AppState) {
+    println!("👀 Starting filesystem watcher...");
+
+    let (event_sender, mut event_receiver) = mpsc::unbounded_channel::<notify::Result<Event>>();
+    let mut watcher = match notify::recommended_watcher(move |result| {
+        let _ = event_sender.send(result);
+    }) {
+        Ok(watcher) => watcher,
+        Err(e) => {
+            eprintln!("❌ Failed to start filesystem watcher, changes will only be picked up by full scans: {}", e);
+            return;
+        }
+    };
+
+    let mut watched_folders: HashSet<PathBuf> = HashSet::new();
+    let mut pending_paths: HashSet<PathBuf> = HashSet::new();
+    let mut first_event_at: Option<Instant> = None;
+    let mut last_event_at = Instant::now();
+    let mut refresh_interval = tokio::time::interval(Duration::from_secs(WATCHER_REFRESH_INTERVAL_SECS));
+
+    loop {
+        // Scan once events have been quiet for the debounce period, or have been pending too long
+        let scan_deadline = match first_event_at {
+            Some(first_event_at) => (last_event_at + Duration::from_secs(WATCHER_DEBOUNCE_SECS))
+                .min(first_event_at + Duration::from_secs(WATCHER_MAX_DELAY_SECS)),
+            None => Instant::now() + Duration::from_secs(WATCHER_REFRESH_INTERVAL_SECS),
+        };
+
+        tokio::select! {
+            _ = refresh_interval.tick() => {
+                if let Err(e) = sync_watched_folders(&app_state, &mut watcher, &mut watched_folders).await {
+                    eprintln!("❌ Failed to update watched folders: {}", e);
+                }
+            }
+            result = event_receiver.recv() => {
+                match result {
+                    Some(Ok(event)) => {
+                        if !is_relevant_event(&event) {
+                            continue;
+                        }
+                        let paths: Vec<PathBuf> = event.paths.into_iter().filter(|path| is_relevant_path(path)).collect();
+                        if paths.is_empty() {
+                            continue;
+                        }
+                        pending_paths.extend(paths);
+                        last_event_at = Instant::now();
+                        first_event_at.get_or_insert(last_event_at);
+                    }
+                    Some(Err(e)) => eprintln!("❌ Filesystem watcher error: {}", e),
+                    None => return,
+                }
+            }
+            _ = tokio::time::sleep_until(scan_deadline), if first_event_at.is_some() => {
+                first_event_at = None;
+                let changed_paths: Vec<PathBuf> = pending_paths.drain().collect();
+                if let Err(e) = scan_changed_paths(&app_state, &changed_paths).await {
+                    eprintln!("❌ Failed to scan changed paths: {}", e);
+                }
+            }
+        }
+    }
+}
+
+/// Watch the folders of all video indexes and stop watching folders that were removed
+async fn sync_watched_folders(
+    app_state: &AppState,
+    watcher: &mut RecommendedWatcher,
+    watched_folders: &mut HashSet<PathBuf>
+) -> Result<(), anyhow::Error> {
+    let indexes_repo = IndexesRepo::new(app_state.db_pool.clone());
+    let configured_folders: HashSet<PathBuf> = indexes_repo.get_indexes_by_type("videos").await?
+        .iter()
+        .flat_map(|index| index.folders())
+        .map(PathBuf::from)
+        .filter(|folder| folder.is_dir())
+        .collect();
+
+    for folder in watched_folders.difference(&configured_folders).cloned().collect::<Vec<_>>() {
+        // The folder may already be gone, in which case the OS dropped the watch itself
+        let _ = watcher.unwatch(&folder);
+        watched_folders.remove(&folder);
+        println!("👀 Stopped watching folder: {}", folder.display());
+    }
+
+    for folder in configured_folders {
+        if watched_folders.contains(&folder) {
+            continue;
+        }
+        match watcher.watch(&folder, RecursiveMode::Recursive) {
+            Ok(()) => {
+                println!("👀 Watching folder: {}", folder.display());
+                watched_folders.insert(folder);
+            }
+            Err(e) => eprintln!("❌ Failed to watch folder '{}': {}", folder.display(), e),
+        }
+    }
+
+    Ok(())
+}
+
+/// Start an incremental scan for every video index containing some of the changed paths
+/// Each runs in the background, so a paused scan of one index doesn't hold up the others
+/// (scans of the same index still run one after the other)
+async fn scan_changed_paths(app_state: &AppState, changed_paths: &[PathBuf]) -> Result<(), anyhow::Error> {
+    let indexes_repo = IndexesRepo::new(app_state.db_pool.clone());
+
+    for index in indexes_repo.get_indexes_by_type("videos").await? {
+        let index_paths = paths_in_folders(changed_paths, &index.folders());
+        if index_paths.is_empty() {
+            continue;
+        }
+
+        let app_state = app_state.clone();
+        tokio::spawn(async move {
+            if let Err(e) = scan_video_paths(&index, &index_paths, &app_state).await {
+                eprintln!("❌ Incremental scan failed for index '{}' (ID: {}): {}", index.name, index.id, e);
+            }
+        });
+    }
+
+    Ok(())
+}
+
+/// Only changes to content, creations, removals and renames can affect the library
+fn is_relevant_event(event: &Event) -> bool {
+    matches!(
+        event.kind,
+        EventKind::Any
+            | EventKind::Create(_)
+            | EventKind::Remove(_)
+            | EventKind::Modify(ModifyKind::Any)
+            | EventKind::Modify(ModifyKind::Data(_))
+            | EventKind::Modify(ModifyKind::Name(_))
+    )
+}
+
+/// Folders and paths that no longer exist (possibly removed folders) are always relevant,
+/// existing files only when they are videos or sidecar subtitles
+fn is_relevant_path(path: &Path) -> bool {
+    !path.is_file() || is_video_file(path) || is_subtitle_file(path)
+}
+
+/// Get the changed paths inside any of the folders, dropping paths already covered by a changed parent folder
+fn paths_in_folders(changed_paths: &[PathBuf], folders: &[String]) -> Vec<PathBuf> {
+    let mut paths: Vec<PathBuf> = changed_paths
+        .iter()
+        .filter(|path| folders.iter().any(|folder| path.starts_with(folder)))
+        .cloned()
+        .collect();
+    paths.sort();
+    paths.dedup();
+
+    let all_paths = paths.clone();
+    paths.retain(|path| !all_paths.iter().any(|other| other != path && path.starts_with(other)));
+    paths
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_paths_in_folders() {
+        let folders = vec!["/media/tv".to_string(), "/media/movies".to_string()];
+        let changed_paths = vec![
+            PathBuf::from("/media/tv/Show/Season 2"),
+            PathBuf::from("/media/tv/Show/Season 2/Show S02E01.mkv"),
+            PathBuf::from("/media/movies/Movie (2020)/Movie (2020).mkv"),
+            PathBuf::from("/media/movies/Movie (2020)/Movie (2020).mkv"),
+            PathBuf::from("/media/tvshows/Other/Other S01E01.mkv"),
+            PathBuf::from("/downloads/file.mkv"),
+        ];
+
+        let paths = paths_in_folders(&changed_paths, &folders);
+
+        assert_eq!(paths, vec![
+            PathBuf::from("/media/movies/Movie (2020)/Movie (2020).mkv"),
+            PathBuf::from("/media/tv/Show/Season 2"),
+        ]);
+    }
+}
@@ -1,13 +1,25 @@
 use crate::api::state::AppState;
-use crate::db::repos::IndexesRepo;
+use crate::constants::{SCAN_IDLE_AFTER_SECS, SCAN_MAX_ATTEMPTS, SCAN_RETRY_BASE_SECS, SCAN_RETRY_MAX_SECS};
+use crate::db::models::Index;
+use crate::db::repos::{IndexesRepo, ScanRunsRepo};
+use crate::scanning::schedule::is_scan_due;
+use crate::scanning::music_scanning::scan_music_index;
+use crate::scanning::photo_scanning::scan_photo_index;
+use crate::scanning::progress::{is_scan_paused, subscribe_scan_events};
 use crate::scanning::video_scanning::scan_video_index;
+use crate::utils::activity::is_playback_idle;
 use std::time::Duration;
+use tokio::sync::broadcast;
 use tokio::time::sleep;
 
 /// Background scanning process that continuously scans indexes
 pub async fn start_scanning_process(app_state: AppState) {
     println!("🔍 Starting background scanning process...");
     
+    if let Err(e) = recover_interrupted_scans(&app_state).await {
+        eprintln!("Error recovering interrupted scans: {}", e);
+    }
+    
     loop {
         let scanned = match process_scanning_cycle(&app_state).await {
             Ok(scanned) => scanned,
@@ -27,71 +39,218 @@ pub async fn start_scanning_process(app_state: AppState) {
     }
 }
 
-/// Process one scanning cycle: check for scanning/queued indexes and scan them
-async fn process_scanning_cycle(app_state: &AppState) -> Result<bool, anyhow::Error> {
+/// Indexes still marked as scanning were interrupted by a crash or shutdown of the previous session
+/// They are marked as failed, so they are retried with backoff like any failed scan and a scan
+/// that brings the server down is not restarted forever
+async fn recover_interrupted_scans(app_state: &AppState) -> Result<(), anyhow::Error> {
     let indexes_repo = IndexesRepo::new(app_state.db_pool.clone());
+    let scan_runs_repo = ScanRunsRepo::new(app_state.db_pool.clone());
     
-    // First, check if there are any indexes with status "scanning" (recovery from crash)
-    let scanning_indexes = indexes_repo.get_indexes_by_scan_status("scanning").await?;
+    // Runs started from now on belong to this session (e.g. incremental scans of the watcher)
+    let session_started_at = chrono::Utc::now().timestamp();
+    scan_runs_repo.interrupt_running_scan_runs(session_started_at).await?;
     
+    let scanning_indexes = indexes_repo.get_indexes_by_scan_status("scanning").await?;
     if !scanning_indexes.is_empty() {
         println!("🔄 Found {} index(es) with 'scanning' status - recovering from previous session", scanning_indexes.len());
-        
-        for index in scanning_indexes {
-            println!("🔄 Restarting scan for index '{}' (ID: {})", index.name, index.id);
-            match index.r#type.as_str() {
-                "videos" => {
-                    scan_video_index(&indexes_repo, &index, app_state).await?;
+    }
+    
+    for index in scanning_indexes {
+        println!("🔄 Scan of index '{}' (ID: {}) was interrupted, it will be retried", index.name, index.id);
+        indexes_repo.update_scan_status(index.id, "failed".to_string()).await?;
+    }
+    
+    Ok(())
+}
+
+/// Process one scanning cycle: scan the next queued index, or retry a failed one once its backoff expired
+async fn process_scanning_cycle(app_state: &AppState) -> Result<bool, anyhow::Error> {
+    let indexes_repo = IndexesRepo::new(app_state.db_pool.clone());
+    let scan_runs_repo = ScanRunsRepo::new(app_state.db_pool.clone());
+    
+    queue_scheduled_indexes(&indexes_repo).await?;
+    
+    // Queued indexes come first, starting with the oldest last_scanned_at
+    let queued_indexes = indexes_repo.get_indexes_by_scan_status("queued").await?;
+    let next_index = match queued_indexes.into_iter().min_by_key(|index| index.last_scanned_at) {
+        Some(index) => {
+            println!("📋 Found queued index '{}' (ID: {}) with oldest last_scanned_at: {}",
+                     index.name, index.id, index.last_scanned_at);
+            index
+        }
+        None => match find_index_to_retry(&indexes_repo, &scan_runs_repo).await? {
+            Some(index) => index,
+            None => {
+                println!("📭 No queued indexes found");
+                return Ok(false);
+            }
+        },
+    };
+    
+    run_scan(next_index, app_state).await
+}
+
+/// Scan an index in the background, waiting until the scan finishes or is paused
+/// A paused scan gives up its turn, so the next index is scanned meanwhile and the paused
+/// scan carries on by itself once resumed
+async fn run_scan(index: Index, app_state: &AppState) -> Result<bool, anyhow::Error> {
+    let index_id = index.id;
+    // Subscribe before the scan starts so its pause can't be missed
+    let mut scan_events = subscribe_scan_events();
+    let app_state = app_state.clone();
+    let mut scan = tokio::spawn(async move {
+        let indexes_repo = IndexesRepo::new(app_state.db_pool.clone());
+        let scan_runs_repo = ScanRunsRepo::new(app_state.db_pool.clone());
+        scan_index(&indexes_repo, &scan_runs_repo, &index, &app_state).await
+    });
+
+    loop {
+        tokio::select! {
+            result = &mut scan => return result?,
+            event = scan_events.recv() => match event {
+                Ok(scan_job) if scan_job.index_id == index_id && scan_job.status == "paused" => {
+                    println!("⏸️  Scan of index {} is paused, moving on to other indexes", index_id);
+                    return Ok(true);
                 }
-                _ => {
-                    println!("⚠️  Index type '{}' not supported yet for index '{}' (ID: {})", index.r#type, index.name, index.id);
-                    indexes_repo.update_scan_status(index.id, "failed".to_string()).await?;
+                Ok(_) => {}
+                // The pause may have been among the skipped updates
+                Err(broadcast::error::RecvError::Lagged(_)) if is_scan_paused(index_id) => {
+                    println!("⏸️  Scan of index {} is paused, moving on to other indexes", index_id);
+                    return Ok(true);
                 }
-            }
+                Err(broadcast::error::RecvError::Lagged(_)) => {}
+                Err(broadcast::error::RecvError::Closed) => return scan.await?,
+            },
         }
-        return Ok(true);
     }
+}
+
+/// Queue the scanned indexes whose rescan schedule is due
+async fn queue_scheduled_indexes(indexes_repo: &IndexesRepo) -> Result<(), anyhow::Error> {
+    let now = chrono::Local::now().naive_local();
+    let idle = is_playback_idle(SCAN_IDLE_AFTER_SECS);
     
-    // No scanning indexes found, check for queued indexes
-    let queued_indexes = indexes_repo.get_indexes_by_scan_status("queued").await?;
-    
-    if queued_indexes.is_empty() {
-        println!("📭 No queued indexes found");
-        return Ok(false);
+    for index in indexes_repo.get_indexes_by_scan_status("done").await? {
+        let last_scanned_at = index.last_scanned_at_datetime()
+            .map(|last_scanned_at| last_scanned_at.with_timezone(&chrono::Local).naive_local());
+        
+        if is_scan_due(&index.scan_schedule(), last_scanned_at, now, idle) {
+            println!("⏰ Scheduled rescan of index '{}' (ID: {}) is due", index.name, index.id);
+            indexes_repo.update_scan_status(index.id, "queued".to_string()).await?;
+        }
     }
     
-    // Find the queued index with the oldest last_scanned_at
-    let oldest_index = queued_indexes
-        .into_iter()
-        .min_by_key(|index| index.last_scanned_at)
-        .expect("At least one queued index should exist");
+    Ok(())
+}
+
+/// Find a failed index whose automatic retry is due
+async fn find_index_to_retry(indexes_repo: &IndexesRepo, scan_runs_repo: &ScanRunsRepo) -> Result<Option<Index>, anyhow::Error> {
+    let now = chrono::Utc::now().timestamp();
     
-    println!("📋 Found queued index '{}' (ID: {}) with oldest last_scanned_at: {}", 
-             oldest_index.name, oldest_index.id, oldest_index.last_scanned_at);
+    for index in indexes_repo.get_indexes_by_scan_status("failed").await? {
+        let (failures, last_failed_at) = scan_runs_repo.get_consecutive_failures(index.id).await?;
+        // Indexes without failed runs failed for another reason (e.g. unsupported type)
+        if failures == 0 || failures >= SCAN_MAX_ATTEMPTS {
+            continue;
+        }
+        
+        if now >= last_failed_at.unwrap_or(0) + retry_delay_secs(failures) {
+            println!("🔁 Retrying scan of index '{}' (ID: {}) after {} failed attempt(s)", index.name, index.id, failures);
+            return Ok(Some(index));
+        }
+    }
     
+    Ok(None)
+}
+
+/// Delay before retrying a scan that failed the given number of times in a row
+fn retry_delay_secs(failures: i64) -> i64 {
+    let exponent = (failures - 1).clamp(0, 30) as u32;
+    SCAN_RETRY_BASE_SECS.saturating_mul(1 << exponent).min(SCAN_RETRY_MAX_SECS)
+}
+
+/// Scan an index, marking it as failed when the scan fails
+async fn scan_index(indexes_repo: &IndexesRepo, scan_runs_repo: &ScanRunsRepo, index: &Index, app_state: &AppState) -> Result<bool, anyhow::Error> {
     // Set status to scanning
-    indexes_repo.update_scan_status(oldest_index.id, "scanning".to_string()).await?;
-    
-    // Scan the index
-    match oldest_index.r#type.as_str() {
-        "videos" => {
-            if let Err(e) = scan_video_index(&indexes_repo, &oldest_index, app_state).await {
-                eprintln!("❌ Failed to scan index '{}' (ID: {}): {}", oldest_index.name, oldest_index.id, e);
-                // Reset status back to failed so it can be retried later
-                if let Err(reset_err) = indexes_repo.update_scan_status(oldest_index.id, "failed".to_string()).await {
-                    eprintln!("❌ Failed to reset scan status for index '{}' (ID: {}): {}", oldest_index.name, oldest_index.id, reset_err);
-                }
-                return Ok(false); // Return false so we wait before trying again
-            }
-        }
+    indexes_repo.update_scan_status(index.id, "scanning".to_string()).await?;
+    
+    let result = match index.r#type.as_str() {
+        "videos" => scan_video_index(indexes_repo, index, app_state).await,
+        "photos" => scan_photo_index(indexes_repo, index, app_state).await,
+        "audio" => scan_music_index(indexes_repo, index, app_state).await,
         _ => {
-            println!("⚠️  Index type '{}' not supported yet for index '{}' (ID: {})", oldest_index.r#type, oldest_index.name, oldest_index.id);
-            if let Err(e) = indexes_repo.update_scan_status(oldest_index.id, "failed".to_string()).await {
-                eprintln!("❌ Failed to set scan status to 'failed' for index '{}' (ID: {}): {}", oldest_index.name, oldest_index.id, e);
+            println!("⚠️  Index type '{}' not supported yet for index '{}' (ID: {})", index.r#type, index.name, index.id);
+            if let Err(e) = indexes_repo.update_scan_status(index.id, "failed".to_string()).await {
+                eprintln!("❌ Failed to set scan status to 'failed' for index '{}' (ID: {}): {}", index.name, index.id, e);
             }
             return Ok(false); // Return false so we wait before trying again
         }
+    };
+    
+    if let Err(e) = result {
+        eprintln!("❌ Failed to scan index '{}' (ID: {}): {}", index.name, index.id, e);
+        // Set status to failed so it is retried with backoff
+        if let Err(reset_err) = indexes_repo.update_scan_status(index.id, "failed".to_string()).await {
+            eprintln!("❌ Failed to reset scan status for index '{}' (ID: {}): {}", index.name, index.id, reset_err);
+        }
+        
+        let (failures, _) = scan_runs_repo.get_consecutive_failures(index.id).await?;
+        if failures >= SCAN_MAX_ATTEMPTS {
+            eprintln!("❌ Scan of index '{}' (ID: {}) failed {} times in a row, not retrying until it is queued again",
+                      index.name, index.id, failures);
+        } else {
+            println!("🔁 Retrying scan of index '{}' (ID: {}) in {} seconds", index.name, index.id, retry_delay_secs(failures));
+        }
+        return Ok(false); // Return false so we wait before trying again
     }
     
     Ok(true)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::DataDir;
+    use crate::db::migrations::run_migrations;
+    use crate::db::pool::connect_pool;
+    use std::sync::Arc;
+    
+    #[tokio::test]
+    async fn test_scan_of_missing_folder_fails() {
+        let data_dir = std::env::temp_dir().join(format!("scanning_process_test_{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&data_dir).unwrap();
+        let db_path = data_dir.join("app.sqlite3");
+        let pool = connect_pool(&db_path).await.unwrap();
+        run_migrations(&pool, &db_path).await.unwrap();
+        let app_state = AppState::new(Arc::new(DataDir(data_dir.clone())), pool.clone());
+        let indexes_repo = IndexesRepo::new(pool.clone());
+        let scan_runs_repo = ScanRunsRepo::new(pool.clone());
+        
+        let missing_folder = data_dir.join("unplugged");
+        let metadata = serde_json::json!({ "folders": [missing_folder.to_string_lossy()] });
+        let index_id = indexes_repo.add_index("Movies".to_string(), "videos".to_string(), None, metadata).await.unwrap();
+        let index = indexes_repo.get_index_by_id(index_id).await.unwrap().unwrap();
+        
+        assert!(!scan_index(&indexes_repo, &scan_runs_repo, &index, &app_state).await.unwrap());
+        
+        let runs = scan_runs_repo.get_scan_runs(index_id, 10).await.unwrap();
+        assert_eq!(runs.len(), 1);
+        assert_eq!(runs[0].outcome, "failed");
+        assert!(runs[0].error_message.as_deref().is_some_and(|message| message.contains("unplugged")));
+        assert_eq!(scan_runs_repo.get_consecutive_failures(index_id).await.unwrap().0, 1);
+        let index = indexes_repo.get_index_by_id(index_id).await.unwrap().unwrap();
+        assert_eq!(index.scan_status, "failed");
+        assert!(index.last_scanned_at_datetime().is_none());
+        
+        pool.close().await;
+        std::fs::remove_dir_all(&data_dir).unwrap();
+    }
+    
+    #[test]
+    fn test_retry_delay_secs() {
+        assert_eq!(retry_delay_secs(1), SCAN_RETRY_BASE_SECS);
+        assert_eq!(retry_delay_secs(2), SCAN_RETRY_BASE_SECS * 2);
+        assert_eq!(retry_delay_secs(4), SCAN_RETRY_BASE_SECS * 8);
+        assert_eq!(retry_delay_secs(100), SCAN_RETRY_MAX_SECS);
+    }
+}
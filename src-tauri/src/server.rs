@@ -0,0 +1,109 @@
+//! Startup shared by the desktop app and the headless server
+
+use crate::api::state::AppState;
+use crate::api::controllers;
+use crate::config::{self, PathProvider};
+use crate::{backup, db, scanning, scanning_process, transcoding, utils};
+use sqlx::SqlitePool;
+use std::net::IpAddr;
+use std::sync::Arc;
+
+/// Open the database and initialize the controllers of the HTTPS server
+/// A restore queued by the previous session is moved into place first, then the schema is migrated
+pub async fn init_server(paths: Arc<dyn PathProvider>) -> anyhow::Result<SqlitePool> {
+    if let Some(restored) = backup::apply_pending_restore(&config::get_app_data_dir(paths.as_ref())?).await? {
+        println!("💾 Restored a backup of server version {} (schema version {})", restored.server_version, restored.schema_version);
+    }
+
+    let db_path = config::sqlite_path(paths.as_ref())?;
+    let db_pool = db::pool::connect_pool(&db_path).await?;
+    db::migrations::run_migrations(&db_pool, &db_path).await?;
+
+    // Initialize token repository
+    utils::token::init_token_repo(db_pool.clone());
+
+    // Initialize icon and auth paths for HTTPS server
+    controllers::icon::init_icon_paths(paths.clone());
+    controllers::auth::init_auth_paths(paths.clone());
+
+    // Initialize auth database pool for HTTPS server
+    controllers::auth::init_auth_db_pool(db_pool.clone());
+
+    // Initialize stream database pool for HTTPS server
+    controllers::stream::init_stream_db_pool(db_pool.clone());
+
+    // Initialize media database pool for HTTPS server
+    controllers::media::init_media_db_pool(db_pool.clone());
+
+    // Initialize transcode database pool and session manager for HTTPS server
+    controllers::transcode::init_transcode_db_pool(db_pool.clone());
+    transcoding::init_transcode_manager(config::transcode_cache_dir(paths.as_ref())?);
+
+    // Initialize photos database pool and thumbnails directory for HTTPS server
+    controllers::photos::init_photos_db_pool(db_pool.clone());
+    utils::image::init_thumbnails_dir(config::thumbnails_dir(paths.as_ref())?);
+
+    // Initialize music database pool for HTTPS server
+    controllers::music::init_music_db_pool(db_pool.clone());
+
+    // Initialize watch state database pool for HTTPS server
+    controllers::watch::init_watch_db_pool(db_pool.clone());
+
+    Ok(db_pool)
+}
+
+/// Start the HTTP and HTTPS servers and the background processes
+/// Must be called from within the Tokio runtime
+pub fn start_server_tasks(app_state: AppState, http_host: IpAddr, http_port: u16, startup_token: String) -> anyhow::Result<()> {
+    // Start HTTP server for browser communication and static file serving
+    let app_state_http = app_state.clone();
+    tokio::spawn(async move {
+        if let Err(e) = crate::start_http_server(http_host, http_port, app_state_http, startup_token).await {
+            eprintln!("Failed to start HTTP server: {}", e);
+        }
+    });
+
+    // Start HTTPS server for network access
+    let app_state_https = app_state.clone();
+    tokio::spawn(async move {
+        match crate::start_https_server(app_state_https).await {
+            Ok(port) => {
+                println!("✅ HTTPS server started successfully on port {}", port);
+            }
+            Err(e) => {
+                eprintln!("Failed to start HTTPS server: {}", e);
+            }
+        }
+    });
+
+    // Start background scanning process
+    let app_state_scanning = app_state.clone();
+    tokio::spawn(async move {
+        scanning_process::start_scanning_process(app_state_scanning).await;
+    });
+
+    // Start filesystem watcher for incremental rescans of changed paths
+    let app_state_watcher = app_state.clone();
+    tokio::spawn(async move {
+        scanning::start_watcher_process(app_state_watcher).await;
+    });
+
+    // Start background pruning of expired client tokens
+    tokio::spawn(async move {
+        utils::token::start_token_pruning_process().await;
+    });
+
+    // Start background cleanup of idle transcode sessions
+    tokio::spawn(async move {
+        transcoding::start_transcode_cleanup_process().await;
+    });
+
+    // Start automatic backups of the database, config and icons
+    let db_pool_backup = app_state.db_pool.clone();
+    let data_dir_backup = config::get_app_data_dir(app_state.paths.as_ref())?;
+    tokio::spawn(async move {
+        backup::start_backup_process(db_pool_backup, data_dir_backup).await;
+    });
+
+    Ok(())
+}
@@ -0,0 +1,91 @@
+use crate::constants::HLS_SEGMENT_DURATION_SECS;
+
+/// Name of the variant playlist served for a transcode session
+pub const VARIANT_PLAYLIST_NAME: &str = "stream.m3u8";
+
+/// Name of the master playlist served for a transcode session
+pub const MASTER_PLAYLIST_NAME: &str = "master.m3u8";
+
+/// Get the file name of a segment written by ffmpeg
+pub fn segment_file_name(index: u64) -> String {
+    format!("segment_{:05}.ts", index)
+}
+
+/// Parse a segment index from a requested file name ("segment_00012.ts")
+/// Anything else is rejected so requests can never point outside the session directory
+pub fn parse_segment_name(name: &str) -> Option<u64> {
+    let index = name.strip_prefix("segment_")?;
+    let index = index.strip_suffix(".ts")?;
+    if index.is_empty() || !index.chars().all(|c| c.is_ascii_digit()) {
+        return None;
+    }
+    index.parse::<u64>().ok()
+}
+
+/// Number of segments needed to cover the given duration
+pub fn segment_count(duration_ms: i64) -> u64 {
+    let segment_ms = HLS_SEGMENT_DURATION_SECS * 1000;
+    (duration_ms.max(0) as u64).div_ceil(segment_ms)
+}
+
+/// Build the master playlist pointing to the single variant of a session
+/// `query` is appended to URIs (e.g. "?key=...") so players without custom headers stay authorized
+pub fn build_master_playlist(bandwidth: u64, query: &str) -> String {
+    format!(
+        "#EXTM3U\n#EXT-X-VERSION:3\n#EXT-X-STREAM-INF:BANDWIDTH={},CODECS=\"avc1.640028,mp4a.40.2\"\n{}{}\n",
+        bandwidth, VARIANT_PLAYLIST_NAME, query
+    )
+}
+
+/// Build a complete VOD playlist for the whole duration
+/// Listing every segment up front lets players seek anywhere; unproduced segments are transcoded on request
+pub fn build_variant_playlist(duration_ms: i64, query: &str) -> String {
+    let segment_ms = (HLS_SEGMENT_DURATION_SECS * 1000) as i64;
+    let count = segment_count(duration_ms);
+
+    let mut playlist = format!(
+        "#EXTM3U\n#EXT-X-VERSION:3\n#EXT-X-TARGETDURATION:{}\n#EXT-X-MEDIA-SEQUENCE:0\n#EXT-X-PLAYLIST-TYPE:VOD\n",
+        HLS_SEGMENT_DURATION_SECS
+    );
+
+    for index in 0..count {
+        let remaining_ms = duration_ms - index as i64 * segment_ms;
+        let length_ms = remaining_ms.min(segment_ms);
+        playlist.push_str(&format!(
+            "#EXTINF:{:.3},\n{}{}\n",
+            length_ms as f64 / 1000.0,
+            segment_file_name(index),
+            query
+        ));
+    }
+
+    playlist.push_str("#EXT-X-ENDLIST\n");
+    playlist
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_segment_name() {
+        assert_eq!(parse_segment_name("segment_00012.ts"), Some(12));
+        assert_eq!(parse_segment_name("segment_3.ts"), Some(3));
+        assert_eq!(parse_segment_name("segment_3.m4s"), None);
+        assert_eq!(parse_segment_name("segment_.ts"), None);
+        assert_eq!(parse_segment_name("segment_-1.ts"), None);
+        assert_eq!(parse_segment_name("../segment_00001.ts"), None);
+        assert_eq!(parse_segment_name("stream.m3u8"), None);
+    }
+
+    #[test]
+    fn test_build_variant_playlist() {
+        let playlist = build_variant_playlist(13_500, "?key=abc");
+
+        assert_eq!(segment_count(13_500), 3);
+        assert!(playlist.contains("#EXTINF:6.000,\nsegment_00000.ts?key=abc\n"));
+        assert!(playlist.contains("#EXTINF:1.500,\nsegment_00002.ts?key=abc\n"));
+        assert!(!playlist.contains("segment_00003.ts"));
+        assert!(playlist.ends_with("#EXT-X-ENDLIST\n"));
+    }
+}
@@ -0,0 +1,5 @@
+pub mod hls;
+pub mod session;
+
+pub use hls::*;
+pub use session::*;
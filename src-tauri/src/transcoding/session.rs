@@ -0,0 +1,393 @@
+use crate::constants::{HLS_SEGMENT_DURATION_SECS, TRANSCODE_IDLE_TIMEOUT_SECS, TRANSCODE_CLEANUP_INTERVAL_SECS, TRANSCODE_MAX_SESSIONS};
+use crate::transcoding::hls::{segment_file_name, segment_count};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::process::Stdio;
+use std::sync::OnceLock;
+use std::time::{Duration, Instant};
+use tokio::process::{Child, Command};
+use tokio::sync::Mutex;
+
+/// Global transcode session manager
+static TRANSCODE_MANAGER: OnceLock<TranscodeManager> = OnceLock::new();
+
+/// Segments ahead of the encoder that are waited for instead of restarting ffmpeg at the requested position
+const MAX_SEGMENTS_AHEAD: u64 = 3;
+
+/// Maximum time a segment request waits for ffmpeg to produce the segment
+const SEGMENT_WAIT_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Error of starting a session while `TRANSCODE_MAX_SESSIONS` sessions are running
+#[derive(Debug)]
+pub struct TooManySessions;
+
+impl std::fmt::Display for TooManySessions {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Too many transcode sessions are running")
+    }
+}
+
+impl std::error::Error for TooManySessions {}
+
+/// Requested output of a transcode session
+#[derive(Debug, Clone)]
+pub struct TranscodeOptions {
+    /// Target video bitrate in kbps
+    pub video_bitrate_kbps: u32,
+    /// Maximum output height (the source is never upscaled)
+    pub max_height: Option<u32>,
+    /// Number of audio channels to downmix to (None keeps the source layout)
+    pub audio_channels: Option<u32>,
+}
+
+impl Default for TranscodeOptions {
+    fn default() -> Self {
+        Self {
+            video_bitrate_kbps: 4000,
+            max_height: None,
+            audio_channels: Some(2),
+        }
+    }
+}
+
+impl TranscodeOptions {
+    /// Audio bitrate used for the AAC output
+    pub fn audio_bitrate_kbps(&self) -> u32 {
+        match self.audio_channels {
+            Some(channels) if channels <= 2 => 192,
+            _ => 384,
+        }
+    }
+
+    /// Total bandwidth advertised in the master playlist (bits per second)
+    pub fn bandwidth(&self) -> u64 {
+        (self.video_bitrate_kbps as u64 + self.audio_bitrate_kbps() as u64) * 1000
+    }
+}
+
+/// A transcode session the client was handed
+pub struct StartedSession {
+    pub id: String,
+    /// Secret that authorizes requests for the playlists and segments of the session
+    pub key: String,
+}
+
+/// A running (or paused) HLS transcode of a single video part
+struct TranscodeSession {
+    /// Token of the client that started the session
+    owner_token: String,
+    /// Secret carried in the playlist and segment URIs instead of the client's token,
+    /// players fetch them without custom headers and playlists get cached and logged
+    key: String,
+    part_id: i64,
+    input: PathBuf,
+    dir: PathBuf,
+    options: TranscodeOptions,
+    duration_ms: i64,
+    process: Option<Child>,
+    start_segment: u64,
+    last_accessed: Instant,
+}
+
+impl TranscodeSession {
+    /// (Re)start ffmpeg so it produces segments beginning at `start_segment`
+    fn start_ffmpeg(&mut self, start_segment: u64) -> Result<(), anyhow::Error> {
+        self.stop_ffmpeg();
+
+        let child = Command::new(ffmpeg_binary())
+            .args(build_ffmpeg_args(&self.input, &self.dir, &self.options, start_segment))
+            .stdin(Stdio::null())
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .kill_on_drop(true)
+            .spawn()
+            .map_err(|e| anyhow::anyhow!("Failed to start ffmpeg: {}", e))?;
+
+        self.process = Some(child);
+        self.start_segment = start_segment;
+        Ok(())
+    }
+
+    /// Stop the running ffmpeg process, if any
+    fn stop_ffmpeg(&mut self) {
+        if let Some(mut process) = self.process.take() {
+            let _ = process.start_kill();
+        }
+    }
+
+    /// Check whether the ffmpeg process has exited
+    fn ffmpeg_exited(&mut self) -> bool {
+        match self.process.as_mut() {
+            Some(process) => !matches!(process.try_wait(), Ok(None)),
+            None => true,
+        }
+    }
+}
+
+/// Get a session if the key is the one it was started with
+fn authorized_session<'a>(sessions: &'a mut HashMap<String, TranscodeSession>, session_id: &str, key: &str) -> Option<&'a mut TranscodeSession> {
+    sessions.get_mut(session_id).filter(|session| session.key == key)
+}
+
+/// Check whether a segment has been written (unreadable directories count as not written)
+async fn segment_exists(segment_path: &Path) -> bool {
+    tokio::fs::try_exists(segment_path).await.unwrap_or(false)
+}
+
+/// Index of the first segment at or after `start_segment` that has not been written yet
+async fn next_pending_segment(dir: &Path, start_segment: u64) -> u64 {
+    let mut index = start_segment;
+    while segment_exists(&dir.join(segment_file_name(index))).await {
+        index += 1;
+    }
+    index
+}
+
+/// Manager of all transcode sessions
+pub struct TranscodeManager {
+    cache_dir: PathBuf,
+    sessions: Mutex<HashMap<String, TranscodeSession>>,
+}
+
+/// Initialize the global transcode manager, removing leftovers of previous runs
+pub fn init_transcode_manager(cache_dir: PathBuf) {
+    if let Ok(entries) = std::fs::read_dir(&cache_dir) {
+        for entry in entries.flatten() {
+            let _ = std::fs::remove_dir_all(entry.path());
+        }
+    }
+
+    let manager = TranscodeManager {
+        cache_dir,
+        sessions: Mutex::new(HashMap::new()),
+    };
+    if TRANSCODE_MANAGER.set(manager).is_err() {
+        panic!("Failed to initialize transcode manager");
+    }
+}
+
+/// Get the global transcode manager
+pub fn get_transcode_manager() -> Option<&'static TranscodeManager> {
+    TRANSCODE_MANAGER.get()
+}
+
+/// Get the ffmpeg binary to run (`FFMPEG_PATH` overrides the one on PATH)
+pub fn ffmpeg_binary() -> String {
+    std::env::var("FFMPEG_PATH").unwrap_or_else(|_| "ffmpeg".to_string())
+}
+
+/// Build the ffmpeg arguments for a software (CPU only) H.264/AAC HLS encode
+pub fn build_ffmpeg_args(input: &Path, dir: &Path, options: &TranscodeOptions, start_segment: u64) -> Vec<String> {
+    let start_secs = start_segment * HLS_SEGMENT_DURATION_SECS;
+    let video_bitrate = format!("{}k", options.video_bitrate_kbps);
+
+    let mut args: Vec<String> = vec![
+        "-hide_banner".into(), "-loglevel".into(), "error".into(), "-nostdin".into(),
+        "-ss".into(), start_secs.to_string(),
+        "-i".into(), input.to_string_lossy().to_string(),
+        "-map".into(), "0:v:0".into(),
+        "-map".into(), "0:a:0?".into(),
+        "-c:v".into(), "libx264".into(),
+        "-preset".into(), "veryfast".into(),
+        "-pix_fmt".into(), "yuv420p".into(),
+        "-b:v".into(), video_bitrate.clone(),
+        "-maxrate".into(), video_bitrate,
+        "-bufsize".into(), format!("{}k", options.video_bitrate_kbps * 2),
+        "-force_key_frames".into(), format!("expr:gte(t,n_forced*{})", HLS_SEGMENT_DURATION_SECS),
+    ];
+
+    if let Some(max_height) = options.max_height {
+        args.push("-vf".into());
+        args.push(format!("scale=-2:'min({},ih)'", max_height));
+    }
+
+    args.extend(["-c:a".to_string(), "aac".to_string(), "-b:a".to_string(), format!("{}k", options.audio_bitrate_kbps())]);
+    if let Some(channels) = options.audio_channels {
+        args.push("-ac".into());
+        args.push(channels.to_string());
+    }
+
+    // Keep timestamps continuous with the segments of previous runs
+    args.extend([
+        "-output_ts_offset".to_string(), start_secs.to_string(),
+        "-f".to_string(), "hls".to_string(),
+        "-hls_time".to_string(), HLS_SEGMENT_DURATION_SECS.to_string(),
+        "-hls_list_size".to_string(), "0".to_string(),
+        "-hls_playlist_type".to_string(), "event".to_string(),
+        // Segments are written to a temp file and renamed, so an existing segment is always complete
+        "-hls_flags".to_string(), "temp_file".to_string(),
+        "-start_number".to_string(), start_segment.to_string(),
+        "-hls_segment_filename".to_string(), dir.join("segment_%05d.ts").to_string_lossy().to_string(),
+        dir.join("ffmpeg.m3u8").to_string_lossy().to_string(),
+    ]);
+
+    args
+}
+
+impl TranscodeManager {
+    /// Start a new transcode session for a part and return its ID and key
+    /// A session the same client started for the part before is replaced (e.g. to change the quality),
+    /// fails with `TooManySessions` when `TRANSCODE_MAX_SESSIONS` other sessions are running
+    pub async fn start_session(&self, owner_token: &str, part_id: i64, input: PathBuf, duration_ms: i64, options: TranscodeOptions) -> Result<StartedSession, anyhow::Error> {
+        let session_id = uuid::Uuid::new_v4().simple().to_string();
+        let key = uuid::Uuid::new_v4().simple().to_string();
+        let dir = self.cache_dir.join(&session_id);
+        tokio::fs::create_dir_all(&dir).await?;
+
+        let mut session = TranscodeSession {
+            owner_token: owner_token.to_string(),
+            key: key.clone(),
+            part_id,
+            input,
+            dir,
+            options,
+            duration_ms,
+            process: None,
+            start_segment: 0,
+            last_accessed: Instant::now(),
+        };
+
+        let (result, replaced) = {
+            let mut sessions = self.sessions.lock().await;
+            let replaced_ids: Vec<String> = sessions.iter()
+                .filter(|(_, existing)| existing.owner_token == owner_token && existing.part_id == part_id)
+                .map(|(id, _)| id.clone())
+                .collect();
+            let replaced: Vec<TranscodeSession> = replaced_ids.iter().filter_map(|id| sessions.remove(id)).collect();
+
+            let result = if sessions.len() >= TRANSCODE_MAX_SESSIONS {
+                Err(TooManySessions.into())
+            } else {
+                session.start_ffmpeg(0)
+            };
+            if result.is_ok() {
+                println!("🎞️  Started transcode session {} for {}", session_id, session.input.display());
+                sessions.insert(session_id.clone(), session);
+            }
+            (result, replaced)
+        };
+
+        for mut replaced_session in replaced {
+            replaced_session.stop_ffmpeg();
+            let _ = tokio::fs::remove_dir_all(&replaced_session.dir).await;
+        }
+        if let Err(e) = result {
+            let _ = tokio::fs::remove_dir_all(self.cache_dir.join(&session_id)).await;
+            return Err(e);
+        }
+        Ok(StartedSession { id: session_id, key })
+    }
+
+    /// Get the duration and options of a session, marking it as active
+    /// Returns None if the session does not exist or the key doesn't match
+    pub async fn session_info(&self, session_id: &str, key: &str) -> Option<(i64, TranscodeOptions)> {
+        let mut sessions = self.sessions.lock().await;
+        let session = authorized_session(&mut sessions, session_id, key)?;
+        session.last_accessed = Instant::now();
+        Some((session.duration_ms, session.options.clone()))
+    }
+
+    /// Get the path of a segment, waiting for ffmpeg (and restarting it at the segment when seeking)
+    /// Returns None if the session or segment does not exist, or the key doesn't match
+    /// The segment files are checked with the sessions lock released, so other sessions aren't held up
+    pub async fn get_segment(&self, session_id: &str, key: &str, index: u64) -> Result<Option<PathBuf>, anyhow::Error> {
+        let deadline = Instant::now() + SEGMENT_WAIT_TIMEOUT;
+        let mut restarted = false;
+
+        loop {
+            let (dir, start_segment) = {
+                let mut sessions = self.sessions.lock().await;
+                let session = match authorized_session(&mut sessions, session_id, key) {
+                    Some(session) => session,
+                    None => return Ok(None),
+                };
+                session.last_accessed = Instant::now();
+
+                if index >= segment_count(session.duration_ms) {
+                    return Ok(None);
+                }
+                (session.dir.clone(), session.start_segment)
+            };
+
+            let segment_path = dir.join(segment_file_name(index));
+            if segment_exists(&segment_path).await {
+                return Ok(Some(segment_path));
+            }
+            let next_pending = next_pending_segment(&dir, start_segment).await;
+
+            {
+                let mut sessions = self.sessions.lock().await;
+                let session = match authorized_session(&mut sessions, session_id, key) {
+                    Some(session) => session,
+                    None => return Ok(None),
+                };
+
+                let exited = session.ffmpeg_exited();
+                let behind_encoder = index < session.start_segment;
+                // Another request may have restarted ffmpeg while the files were checked
+                let far_ahead = session.start_segment == start_segment && index > next_pending + MAX_SEGMENTS_AHEAD;
+
+                if behind_encoder || far_ahead || exited {
+                    if restarted && exited {
+                        return Err(anyhow::anyhow!("ffmpeg stopped before producing segment {}", index));
+                    }
+                    if !restarted {
+                        println!("⏩ Restarting transcode session {} at segment {}", session_id, index);
+                        session.start_ffmpeg(index)?;
+                        restarted = true;
+                    }
+                }
+            }
+
+            if Instant::now() >= deadline {
+                return Err(anyhow::anyhow!("Timed out waiting for segment {}", index));
+            }
+            tokio::time::sleep(Duration::from_millis(200)).await;
+        }
+    }
+
+    /// Stop a session and remove its files, returns false if it does not exist
+    pub async fn stop_session(&self, session_id: &str) -> bool {
+        let session = self.sessions.lock().await.remove(session_id);
+        match session {
+            Some(mut session) => {
+                session.stop_ffmpeg();
+                let _ = tokio::fs::remove_dir_all(&session.dir).await;
+                println!("🛑 Stopped transcode session {}", session_id);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Stop all sessions that have not been accessed within the idle timeout
+    pub async fn cleanup_idle_sessions(&self) -> usize {
+        let idle_timeout = Duration::from_secs(TRANSCODE_IDLE_TIMEOUT_SECS);
+        let idle_ids: Vec<String> = self.sessions.lock().await
+            .iter()
+            .filter(|(_, session)| session.last_accessed.elapsed() > idle_timeout)
+            .map(|(id, _)| id.clone())
+            .collect();
+
+        for session_id in &idle_ids {
+            self.stop_session(session_id).await;
+        }
+        idle_ids.len()
+    }
+}
+
+/// Background task that periodically stops idle transcode sessions
+pub async fn start_transcode_cleanup_process() {
+    let mut interval = tokio::time::interval(Duration::from_secs(TRANSCODE_CLEANUP_INTERVAL_SECS));
+
+    loop {
+        interval.tick().await;
+
+        if let Some(manager) = get_transcode_manager() {
+            let stopped = manager.cleanup_idle_sessions().await;
+            if stopped > 0 {
+                println!("🧹 Cleaned up {} idle transcode session(s)", stopped);
+            }
+        }
+    }
+}
@@ -0,0 +1,14 @@
+use std::sync::atomic::{AtomicI64, Ordering};
+
+/// Unix timestamp of the last playback request (0 when nothing was played yet)
+static LAST_PLAYBACK_AT: AtomicI64 = AtomicI64::new(0);
+
+/// Record that a client is playing media (direct streams and transcode requests)
+pub fn record_playback_activity() {
+    LAST_PLAYBACK_AT.store(chrono::Utc::now().timestamp(), Ordering::Relaxed);
+}
+
+/// Check whether nothing has been played for the given number of seconds
+pub fn is_playback_idle(idle_after_secs: i64) -> bool {
+    chrono::Utc::now().timestamp() - LAST_PLAYBACK_AT.load(Ordering::Relaxed) >= idle_after_secs
+}
@@ -0,0 +1,142 @@
+use std::fs::File;
+use std::path::Path;
+use symphonia::core::formats::FormatOptions;
+use symphonia::core::io::MediaSourceStream;
+use symphonia::core::meta::{MetadataOptions, MetadataRevision, StandardTagKey, StandardVisualKey};
+use symphonia::core::probe::Hint;
+
+/// Information read from the tags (ID3v2, Vorbis comments, FLAC, MP4) and stream of a music file
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct AudioTags {
+    pub title: Option<String>,
+    pub artist: Option<String>,
+    pub album_artist: Option<String>,
+    pub album: Option<String>,
+    pub sort_title: Option<String>,
+    pub sort_artist: Option<String>,
+    pub sort_album: Option<String>,
+    pub track_number: Option<i64>,
+    pub disc_number: Option<i64>,
+    pub year: Option<i64>,
+    pub genre: Option<String>,
+    pub duration_ms: Option<i64>,
+    pub codec: Option<String>,
+    pub sample_rate: Option<i64>,
+    pub channels: Option<i64>,
+    /// Embedded cover art, preferring the front cover
+    pub cover: Option<Vec<u8>>,
+}
+
+/// Read the tags, stream information and embedded cover art of a music file
+/// This does blocking file IO, so call it from a blocking thread
+pub fn read_audio_tags(path: &Path) -> Result<AudioTags, anyhow::Error> {
+    let source = MediaSourceStream::new(Box::new(File::open(path)?), Default::default());
+    let mut hint = Hint::new();
+    if let Some(extension) = path.extension().and_then(|ext| ext.to_str()) {
+        hint.with_extension(extension);
+    }
+
+    let mut probed = symphonia::default::get_probe()
+        .format(&hint, source, &FormatOptions::default(), &MetadataOptions::default())?;
+
+    let mut tags = AudioTags::default();
+
+    // ID3v2 tags are read before the container, the container tags take precedence
+    if let Some(revision) = probed.metadata.get().as_ref().and_then(|metadata| metadata.current()) {
+        apply_metadata_revision(&mut tags, revision);
+    }
+    if let Some(revision) = probed.format.metadata().current() {
+        apply_metadata_revision(&mut tags, revision);
+    }
+
+    if let Some(track) = probed.format.default_track() {
+        let params = &track.codec_params;
+        tags.codec = symphonia::default::get_codecs()
+            .get_codec(params.codec)
+            .map(|descriptor| descriptor.short_name.to_string());
+        tags.sample_rate = params.sample_rate.map(|sample_rate| sample_rate as i64);
+        tags.channels = params.channels.map(|channels| channels.count() as i64);
+        tags.duration_ms = match (params.time_base, params.n_frames) {
+            (Some(time_base), Some(n_frames)) => {
+                let time = time_base.calc_time(n_frames);
+                Some((time.seconds as f64 * 1000.0 + time.frac * 1000.0) as i64)
+            }
+            _ => None,
+        };
+    }
+
+    Ok(tags)
+}
+
+/// Copy the tags of a metadata revision, overwriting the ones already read
+fn apply_metadata_revision(tags: &mut AudioTags, revision: &MetadataRevision) {
+    for tag in revision.tags() {
+        // Some formats (e.g. RIFF INFO) keep the NUL terminators of their strings
+        let value = tag.value.to_string().trim_matches(|c: char| c.is_whitespace() || c == '\0').to_string();
+        if value.is_empty() {
+            continue;
+        }
+
+        match tag.std_key {
+            Some(StandardTagKey::TrackTitle) => tags.title = Some(value),
+            Some(StandardTagKey::Artist) => tags.artist = Some(value),
+            Some(StandardTagKey::AlbumArtist) => tags.album_artist = Some(value),
+            Some(StandardTagKey::Album) => tags.album = Some(value),
+            Some(StandardTagKey::SortTrackTitle) => tags.sort_title = Some(value),
+            // The album artist sort key wins over the track artist one, like the album artist itself
+            Some(StandardTagKey::SortAlbumArtist) => tags.sort_artist = Some(value),
+            Some(StandardTagKey::SortArtist) => tags.sort_artist = tags.sort_artist.take().or(Some(value)),
+            Some(StandardTagKey::SortAlbum) => tags.sort_album = Some(value),
+            Some(StandardTagKey::TrackNumber) => tags.track_number = parse_tag_number(&value).or(tags.track_number),
+            Some(StandardTagKey::DiscNumber) => tags.disc_number = parse_tag_number(&value).or(tags.disc_number),
+            Some(StandardTagKey::Date) | Some(StandardTagKey::ReleaseDate) | Some(StandardTagKey::OriginalDate) => {
+                // The first date found wins, so the release date isn't replaced by the original date
+                tags.year = tags.year.or_else(|| parse_tag_year(&value));
+            }
+            Some(StandardTagKey::Genre) => tags.genre = Some(value),
+            _ => {}
+        }
+    }
+
+    let front_cover = revision.visuals().iter()
+        .find(|visual| visual.usage == Some(StandardVisualKey::FrontCover))
+        .or_else(|| revision.visuals().first());
+    if let Some(visual) = front_cover {
+        tags.cover = Some(visual.data.to_vec());
+    }
+}
+
+/// Parse a track or disc number tag ("3", "03/12")
+pub fn parse_tag_number(value: &str) -> Option<i64> {
+    value.split('/').next()?.trim().parse::<i64>().ok().filter(|number| *number > 0)
+}
+
+/// Parse the year of a date tag ("2004", "2004-05-01", "2004-05-01T00:00:00Z")
+pub fn parse_tag_year(value: &str) -> Option<i64> {
+    let year: String = value.trim().chars().take_while(|c| c.is_ascii_digit()).collect();
+    if year.len() != 4 {
+        return None;
+    }
+    year.parse::<i64>().ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_tag_number() {
+        assert_eq!(parse_tag_number("3"), Some(3));
+        assert_eq!(parse_tag_number("03/12"), Some(3));
+        assert_eq!(parse_tag_number("0"), None);
+        assert_eq!(parse_tag_number("A1"), None);
+    }
+
+    #[test]
+    fn test_parse_tag_year() {
+        assert_eq!(parse_tag_year("2004"), Some(2004));
+        assert_eq!(parse_tag_year("2004-05-01T00:00:00Z"), Some(2004));
+        assert_eq!(parse_tag_year("04"), None);
+        assert_eq!(parse_tag_year("unknown"), None);
+    }
+}
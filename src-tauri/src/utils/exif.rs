@@ -0,0 +1,111 @@
+use chrono::{Local, NaiveDateTime, TimeZone};
+use exif::{In, Reader, Tag, Value};
+use std::fs::File;
+use std::io::BufReader;
+use std::path::Path;
+
+/// Information read from the EXIF data of a photo
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct PhotoMetadata {
+    pub taken_at: Option<i64>,
+    pub camera_make: Option<String>,
+    pub camera_model: Option<String>,
+    pub latitude: Option<f64>,
+    pub longitude: Option<f64>,
+    pub orientation: Option<u32>,
+}
+
+/// Read the EXIF data of a photo
+/// Returns empty metadata for files without EXIF data (e.g. most PNGs)
+/// This does blocking file IO, so call it from a blocking thread
+pub fn read_photo_metadata(path: &Path) -> Result<PhotoMetadata, anyhow::Error> {
+    let mut reader = BufReader::new(File::open(path)?);
+    let exif = match Reader::new().read_from_container(&mut reader) {
+        Ok(exif) => exif,
+        Err(exif::Error::NotFound(_)) | Err(exif::Error::InvalidFormat(_)) => return Ok(PhotoMetadata::default()),
+        Err(e) => return Err(e.into()),
+    };
+
+    let ascii = |tag: Tag| -> Option<String> {
+        match &exif.get_field(tag, In::PRIMARY)?.value {
+            Value::Ascii(values) => values.first()
+                .map(|value| String::from_utf8_lossy(value).trim().to_string())
+                .filter(|value| !value.is_empty()),
+            _ => None,
+        }
+    };
+    let rationals = |tag: Tag| -> Option<Vec<f64>> {
+        match &exif.get_field(tag, In::PRIMARY)?.value {
+            Value::Rational(values) => Some(values.iter().map(|value| value.to_f64()).collect()),
+            _ => None,
+        }
+    };
+
+    let taken_at = ascii(Tag::DateTimeOriginal)
+        .or_else(|| ascii(Tag::DateTime))
+        .and_then(|value| parse_exif_datetime(&value))
+        // EXIF dates carry no timezone, they are the local time of the camera
+        .and_then(|datetime| Local.from_local_datetime(&datetime).earliest())
+        .map(|datetime| datetime.timestamp());
+
+    let latitude = rationals(Tag::GPSLatitude)
+        .and_then(|dms| gps_to_decimal(&dms, ascii(Tag::GPSLatitudeRef).as_deref()));
+    let longitude = rationals(Tag::GPSLongitude)
+        .and_then(|dms| gps_to_decimal(&dms, ascii(Tag::GPSLongitudeRef).as_deref()));
+
+    Ok(PhotoMetadata {
+        taken_at,
+        camera_make: ascii(Tag::Make),
+        camera_model: ascii(Tag::Model),
+        latitude,
+        longitude,
+        orientation: exif.get_field(Tag::Orientation, In::PRIMARY)
+            .and_then(|field| field.value.get_uint(0))
+            .filter(|orientation| (1..=8).contains(orientation)),
+    })
+}
+
+/// Parse an EXIF date ("2024:05:01 14:30:00")
+pub fn parse_exif_datetime(value: &str) -> Option<NaiveDateTime> {
+    NaiveDateTime::parse_from_str(value.trim(), "%Y:%m:%d %H:%M:%S").ok()
+}
+
+/// Convert GPS degrees, minutes and seconds to decimal degrees
+/// South latitudes and west longitudes are negative
+pub fn gps_to_decimal(dms: &[f64], reference: Option<&str>) -> Option<f64> {
+    let (degrees, minutes, seconds) = match dms {
+        [degrees, minutes, seconds, ..] => (*degrees, *minutes, *seconds),
+        _ => return None,
+    };
+    let decimal = degrees + minutes / 60.0 + seconds / 3600.0;
+    if !decimal.is_finite() {
+        return None;
+    }
+
+    match reference {
+        Some("S") | Some("W") => Some(-decimal),
+        _ => Some(decimal),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_exif_datetime() {
+        let datetime = parse_exif_datetime("2024:05:01 14:30:05").unwrap();
+        assert_eq!(datetime.to_string(), "2024-05-01 14:30:05");
+        assert_eq!(parse_exif_datetime("0000:00:00 00:00:00"), None);
+        assert_eq!(parse_exif_datetime(""), None);
+    }
+
+    #[test]
+    fn test_gps_to_decimal() {
+        let latitude = gps_to_decimal(&[40.0, 26.0, 46.0], Some("N")).unwrap();
+        assert!((latitude - 40.446111).abs() < 0.0001);
+        let longitude = gps_to_decimal(&[79.0, 58.0, 56.0], Some("W")).unwrap();
+        assert!((longitude + 79.982222).abs() < 0.0001);
+        assert_eq!(gps_to_decimal(&[40.0, 26.0], Some("N")), None);
+    }
+}
@@ -6,21 +6,22 @@ use xxhash_rust::xxh3::xxh3_128;
 /// Calculate fast hash for a file using xxHash128
 /// For files < 40MB: hash the entire file
 /// For files >= 40MB: hash 5 evenly spaced segments (first 4MB, 3 evenly spaced 4MB segments, last 4MB)
-pub async fn calculate_fast_hash(file_path: &Path) -> Result<String, anyhow::Error> {
+/// This does blocking file IO, so call it from a blocking thread (e.g. `spawn_blocking`)
+pub fn calculate_fast_hash(file_path: &Path) -> Result<String, anyhow::Error> {
     let mut file = File::open(file_path)?;
     let file_size = file.metadata()?.len();
     
     // If file is under 40MB, hash the entire file
     if file_size < 40 * 1024 * 1024 {
-        return hash_entire_file(&mut file).await;
+        return hash_entire_file(&mut file);
     }
     
     // For files >= 40MB, hash 5 segments
-    hash_file_segments(&mut file, file_size).await
+    hash_file_segments(&mut file, file_size)
 }
 
 /// Hash the entire file
-async fn hash_entire_file(file: &mut File) -> Result<String, anyhow::Error> {
+fn hash_entire_file(file: &mut File) -> Result<String, anyhow::Error> {
     file.seek(SeekFrom::Start(0))?;
     
     let mut buffer = Vec::new();
@@ -31,7 +32,7 @@ async fn hash_entire_file(file: &mut File) -> Result<String, anyhow::Error> {
 }
 
 /// Hash 5 segments of a large file (evenly spaced segments)
-async fn hash_file_segments(file: &mut File, file_size: u64) -> Result<String, anyhow::Error> {
+fn hash_file_segments(file: &mut File, file_size: u64) -> Result<String, anyhow::Error> {
     const SEGMENT_SIZE: u64 = 4 * 1024 * 1024; // 4MB
     let mut combined_data = Vec::new();
     
@@ -1,4 +1,6 @@
-use image::ImageFormat;
+use image::{DynamicImage, ImageFormat};
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
 
 // Helper function to detect image format and return appropriate extension
 pub fn detect_image_extension(image_data: &[u8]) -> Result<&'static str, String> {
@@ -29,3 +31,140 @@ pub fn detect_image_extension(image_data: &[u8]) -> Result<&'static str, String>
         }
     }
 }
+
+/// Directory where generated thumbnails are stored
+static THUMBNAILS_DIR: OnceLock<PathBuf> = OnceLock::new();
+
+/// Initialize the directory where generated thumbnails are stored
+pub fn init_thumbnails_dir(dir: PathBuf) {
+    THUMBNAILS_DIR.set(dir).expect("Failed to initialize thumbnails directory");
+}
+
+/// Get the path of the thumbnail of a photo (thumbnails are keyed by content hash, so moved photos keep theirs)
+/// Returns None when the thumbnails directory is not initialized
+pub fn photo_thumbnail_path(fast_hash: &str) -> Option<PathBuf> {
+    Some(THUMBNAILS_DIR.get()?.join("photos").join(format!("{}.jpg", fast_hash)))
+}
+
+/// Get the path of the cover art extracted for a music album
+/// Returns None when the thumbnails directory is not initialized
+pub fn music_cover_path(album_id: i64) -> Option<PathBuf> {
+    Some(THUMBNAILS_DIR.get()?.join("music").join(format!("{}.jpg", album_id)))
+}
+
+/// Get the folder holding all cached artwork of a video item
+/// Returns None when the thumbnails directory is not initialized
+pub fn video_artwork_dir(item_id: i64) -> Option<PathBuf> {
+    Some(video_artwork_root()?.join(item_id.to_string()))
+}
+
+/// Get the path of cached video artwork (`poster`, `fanart` or `thumb`), or of its variant resized to `width`
+/// Returns None when the thumbnails directory is not initialized
+pub fn video_artwork_path(item_id: i64, kind: &str, width: Option<u32>) -> Option<PathBuf> {
+    let file_name = match width {
+        Some(width) => format!("{}-{}.jpg", kind, width),
+        None => format!("{}.jpg", kind),
+    };
+    Some(video_artwork_dir(item_id)?.join(file_name))
+}
+
+/// Get the folder holding the artwork folders of all video items
+/// Returns None when the thumbnails directory is not initialized
+pub fn video_artwork_root() -> Option<PathBuf> {
+    Some(THUMBNAILS_DIR.get()?.join("artwork"))
+}
+
+/// Get the folder holding the subtitle streams extracted from a video part
+/// Returns None when the thumbnails directory is not initialized
+pub fn extracted_subtitles_dir(part_id: i64) -> Option<PathBuf> {
+    Some(extracted_subtitles_root()?.join(part_id.to_string()))
+}
+
+/// Get the path of a text subtitle stream extracted from a video part (`format` is the file extension)
+/// Returns None when the thumbnails directory is not initialized
+pub fn extracted_subtitle_path(part_id: i64, stream_index: i64, format: &str) -> Option<PathBuf> {
+    Some(extracted_subtitles_dir(part_id)?.join(format!("{}.{}", stream_index, format)))
+}
+
+/// Get the folder holding the subtitle folders of all video parts
+/// Returns None when the thumbnails directory is not initialized
+pub fn extracted_subtitles_root() -> Option<PathBuf> {
+    Some(THUMBNAILS_DIR.get()?.join("subtitles"))
+}
+
+/// Create a JPEG thumbnail that fits within `max_size` pixels, upright according to the EXIF orientation
+/// This does blocking file IO and decoding, so call it from a blocking thread
+pub fn create_thumbnail(source: &Path, destination: &Path, max_size: u32, orientation: Option<u32>) -> Result<(), anyhow::Error> {
+    let image = image::open(source)?;
+    save_thumbnail(image, destination, max_size, orientation.unwrap_or(1))
+}
+
+/// Create a JPEG thumbnail that fits within `max_size` pixels from encoded image data (e.g. embedded cover art)
+/// This does blocking file IO and decoding, so call it from a blocking thread
+pub fn create_thumbnail_from_bytes(data: &[u8], destination: &Path, max_size: u32) -> Result<(), anyhow::Error> {
+    let image = image::load_from_memory(data)?;
+    save_thumbnail(image, destination, max_size, 1)
+}
+
+/// Create a JPEG copy of an image scaled to `width` pixels wide, keeping its aspect ratio
+/// This does blocking file IO and decoding, so call it from a blocking thread
+pub fn create_resized_to_width(source: &Path, destination: &Path, width: u32) -> Result<(), anyhow::Error> {
+    let image = image::open(source)?;
+    let scaled = if image.width() > width { image.thumbnail(width, u32::MAX) } else { image };
+    save_jpeg(&scaled, destination)
+}
+
+/// Get the width and height of an image file without decoding it
+pub fn image_dimensions(path: &Path) -> Result<(u32, u32), anyhow::Error> {
+    Ok(image::image_dimensions(path)?)
+}
+
+/// Scale an image down, orient it and save it as JPEG
+fn save_thumbnail(image: DynamicImage, destination: &Path, max_size: u32, orientation: u32) -> Result<(), anyhow::Error> {
+    // `thumbnail` also scales small images up, so those are kept at their size
+    let scaled = if image.width() > max_size || image.height() > max_size {
+        image.thumbnail(max_size, max_size)
+    } else {
+        image
+    };
+    save_jpeg(&apply_exif_orientation(scaled, orientation), destination)
+}
+
+/// Save an image as JPEG, creating the destination folder if needed.
+/// The image is written to a temporary file next to the destination and renamed into place,
+/// so concurrent writers never interleave and readers never see a half-written file.
+fn save_jpeg(image: &DynamicImage, destination: &Path) -> Result<(), anyhow::Error> {
+    if let Some(parent) = destination.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    let file_name = destination
+        .file_name()
+        .ok_or_else(|| anyhow::anyhow!("Invalid image destination: {}", destination.display()))?
+        .to_string_lossy();
+    let temp_path = destination.with_file_name(format!(".{}.{}.tmp", file_name, uuid::Uuid::new_v4()));
+
+    // JPEG has no alpha channel
+    let result = DynamicImage::ImageRgb8(image.to_rgb8())
+        .save_with_format(&temp_path, ImageFormat::Jpeg)
+        .map_err(anyhow::Error::from)
+        .and_then(|_| std::fs::rename(&temp_path, destination).map_err(anyhow::Error::from));
+    if result.is_err() {
+        let _ = std::fs::remove_file(&temp_path);
+    }
+
+    result
+}
+
+/// Rotate and flip an image according to an EXIF orientation (1-8)
+fn apply_exif_orientation(image: DynamicImage, orientation: u32) -> DynamicImage {
+    match orientation {
+        2 => image.fliph(),
+        3 => image.rotate180(),
+        4 => image.flipv(),
+        5 => image.rotate90().fliph(),
+        6 => image.rotate90(),
+        7 => image.rotate270().fliph(),
+        8 => image.rotate270(),
+        _ => image,
+    }
+}
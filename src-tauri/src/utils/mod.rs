@@ -3,11 +3,25 @@ pub mod network;
 pub mod token;
 pub mod hash;
 pub mod video_classifier;
+pub mod probe;
+pub mod activity;
+pub mod exif;
+pub mod audio_tags;
+pub mod nfo;
+pub mod subtitles;
+pub mod search;
 
 pub use image::*;
 pub use network::*;
 pub use token::*;
 pub use hash::*;
 pub use video_classifier::*;
+pub use probe::*;
+pub use activity::*;
+pub use exif::*;
+pub use audio_tags::*;
+pub use nfo::*;
+pub use subtitles::*;
+pub use search::*;
 // Only export the main function from classifier2 to avoid conflicts
 pub use video_classifier::classify_path;
@@ -0,0 +1,205 @@
+use once_cell::sync::Lazy;
+use regex::Regex;
+use roxmltree::{Document, Node};
+use std::collections::HashMap;
+
+/// IMDb and TMDB links in NFO files that only hold a URL
+static IMDB_URL_ID: Lazy<Regex> = Lazy::new(|| Regex::new(r"imdb\.com/title/(tt\d+)").unwrap());
+static TMDB_URL_ID: Lazy<Regex> = Lazy::new(|| Regex::new(r"themoviedb\.org/(?:movie|tv)/(\d+)").unwrap());
+
+/// XML declarations and DOCTYPEs, stripped before wrapping multi-episode files in a single root
+static XML_PROLOG: Lazy<Regex> = Lazy::new(|| Regex::new(r"(?s)<\?xml.*?\?>|<!DOCTYPE[^>]*>").unwrap());
+
+/// Information read from a Kodi NFO file (`movie.nfo`, `tvshow.nfo` or an episode NFO)
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct NfoMetadata {
+    /// Root element: "movie", "tvshow" or "episodedetails" (empty for NFOs that only hold a URL)
+    pub kind: String,
+    pub title: Option<String>,
+    pub original_title: Option<String>,
+    pub sort_title: Option<String>,
+    pub year: Option<i64>,
+    pub plot: Option<String>,
+    pub tagline: Option<String>,
+    pub genres: Vec<String>,
+    /// Default rating out of 10, with its vote count
+    pub rating: Option<f64>,
+    pub votes: Option<i64>,
+    /// Premiere (movies, shows) or air date (episodes) as "YYYY-MM-DD"
+    pub premiered: Option<String>,
+    pub season: Option<i64>,
+    pub episode: Option<i64>,
+    /// Provider IDs keyed by provider ("imdb", "tmdb", "tvdb")
+    pub external_ids: HashMap<String, String>,
+}
+
+/// Parse the content of an NFO file
+/// Multi-episode files hold one `<episodedetails>` per episode, so every entry is returned
+pub fn parse_nfo(content: &str) -> Result<Vec<NfoMetadata>, anyhow::Error> {
+    // Several root elements aren't valid XML, and files often end with a scraper URL after the XML
+    let content = content.trim_start_matches('\u{feff}');
+    let wrapped = format!("<nfo>{}</nfo>", XML_PROLOG.replace_all(content, ""));
+    let entries: Vec<NfoMetadata> = match Document::parse(&wrapped) {
+        Ok(document) => document.root_element().children()
+            .filter(|node| matches!(node.tag_name().name(), "movie" | "tvshow" | "episodedetails"))
+            .map(parse_entry)
+            .collect(),
+        Err(_) => Vec::new(),
+    };
+    if !entries.is_empty() {
+        return Ok(entries);
+    }
+
+    // NFOs may only hold the URL of the movie, to pin the match
+    let mut url_only = NfoMetadata::default();
+    if let Some(caps) = IMDB_URL_ID.captures(content) {
+        url_only.external_ids.insert("imdb".to_string(), caps[1].to_string());
+    }
+    if let Some(caps) = TMDB_URL_ID.captures(content) {
+        url_only.external_ids.insert("tmdb".to_string(), caps[1].to_string());
+    }
+    if url_only.external_ids.is_empty() {
+        return Err(anyhow::anyhow!("No movie, show or episode found in NFO"));
+    }
+    Ok(vec![url_only])
+}
+
+/// Read one `<movie>`, `<tvshow>` or `<episodedetails>` element
+fn parse_entry(node: Node) -> NfoMetadata {
+    let text = |name: &str| child_text(node, name);
+    let number = |name: &str| text(name).and_then(|value| value.parse::<i64>().ok());
+
+    let mut nfo = NfoMetadata {
+        kind: node.tag_name().name().to_string(),
+        title: text("title"),
+        original_title: text("originaltitle"),
+        sort_title: text("sorttitle"),
+        plot: text("plot").or_else(|| text("outline")),
+        tagline: text("tagline"),
+        premiered: text("premiered").or_else(|| text("aired")).or_else(|| text("releasedate")),
+        season: number("season"),
+        episode: number("episode"),
+        ..Default::default()
+    };
+    nfo.year = number("year")
+        .filter(|year| *year > 0)
+        .or_else(|| nfo.premiered.as_deref()?.get(..4)?.parse::<i64>().ok());
+
+    nfo.genres = node.children()
+        .filter(|child| child.has_tag_name("genre"))
+        .filter_map(|child| non_empty_text(child))
+        // Some scrapers put every genre in one element ("Action / Crime")
+        .flat_map(|genres| genres.split(" / ").map(|genre| genre.trim().to_string()).collect::<Vec<_>>())
+        .filter(|genre| !genre.is_empty())
+        .collect();
+
+    // <ratings><rating name="imdb" default="true"><value>8.7</value><votes>100</votes></rating></ratings>
+    let ratings: Vec<Node> = node.children()
+        .filter(|child| child.has_tag_name("ratings"))
+        .flat_map(|ratings| ratings.children().filter(|rating| rating.has_tag_name("rating")))
+        .collect();
+    let default_rating = ratings.iter().find(|rating| rating.attribute("default") == Some("true")).or(ratings.first());
+    match default_rating {
+        Some(rating) => {
+            let max = rating.attribute("max").and_then(|max| max.parse::<f64>().ok()).filter(|max| *max > 0.0).unwrap_or(10.0);
+            nfo.rating = child_text(*rating, "value").and_then(|value| value.parse::<f64>().ok()).map(|value| value * 10.0 / max);
+            nfo.votes = child_text(*rating, "votes").and_then(|votes| votes.replace(',', "").parse::<i64>().ok());
+        }
+        // Older files have a plain <rating>8.7</rating>
+        None => {
+            nfo.rating = text("rating").and_then(|value| value.parse::<f64>().ok());
+            nfo.votes = text("votes").and_then(|votes| votes.replace(',', "").parse::<i64>().ok());
+        }
+    }
+
+    // <uniqueid type="tmdb">603</uniqueid>, then the older <id>, <imdbid>, <tmdbid> and <tvdbid>
+    for uniqueid in node.children().filter(|child| child.has_tag_name("uniqueid")) {
+        if let (Some(provider), Some(id)) = (uniqueid.attribute("type"), non_empty_text(uniqueid)) {
+            nfo.external_ids.entry(provider.to_lowercase()).or_insert(id);
+        }
+    }
+    for (element, provider) in [("imdbid", "imdb"), ("tmdbid", "tmdb"), ("tvdbid", "tvdb")] {
+        if let Some(id) = text(element) {
+            nfo.external_ids.entry(provider.to_string()).or_insert(id);
+        }
+    }
+    if let Some(id) = text("id") {
+        // <id> is the IMDb ID for movies and usually the TVDB ID for shows
+        let provider = if id.starts_with("tt") { "imdb" } else if nfo.kind == "movie" { "tmdb" } else { "tvdb" };
+        nfo.external_ids.entry(provider.to_string()).or_insert(id);
+    }
+
+    nfo
+}
+
+/// Get the trimmed text of the first child element with the given name
+fn child_text(node: Node, name: &str) -> Option<String> {
+    node.children().find(|child| child.has_tag_name(name)).and_then(non_empty_text)
+}
+
+/// Get the trimmed text of an element, None when empty
+fn non_empty_text(node: Node) -> Option<String> {
+    node.text().map(|text| text.trim().to_string()).filter(|text| !text.is_empty())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_movie_nfo() {
+        let content = r#"<?xml version="1.0" encoding="UTF-8" standalone="yes" ?>
+<movie>
+    <title>The Matrix</title>
+    <sorttitle>Matrix 1</sorttitle>
+    <ratings>
+        <rating name="themoviedb" max="10"><value>8.1</value><votes>20000</votes></rating>
+        <rating name="imdb" max="10" default="true"><value>8.7</value><votes>1,900,000</votes></rating>
+    </ratings>
+    <plot>A hacker learns the truth.</plot>
+    <genre>Action</genre>
+    <genre>Science Fiction</genre>
+    <uniqueid type="imdb" default="true">tt0133093</uniqueid>
+    <uniqueid type="tmdb">603</uniqueid>
+    <premiered>1999-03-30</premiered>
+</movie>
+https://www.themoviedb.org/movie/603"#;
+        let nfo = parse_nfo(content).unwrap().remove(0);
+
+        assert_eq!(nfo.kind, "movie");
+        assert_eq!(nfo.title.as_deref(), Some("The Matrix"));
+        assert_eq!(nfo.sort_title.as_deref(), Some("Matrix 1"));
+        assert_eq!(nfo.year, Some(1999));
+        assert_eq!(nfo.rating, Some(8.7));
+        assert_eq!(nfo.votes, Some(1_900_000));
+        assert_eq!(nfo.genres, vec!["Action".to_string(), "Science Fiction".to_string()]);
+        assert_eq!(nfo.external_ids.get("imdb").map(String::as_str), Some("tt0133093"));
+        assert_eq!(nfo.external_ids.get("tmdb").map(String::as_str), Some("603"));
+    }
+
+    #[test]
+    fn test_parse_multi_episode_nfo() {
+        let content = "<episodedetails><title>Part One</title><season>1</season><episode>1</episode><aired>2010-01-01</aired></episodedetails>\n\
+                       <episodedetails><title>Part Two</title><season>1</season><episode>2</episode><rating>7.5</rating></episodedetails>";
+        let entries = parse_nfo(content).unwrap();
+
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[0].year, Some(2010));
+        assert_eq!(entries[1].title.as_deref(), Some("Part Two"));
+        assert_eq!(entries[1].episode, Some(2));
+        assert_eq!(entries[1].rating, Some(7.5));
+    }
+
+    #[test]
+    fn test_parse_legacy_ids_and_url_only_nfo() {
+        let show = parse_nfo("<tvshow><title>Show</title><id>12345</id><genre>Drama / Crime</genre></tvshow>").unwrap().remove(0);
+        assert_eq!(show.external_ids.get("tvdb").map(String::as_str), Some("12345"));
+        assert_eq!(show.genres, vec!["Drama".to_string(), "Crime".to_string()]);
+
+        let url_only = parse_nfo("https://www.imdb.com/title/tt0113277/").unwrap().remove(0);
+        assert_eq!(url_only.title, None);
+        assert_eq!(url_only.external_ids.get("imdb").map(String::as_str), Some("tt0113277"));
+
+        assert!(parse_nfo("not an nfo").is_err());
+    }
+}
@@ -0,0 +1,286 @@
+use serde::Deserialize;
+use std::collections::HashMap;
+use crate::constants::FFPROBE_TIMEOUT_SECS;
+use std::path::Path;
+use std::time::Duration;
+use tokio::process::Command;
+use tokio::sync::OnceCell;
+
+/// Cached ffprobe version label (None when ffprobe is not available)
+static FFPROBE_VERSION: OnceCell<Option<String>> = OnceCell::const_new();
+
+/// Technical information about a media file
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ProbeResult {
+    pub container: Option<String>,
+    pub resolution: Option<String>,
+    pub hdr: bool,
+    pub audio_channels: Option<i64>,
+    pub bitrate: Option<i64>,
+    pub duration_ms: Option<i64>,
+    pub streams: Vec<ProbedStream>,
+    /// Index of the embedded cover art stream (MP4 `covr` atom or MKV image attachment)
+    pub cover_stream_index: Option<i64>,
+}
+
+/// A single video, audio or subtitle stream of a media file
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ProbedStream {
+    pub stream_index: i64,
+    pub kind: String,
+    pub codec: Option<String>,
+    pub language: Option<String>,
+    pub title: Option<String>,
+    pub channels: Option<i64>,
+    pub width: Option<i64>,
+    pub height: Option<i64>,
+    pub is_default: bool,
+    pub is_forced: bool,
+    /// Subtitles for the deaf and hard of hearing
+    pub is_hearing_impaired: bool,
+}
+
+/// Raw `ffprobe -print_format json` output (only the fields we use)
+#[derive(Debug, Deserialize)]
+struct FfprobeOutput {
+    #[serde(default)]
+    streams: Vec<FfprobeStream>,
+    format: Option<FfprobeFormat>,
+}
+
+#[derive(Debug, Deserialize)]
+struct FfprobeStream {
+    index: i64,
+    codec_type: Option<String>,
+    codec_name: Option<String>,
+    width: Option<i64>,
+    height: Option<i64>,
+    channels: Option<i64>,
+    color_transfer: Option<String>,
+    #[serde(default)]
+    side_data_list: Vec<HashMap<String, serde_json::Value>>,
+    #[serde(default)]
+    disposition: HashMap<String, i64>,
+    #[serde(default)]
+    tags: HashMap<String, String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct FfprobeFormat {
+    format_name: Option<String>,
+    duration: Option<String>,
+    bit_rate: Option<String>,
+}
+
+/// Get the ffprobe binary to run (`FFPROBE_PATH` overrides the one on PATH)
+pub fn ffprobe_binary() -> String {
+    std::env::var("FFPROBE_PATH").unwrap_or_else(|_| "ffprobe".to_string())
+}
+
+/// Get the version label of the installed ffprobe (e.g. "ffprobe-7.0.1")
+/// Returns None when ffprobe cannot be run
+pub async fn ffprobe_version() -> Option<String> {
+    FFPROBE_VERSION.get_or_init(|| async {
+        let output = Command::new(ffprobe_binary())
+            .arg("-version")
+            .output()
+            .await
+            .ok()?;
+
+        if !output.status.success() {
+            return None;
+        }
+
+        // First line looks like "ffprobe version 7.0.1 Copyright (c) ..."
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        let version = stdout.lines().next()?.split_whitespace().nth(2)?;
+        Some(format!("ffprobe-{}", version))
+    }).await.clone()
+}
+
+/// Run ffprobe on a media file and collect its technical information
+pub async fn probe_media_file(path: &Path) -> Result<ProbeResult, anyhow::Error> {
+    let output = Command::new(ffprobe_binary())
+        .args(["-v", "error", "-print_format", "json", "-show_format", "-show_streams"])
+        .arg(path)
+        .kill_on_drop(true)
+        .output();
+
+    // A hung ffprobe (e.g. on a stalled network share) is killed when the timed out future is dropped
+    let output = match tokio::time::timeout(Duration::from_secs(FFPROBE_TIMEOUT_SECS), output).await {
+        Ok(output) => output?,
+        Err(_) => {
+            return Err(anyhow::anyhow!(
+                "ffprobe timed out after {}s for {}",
+                FFPROBE_TIMEOUT_SECS,
+                path.display()
+            ));
+        }
+    };
+
+    if !output.status.success() {
+        return Err(anyhow::anyhow!(
+            "ffprobe failed for {}: {}",
+            path.display(),
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+
+    parse_ffprobe_output(&String::from_utf8_lossy(&output.stdout), path)
+}
+
+/// Parse ffprobe JSON output into a `ProbeResult`
+pub fn parse_ffprobe_output(json: &str, path: &Path) -> Result<ProbeResult, anyhow::Error> {
+    let output: FfprobeOutput = serde_json::from_str(json)?;
+    let mut result = ProbeResult::default();
+
+    if let Some(format) = &output.format {
+        result.duration_ms = format.duration.as_deref()
+            .and_then(|d| d.parse::<f64>().ok())
+            .map(|seconds| (seconds * 1000.0).round() as i64);
+        result.bitrate = format.bit_rate.as_deref().and_then(|b| b.parse::<i64>().ok());
+    }
+    result.container = container_name(path, output.format.as_ref().and_then(|f| f.format_name.as_deref()));
+
+    for stream in &output.streams {
+        let kind = match stream.codec_type.as_deref() {
+            Some("video") => {
+                // Skip embedded cover art (attached pictures), remembering the first one as the cover
+                if stream.disposition.get("attached_pic").copied().unwrap_or(0) == 1 {
+                    result.cover_stream_index = result.cover_stream_index.or(Some(stream.index));
+                    continue;
+                }
+                "video"
+            }
+            Some("audio") => "audio",
+            Some("subtitle") => "subtitle",
+            _ => continue,
+        };
+
+        result.streams.push(ProbedStream {
+            stream_index: stream.index,
+            kind: kind.to_string(),
+            codec: stream.codec_name.clone(),
+            language: stream.tags.get("language").filter(|l| l.as_str() != "und").cloned(),
+            title: stream.tags.get("title").cloned(),
+            channels: stream.channels,
+            width: stream.width,
+            height: stream.height,
+            is_default: stream.disposition.get("default").copied().unwrap_or(0) == 1,
+            is_forced: stream.disposition.get("forced").copied().unwrap_or(0) == 1,
+            is_hearing_impaired: stream.disposition.get("hearing_impaired").copied().unwrap_or(0) == 1,
+        });
+    }
+
+    // Version level info comes from the first video stream and the default (or first) audio stream
+    if let Some(video) = output.streams.iter().find(|s| {
+        s.codec_type.as_deref() == Some("video") && s.disposition.get("attached_pic").copied().unwrap_or(0) == 0
+    }) {
+        if let (Some(width), Some(height)) = (video.width, video.height) {
+            result.resolution = Some(resolution_label(width, height));
+        }
+        result.hdr = is_hdr_stream(video);
+    }
+
+    let audio_streams: Vec<&ProbedStream> = result.streams.iter().filter(|s| s.kind == "audio").collect();
+    result.audio_channels = audio_streams.iter()
+        .find(|s| s.is_default)
+        .or(audio_streams.first())
+        .and_then(|s| s.channels);
+
+    Ok(result)
+}
+
+/// Map video dimensions to a resolution label ("2160p", "1080p", ...)
+/// Width is considered too, so letterboxed encodes (e.g. 1920x800) get the expected label
+pub fn resolution_label(width: i64, height: i64) -> String {
+    if width >= 3200 || height >= 1800 {
+        "2160p".to_string()
+    } else if width >= 1800 || height >= 1000 {
+        "1080p".to_string()
+    } else if width >= 1200 || height >= 700 {
+        "720p".to_string()
+    } else if height >= 560 {
+        "576p".to_string()
+    } else if height >= 400 {
+        "480p".to_string()
+    } else {
+        "sd".to_string()
+    }
+}
+
+/// Check whether a video stream uses an HDR transfer function or carries Dolby Vision metadata
+fn is_hdr_stream(stream: &FfprobeStream) -> bool {
+    let hdr_transfer = matches!(stream.color_transfer.as_deref(), Some("smpte2084") | Some("arib-std-b67"));
+    let dolby_vision = stream.side_data_list.iter().any(|side_data| {
+        side_data.get("side_data_type")
+            .and_then(|t| t.as_str())
+            .is_some_and(|t| t.contains("DOVI"))
+    });
+    hdr_transfer || dolby_vision
+}
+
+/// Get the container name, preferring the file extension over ffprobe's demuxer list
+/// (ffprobe reports "mov,mp4,m4a,3gp,3g2,mj2" for all ISO media files)
+fn container_name(path: &Path, format_name: Option<&str>) -> Option<String> {
+    let extension = path.extension().and_then(|ext| ext.to_str()).map(|ext| ext.to_lowercase());
+    match extension.as_deref() {
+        Some("mkv") | Some("mp4") | Some("m4v") | Some("mov") | Some("avi") | Some("webm")
+        | Some("ts") | Some("m2ts") | Some("wmv") | Some("flv") | Some("mpg") | Some("mpeg") => extension,
+        _ => format_name
+            .and_then(|name| name.split(',').next())
+            .map(|name| if name == "matroska" { "mkv".to_string() } else { name.to_string() }),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SAMPLE_OUTPUT: &str = r#"{
+        "streams": [
+            {"index": 0, "codec_type": "video", "codec_name": "hevc", "width": 3840, "height": 1600,
+             "color_transfer": "smpte2084", "disposition": {"default": 1, "forced": 0, "attached_pic": 0}},
+            {"index": 1, "codec_type": "audio", "codec_name": "aac", "channels": 2,
+             "disposition": {"default": 0, "forced": 0}, "tags": {"language": "eng", "title": "Commentary"}},
+            {"index": 2, "codec_type": "audio", "codec_name": "eac3", "channels": 6,
+             "disposition": {"default": 1, "forced": 0}, "tags": {"language": "eng"}},
+            {"index": 3, "codec_type": "subtitle", "codec_name": "subrip",
+             "disposition": {"default": 0, "forced": 1}, "tags": {"language": "und"}},
+            {"index": 4, "codec_type": "video", "codec_name": "mjpeg", "width": 600, "height": 900,
+             "disposition": {"default": 0, "attached_pic": 1}},
+            {"index": 5, "codec_type": "attachment", "codec_name": "ttf"}
+        ],
+        "format": {"format_name": "matroska,webm", "duration": "7260.512000", "bit_rate": "18000000"}
+    }"#;
+
+    #[test]
+    fn test_parse_ffprobe_output() {
+        let result = parse_ffprobe_output(SAMPLE_OUTPUT, Path::new("/movies/Movie (2020)/Movie.mkv")).unwrap();
+
+        assert_eq!(result.container.as_deref(), Some("mkv"));
+        assert_eq!(result.resolution.as_deref(), Some("2160p"));
+        assert!(result.hdr);
+        assert_eq!(result.audio_channels, Some(6));
+        assert_eq!(result.bitrate, Some(18_000_000));
+        assert_eq!(result.duration_ms, Some(7_260_512));
+
+        // Cover art and attachments are not streams we track
+        assert_eq!(result.streams.len(), 4);
+        assert_eq!(result.cover_stream_index, Some(4));
+        assert_eq!(result.streams[1].title.as_deref(), Some("Commentary"));
+        assert_eq!(result.streams[3].kind, "subtitle");
+        assert_eq!(result.streams[3].language, None);
+        assert!(result.streams[3].is_forced);
+    }
+
+    #[test]
+    fn test_resolution_label() {
+        assert_eq!(resolution_label(3840, 2160), "2160p");
+        assert_eq!(resolution_label(1920, 800), "1080p");
+        assert_eq!(resolution_label(1280, 720), "720p");
+        assert_eq!(resolution_label(720, 576), "576p");
+        assert_eq!(resolution_label(720, 480), "480p");
+        assert_eq!(resolution_label(320, 240), "sd");
+    }
+}
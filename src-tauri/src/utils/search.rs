@@ -0,0 +1,33 @@
+/// Longest number of words used from a search query
+const MAX_QUERY_WORDS: usize = 10;
+
+/// Build an FTS5 match expression from a user search query
+/// Every word must match the start of a token ("matr rel" finds "The Matrix Reloaded"); punctuation
+/// and FTS operators are dropped, so user input can't break the expression. Returns None without words.
+pub fn fts_match_query(query: &str) -> Option<String> {
+    let terms: Vec<String> = query
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .take(MAX_QUERY_WORDS)
+        .map(|word| format!("\"{}\"*", word))
+        .collect();
+
+    if terms.is_empty() {
+        None
+    } else {
+        Some(terms.join(" "))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fts_match_query() {
+        assert_eq!(fts_match_query("matr rel").as_deref(), Some("\"matr\"* \"rel\"*"));
+        assert_eq!(fts_match_query("Amélie").as_deref(), Some("\"Amélie\"*"));
+        assert_eq!(fts_match_query("\"office\" OR (NEAR*)").as_deref(), Some("\"office\"* \"OR\"* \"NEAR\"*"));
+        assert_eq!(fts_match_query("  -- "), None);
+    }
+}
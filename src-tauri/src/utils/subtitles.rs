@@ -0,0 +1,169 @@
+use once_cell::sync::Lazy;
+use regex::Regex;
+use std::path::Path;
+
+/// Extensions of the text subtitle files picked up next to videos
+pub const SUBTITLE_EXTENSIONS: [&str; 4] = ["srt", "ass", "ssa", "vtt"];
+
+/// Common languages as (ISO 639-2 code, other codes and names used in file names)
+const LANGUAGES: [(&str, &[&str]); 24] = [
+    ("eng", &["en", "english"]),
+    ("fre", &["fr", "fra", "french", "francais"]),
+    ("ger", &["de", "deu", "german", "deutsch"]),
+    ("spa", &["es", "spanish", "espanol"]),
+    ("ita", &["it", "italian", "italiano"]),
+    ("por", &["pt", "portuguese", "pob", "pt-br", "brazilian"]),
+    ("dut", &["nl", "nld", "dutch", "nederlands"]),
+    ("swe", &["sv", "swedish", "svenska"]),
+    ("nor", &["no", "nb", "nob", "norwegian", "norsk"]),
+    ("dan", &["da", "danish", "dansk"]),
+    ("fin", &["fi", "finnish", "suomi"]),
+    ("pol", &["pl", "polish", "polski"]),
+    ("cze", &["cs", "ces", "czech"]),
+    ("hun", &["hu", "hungarian", "magyar"]),
+    ("gre", &["el", "ell", "greek"]),
+    ("tur", &["tr", "turkish"]),
+    ("rus", &["ru", "russian"]),
+    ("ukr", &["uk", "ukrainian"]),
+    ("ara", &["ar", "arabic"]),
+    ("heb", &["he", "hebrew"]),
+    ("hin", &["hi", "hindi"]),
+    ("chi", &["zh", "zho", "chinese"]),
+    ("jpn", &["ja", "japanese"]),
+    ("kor", &["ko", "korean"]),
+];
+
+/// Name tags marking forced and SDH subtitles
+const FORCED_TAGS: [&str; 2] = ["forced", "foreign"];
+const SDH_TAGS: [&str; 2] = ["sdh", "cc"];
+
+/// Track number prefix of subtitles in release Subs folders ("2_English")
+static TRACK_PREFIX: Lazy<Regex> = Lazy::new(|| Regex::new(r"^\d+_").unwrap());
+
+/// SRT cue timing ("00:00:01,000 --> 00:00:04,000", optionally followed by coordinates)
+static SRT_TIMING: Lazy<Regex> = Lazy::new(|| Regex::new(r"^(\d+:\d{2}:\d{2})[,.](\d{3})\s*-->\s*(\d+:\d{2}:\d{2})[,.](\d{3})").unwrap());
+
+/// ASS override blocks some SRT files carry ("{\an8}")
+static OVERRIDE_TAG: Lazy<Regex> = Lazy::new(|| Regex::new(r"\{\\[^}]*\}").unwrap());
+
+/// Details of a subtitle file taken from its name
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct SubtitleTags {
+    /// ISO 639-2 language code ("eng")
+    pub language: Option<String>,
+    /// Name tags that aren't a language or flag ("commentary")
+    pub title: Option<String>,
+    pub is_default: bool,
+    pub is_forced: bool,
+    pub is_sdh: bool,
+}
+
+/// Check if a path has one of the subtitle file extensions
+pub fn is_subtitle_file(path: &Path) -> bool {
+    subtitle_format(path).is_some()
+}
+
+/// Get the subtitle format of a file from its extension ("srt", "ass", "ssa" or "vtt")
+pub fn subtitle_format(path: &Path) -> Option<&'static str> {
+    let ext = path.extension()?.to_str()?.to_lowercase();
+    SUBTITLE_EXTENSIONS.iter().find(|format| **format == ext).copied()
+}
+
+/// Parse the tags of a sidecar subtitle named after a video ("Movie.en.forced" for "Movie")
+/// Returns None when the subtitle belongs to another video; names are compared case-insensitively
+pub fn parse_sidecar_name(video_stem: &str, subtitle_stem: &str) -> Option<SubtitleTags> {
+    let video_stem = video_stem.to_lowercase();
+    let subtitle_stem = subtitle_stem.to_lowercase();
+    if subtitle_stem == video_stem {
+        return Some(SubtitleTags::default());
+    }
+    let tags = subtitle_stem.strip_prefix(&video_stem)?.strip_prefix('.')?;
+    Some(parse_subtitle_tags(tags))
+}
+
+/// Parse the tags of a subtitle name that isn't tied to a video name ("2_English", "en.sdh")
+pub fn parse_subtitle_tags(name: &str) -> SubtitleTags {
+    let mut tags = SubtitleTags::default();
+    let mut title_words: Vec<&str> = Vec::new();
+
+    let name = TRACK_PREFIX.replace(name, "");
+    for tag in name.split(['.', '_', ' ']).filter(|tag| !tag.is_empty()) {
+        let lower = tag.to_lowercase();
+        if FORCED_TAGS.contains(&lower.as_str()) {
+            tags.is_forced = true;
+        } else if SDH_TAGS.contains(&lower.as_str()) || (lower == "hi" && tags.language.is_some()) {
+            // "hi" (hearing impaired) is also Hindi, so it only marks SDH after the language
+            tags.is_sdh = true;
+        } else if lower == "default" {
+            tags.is_default = true;
+        } else if let (None, Some(language)) = (&tags.language, normalize_language(&lower)) {
+            tags.language = Some(language);
+        } else {
+            title_words.push(tag);
+        }
+    }
+
+    if !title_words.is_empty() {
+        tags.title = Some(title_words.join(" "));
+    }
+    tags
+}
+
+/// Normalize a language code or name to its ISO 639-2 code ("en", "English" and "eng" become "eng")
+pub fn normalize_language(value: &str) -> Option<String> {
+    let value = value.to_lowercase();
+    LANGUAGES.iter()
+        .find(|(code, aliases)| *code == value || aliases.contains(&value.as_str()))
+        .map(|(code, _)| code.to_string())
+}
+
+/// Convert SRT subtitles to WebVTT for browser players
+/// Cue numbers are kept as cue identifiers, timings get a dot before the milliseconds
+pub fn srt_to_vtt(content: &str) -> String {
+    let content = content.trim_start_matches('\u{feff}').replace("\r\n", "\n").replace('\r', "\n");
+    let mut vtt = String::from("WEBVTT\n\n");
+
+    for line in content.lines() {
+        if let Some(caps) = SRT_TIMING.captures(line.trim()) {
+            vtt.push_str(&format!("{}.{} --> {}.{}", &caps[1], &caps[2], &caps[3], &caps[4]));
+        } else {
+            // "-->" may only appear in timing lines
+            vtt.push_str(&OVERRIDE_TAG.replace_all(line, "").replace("-->", "->"));
+        }
+        vtt.push('\n');
+    }
+
+    vtt
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_sidecar_name() {
+        assert_eq!(parse_sidecar_name("Movie (2010)", "movie (2010)"), Some(SubtitleTags::default()));
+        assert_eq!(parse_sidecar_name("Movie (2010)", "Other"), None);
+        // Another video whose name starts with the same words
+        assert_eq!(parse_sidecar_name("Movie", "Movie 2.en"), None);
+
+        let forced = parse_sidecar_name("Movie", "Movie.en.forced").unwrap();
+        assert_eq!((forced.language.as_deref(), forced.is_forced, forced.is_sdh), (Some("eng"), true, false));
+
+        let sdh = parse_sidecar_name("S01E01", "S01E01.English.SDH.commentary").unwrap();
+        assert_eq!((sdh.language.as_deref(), sdh.is_sdh, sdh.title.as_deref()), (Some("eng"), true, Some("commentary")));
+    }
+
+    #[test]
+    fn test_parse_subtitle_tags() {
+        assert_eq!(parse_subtitle_tags("2_English").language.as_deref(), Some("eng"));
+        assert_eq!(parse_subtitle_tags("hi").language.as_deref(), Some("hin"));
+        assert_eq!(parse_subtitle_tags("pt-BR").language.as_deref(), Some("por"));
+    }
+
+    #[test]
+    fn test_srt_to_vtt() {
+        let srt = "\u{feff}1\r\n00:00:01,000 --> 00:00:04,500 X1:10 X2:20\r\n{\\an8}Hello\r\n\r\n2\r\n00:01:02,003 --> 00:01:03,000\r\nWorld\r\n";
+        assert_eq!(srt_to_vtt(srt), "WEBVTT\n\n1\n00:00:01.000 --> 00:00:04.500\nHello\n\n2\n00:01:02.003 --> 00:01:03.000\nWorld\n");
+    }
+}
@@ -2,7 +2,11 @@ use rand::RngCore;
 use base64::{Engine as _, engine::general_purpose};
 use sha2::{Sha256, Digest};
 use sqlx::SqlitePool;
+use chrono::Utc;
+use std::time::Duration;
 use crate::db::repos::TokensRepo;
+use crate::db::models::TokenSession;
+use crate::constants::{TOKEN_IDLE_EXPIRY_SECS, TOKEN_TOUCH_INTERVAL_SECS, TOKEN_PRUNE_INTERVAL_SECS};
 
 /// Token repository instance for database operations
 static TOKEN_REPO: std::sync::OnceLock<TokensRepo> = std::sync::OnceLock::new();
@@ -21,22 +25,78 @@ pub fn init_token_repo(pool: SqlitePool) {
 }
 
 /// Add a new token to storage (stores the hashed token)
-pub async fn add_token_to_storage(token: &str, user_agent: &str) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+pub async fn add_token_to_storage(token: &str, user_agent: &str, ip_address: Option<&str>) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
     let repo = TOKEN_REPO.get().ok_or("Token repository not initialized")?;
     
     // Store the hashed token instead of the plain token
     let hashed_token = hash_token(token);
-    repo.add_token(hashed_token, user_agent.to_string()).await?;
+    repo.add_token(hashed_token, user_agent.to_string(), ip_address.map(|ip| ip.to_string())).await?;
     Ok(())
 }
 
-/// Check if a token exists in storage (checks against hashed tokens)
+/// Check if a token exists in storage and has not expired (checks against hashed tokens)
 pub async fn token_exists(token: &str) -> Result<bool, Box<dyn std::error::Error + Send + Sync>> {
     let repo = TOKEN_REPO.get().ok_or("Token repository not initialized")?;
     
     // Check against hashed token
     let hashed_token = hash_token(token);
-    Ok(repo.token_exists(&hashed_token).await?)
+    let active_since = Utc::now().timestamp() - TOKEN_IDLE_EXPIRY_SECS;
+    Ok(repo.token_exists(&hashed_token, active_since).await?)
+}
+
+/// Validate a token and refresh its sliding expiry
+pub async fn authenticate_token(token: &str, ip_address: Option<&str>) -> Result<bool, Box<dyn std::error::Error + Send + Sync>> {
+    if !token_exists(token).await? {
+        return Ok(false);
+    }
+    
+    let repo = TOKEN_REPO.get().ok_or("Token repository not initialized")?;
+    let now = Utc::now().timestamp();
+    
+    // Throttle writes so streaming (many range requests) doesn't update the row every time
+    repo.touch_token(&hash_token(token), now, ip_address, now - TOKEN_TOUCH_INTERVAL_SECS).await?;
+    Ok(true)
+}
+
+/// Get all sessions whose token has not expired
+pub async fn get_active_sessions() -> Result<Vec<TokenSession>, Box<dyn std::error::Error + Send + Sync>> {
+    let repo = TOKEN_REPO.get().ok_or("Token repository not initialized")?;
+    let active_since = Utc::now().timestamp() - TOKEN_IDLE_EXPIRY_SECS;
+    Ok(repo.get_active_sessions(active_since).await?)
+}
+
+/// Revoke a single session, returns false if it does not exist
+pub async fn revoke_session(session_id: i64) -> Result<bool, Box<dyn std::error::Error + Send + Sync>> {
+    let repo = TOKEN_REPO.get().ok_or("Token repository not initialized")?;
+    Ok(repo.delete_session(session_id).await?)
+}
+
+/// Revoke all sessions, returns the number of revoked sessions
+pub async fn revoke_all_sessions() -> Result<u64, Box<dyn std::error::Error + Send + Sync>> {
+    let repo = TOKEN_REPO.get().ok_or("Token repository not initialized")?;
+    Ok(repo.delete_all_tokens().await?)
+}
+
+/// Delete expired tokens from storage
+pub async fn prune_expired_tokens() -> Result<u64, Box<dyn std::error::Error + Send + Sync>> {
+    let repo = TOKEN_REPO.get().ok_or("Token repository not initialized")?;
+    let unused_since = Utc::now().timestamp() - TOKEN_IDLE_EXPIRY_SECS;
+    Ok(repo.delete_expired_tokens(unused_since).await?)
+}
+
+/// Background task that periodically prunes expired tokens
+pub async fn start_token_pruning_process() {
+    let mut interval = tokio::time::interval(Duration::from_secs(TOKEN_PRUNE_INTERVAL_SECS));
+    
+    loop {
+        interval.tick().await;
+        
+        match prune_expired_tokens().await {
+            Ok(0) => {}
+            Ok(count) => println!("🔑 Pruned {} expired token(s)", count),
+            Err(e) => eprintln!("Failed to prune expired tokens: {}", e),
+        }
+    }
 }
 
 /// Hash a token using SHA256
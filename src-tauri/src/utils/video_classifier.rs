@@ -789,7 +789,7 @@ fn parse_version_and_part_from_suffix_movie(suffix: &str, movie_info: &mut Movie
     }
 }
 
-fn parse_external_ids(text: &str) -> HashMap<String, String> {
+pub fn parse_external_ids(text: &str) -> HashMap<String, String> {
     let mut ids = HashMap::new();
     
     for caps in EXTERNAL_ID.captures_iter(text) {